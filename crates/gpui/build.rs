@@ -52,6 +52,7 @@ fn generate_shader_bindings() -> PathBuf {
         "PointF".into(),
         "Hsla".into(),
         "ContentMask".into(),
+        "TransformationMatrix".into(),
         "Uniforms".into(),
         "AtlasTile".into(),
         "PathRasterizationInputIndex".into(),
@@ -62,12 +63,21 @@ fn generate_shader_bindings() -> PathBuf {
         "Underline".into(),
         "UnderlineInputIndex".into(),
         "Quad".into(),
+        "Background".into(),
+        "BackgroundTag".into(),
+        "GradientStop".into(),
+        "ColorSpace".into(),
         "SpriteInputIndex".into(),
         "MonochromeSprite".into(),
         "PolychromeSprite".into(),
         "PathSprite".into(),
+        "PathCoverage".into(),
+        "BorderStyle".into(),
         "SurfaceInputIndex".into(),
         "SurfaceBounds".into(),
+        "FilterInputIndex".into(),
+        "FilterEffect".into(),
+        "ColorMatrix".into(),
     ]);
     config.no_includes = true;
     config.enumeration.prefix_with_name = true;
@@ -78,6 +88,7 @@ fn generate_shader_bindings() -> PathBuf {
         crate_dir.join("src/scene.rs"),
         crate_dir.join("src/geometry.rs"),
         crate_dir.join("src/color.rs"),
+        crate_dir.join("src/style.rs"),
         crate_dir.join("src/window.rs"),
         crate_dir.join("src/platform.rs"),
         crate_dir.join("src/platform/mac/metal_renderer.rs"),
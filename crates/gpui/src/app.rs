@@ -2,6 +2,8 @@ mod async_context;
 mod entity_map;
 mod model_context;
 #[cfg(any(test, feature = "test-support"))]
+mod screenshot;
+#[cfg(any(test, feature = "test-support"))]
 mod test_context;
 
 pub use async_context::*;
@@ -9,6 +11,8 @@ use derive_more::{Deref, DerefMut};
 pub use entity_map::*;
 pub use model_context::*;
 use refineable::Refineable;
+#[cfg(any(test, feature = "test-support"))]
+pub(crate) use screenshot::*;
 use smol::future::FutureExt;
 #[cfg(any(test, feature = "test-support"))]
 pub use test_context::*;
@@ -0,0 +1,218 @@
+use crate::{DevicePixels, Size};
+use anyhow::{anyhow, bail, Context as _, Result};
+use image::{Rgba, RgbaImage};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// The scale factor windows are rendered at for screenshot assertions, so goldens
+/// don't depend on the density of the display the tests happen to run on.
+pub(crate) const SCREENSHOT_SCALE_FACTOR: f32 = 2.;
+
+/// The largest difference allowed between corresponding color channels of a
+/// screenshot and its golden image.
+pub(crate) const DEFAULT_SCREENSHOT_TOLERANCE: u8 = 2;
+
+/// When this environment variable is set, screenshot assertions overwrite their
+/// golden images instead of comparing against them.
+const UPDATE_SCREENSHOTS_ENV_VAR: &str = "UPDATE_SCREENSHOTS";
+
+/// Converts a framebuffer of straight-alpha RGBA pixels into an image.
+pub(crate) fn screenshot_image(size: Size<DevicePixels>, pixels: &[u8]) -> RgbaImage {
+    RgbaImage::from_raw(
+        size.width.0.max(0) as u32,
+        size.height.0.max(0) as u32,
+        pixels.to_vec(),
+    )
+    .expect("framebuffer size doesn't match its dimensions")
+}
+
+/// The directory holding the golden images of the crate under test.
+pub(crate) fn golden_dir() -> PathBuf {
+    env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join("tests")
+        .join("screenshots")
+}
+
+/// Whether goldens should be regenerated rather than compared against.
+pub(crate) fn should_update_goldens() -> bool {
+    env::var(UPDATE_SCREENSHOTS_ENV_VAR).is_ok_and(|value| !value.is_empty() && value != "0")
+}
+
+/// Compares a screenshot with the golden image `{name}.png` in the given directory.
+///
+/// On mismatch, `{name}.actual.png` and `{name}.diff.png` are written next to the
+/// golden and an error describing the mismatch is returned. When `update` is true,
+/// the screenshot replaces the golden instead.
+pub(crate) fn compare_with_golden(
+    golden_dir: &Path,
+    name: &str,
+    actual: &RgbaImage,
+    tolerance: u8,
+    update: bool,
+) -> Result<()> {
+    let golden_path = golden_dir.join(format!("{name}.png"));
+    let actual_path = golden_dir.join(format!("{name}.actual.png"));
+    let diff_path = golden_dir.join(format!("{name}.diff.png"));
+
+    if update {
+        fs::create_dir_all(golden_dir)
+            .with_context(|| format!("failed to create {golden_dir:?}"))?;
+        actual
+            .save(&golden_path)
+            .with_context(|| format!("failed to write golden {golden_path:?}"))?;
+        fs::remove_file(&actual_path).ok();
+        fs::remove_file(&diff_path).ok();
+        return Ok(());
+    }
+
+    let expected = match image::open(&golden_path) {
+        Ok(expected) => expected.to_rgba8(),
+        Err(error) => {
+            save_failure(actual, &actual_path, None, &diff_path)?;
+            return Err(anyhow!(error)).with_context(|| {
+                format!(
+                    "failed to read golden {golden_path:?}, run with {UPDATE_SCREENSHOTS_ENV_VAR}=1 to create it. \
+                    The screenshot was written to {actual_path:?}"
+                )
+            });
+        }
+    };
+
+    if expected.dimensions() != actual.dimensions() {
+        save_failure(actual, &actual_path, None, &diff_path)?;
+        bail!(
+            "screenshot {name:?} is {:?} but its golden is {:?}. The screenshot was written to {actual_path:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        );
+    }
+
+    let mut mismatched_pixels = 0;
+    let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let actual_pixel = actual.get_pixel(x, y);
+        let expected_pixel = expected.get_pixel(x, y);
+        let matches = actual_pixel
+            .0
+            .iter()
+            .zip(expected_pixel.0.iter())
+            .all(|(actual, expected)| actual.abs_diff(*expected) <= tolerance);
+        if matches {
+            // Show matching pixels as a faded version of the golden for context.
+            let [r, g, b, a] = expected_pixel.0;
+            let luminance = (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32)
+                * (a as f32 / 255.)
+                + 255. * (1. - a as f32 / 255.);
+            let faded = 255 - ((255. - luminance) / 4.) as u8;
+            Rgba([faded, faded, faded, 255])
+        } else {
+            mismatched_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        }
+    });
+
+    if mismatched_pixels == 0 {
+        fs::remove_file(&actual_path).ok();
+        fs::remove_file(&diff_path).ok();
+        return Ok(());
+    }
+
+    save_failure(actual, &actual_path, Some(&diff), &diff_path)?;
+    bail!(
+        "screenshot {name:?} differs from its golden in {mismatched_pixels} pixels (tolerance {tolerance}). \
+        Wrote {actual_path:?} and {diff_path:?}, run with {UPDATE_SCREENSHOTS_ENV_VAR}=1 to accept the change"
+    )
+}
+
+fn save_failure(
+    actual: &RgbaImage,
+    actual_path: &Path,
+    diff: Option<&RgbaImage>,
+    diff_path: &Path,
+) -> Result<()> {
+    if let Some(parent) = actual_path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("failed to create {parent:?}"))?;
+    }
+    actual
+        .save(actual_path)
+        .with_context(|| format!("failed to write {actual_path:?}"))?;
+    if let Some(diff) = diff {
+        diff.save(diff_path)
+            .with_context(|| format!("failed to write {diff_path:?}"))?;
+    } else {
+        fs::remove_file(diff_path).ok();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        div, px, size, IntoElement, ParentElement, Render, Styled, TestAppContext, ViewContext,
+    };
+
+    struct Swatches;
+
+    impl Render for Swatches {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div()
+                .size_full()
+                .bg(crate::white())
+                .p(px(4.))
+                .flex()
+                .gap(px(4.))
+                .child(div().size(px(16.)).bg(crate::rgb(0xff0000)).rounded(px(4.)))
+                .child(
+                    div()
+                        .size(px(16.))
+                        .border_2()
+                        .border_color(crate::rgb(0x0000ff))
+                        .bg(crate::rgb(0x00ff00)),
+                )
+        }
+    }
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    #[crate::test]
+    fn test_assert_screenshot(cx: &mut TestAppContext) {
+        let (_, cx) = cx.add_window_view(|_| Swatches);
+        cx.simulate_resize(size(px(48.), px(24.)));
+        cx.assert_screenshot("swatches");
+    }
+
+    #[test]
+    fn test_compare_with_golden() {
+        let dir = env::temp_dir().join(format!("gpui-screenshots-{}", uuid::Uuid::new_v4()));
+        let golden = RgbaImage::from_pixel(4, 4, Rgba([10, 20, 30, 255]));
+        compare_with_golden(&dir, "square", &golden, 2, true).unwrap();
+
+        let within_tolerance = RgbaImage::from_pixel(4, 4, Rgba([12, 18, 30, 255]));
+        compare_with_golden(&dir, "square", &within_tolerance, 2, false).unwrap();
+        assert!(!dir.join("square.actual.png").exists());
+
+        let mut changed = golden.clone();
+        changed.put_pixel(1, 2, Rgba([200, 20, 30, 255]));
+        let error = compare_with_golden(&dir, "square", &changed, 2, false).unwrap_err();
+        assert!(error.to_string().contains("1 pixels"), "{error}");
+        let diff = image::open(dir.join("square.diff.png")).unwrap().to_rgba8();
+        assert_eq!(diff.get_pixel(1, 2), &Rgba([255, 0, 0, 255]));
+        assert_ne!(diff.get_pixel(0, 0), &Rgba([255, 0, 0, 255]));
+        let actual = image::open(dir.join("square.actual.png"))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(actual, changed);
+
+        let resized = RgbaImage::from_pixel(2, 4, Rgba([10, 20, 30, 255]));
+        assert!(compare_with_golden(&dir, "square", &resized, 2, false).is_err());
+        assert!(compare_with_golden(&dir, "missing", &golden, 2, false).is_err());
+        assert!(dir.join("missing.actual.png").exists());
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
@@ -1,13 +1,18 @@
 use crate::{
-    Action, AnyElement, AnyView, AnyWindowHandle, AppCell, AppContext, AsyncAppContext,
-    AvailableSpace, BackgroundExecutor, Bounds, ClipboardItem, Context, Entity, EventEmitter,
-    ForegroundExecutor, InputEvent, Keystroke, Model, ModelContext, Pixels, Platform, Point,
-    Render, Result, Size, Task, TestDispatcher, TestPlatform, TestWindow, TextSystem, View,
-    ViewContext, VisualContext, WindowContext, WindowHandle, WindowOptions,
+    compare_with_golden, golden_dir, screenshot_image, should_update_goldens, Action, AnyElement,
+    AnyView, AnyWindowHandle, AppCell, AppContext, AsyncAppContext, AvailableSpace,
+    BackgroundExecutor, Bounds, ClipboardItem, Context, CpuRenderer, DevicePixels, DisplayId,
+    Entity, EventEmitter, ForegroundExecutor, GlobalPixels, InputEvent, InputRecording, Keystroke,
+    Model, ModelContext, Pixels, Platform, PlatformWindow, Point, RecordedEventKind, Render,
+    Result, Size, Task, TestDispatcher, TestPlatform, TestWindow, TextSystem, View, ViewContext,
+    VisualContext, WindowContext, WindowHandle, WindowOptions, DEFAULT_SCREENSHOT_TOLERANCE,
+    SCREENSHOT_SCALE_FACTOR,
 };
 use anyhow::{anyhow, bail};
 use futures::{Stream, StreamExt};
-use std::{cell::RefCell, future::Future, ops::Deref, rc::Rc, sync::Arc, time::Duration};
+use std::{
+    cell::RefCell, future::Future, ops::Deref, path::Path, rc::Rc, sync::Arc, time::Duration,
+};
 
 /// A TestAppContext is provided to tests created with `#[gpui::test]`, it provides
 /// an implementation of `Context` with additional methods that are useful in tests.
@@ -241,6 +246,27 @@ impl TestAppContext {
         self.test_window(window_handle).simulate_resize(size);
     }
 
+    /// Adds a display to the test platform, returning its id. Tests start with a single
+    /// 1920x1080 display with a scale factor of 2.
+    pub fn add_display(
+        &self,
+        uuid: uuid::Uuid,
+        bounds: Bounds<GlobalPixels>,
+        scale_factor: f32,
+    ) -> DisplayId {
+        self.test_platform.add_display(uuid, bounds, scale_factor)
+    }
+
+    /// Simulates the user dragging the window onto another display.
+    pub fn simulate_window_move(&self, window_handle: AnyWindowHandle, display_id: DisplayId) {
+        let display = self
+            .test_platform
+            .test_display(display_id)
+            .unwrap_or_else(|| panic!("no display with id {display_id:?}"));
+        self.test_window(window_handle)
+            .simulate_move_to_display(display);
+    }
+
     /// Returns all windows open in the test.
     pub fn windows(&self) -> Vec<AnyWindowHandle> {
         self.app.borrow().windows().clone()
@@ -630,11 +656,110 @@ impl<'a> VisualTestContext {
         self.simulate_window_resize(self.window, size)
     }
 
+    /// Simulates the user dragging the window onto another display.
+    pub fn simulate_move_to_display(&self, display_id: DisplayId) {
+        self.simulate_window_move(self.window, display_id)
+    }
+
+    /// Replays a recording made with [`WindowContext::start_recording_input`] against this
+    /// window. The window is first restored to the size, scale factor and activation it had
+    /// when recording started, then each recorded event is delivered as if it came from the
+    /// platform, advancing the clock by the time that passed between events.
+    /// Automatically runs until parked after each event.
+    pub fn replay(&mut self, path: impl AsRef<Path>) {
+        let recording = InputRecording::load(path).unwrap_or_else(|error| panic!("{error:#}"));
+        let mut test_window = self.test_window(self.window);
+
+        let initial_state = &recording.initial_state;
+        if test_window.scale_factor() != initial_state.scale_factor {
+            test_window.simulate_scale_factor_change(initial_state.scale_factor);
+        }
+        if test_window.content_size() != initial_state.size {
+            test_window.simulate_resize(initial_state.size);
+        }
+        test_window.simulate_active_status_change(initial_state.active);
+        self.run_until_parked();
+
+        let mut elapsed = Duration::ZERO;
+        for event in recording.events {
+            if event.offset > elapsed {
+                self.executor().advance_clock(event.offset - elapsed);
+                elapsed = event.offset;
+            }
+            match event.kind {
+                RecordedEventKind::Input(input) => {
+                    test_window.simulate_input(input);
+                }
+                RecordedEventKind::Resize { size, scale_factor } => {
+                    if test_window.scale_factor() != scale_factor {
+                        test_window.simulate_scale_factor_change(scale_factor);
+                    }
+                    test_window.simulate_resize(size);
+                }
+                RecordedEventKind::ActiveStatusChange { active } => {
+                    test_window.simulate_active_status_change(active);
+                }
+            }
+            self.run_until_parked();
+        }
+    }
+
     /// debug_bounds returns the bounds of the element with the given selector.
     pub fn debug_bounds(&mut self, selector: &'static str) -> Option<Bounds<Pixels>> {
         self.update(|cx| cx.window.rendered_frame.debug_bounds.get(selector).copied())
     }
 
+    /// Renders the window's current frame on the CPU and compares it with the golden image
+    /// `tests/screenshots/{name}.png` of the crate under test, allowing each color channel
+    /// to differ by a small tolerance. The window is rendered at a fixed scale factor of 2.
+    ///
+    /// On mismatch, `{name}.actual.png` and `{name}.diff.png` are written next to the golden
+    /// and the test panics. Run with `UPDATE_SCREENSHOTS=1` to create or update goldens.
+    pub fn assert_screenshot(&mut self, name: &str) {
+        self.assert_screenshot_with_tolerance(name, DEFAULT_SCREENSHOT_TOLERANCE)
+    }
+
+    /// Like [`VisualTestContext::assert_screenshot`], but allows each color channel to
+    /// differ from the golden by up to `tolerance`.
+    pub fn assert_screenshot_with_tolerance(&mut self, name: &str, tolerance: u8) {
+        let screenshot = self.screenshot();
+        if let Err(error) = compare_with_golden(
+            &golden_dir(),
+            name,
+            &screenshot,
+            tolerance,
+            should_update_goldens(),
+        ) {
+            panic!("{error:#}");
+        }
+    }
+
+    fn screenshot(&mut self) -> image::RgbaImage {
+        let mut test_window = self.test_window(self.window);
+        let scale_factor = test_window.scale_factor();
+        if scale_factor != SCREENSHOT_SCALE_FACTOR {
+            test_window.simulate_scale_factor_change(SCREENSHOT_SCALE_FACTOR);
+            self.run_until_parked();
+        }
+
+        let screenshot = self.update(|cx| {
+            let size = cx
+                .window
+                .viewport_size
+                .map(|pixels| DevicePixels((pixels.0 * SCREENSHOT_SCALE_FACTOR).ceil() as i32));
+            let sprite_atlas = test_window.0.lock().sprite_atlas.clone();
+            let mut renderer = CpuRenderer::new(size);
+            renderer.draw(&cx.window.rendered_frame.scene, sprite_atlas.as_ref());
+            screenshot_image(renderer.size(), renderer.pixels())
+        });
+
+        if scale_factor != SCREENSHOT_SCALE_FACTOR {
+            test_window.simulate_scale_factor_change(scale_factor);
+            self.run_until_parked();
+        }
+        screenshot
+    }
+
     /// Draw an element to the window. Useful for simulating events or actions
     pub fn draw(
         &mut self,
@@ -379,6 +379,15 @@ impl Hsla {
     pub fn fade_out(&mut self, factor: f32) {
         self.a *= 1.0 - factor.clamp(0., 1.);
     }
+
+    /// Returns a copy of the color with its alpha multiplied by the given factor, which
+    /// should be between 0.0 (fully transparent) and 1.0 (unchanged).
+    pub fn opacity(&self, factor: f32) -> Self {
+        Hsla {
+            a: self.a * factor.clamp(0., 1.),
+            ..*self
+        }
+    }
 }
 
 impl From<Rgba> for Hsla {
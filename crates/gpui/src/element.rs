@@ -237,6 +237,12 @@ trait ElementObject {
         cx: &mut ElementContext,
     ) -> Size<Pixels>;
 
+    fn measure_in_scratch(
+        &mut self,
+        available_space: Size<AvailableSpace>,
+        cx: &mut ElementContext,
+    ) -> Size<Pixels>;
+
     fn draw(
         &mut self,
         origin: Point<Pixels>,
@@ -312,37 +318,59 @@ impl<E: Element> DrawableElement<E> {
                 frame_state,
                 ..
             } => {
-                let bounds = cx.layout_bounds(layout_id);
-
-                if let Some(mut frame_state) = frame_state {
-                    self.element
-                        .take()
-                        .unwrap()
-                        .paint(bounds, &mut frame_state, cx);
-                    Some(frame_state)
-                } else {
-                    let element_id = self
-                        .element
-                        .as_ref()
-                        .unwrap()
-                        .element_id()
-                        .expect("if we don't have frame state, we should have element state");
-                    cx.with_element_state(element_id, |element_state, cx| {
-                        let mut element_state = element_state.unwrap();
+                // Sticky elements are moved as they're painted, so they stay in document order.
+                let sticky_offset = cx.sticky_offset(layout_id);
+                cx.with_element_offset(sticky_offset, |cx| {
+                    let bounds = cx.layout_bounds(layout_id);
+
+                    if let Some(mut frame_state) = frame_state {
                         self.element
                             .take()
                             .unwrap()
-                            .paint(bounds, &mut element_state, cx);
-                        ((), element_state)
-                    });
-                    None
-                }
+                            .paint(bounds, &mut frame_state, cx);
+                        Some(frame_state)
+                    } else {
+                        let element_id =
+                            self.element.as_ref().unwrap().element_id().expect(
+                                "if we don't have frame state, we should have element state",
+                            );
+                        cx.with_element_state(element_id, |element_state, cx| {
+                            let mut element_state = element_state.unwrap();
+                            self.element
+                                .take()
+                                .unwrap()
+                                .paint(bounds, &mut element_state, cx);
+                            ((), element_state)
+                        });
+                        None
+                    }
+                })
             }
 
             _ => panic!("must call layout before paint"),
         }
     }
 
+    fn measure_in_scratch(
+        &mut self,
+        available_space: Size<AvailableSpace>,
+        cx: &mut ElementContext,
+    ) -> Size<Pixels> {
+        if !matches!(&self.phase, ElementDrawPhase::Start) {
+            panic!("cannot measure in scratch after requesting layout");
+        }
+
+        let size = cx.with_scratch_layout(|cx| {
+            let layout_id = self.request_layout(cx);
+            cx.compute_layout(layout_id, available_space);
+            cx.layout_bounds(layout_id).size
+        });
+        // The descendants are still holding layout ids from the scratch tree, but requesting
+        // this element's layout again requests theirs as well, replacing them.
+        self.phase = ElementDrawPhase::Start;
+        size
+    }
+
     fn measure(
         &mut self,
         available_space: Size<AvailableSpace>,
@@ -419,6 +447,14 @@ where
         DrawableElement::measure(self.as_mut().unwrap(), available_space, cx)
     }
 
+    fn measure_in_scratch(
+        &mut self,
+        available_space: Size<AvailableSpace>,
+        cx: &mut ElementContext,
+    ) -> Size<Pixels> {
+        DrawableElement::measure_in_scratch(self.as_mut().unwrap(), available_space, cx)
+    }
+
     fn draw(
         &mut self,
         origin: Point<Pixels>,
@@ -464,6 +500,25 @@ impl AnyElement {
         self.0.measure(available_space, cx)
     }
 
+    /// Returns the size this element would take up within the given available space, without
+    /// affecting the layout of the frame. The element is laid out in a separate layout tree and
+    /// left as it was before, so it can still be added to the frame's layout and painted afterwards.
+    /// Element state created while measuring is discarded, and existing element state is left in
+    /// the frame it was found in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element's layout has already been requested, as it has to be measured
+    /// before it's added to the frame's layout. Measure elements before calling
+    /// [`AnyElement::request_layout`], [`AnyElement::measure`] or [`AnyElement::draw`] on them.
+    pub fn measure_in_scratch(
+        &mut self,
+        available_space: Size<AvailableSpace>,
+        cx: &mut ElementContext,
+    ) -> Size<Pixels> {
+        self.0.measure_in_scratch(available_space, cx)
+    }
+
     /// Initializes this element and performs layout in the available space, then paints it at the given origin.
     pub fn draw(
         &mut self,
@@ -547,3 +602,109 @@ impl Element for () {
     ) {
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        canvas, div, point, px, size, InteractiveElement as _, Styled as _, TestAppContext,
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    #[crate::test]
+    fn test_measure_without_painting(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let painted_bounds = Rc::new(RefCell::new(Vec::new()));
+        cx.update(|cx| {
+            let view_id = cx.window.root_view.as_ref().unwrap().entity_id();
+            cx.with_element_context(|cx| {
+                cx.with_view_id(view_id, |cx| {
+                    let mut element = div()
+                        .flex()
+                        .child(div().w(px(30.)).h(px(10.)))
+                        .child(
+                            canvas({
+                                let painted_bounds = painted_bounds.clone();
+                                move |bounds, _| painted_bounds.borrow_mut().push(*bounds)
+                            })
+                            .w(px(20.))
+                            .h(px(15.)),
+                        )
+                        .into_any();
+
+                    let max_content = size(AvailableSpace::MaxContent, AvailableSpace::MaxContent);
+                    assert_eq!(
+                        element.measure_in_scratch(max_content, cx),
+                        size(px(50.), px(15.))
+                    );
+                    assert_eq!(
+                        element.measure_in_scratch(max_content, cx),
+                        size(px(50.), px(15.))
+                    );
+                    assert!(painted_bounds.borrow().is_empty());
+
+                    element.draw(point(px(5.), px(5.)), max_content, cx);
+                })
+            })
+        });
+        assert_eq!(
+            *painted_bounds.borrow(),
+            [Bounds::new(point(px(35.), px(5.)), size(px(20.), px(15.)))]
+        );
+    }
+
+    #[crate::test]
+    fn test_measure_in_scratch_then_paint_nested_element(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let painted_bounds = Rc::new(RefCell::new(Vec::new()));
+        cx.update(|cx| {
+            let view_id = cx.window.root_view.as_ref().unwrap().entity_id();
+            cx.with_element_context(|cx| {
+                cx.with_view_id(view_id, |cx| {
+                    let mut element = div()
+                        .p(px(2.))
+                        .child(
+                            div()
+                                .id("inner")
+                                .flex()
+                                .child(div().w(px(30.)).h(px(10.)))
+                                .child(
+                                    canvas({
+                                        let painted_bounds = painted_bounds.clone();
+                                        move |bounds, _| painted_bounds.borrow_mut().push(*bounds)
+                                    })
+                                    .w(px(20.))
+                                    .h(px(15.)),
+                                ),
+                        )
+                        .into_any();
+
+                    let element_state_count = cx.window.next_frame.element_states.len();
+                    let max_content = size(AvailableSpace::MaxContent, AvailableSpace::MaxContent);
+                    assert_eq!(
+                        element.measure_in_scratch(max_content, cx),
+                        size(px(54.), px(19.))
+                    );
+                    // The state the stateful child created for measuring was discarded.
+                    assert_eq!(
+                        cx.window.next_frame.element_states.len(),
+                        element_state_count
+                    );
+
+                    element.draw(point(px(5.), px(5.)), max_content, cx);
+                    assert_eq!(
+                        cx.window.next_frame.element_states.len(),
+                        element_state_count + 1
+                    );
+                })
+            })
+        });
+        assert_eq!(
+            *painted_bounds.borrow(),
+            [Bounds::new(point(px(37.), px(7.)), size(px(20.), px(15.)))]
+        );
+    }
+}
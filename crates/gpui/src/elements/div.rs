@@ -17,11 +17,11 @@
 
 use crate::{
     point, px, size, Action, AnyDrag, AnyElement, AnyTooltip, AnyView, AppContext, Bounds,
-    ClickEvent, DispatchPhase, Element, ElementContext, ElementId, FocusHandle, IntoElement,
-    IsZero, KeyContext, KeyDownEvent, KeyUpEvent, LayoutId, MouseButton, MouseDownEvent,
-    MouseMoveEvent, MouseUpEvent, ParentElement, Pixels, Point, Render, ScrollWheelEvent,
-    SharedString, Size, StackingOrder, Style, StyleRefinement, Styled, Task, View, Visibility,
-    WindowContext,
+    ClickEvent, ContentMask, DispatchPhase, Element, ElementContext, ElementId, FocusHandle,
+    IntoElement, IsZero, KeyContext, KeyDownEvent, KeyUpEvent, LayoutId, MouseButton,
+    MouseDownEvent, MouseMoveEvent, MouseUpEvent, ParentElement, Pixels, Point, Render,
+    ScrollWheelEvent, SharedString, Size, StackingOrder, Style, StyleRefinement, Styled, Task,
+    TransformationMatrix, View, Visibility, WindowContext,
 };
 
 use collections::HashMap;
@@ -1205,19 +1205,53 @@ pub struct InteractiveBounds {
     pub bounds: Bounds<Pixels>,
     /// The 'stacking order', or depth, for this element
     pub stacking_order: StackingOrder,
+    /// The transformation that maps the bounds into the window
+    transform: TransformationMatrix,
+    /// The content mask the element was painted with, whose rounded corners also clip the bounds
+    content_mask: ContentMask<Pixels>,
 }
 
 impl InteractiveBounds {
+    /// Creates the interactive bounds of an element painted with the given bounds and transformation
+    /// at the current stacking order, clipped to the current content mask
+    pub fn new(
+        bounds: Bounds<Pixels>,
+        transform: TransformationMatrix,
+        cx: &ElementContext,
+    ) -> Self {
+        // The content mask is in window space, so map it back into the element's space to clip.
+        let content_mask = cx.content_mask();
+        let mask_bounds = transform
+            .inverse()
+            .map(|inverse| inverse.apply_to_bounds(&content_mask.bounds))
+            .unwrap_or_default();
+        Self {
+            bounds: bounds.intersect(&mask_bounds),
+            stacking_order: cx.stacking_order().clone(),
+            transform,
+            content_mask,
+        }
+    }
+
+    /// Checks whether this point in the window falls inside these bounds once they are transformed,
+    /// regardless of what was painted on top of them
+    pub fn contains(&self, point: &Point<Pixels>) -> bool {
+        self.content_mask.contains(point)
+            && self
+                .transform
+                .untransform(*point)
+                .is_some_and(|point| self.bounds.contains(&point))
+    }
+
     /// Checks whether this point was inside these bounds, and that these bounds where the topmost layer
     pub fn visibly_contains(&self, point: &Point<Pixels>, cx: &WindowContext) -> bool {
-        self.bounds.contains(point) && cx.was_top_layer(point, &self.stacking_order)
+        self.contains(point) && cx.was_top_layer(point, &self.stacking_order)
     }
 
     /// Checks whether this point was inside these bounds, and that these bounds where the topmost layer
     /// under an active drag
     pub fn drag_target_contains(&self, point: &Point<Pixels>, cx: &WindowContext) -> bool {
-        self.bounds.contains(point)
-            && cx.was_top_layer_under_active_drag(point, &self.stacking_order)
+        self.contains(point) && cx.was_top_layer_under_active_drag(point, &self.stacking_order)
     }
 }
 
@@ -1278,6 +1312,21 @@ impl Interactivity {
         f: impl FnOnce(&Style, Point<Pixels>, &mut ElementContext),
     ) {
         let style = self.compute_style(Some(bounds), element_state, cx);
+        let transform = style.transform.map(|transform| transform.to_matrix(bounds));
+        cx.with_transform(transform, |cx| {
+            self.paint_with_style(bounds, content_size, style, element_state, cx, f)
+        });
+    }
+
+    fn paint_with_style(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        content_size: Size<Pixels>,
+        style: Style,
+        element_state: &mut InteractiveElementState,
+        cx: &mut ElementContext,
+        f: impl FnOnce(&Style, Point<Pixels>, &mut ElementContext),
+    ) {
         let z_index = style.z_index.unwrap_or(0);
 
         #[cfg(any(feature = "test-support", test))]
@@ -1421,15 +1470,13 @@ impl Interactivity {
                             });
                         }
 
-                        let interactive_bounds = InteractiveBounds {
-                            bounds: bounds.intersect(&cx.content_mask().bounds),
-                            stacking_order: cx.stacking_order().clone(),
-                        };
+                        let interactive_bounds = InteractiveBounds::new(bounds, cx.transform(), cx);
 
                         if self.block_mouse
-                            || style.background.as_ref().is_some_and(|fill| {
-                                fill.color().is_some_and(|color| !color.is_transparent())
-                            })
+                            || style
+                                .background
+                                .as_ref()
+                                .is_some_and(|fill| !fill.is_transparent())
                         {
                             cx.add_opaque_layer(interactive_bounds.bounds);
                         }
@@ -1499,11 +1546,11 @@ impl Interactivity {
                             || self.base_style.mouse_cursor.is_some()
                             || cx.active_drag.is_some() && !self.drag_over_styles.is_empty()
                         {
-                            let bounds = bounds.intersect(&cx.content_mask().bounds);
-                            let hovered = bounds.contains(&cx.mouse_position());
+                            let interactive_bounds = interactive_bounds.clone();
+                            let hovered = interactive_bounds.contains(&cx.mouse_position());
                             cx.on_mouse_event(move |event: &MouseMoveEvent, phase, cx| {
                                 if phase == DispatchPhase::Capture
-                                    && bounds.contains(&event.position) != hovered
+                                    && interactive_bounds.contains(&event.position) != hovered
                                 {
                                     cx.refresh();
                                 }
@@ -1772,7 +1819,8 @@ impl Interactivity {
                             cx.on_mouse_event(move |down: &MouseDownEvent, phase, cx| {
                                 if phase == DispatchPhase::Bubble && !cx.default_prevented() {
                                     let group = active_group_bounds
-                                        .map_or(false, |bounds| bounds.contains(&down.position));
+                                        .as_ref()
+                                        .is_some_and(|bounds| bounds.contains(&down.position));
                                     let element =
                                         interactive_bounds.visibly_contains(&down.position, cx);
                                     if group || element {
@@ -1866,7 +1914,11 @@ impl Interactivity {
                         }
 
                         if let Some(group) = self.group.clone() {
-                            GroupBounds::push(group, bounds, cx);
+                            GroupBounds::push(
+                                group,
+                                InteractiveBounds::new(bounds, cx.transform(), cx),
+                                cx,
+                            );
                         }
 
                         let scroll_offset = element_state
@@ -1897,7 +1949,16 @@ impl Interactivity {
                                     cx.on_action(action_type, listener)
                                 }
 
-                                f(&style, scroll_offset.unwrap_or_default(), cx)
+                                let scroll_offset = scroll_offset.unwrap_or_default();
+                                if overflow.x == Overflow::Scroll || overflow.y == Overflow::Scroll
+                                {
+                                    let (viewport, _) = style.padding_box(bounds, cx.rem_size());
+                                    cx.with_scroll_viewport(viewport, |cx| {
+                                        f(&style, scroll_offset, cx)
+                                    })
+                                } else {
+                                    f(&style, scroll_offset, cx)
+                                }
                             },
                         );
 
@@ -1937,6 +1998,13 @@ impl Interactivity {
 
             if let Some(bounds) = bounds {
                 let mouse_position = cx.mouse_position();
+                let transform = style
+                    .transform
+                    .map_or_else(TransformationMatrix::unit, |transform| {
+                        transform.to_matrix(bounds)
+                    });
+                let interactive_bounds =
+                    InteractiveBounds::new(bounds, cx.transform().compose(transform), cx);
                 if !cx.has_active_drag() {
                     if let Some(group_hover) = self.group_hover_style.as_ref() {
                         if let Some(group_bounds) =
@@ -1951,11 +2019,7 @@ impl Interactivity {
                     }
 
                     if let Some(hover_style) = self.hover_style.as_ref() {
-                        if bounds
-                            .intersect(&cx.content_mask().bounds)
-                            .contains(&mouse_position)
-                            && cx.was_top_layer(&mouse_position, cx.stacking_order())
-                        {
+                        if interactive_bounds.visibly_contains(&mouse_position, cx) {
                             style.refine(hover_style);
                         }
                     }
@@ -1982,13 +2046,7 @@ impl Interactivity {
 
                         for (state_type, drag_over_style) in &self.drag_over_styles {
                             if *state_type == drag.value.as_ref().type_id()
-                                && bounds
-                                    .intersect(&cx.content_mask().bounds)
-                                    .contains(&mouse_position)
-                                && cx.was_top_layer_under_active_drag(
-                                    &mouse_position,
-                                    cx.stacking_order(),
-                                )
+                                && interactive_bounds.drag_target_contains(&mouse_position, cx)
                             {
                                 style.refine(drag_over_style);
                             }
@@ -2055,10 +2113,10 @@ impl ElementClickedState {
 }
 
 #[derive(Default)]
-pub(crate) struct GroupBounds(HashMap<SharedString, SmallVec<[Bounds<Pixels>; 1]>>);
+pub(crate) struct GroupBounds(HashMap<SharedString, SmallVec<[InteractiveBounds; 1]>>);
 
 impl GroupBounds {
-    pub fn get(name: &SharedString, cx: &mut AppContext) -> Option<Bounds<Pixels>> {
+    pub fn get(name: &SharedString, cx: &mut AppContext) -> Option<InteractiveBounds> {
         cx.default_global::<Self>()
             .0
             .get(name)
@@ -2066,7 +2124,7 @@ impl GroupBounds {
             .cloned()
     }
 
-    pub fn push(name: SharedString, bounds: Bounds<Pixels>, cx: &mut AppContext) {
+    pub fn push(name: SharedString, bounds: InteractiveBounds, cx: &mut AppContext) {
         cx.default_global::<Self>()
             .0
             .entry(name)
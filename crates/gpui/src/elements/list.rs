@@ -501,15 +501,21 @@ impl Element for List {
         new_items.append(cursor.suffix(&()), &());
 
         // Paint the visible items
-        cx.with_content_mask(Some(ContentMask { bounds }), |cx| {
-            let mut item_origin = bounds.origin;
-            item_origin.y -= scroll_top.offset_in_item;
-            for item_element in &mut item_elements {
-                let item_height = item_element.measure(available_item_space, cx).height;
-                item_element.draw(item_origin, available_item_space, cx);
-                item_origin.y += item_height;
-            }
-        });
+        cx.with_content_mask(
+            Some(ContentMask {
+                bounds,
+                ..Default::default()
+            }),
+            |cx| {
+                let mut item_origin = bounds.origin;
+                item_origin.y -= scroll_top.offset_in_item;
+                for item_element in &mut item_elements {
+                    let item_height = item_element.measure(available_item_space, cx).height;
+                    item_element.draw(item_origin, available_item_space, cx);
+                    item_origin.y += item_height;
+                }
+            },
+        );
 
         state.items = new_items;
         state.last_layout_bounds = Some(bounds);
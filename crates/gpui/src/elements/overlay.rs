@@ -1,16 +1,11 @@
 use smallvec::SmallVec;
-use taffy::style::{Display, Position};
+use taffy::style::Display;
 
 use crate::{
-    point, AnyElement, Bounds, Element, ElementContext, IntoElement, LayoutId, ParentElement,
-    Pixels, Point, Size, Style,
+    point, size, AnyElement, AvailableSpace, Bounds, Element, ElementContext, IntoElement,
+    ParentElement, Pixels, Point, Position, Size, Style,
 };
 
-/// The state that the overlay element uses to track its children.
-pub struct OverlayState {
-    child_layout_ids: SmallVec<[LayoutId; 4]>,
-}
-
 /// An overlay element that can be used to display UI that
 /// floats on top of other UI elements.
 pub struct Overlay {
@@ -22,7 +17,8 @@ pub struct Overlay {
 }
 
 /// overlay gives you a floating element that will avoid overflowing the window bounds.
-/// Its children should have no margin to avoid measurement issues.
+/// Its children are measured at their max-content size and laid out in a row, and should
+/// have no margin to avoid measurement issues.
 pub fn overlay() -> Overlay {
     Overlay {
         children: SmallVec::new(),
@@ -69,48 +65,45 @@ impl ParentElement for Overlay {
 }
 
 impl Element for Overlay {
-    type State = OverlayState;
+    type State = ();
 
     fn request_layout(
         &mut self,
         _: Option<Self::State>,
         cx: &mut ElementContext,
     ) -> (crate::LayoutId, Self::State) {
-        let child_layout_ids = self
-            .children
-            .iter_mut()
-            .map(|child| child.request_layout(cx))
-            .collect::<SmallVec<_>>();
-
+        // The children are laid out once the overlay has been placed, when it is painted.
         let overlay_style = Style {
             position: Position::Absolute,
             display: Display::Flex,
             ..Style::default()
         };
 
-        let layout_id = cx.request_layout(&overlay_style, child_layout_ids.iter().copied());
-
-        (layout_id, OverlayState { child_layout_ids })
+        (cx.request_layout(&overlay_style, None), ())
     }
 
     fn paint(
         &mut self,
         bounds: crate::Bounds<crate::Pixels>,
-        element_state: &mut Self::State,
+        _: &mut Self::State,
         cx: &mut ElementContext,
     ) {
-        if element_state.child_layout_ids.is_empty() {
+        if self.children.is_empty() {
             return;
         }
 
-        let mut child_min = point(Pixels::MAX, Pixels::MAX);
-        let mut child_max = Point::default();
-        for child_layout_id in &element_state.child_layout_ids {
-            let child_bounds = cx.layout_bounds(*child_layout_id);
-            child_min = child_min.min(&child_bounds.origin);
-            child_max = child_max.max(&child_bounds.lower_right());
-        }
-        let size: Size<Pixels> = (child_max - child_min).into();
+        let available_space = size(AvailableSpace::MaxContent, AvailableSpace::MaxContent);
+        let child_sizes = self
+            .children
+            .iter_mut()
+            .map(|child| child.measure_in_scratch(available_space, cx))
+            .collect::<SmallVec<[_; 2]>>();
+        let size = child_sizes
+            .iter()
+            .fold(Size::<Pixels>::default(), |size, child_size| Size {
+                width: size.width + child_size.width,
+                height: size.height.max(child_size.height),
+            });
 
         let (origin, mut desired) = self.position_mode.get_position_and_bounds(
             self.anchor_position,
@@ -165,14 +158,12 @@ impl Element for Overlay {
             desired.origin.y = limits.origin.y;
         }
 
-        let mut offset = cx.element_offset() + desired.origin - bounds.origin;
-        offset = point(offset.x.round(), offset.y.round());
-        cx.with_absolute_element_offset(offset, |cx| {
-            cx.break_content_mask(|cx| {
-                for child in &mut self.children {
-                    child.paint(cx);
-                }
-            })
+        let mut child_origin = point(desired.origin.x.round(), desired.origin.y.round());
+        cx.break_content_mask(|cx| {
+            for (child, child_size) in self.children.iter_mut().zip(child_sizes) {
+                child.draw(child_origin, available_space, cx);
+                child_origin.x += child_size.width;
+            }
         })
     }
 }
@@ -296,3 +287,56 @@ impl AnchorCorner {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{canvas, px, Styled as _, TestAppContext};
+    use std::{cell::RefCell, rc::Rc};
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    #[crate::test]
+    fn test_overlay_switches_anchor_to_fit_measured_children(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let painted_bounds = Rc::new(RefCell::new(Vec::new()));
+        let viewport_width = cx.update(|cx| {
+            let view_id = cx.window.root_view.as_ref().unwrap().entity_id();
+            cx.with_element_context(|cx| {
+                cx.with_view_id(view_id, |cx| {
+                    let viewport_width = cx.viewport_size().width;
+                    let child = |width, height| {
+                        let painted_bounds = painted_bounds.clone();
+                        canvas(move |bounds, _| painted_bounds.borrow_mut().push(*bounds))
+                            .w(px(width))
+                            .h(px(height))
+                    };
+                    // Anchored by its top left corner, the overlay would overflow the window.
+                    let mut element = overlay()
+                        .position(point(viewport_width - px(10.), px(10.)))
+                        .child(child(40., 20.))
+                        .child(child(30., 25.))
+                        .into_any();
+
+                    let max_content = size(AvailableSpace::MaxContent, AvailableSpace::MaxContent);
+                    element.draw(Point::default(), max_content, cx);
+                    viewport_width
+                })
+            })
+        });
+        assert_eq!(
+            *painted_bounds.borrow(),
+            [
+                Bounds::new(
+                    point(viewport_width - px(80.), px(10.)),
+                    size(px(40.), px(20.))
+                ),
+                Bounds::new(
+                    point(viewport_width - px(40.), px(10.)),
+                    size(px(30.), px(25.))
+                ),
+            ]
+        );
+    }
+}
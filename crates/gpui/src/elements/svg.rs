@@ -8,6 +8,17 @@ use util::ResultExt;
 pub struct Svg {
     interactivity: Interactivity,
     path: Option<SharedString>,
+    color_mode: SvgColorMode,
+}
+
+/// How the pixels of an SVG element are colored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SvgColorMode {
+    /// Only the shape of the SVG is used, filled with the element's text color.
+    #[default]
+    Monochrome,
+    /// The SVG's own fills, strokes and gradients are painted.
+    FullColor,
 }
 
 /// Create a new SVG element.
@@ -15,6 +26,7 @@ pub fn svg() -> Svg {
     Svg {
         interactivity: Interactivity::default(),
         path: None,
+        color_mode: SvgColorMode::default(),
     }
 }
 
@@ -24,6 +36,13 @@ impl Svg {
         self.path = Some(path.into());
         self
     }
+
+    /// Set how the SVG is colored. Monochrome SVGs are tinted with the text color, and
+    /// full color ones keep their own.
+    pub fn color_mode(mut self, color_mode: SvgColorMode) -> Self {
+        self.color_mode = color_mode;
+        self
+    }
 }
 
 impl Element for Svg {
@@ -49,8 +68,18 @@ impl Element for Svg {
     {
         self.interactivity
             .paint(bounds, bounds.size, element_state, cx, |style, _, cx| {
-                if let Some((path, color)) = self.path.as_ref().zip(style.text.color) {
-                    cx.paint_svg(bounds, path.clone(), color).log_err();
+                let Some(path) = self.path.as_ref() else {
+                    return;
+                };
+                match self.color_mode {
+                    SvgColorMode::Monochrome => {
+                        if let Some(color) = style.text.color {
+                            cx.paint_svg(bounds, path.clone(), color).log_err();
+                        }
+                    }
+                    SvgColorMode::FullColor => {
+                        cx.paint_full_color_svg(bounds, path.clone()).log_err();
+                    }
                 }
             })
     }
@@ -146,7 +146,12 @@ impl Element for StyledText {
         cx: &mut ElementContext,
     ) -> (LayoutId, Self::State) {
         let mut state = TextState::default();
-        let layout_id = state.layout(self.text.clone(), self.runs.take(), cx);
+        let runs = if cx.is_measuring_in_scratch() {
+            self.runs.clone()
+        } else {
+            self.runs.take()
+        };
+        let layout_id = state.layout(self.text.clone(), runs, cx);
         (layout_id, state)
     }
 
@@ -201,11 +206,21 @@ impl TextState {
             vec![text_style.to_run(text.len())]
         };
 
-        let layout_id = cx.request_measured_layout(Default::default(), {
+        let wraps = text_style.white_space == WhiteSpace::Normal;
+        let measure_inputs = (
+            text.clone(),
+            font_size,
+            line_height,
+            wraps,
+            runs.iter()
+                .map(|run| (run.len, run.font.clone()))
+                .collect::<SmallVec<[_; 4]>>(),
+        );
+        let layout_id = cx.request_cached_measured_layout(Default::default(), measure_inputs, {
             let element_state = self.clone();
 
             move |known_dimensions, available_space, cx| {
-                let wrap_width = if text_style.white_space == WhiteSpace::Normal {
+                let wrap_width = if wraps {
                     known_dimensions.width.or(match available_space.width {
                         crate::AvailableSpace::Definite(x) => Some(x),
                         _ => None,
@@ -121,8 +121,9 @@ impl Element for UniformList {
         let (layout_id, interactive) =
             self.interactivity
                 .layout(state.map(|s| s.interactive), cx, |style, cx| {
-                    cx.request_measured_layout(
+                    cx.request_cached_measured_layout(
                         style,
+                        (item_size, max_items),
                         move |known_dimensions, available_space, _cx| {
                             let desired_height = item_size.height * max_items;
                             let width =
@@ -239,7 +240,10 @@ impl Element for UniformList {
 
                     let mut items = (self.render_items)(visible_range.clone(), cx);
                     cx.with_z_index(1, |cx| {
-                        let content_mask = ContentMask { bounds };
+                        let content_mask = ContentMask {
+                            bounds,
+                            ..Default::default()
+                        };
                         cx.with_content_mask(Some(content_mask), |cx| {
                             for (item, ix) in items.iter_mut().zip(visible_range) {
                                 let item_origin = padded_bounds.origin
@@ -294,7 +298,7 @@ impl UniformList {
             }),
             AvailableSpace::MinContent,
         );
-        item_to_measure.measure(available_space, cx)
+        item_to_measure.measure_in_scratch(available_space, cx)
     }
 
     /// Track and render scroll state of this list with reference to the given scroll handle.
@@ -13,7 +13,7 @@ use std::{
         Arc,
     },
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use util::TryFutureExt;
 use waker_fn::waker_fn;
@@ -321,6 +321,12 @@ impl BackgroundExecutor {
         self.dispatcher.as_test().unwrap().deprioritize(task_label)
     }
 
+    /// The current time, according to the platform. In tests, time only moves forward
+    /// when the clock is advanced.
+    pub fn now(&self) -> Instant {
+        self.dispatcher.now()
+    }
+
     /// in tests, move time forward. This does not run any tasks, but does make `timer`s ready.
     #[cfg(any(test, feature = "test-support"))]
     pub fn advance_clock(&self, duration: Duration) {
@@ -2,14 +2,21 @@
 //! can be used to describe common units, concepts, and the relationships
 //! between them.
 
+use collections::FxHashMap;
 use core::fmt::Debug;
 use derive_more::{Add, AddAssign, Div, DivAssign, Mul, Neg, Sub, SubAssign};
+use parking_lot::Mutex;
 use refineable::Refineable;
 use serde_derive::{Deserialize, Serialize};
+use smallvec::SmallVec;
 use std::{
     cmp::{self, PartialOrd},
+    collections::hash_map::DefaultHasher,
     fmt,
-    ops::{Add, Div, Mul, MulAssign, Sub},
+    hash::{Hash, Hasher},
+    mem,
+    ops::{Add, Div, Mul, MulAssign, Neg, Sub},
+    ptr,
 };
 
 /// An axis along which a measurement can be made.
@@ -55,7 +62,21 @@ pub trait Along {
 /// let point = Point { x: 10, y: 20 };
 /// println!("{:?}", point); // Outputs: Point { x: 10, y: 20 }
 /// ```
-#[derive(Refineable, Default, Add, AddAssign, Sub, SubAssign, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(
+    Refineable,
+    Default,
+    Add,
+    AddAssign,
+    Sub,
+    SubAssign,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
 #[refineable(Debug)]
 #[repr(C)]
 pub struct Point<T: Default + Clone + Debug> {
@@ -2412,6 +2433,13 @@ pub enum DefiniteLength {
     Absolute(AbsoluteLength),
     /// A relative length specified as a fraction of the parent's size, between 0 and 1.
     Fraction(f32),
+    /// A sum of absolute and relative lengths, like the CSS `calc(100% - 2rem)`, built by adding
+    /// lengths to or subtracting them from one another.
+    Calc(CalcLength),
+    /// A length that picks between other lengths, like the CSS `min()`, `max()` and `clamp()`,
+    /// built with [`DefiniteLength::min`], [`DefiniteLength::max`] and [`DefiniteLength::clamp`].
+    /// Expressions are interned, so that lengths stay small and `Copy`.
+    Expression(&'static LengthExpression),
 }
 
 impl DefiniteLength {
@@ -2419,6 +2447,7 @@ impl DefiniteLength {
     ///
     /// If the `DefiniteLength` is an absolute length, it will be directly converted to `Pixels`.
     /// If it is a fraction, the fraction will be multiplied by the `base_size` to get the length in pixels.
+    /// Sums and expressions convert each of their parts this way, then add them or pick between them.
     ///
     /// # Arguments
     ///
@@ -2432,7 +2461,7 @@ impl DefiniteLength {
     /// # Examples
     ///
     /// ```
-    /// # use zed::{DefiniteLength, AbsoluteLength, Pixels, px, rems};
+    /// # use zed::{DefiniteLength, AbsoluteLength, Pixels, px, relative, rems};
     /// let length_in_pixels = DefiniteLength::Absolute(AbsoluteLength::Pixels(px(42.0)));
     /// let length_in_rems = DefiniteLength::Absolute(AbsoluteLength::Rems(rems(2.0)));
     /// let length_as_fraction = DefiniteLength::Fraction(0.5);
@@ -2442,6 +2471,10 @@ impl DefiniteLength {
     /// assert_eq!(length_in_pixels.to_pixels(base_size, rem_size), Pixels(42.0));
     /// assert_eq!(length_in_rems.to_pixels(base_size, rem_size), Pixels(32.0));
     /// assert_eq!(length_as_fraction.to_pixels(base_size, rem_size), Pixels(50.0));
+    ///
+    /// let clamped = DefiniteLength::clamp(px(20.0), relative(0.3), px(40.0));
+    /// assert_eq!((relative(1.0) - rems(2.0)).to_pixels(base_size, rem_size), Pixels(68.0));
+    /// assert_eq!(clamped.to_pixels(base_size, rem_size), Pixels(30.0));
     /// ```
     pub fn to_pixels(&self, base_size: AbsoluteLength, rem_size: Pixels) -> Pixels {
         match self {
@@ -2450,6 +2483,112 @@ impl DefiniteLength {
                 AbsoluteLength::Pixels(px) => px * *fraction,
                 AbsoluteLength::Rems(rems) => rems * rem_size * *fraction,
             },
+            DefiniteLength::Calc(calc) => calc.to_pixels(base_size.to_pixels(rem_size), rem_size),
+            DefiniteLength::Expression(expression) => {
+                expression.to_pixels(base_size.to_pixels(rem_size), rem_size)
+            }
+        }
+    }
+
+    /// Reduces this length to a single absolute length or fraction of the parent's size, which
+    /// is all taffy can lay out, given the size of the parent when it's known.
+    ///
+    /// Expressions that mix the two need the parent's size. When it isn't known, fractions of it
+    /// count as zero, as they do in taffy for margins and padding: `calc(100% - 2rem)` resolves
+    /// to `-2rem`, and `max(50%, 200px)` to `200px`.
+    pub(crate) fn resolve(&self, base_size: Option<Pixels>, rem_size: Pixels) -> DefiniteLength {
+        match self {
+            DefiniteLength::Absolute(_) | DefiniteLength::Fraction(_) => *self,
+            _ if !self.has_fraction() => self.base_pixels(Pixels::ZERO, rem_size).into(),
+            // Every part of the expression scales with the parent's size, so the whole does.
+            _ if !self.has_absolute() => {
+                DefiniteLength::Fraction(self.base_pixels(px(1.), rem_size).0)
+            }
+            _ => self
+                .base_pixels(base_size.unwrap_or(Pixels::ZERO), rem_size)
+                .into(),
+        }
+    }
+
+    /// Whether this length resolves differently depending on the size of the parent, beyond
+    /// what taffy can express with a fraction of it.
+    pub(crate) fn depends_on_base_size(&self) -> bool {
+        self.has_fraction() && self.has_absolute()
+    }
+
+    fn base_pixels(&self, base_size: Pixels, rem_size: Pixels) -> Pixels {
+        self.to_pixels(AbsoluteLength::Pixels(base_size), rem_size)
+    }
+
+    fn has_fraction(&self) -> bool {
+        match self {
+            DefiniteLength::Absolute(_) => false,
+            DefiniteLength::Fraction(_) => true,
+            DefiniteLength::Calc(calc) => !calc.is_absolute(),
+            DefiniteLength::Expression(expression) => expression
+                .operands()
+                .iter()
+                .any(|operand| operand.has_fraction()),
+        }
+    }
+
+    fn has_absolute(&self) -> bool {
+        match self {
+            DefiniteLength::Absolute(_) => true,
+            DefiniteLength::Fraction(_) => false,
+            DefiniteLength::Calc(calc) => !calc.is_relative(),
+            DefiniteLength::Expression(expression) => expression
+                .operands()
+                .iter()
+                .any(|operand| operand.has_absolute()),
+        }
+    }
+
+    /// Constructs a length that is the smaller of two lengths, like the CSS `min(50%, 400px)`.
+    pub fn min(a: impl Into<DefiniteLength>, b: impl Into<DefiniteLength>) -> DefiniteLength {
+        DefiniteLength::Expression(LengthExpression::Min(a.into(), b.into()).intern())
+    }
+
+    /// Constructs a length that is the larger of two lengths, like the CSS `max(50%, 200px)`.
+    pub fn max(a: impl Into<DefiniteLength>, b: impl Into<DefiniteLength>) -> DefiniteLength {
+        DefiniteLength::Expression(LengthExpression::Max(a.into(), b.into()).intern())
+    }
+
+    /// Constructs a length that is `preferred`, kept between `min` and `max`, like the CSS
+    /// `clamp(200px, 30%, 600px)`. If `min` is larger than `max`, `min` wins.
+    pub fn clamp(
+        min: impl Into<DefiniteLength>,
+        preferred: impl Into<DefiniteLength>,
+        max: impl Into<DefiniteLength>,
+    ) -> DefiniteLength {
+        Self::max(min, Self::min(preferred, max))
+    }
+
+    /// The sum this length is made of, unless it picks between lengths.
+    fn linear(&self) -> Option<CalcLength> {
+        match self {
+            DefiniteLength::Absolute(AbsoluteLength::Pixels(pixels)) => Some((*pixels).into()),
+            DefiniteLength::Absolute(AbsoluteLength::Rems(rems)) => Some((*rems).into()),
+            DefiniteLength::Fraction(fraction) => Some(CalcLength {
+                fraction: *fraction,
+                ..Default::default()
+            }),
+            DefiniteLength::Calc(calc) => Some(*calc),
+            DefiniteLength::Expression(_) => None,
+        }
+    }
+
+    /// Feeds this length to the given hasher, for interning expressions made of it.
+    fn hash_bits(&self, state: &mut impl Hasher) {
+        match (self.linear(), self) {
+            (Some(calc), _) => {
+                calc.pixels.hash(state);
+                calc.rems.0.to_bits().hash(state);
+                calc.fraction.to_bits().hash(state);
+            }
+            // Expressions are interned, so equal ones are the same.
+            (None, DefiniteLength::Expression(expression)) => ptr::hash(*expression, state),
+            (None, _) => {}
         }
     }
 }
@@ -2459,6 +2598,8 @@ impl Debug for DefiniteLength {
         match self {
             DefiniteLength::Absolute(length) => Debug::fmt(length, f),
             DefiniteLength::Fraction(fract) => write!(f, "{}%", (fract * 100.0) as i32),
+            DefiniteLength::Calc(calc) => Debug::fmt(calc, f),
+            DefiniteLength::Expression(expression) => Debug::fmt(expression, f),
         }
     }
 }
@@ -2487,6 +2628,173 @@ impl Default for DefiniteLength {
     }
 }
 
+impl<T: Into<DefiniteLength>> Add<T> for DefiniteLength {
+    type Output = DefiniteLength;
+
+    /// Adds two lengths, like the CSS `calc(a + b)`.
+    fn add(self, other: T) -> DefiniteLength {
+        let other = other.into();
+        match (self.linear(), other.linear()) {
+            (Some(a), Some(b)) => DefiniteLength::Calc(a + b),
+            _ => DefiniteLength::Expression(LengthExpression::Sum(self, other).intern()),
+        }
+    }
+}
+
+impl<T: Into<DefiniteLength>> Sub<T> for DefiniteLength {
+    type Output = DefiniteLength;
+
+    /// Subtracts a length from another, like the CSS `calc(a - b)`.
+    fn sub(self, other: T) -> DefiniteLength {
+        self + -other.into()
+    }
+}
+
+/// A sum of a length in pixels, a length in rems and a fraction of the parent's size.
+///
+/// Built by adding and subtracting [`DefiniteLength`]s, e.g. `relative(1.) - rems(2.)` for the
+/// CSS `calc(100% - 2rem)`.
+#[derive(Clone, Copy, Default, Add, Sub, Neg, PartialEq)]
+pub struct CalcLength {
+    /// The part of the length in pixels.
+    pub pixels: Pixels,
+    /// The part of the length in rems.
+    pub rems: Rems,
+    /// The part of the length that is a fraction of the parent's size.
+    pub fraction: f32,
+}
+
+impl CalcLength {
+    /// Returns the sum of the absolute parts of this length, in pixels.
+    pub fn absolute(&self, rem_size: Pixels) -> Pixels {
+        self.pixels + self.rems * rem_size
+    }
+
+    /// Converts this length to pixels, given the size of the parent its fraction is of.
+    pub fn to_pixels(&self, base_size: Pixels, rem_size: Pixels) -> Pixels {
+        self.absolute(rem_size) + base_size * self.fraction
+    }
+
+    fn is_absolute(&self) -> bool {
+        self.fraction == 0.
+    }
+
+    fn is_relative(&self) -> bool {
+        self.pixels.is_zero() && self.rems.is_zero()
+    }
+}
+
+impl Debug for CalcLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "calc({}% + {:?} + {:?})",
+            self.fraction * 100.,
+            self.pixels,
+            self.rems
+        )
+    }
+}
+
+impl From<Pixels> for CalcLength {
+    fn from(pixels: Pixels) -> Self {
+        Self {
+            pixels,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Rems> for CalcLength {
+    fn from(rems: Rems) -> Self {
+        Self {
+            rems,
+            ..Default::default()
+        }
+    }
+}
+
+/// A length that picks between other lengths depending on the size of the parent, and so can't
+/// be reduced to a [`CalcLength`]. Its operands can be expressions themselves, like in the CSS
+/// `min(max(50%, 200px), 100% - 2rem)`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum LengthExpression {
+    /// The sum of two lengths.
+    Sum(DefiniteLength, DefiniteLength),
+    /// The smaller of two lengths.
+    Min(DefiniteLength, DefiniteLength),
+    /// The larger of two lengths.
+    Max(DefiniteLength, DefiniteLength),
+}
+
+impl LengthExpression {
+    /// Converts this length to pixels, given the size of the parent its fractions are of.
+    pub fn to_pixels(&self, base_size: Pixels, rem_size: Pixels) -> Pixels {
+        let [a, b] = self
+            .operands()
+            .map(|operand| operand.base_pixels(base_size, rem_size));
+        match self {
+            LengthExpression::Sum(..) => a + b,
+            LengthExpression::Min(..) => a.min(b),
+            LengthExpression::Max(..) => a.max(b),
+        }
+    }
+
+    fn operands(&self) -> [&DefiniteLength; 2] {
+        match self {
+            LengthExpression::Sum(a, b)
+            | LengthExpression::Min(a, b)
+            | LengthExpression::Max(a, b) => [a, b],
+        }
+    }
+
+    /// Returns this expression, allocated for the rest of the program. Each distinct expression
+    /// is only allocated once, however many times it's built, as styles are every frame.
+    fn intern(self) -> &'static LengthExpression {
+        lazy_static::lazy_static! {
+            static ref EXPRESSIONS: Mutex<FxHashMap<u64, SmallVec<[&'static LengthExpression; 1]>>> =
+                Mutex::default();
+        }
+
+        let mut hasher = DefaultHasher::new();
+        mem::discriminant(&self).hash(&mut hasher);
+        for operand in self.operands() {
+            operand.hash_bits(&mut hasher);
+        }
+        let mut expressions = EXPRESSIONS.lock();
+        let interned = expressions.entry(hasher.finish()).or_default();
+        if let Some(expression) = interned.iter().find(|expression| ***expression == self) {
+            return expression;
+        }
+        let expression = Box::leak(Box::new(self));
+        interned.push(expression);
+        expression
+    }
+}
+
+impl Neg for &'static LengthExpression {
+    type Output = &'static LengthExpression;
+
+    fn neg(self) -> &'static LengthExpression {
+        match *self {
+            LengthExpression::Sum(a, b) => LengthExpression::Sum(-a, -b),
+            LengthExpression::Min(a, b) => LengthExpression::Max(-a, -b),
+            LengthExpression::Max(a, b) => LengthExpression::Min(-a, -b),
+        }
+        .intern()
+    }
+}
+
+impl Debug for LengthExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LengthExpression::Sum(a, b) => write!(f, "calc({:?} + {:?})", a, b),
+            LengthExpression::Min(a, b) => write!(f, "min({:?}, {:?})", a, b),
+            LengthExpression::Max(a, b) => write!(f, "max({:?}, {:?})", a, b),
+        }
+    }
+}
+
 /// A length that can be defined in pixels, rems, percent of parent, or auto.
 #[derive(Clone, Copy)]
 pub enum Length {
@@ -2496,6 +2804,25 @@ pub enum Length {
     Auto,
 }
 
+impl Length {
+    /// Reduces this length to one taffy can lay out, given the size of the parent when it's known.
+    pub(crate) fn resolve(&self, base_size: Option<Pixels>, rem_size: Pixels) -> Length {
+        match self {
+            Length::Definite(length) => Length::Definite(length.resolve(base_size, rem_size)),
+            Length::Auto => Length::Auto,
+        }
+    }
+
+    /// Whether this length resolves differently depending on the size of the parent, beyond
+    /// what taffy can express with a fraction of it.
+    pub(crate) fn depends_on_base_size(&self) -> bool {
+        match self {
+            Length::Definite(length) => length.depends_on_base_size(),
+            Length::Auto => false,
+        }
+    }
+}
+
 impl Debug for Length {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -2706,10 +3033,21 @@ impl IsZero for DefiniteLength {
         match self {
             DefiniteLength::Absolute(length) => length.is_zero(),
             DefiniteLength::Fraction(fraction) => *fraction == 0.,
+            DefiniteLength::Calc(calc) => calc.is_zero(),
+            DefiniteLength::Expression(expression) => expression
+                .operands()
+                .iter()
+                .all(|operand| operand.is_zero()),
         }
     }
 }
 
+impl IsZero for CalcLength {
+    fn is_zero(&self) -> bool {
+        self.pixels.is_zero() && self.rems.is_zero() && self.fraction == 0.
+    }
+}
+
 impl IsZero for Length {
     fn is_zero(&self) -> bool {
         match self {
@@ -2789,4 +3127,96 @@ mod tests {
         // Test Case 3: Bounds intersecting with themselves
         assert_eq!(bounds1.intersects(&bounds1), true);
     }
+
+    #[test]
+    fn test_length_expressions() {
+        let rem_size = px(16.);
+        let base_size = AbsoluteLength::Pixels(px(1000.));
+
+        let calc = relative(1.) - rems(2.);
+        assert_eq!(calc.to_pixels(base_size, rem_size), px(968.));
+        assert_eq!(calc.resolve(Some(px(500.)), rem_size), px(468.).into());
+
+        let panel = DefiniteLength::clamp(px(200.), relative(0.3), px(600.));
+        assert_eq!(panel.to_pixels(base_size, rem_size), px(300.));
+        assert_eq!(panel.to_pixels(px(500.).into(), rem_size), px(200.));
+        assert_eq!(panel.to_pixels(px(3000.).into(), rem_size), px(600.));
+        assert!(panel.depends_on_base_size());
+
+        assert_eq!(
+            DefiniteLength::min(relative(0.5), px(400.)).to_pixels(base_size, rem_size),
+            px(400.)
+        );
+        assert_eq!(
+            DefiniteLength::max(relative(0.5), px(400.)).to_pixels(base_size, rem_size),
+            px(500.)
+        );
+
+        // Expressions made of a single kind of length resolve without the parent's size.
+        let absolute = DefiniteLength::min(px(300.), rems(10.)) + px(20.);
+        assert!(!absolute.depends_on_base_size());
+        assert_eq!(absolute.resolve(None, rem_size), px(180.).into());
+        assert_eq!(
+            DefiniteLength::min(relative(0.5), relative(0.25)).resolve(None, rem_size),
+            relative(0.25)
+        );
+
+        // Negating a clamped length swaps its bounds.
+        assert_eq!((-panel).to_pixels(base_size, rem_size), px(-300.));
+        assert_eq!((-panel).to_pixels(px(3000.).into(), rem_size), px(-600.));
+
+        // Expressions nest, and can be added to one another.
+        let nested = DefiniteLength::min(
+            DefiniteLength::max(relative(0.5), px(200.)),
+            relative(1.) - rems(2.),
+        );
+        assert_eq!(nested.to_pixels(base_size, rem_size), px(500.));
+        assert_eq!(nested.to_pixels(px(220.).into(), rem_size), px(188.));
+        let clamped = DefiniteLength::clamp(
+            px(100.),
+            relative(0.3),
+            DefiniteLength::min(relative(0.5), px(250.)),
+        );
+        assert_eq!(clamped.to_pixels(base_size, rem_size), px(250.));
+        assert_eq!(clamped.to_pixels(px(200.).into(), rem_size), px(100.));
+        let sum = DefiniteLength::min(relative(0.5), px(100.))
+            + DefiniteLength::max(relative(0.25), px(50.));
+        assert_eq!(sum.to_pixels(base_size, rem_size), px(350.));
+        assert_eq!(sum.to_pixels(px(100.).into(), rem_size), px(100.));
+        assert_eq!(
+            (relative(1.) - sum).to_pixels(base_size, rem_size),
+            px(650.)
+        );
+
+        // Without the parent's size, fractions of it count as zero.
+        assert_eq!(calc.resolve(None, rem_size), px(-32.).into());
+        assert_eq!(
+            DefiniteLength::max(relative(0.5), px(200.)).resolve(None, rem_size),
+            px(200.).into()
+        );
+        assert_eq!(nested.resolve(None, rem_size), px(-32.).into());
+    }
+
+    #[test]
+    fn test_length_expressions_are_interned() {
+        let expression = |length| match length {
+            DefiniteLength::Expression(expression) => expression,
+            length => panic!("{length:?} isn't an expression"),
+        };
+
+        let a = expression(DefiniteLength::clamp(px(200.), relative(0.3), px(600.)));
+        let b = expression(DefiniteLength::clamp(px(200.), relative(0.3), px(600.)));
+        assert!(ptr::eq(a, b));
+        assert!(!ptr::eq(
+            a,
+            expression(DefiniteLength::clamp(px(200.), relative(0.4), px(600.)))
+        ));
+        assert!(ptr::eq(expression(-(-DefiniteLength::Expression(a))), a));
+    }
+
+    #[test]
+    fn test_lengths_stay_small() {
+        assert_eq!(std::mem::size_of::<DefiniteLength>(), 16);
+        assert_eq!(std::mem::size_of::<Length>(), 16);
+    }
 }
@@ -79,6 +79,7 @@ mod input;
 mod interactive;
 mod key_dispatch;
 mod keymap;
+mod path_builder;
 mod platform;
 pub mod prelude;
 mod scene;
@@ -128,6 +129,7 @@ pub use input::*;
 pub use interactive::*;
 use key_dispatch::*;
 pub use keymap::*;
+pub use path_builder::*;
 pub use platform::*;
 pub use refineable::*;
 pub use scene::*;
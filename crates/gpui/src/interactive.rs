@@ -1,6 +1,7 @@
 use crate::{
     point, seal::Sealed, IntoElement, Keystroke, Modifiers, Pixels, Point, Render, ViewContext,
 };
+use serde_derive::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::{any::Any, fmt::Debug, ops::Deref, path::PathBuf};
 
@@ -17,7 +18,7 @@ pub trait KeyEvent: InputEvent {}
 pub trait MouseEvent: InputEvent {}
 
 /// The key down event equivalent for the platform.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct KeyDownEvent {
     /// The keystroke that was generated.
     pub keystroke: Keystroke,
@@ -35,7 +36,7 @@ impl InputEvent for KeyDownEvent {
 impl KeyEvent for KeyDownEvent {}
 
 /// The key up event equivalent for the platform.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyUpEvent {
     /// The keystroke that was released.
     pub keystroke: Keystroke,
@@ -50,7 +51,7 @@ impl InputEvent for KeyUpEvent {
 impl KeyEvent for KeyUpEvent {}
 
 /// The modifiers changed event equivalent for the platform.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ModifiersChangedEvent {
     /// The new state of the modifier keys
     pub modifiers: Modifiers,
@@ -74,7 +75,7 @@ impl Deref for ModifiersChangedEvent {
 
 /// The phase of a touch motion event.
 /// Based on the winit enum of the same name.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
 pub enum TouchPhase {
     /// The touch started.
     Started,
@@ -86,7 +87,7 @@ pub enum TouchPhase {
 }
 
 /// A mouse down event from the platform
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MouseDownEvent {
     /// Which mouse button was pressed.
     pub button: MouseButton,
@@ -110,7 +111,7 @@ impl InputEvent for MouseDownEvent {
 impl MouseEvent for MouseDownEvent {}
 
 /// A mouse up event from the platform
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MouseUpEvent {
     /// Which mouse button was released.
     pub button: MouseButton,
@@ -144,7 +145,7 @@ pub struct ClickEvent {
 }
 
 /// An enum representing the mouse button that was pressed.
-#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum MouseButton {
     /// The left mouse button.
     Left,
@@ -179,7 +180,7 @@ impl Default for MouseButton {
 }
 
 /// A navigation direction, such as back or forward.
-#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum NavigationDirection {
     /// The back button.
     Back,
@@ -195,7 +196,7 @@ impl Default for NavigationDirection {
 }
 
 /// A mouse move event from the platform
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MouseMoveEvent {
     /// The position of the mouse on the window.
     pub position: Point<Pixels>,
@@ -223,7 +224,7 @@ impl MouseMoveEvent {
 }
 
 /// A mouse wheel event from the platform
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ScrollWheelEvent {
     /// The position of the mouse on the window.
     pub position: Point<Pixels>,
@@ -255,7 +256,7 @@ impl Deref for ScrollWheelEvent {
 }
 
 /// The scroll delta for a scroll wheel event.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum ScrollDelta {
     /// An exact scroll delta in pixels.
     Pixels(Point<Pixels>),
@@ -304,7 +305,7 @@ impl ScrollDelta {
 
 /// A mouse exit event from the platform, generated when the mouse leaves the window.
 /// The position generated should be just outside of the window's bounds.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct MouseExitEvent {
     /// The position of the mouse relative to the window.
     pub position: Point<Pixels>,
@@ -331,7 +332,8 @@ impl Deref for MouseExitEvent {
 }
 
 /// A collection of paths from the platform, such as from a file drop.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<PathBuf>", into = "Vec<PathBuf>")]
 pub struct ExternalPaths(pub(crate) SmallVec<[PathBuf; 2]>);
 
 impl ExternalPaths {
@@ -341,6 +343,18 @@ impl ExternalPaths {
     }
 }
 
+impl From<Vec<PathBuf>> for ExternalPaths {
+    fn from(paths: Vec<PathBuf>) -> Self {
+        Self(paths.into())
+    }
+}
+
+impl From<ExternalPaths> for Vec<PathBuf> {
+    fn from(paths: ExternalPaths) -> Self {
+        paths.0.into_vec()
+    }
+}
+
 impl Render for ExternalPaths {
     fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
         // Intentionally left empty because the platform will render icons for the dragged files
@@ -348,7 +362,7 @@ impl Render for ExternalPaths {
 }
 
 /// A file drop event from the platform, generated when files are dragged and dropped onto the window.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FileDropEvent {
     /// The files have entered the window.
     Entered {
@@ -380,7 +394,7 @@ impl InputEvent for FileDropEvent {
 impl MouseEvent for FileDropEvent {}
 
 /// An enum corresponding to all kinds of platform input events.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum PlatformInput {
     /// A key was pressed.
     KeyDown(KeyDownEvent),
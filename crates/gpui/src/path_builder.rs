@@ -0,0 +1,838 @@
+use crate::{point, px, Path, PathCoverage, Pixels, Point};
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// The furthest, in pixels, that the straight lines and quadratic curves used to
+/// approximate other curves are allowed to stray from them.
+const TOLERANCE: f32 = 0.05;
+
+/// The most pieces a single curve is split into when it's approximated.
+const MAX_CURVE_PIECES: usize = 256;
+
+/// How the ends of open subpaths and dashes are drawn when they're stroked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineCap {
+    /// The stroke stops exactly at the end.
+    #[default]
+    Butt,
+    /// The stroke ends in a half circle centered on the end.
+    Round,
+    /// The stroke extends past the end by half its width.
+    Square,
+}
+
+/// How the corners between the segments of a subpath are drawn when it's stroked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineJoin {
+    /// The outer edges of the segments are extended until they meet, unless that
+    /// would exceed the stroke's miter limit, in which case the corner is beveled.
+    #[default]
+    Miter,
+    /// The corner is rounded off with a circle as wide as the stroke.
+    Round,
+    /// The corner is cut off by a straight line between the outer edges of the segments.
+    Bevel,
+}
+
+/// The width, joins, caps and dashes used to stroke a [`PathBuilder`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrokeStyle {
+    /// The width of the stroke, which is centered on the path.
+    pub width: Pixels,
+    /// How the ends of open subpaths and dashes are drawn.
+    pub cap: LineCap,
+    /// How the corners between segments are drawn.
+    pub join: LineJoin,
+    /// How long a miter join can be, as a multiple of the stroke's width, before it's
+    /// drawn as a bevel instead.
+    pub miter_limit: f32,
+    /// The lengths of alternating dashes and gaps, starting with a dash. The path is
+    /// stroked without gaps when this is empty.
+    pub dashes: Vec<Pixels>,
+    /// How far into the dash pattern the stroke starts.
+    pub dash_offset: Pixels,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        Self {
+            width: px(1.),
+            cap: LineCap::default(),
+            join: LineJoin::default(),
+            miter_limit: 4.,
+            dashes: Vec::new(),
+            dash_offset: px(0.),
+        }
+    }
+}
+
+impl StrokeStyle {
+    /// A stroke of the given width, with miter joins, butt caps and no dashes.
+    pub fn new(width: Pixels) -> Self {
+        Self {
+            width,
+            ..Default::default()
+        }
+    }
+
+    /// Set how the ends of open subpaths and dashes are drawn.
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    /// Set how the corners between segments are drawn.
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    /// Set how long miter joins can be, as a multiple of the stroke's width.
+    pub fn miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Set the lengths of alternating dashes and gaps, starting with a dash.
+    pub fn dashes(mut self, dashes: impl IntoIterator<Item = Pixels>) -> Self {
+        self.dashes = dashes.into_iter().collect();
+        self
+    }
+
+    /// Set how far into the dash pattern the stroke starts.
+    pub fn dash_offset(mut self, dash_offset: Pixels) -> Self {
+        self.dash_offset = dash_offset;
+        self
+    }
+}
+
+/// Builds a shape out of lines, curves and arcs, which can be filled or stroked into a
+/// [`Path`] to paint with [`crate::ElementContext::paint_path`].
+///
+/// ```
+/// # use gpui::{point, px, PathBuilder, StrokeStyle};
+/// let mut builder = PathBuilder::new();
+/// builder.move_to(point(px(0.), px(10.)));
+/// builder.line_to(point(px(10.), px(0.)));
+/// builder.arc_to(point(px(20.), px(10.)), px(10.), false, true);
+/// let path = builder.stroke(&StrokeStyle::new(px(2.)));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct PathBuilder {
+    subpaths: Vec<Subpath>,
+}
+
+#[derive(Clone, Debug)]
+struct Subpath {
+    start: Point<Pixels>,
+    segments: Vec<Segment>,
+    closed: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Segment {
+    Line(Point<Pixels>),
+    Quadratic {
+        ctrl: Point<Pixels>,
+        to: Point<Pixels>,
+    },
+    Cubic {
+        ctrl_a: Point<Pixels>,
+        ctrl_b: Point<Pixels>,
+        to: Point<Pixels>,
+    },
+}
+
+impl Segment {
+    fn to(&self) -> Point<Pixels> {
+        match self {
+            Segment::Line(to) | Segment::Quadratic { to, .. } | Segment::Cubic { to, .. } => *to,
+        }
+    }
+}
+
+impl PathBuilder {
+    /// Create an empty path builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new subpath at the given point.
+    pub fn move_to(&mut self, to: Point<Pixels>) {
+        match self.subpaths.last_mut() {
+            Some(subpath) if !subpath.closed && subpath.segments.is_empty() => {
+                subpath.start = to;
+            }
+            _ => self.subpaths.push(Subpath {
+                start: to,
+                segments: Vec::new(),
+                closed: false,
+            }),
+        }
+    }
+
+    /// Draw a straight line from the current point to the given point.
+    pub fn line_to(&mut self, to: Point<Pixels>) {
+        self.push_segment(Segment::Line(to));
+    }
+
+    /// Draw a quadratic curve from the current point to the given point, using the
+    /// given control point.
+    pub fn curve_to(&mut self, to: Point<Pixels>, ctrl: Point<Pixels>) {
+        self.push_segment(Segment::Quadratic { ctrl, to });
+    }
+
+    /// Draw a cubic curve from the current point to the given point, using the given
+    /// control points.
+    pub fn cubic_bezier_to(
+        &mut self,
+        to: Point<Pixels>,
+        ctrl_a: Point<Pixels>,
+        ctrl_b: Point<Pixels>,
+    ) {
+        self.push_segment(Segment::Cubic { ctrl_a, ctrl_b, to });
+    }
+
+    /// Draw an arc of a circle with the given radius from the current point to the
+    /// given point. Like SVG's arcs, `large_arc` picks the longer of the two arcs
+    /// joining the points and `sweep` picks the one drawn clockwise, and the radius is
+    /// grown if it's too small for the circle to reach both points.
+    pub fn arc_to(&mut self, to: Point<Pixels>, radius: Pixels, large_arc: bool, sweep: bool) {
+        let Some(from) = self.current() else {
+            self.move_to(to);
+            return;
+        };
+        let (from, end) = (unitless(from), unitless(to));
+        let chord = end - from;
+        let chord_length = length(chord);
+        if chord_length == 0. {
+            return;
+        }
+        if radius.0.abs() == 0. {
+            self.line_to(to);
+            return;
+        }
+
+        let radius = radius.0.abs().max(chord_length / 2.);
+        let midpoint = (from + end) * 0.5;
+        let offset = (radius * radius - chord_length * chord_length / 4.)
+            .max(0.)
+            .sqrt();
+        let normal = point(-chord.y, chord.x) * (1. / chord_length);
+        let arc_from = |center: Point<f32>| {
+            let start_angle = angle(from - center);
+            let mut sweep_angle = angle(end - center) - start_angle;
+            if sweep {
+                while sweep_angle <= 0. {
+                    sweep_angle += 2. * PI;
+                }
+            } else {
+                while sweep_angle >= 0. {
+                    sweep_angle -= 2. * PI;
+                }
+            }
+            (center, start_angle, sweep_angle)
+        };
+        let (mut center, mut start_angle, mut sweep_angle) = arc_from(midpoint + normal * offset);
+        if (sweep_angle.abs() > PI) != large_arc {
+            (center, start_angle, sweep_angle) = arc_from(midpoint - normal * offset);
+        }
+
+        // Each quarter circle or less is closely approximated by a single cubic curve.
+        let pieces = (sweep_angle.abs() / FRAC_PI_2).ceil().max(1.) as usize;
+        let piece_angle = sweep_angle / pieces as f32;
+        let handle = 4. / 3. * (piece_angle / 4.).tan() * radius;
+        for piece in 0..pieces {
+            let start = start_angle + piece_angle * piece as f32;
+            let end_angle = start + piece_angle;
+            let (start_direction, end_direction) = (direction(start), direction(end_angle));
+            let ctrl_a = center
+                + start_direction * radius
+                + point(-start_direction.y, start_direction.x) * handle;
+            let ctrl_b =
+                center + end_direction * radius - point(-end_direction.y, end_direction.x) * handle;
+            let piece_end = if piece + 1 == pieces {
+                to
+            } else {
+                pixels(center + end_direction * radius)
+            };
+            self.cubic_bezier_to(piece_end, pixels(ctrl_a), pixels(ctrl_b));
+        }
+    }
+
+    /// Close the current subpath with a straight line back to its start. Drawing
+    /// continues from there in a new subpath.
+    pub fn close(&mut self) {
+        if let Some(subpath) = self.subpaths.last_mut() {
+            if !subpath.segments.is_empty() {
+                subpath.closed = true;
+            }
+        }
+    }
+
+    /// Fill the area enclosed by the subpaths, closing any that are still open. Areas
+    /// enclosed an even number of times are left empty.
+    pub fn fill(&self) -> Path<Pixels> {
+        let origin = self
+            .subpaths
+            .first()
+            .map_or_else(Point::default, |subpath| subpath.start);
+        let mut path = Path::new(origin);
+        let push_edge = |path: &mut Path<Pixels>, from, to| {
+            path.push_triangle((origin, from, to), SOLID);
+        };
+
+        for subpath in &self.subpaths {
+            let mut current = subpath.start;
+            for segment in &subpath.segments {
+                match *segment {
+                    Segment::Line(to) => push_edge(&mut path, current, to),
+                    Segment::Quadratic { ctrl, to } => {
+                        push_edge(&mut path, current, to);
+                        path.push_triangle((current, ctrl, to), CURVE);
+                    }
+                    Segment::Cubic { ctrl_a, ctrl_b, to } => {
+                        let cubic = [current, ctrl_a, ctrl_b, to].map(unitless);
+                        for [from, ctrl, to] in cubic_to_quadratics(cubic) {
+                            let [from, ctrl, to] = [from, ctrl, to].map(pixels);
+                            push_edge(&mut path, from, to);
+                            path.push_triangle((from, ctrl, to), CURVE);
+                        }
+                    }
+                }
+                current = segment.to();
+            }
+            push_edge(&mut path, current, subpath.start);
+        }
+        path
+    }
+
+    /// Stroke the subpaths with the given style.
+    pub fn stroke(&self, style: &StrokeStyle) -> Path<Pixels> {
+        let origin = self
+            .subpaths
+            .first()
+            .map_or_else(Point::default, |subpath| subpath.start);
+        let mut path = Path::new(origin);
+        path.coverage = PathCoverage::Union;
+        let half_width = style.width.0 / 2.;
+        if half_width <= 0. {
+            return path;
+        }
+
+        let mut stroker = Stroker {
+            path: &mut path,
+            style,
+            half_width,
+        };
+        for subpath in &self.subpaths {
+            let (points, closed) = flatten(subpath);
+            match dash_pattern(style) {
+                Some(pattern) => {
+                    for dash in dash_polyline(&points, closed, &pattern, style.dash_offset.0) {
+                        stroker.stroke_polyline(dash, false);
+                    }
+                }
+                None => stroker.stroke_polyline(points, closed),
+            }
+        }
+        path
+    }
+
+    fn current(&self) -> Option<Point<Pixels>> {
+        let subpath = self.subpaths.last()?;
+        if subpath.closed {
+            Some(subpath.start)
+        } else {
+            Some(subpath.segments.last().map_or(subpath.start, Segment::to))
+        }
+    }
+
+    fn push_segment(&mut self, segment: Segment) {
+        match self.subpaths.last_mut() {
+            Some(subpath) if !subpath.closed => subpath.segments.push(segment),
+            Some(subpath) => {
+                let start = subpath.start;
+                self.subpaths.push(Subpath {
+                    start,
+                    segments: vec![segment],
+                    closed: false,
+                });
+            }
+            // Like a canvas, drawing without a current point only moves to the end.
+            None => self.move_to(segment.to()),
+        }
+    }
+}
+
+/// The texture coordinates of a triangle that's entirely filled.
+const SOLID: (Point<f32>, Point<f32>, Point<f32>) = (
+    Point { x: 0., y: 1. },
+    Point { x: 0., y: 1. },
+    Point { x: 0., y: 1. },
+);
+
+/// The texture coordinates of a triangle filled between its first and last vertex and
+/// the quadratic curve they make with the middle one.
+const CURVE: (Point<f32>, Point<f32>, Point<f32>) = (
+    Point { x: 0., y: 0. },
+    Point { x: 0.5, y: 0. },
+    Point { x: 1., y: 1. },
+);
+
+/// Tessellates polylines into the overlapping triangles of a stroke.
+struct Stroker<'a> {
+    path: &'a mut Path<Pixels>,
+    style: &'a StrokeStyle,
+    half_width: f32,
+}
+
+impl Stroker<'_> {
+    fn stroke_polyline(&mut self, mut points: Vec<Point<f32>>, closed: bool) {
+        points.dedup_by(|a, b| length(*a - *b) < f32::EPSILON);
+        if closed && points.len() > 1 && length(points[0] - points[points.len() - 1]) < f32::EPSILON
+        {
+            points.pop();
+        }
+
+        match points.as_slice() {
+            [] => {}
+            [point] => self.dot(*point),
+            _ => {
+                let segment_count = if closed {
+                    points.len()
+                } else {
+                    points.len() - 1
+                };
+                let segment = |ix: usize| (points[ix], points[(ix + 1) % points.len()]);
+                for ix in 0..segment_count {
+                    let (from, to) = segment(ix);
+                    self.segment(from, to);
+                }
+
+                let joins = if closed {
+                    0..points.len()
+                } else {
+                    1..points.len() - 1
+                };
+                for ix in joins {
+                    let (previous, vertex) = segment((ix + points.len() - 1) % points.len());
+                    let (_, next) = segment(ix);
+                    self.join(
+                        vertex,
+                        normalize(vertex - previous),
+                        normalize(next - vertex),
+                    );
+                }
+
+                if !closed {
+                    let first = normalize(points[0] - points[1]);
+                    let last = normalize(points[points.len() - 1] - points[points.len() - 2]);
+                    self.cap(points[0], first);
+                    self.cap(points[points.len() - 1], last);
+                }
+            }
+        }
+    }
+
+    fn segment(&mut self, from: Point<f32>, to: Point<f32>) {
+        let normal = perpendicular(normalize(to - from)) * self.half_width;
+        self.quad([from + normal, to + normal, to - normal, from - normal]);
+    }
+
+    fn join(&mut self, vertex: Point<f32>, incoming: Point<f32>, outgoing: Point<f32>) {
+        let cross = cross(incoming, outgoing);
+        let dot = dot(incoming, outgoing);
+        let reversed = cross.abs() <= f32::EPSILON && dot < 0.;
+        if cross.abs() <= f32::EPSILON && !reversed {
+            return;
+        }
+
+        // The gap between the segments is on the side the path turns away from.
+        let side = if cross > 0. || reversed { -1. } else { 1. };
+        let outer_incoming = perpendicular(incoming) * side;
+        let outer_outgoing = perpendicular(outgoing) * side;
+        let a = vertex + outer_incoming * self.half_width;
+        let b = vertex + outer_outgoing * self.half_width;
+        match self.style.join {
+            LineJoin::Round => {
+                let sweep = if reversed { PI } else { cross.atan2(dot) };
+                self.fan(vertex, outer_incoming, sweep);
+            }
+            LineJoin::Miter => {
+                let cos_half_angle = ((1. + dot) / 2.).max(0.).sqrt();
+                if cos_half_angle > 0. && cos_half_angle.recip() <= self.style.miter_limit {
+                    let tip = vertex
+                        + normalize(outer_incoming + outer_outgoing)
+                            * (self.half_width / cos_half_angle);
+                    self.triangle(vertex, a, tip);
+                    self.triangle(vertex, tip, b);
+                } else {
+                    self.triangle(vertex, a, b);
+                }
+            }
+            LineJoin::Bevel => self.triangle(vertex, a, b),
+        }
+    }
+
+    /// Draws the cap at an end of an open polyline, where `outward` points away from it.
+    fn cap(&mut self, end: Point<f32>, outward: Point<f32>) {
+        let normal = perpendicular(outward);
+        match self.style.cap {
+            LineCap::Butt => {}
+            LineCap::Round => self.fan(end, normal, -PI),
+            LineCap::Square => {
+                let normal = normal * self.half_width;
+                let outward = outward * self.half_width;
+                self.quad([
+                    end + normal,
+                    end + normal + outward,
+                    end - normal + outward,
+                    end - normal,
+                ]);
+            }
+        }
+    }
+
+    /// Draws the caps of a polyline with no length, which only show up when they
+    /// extend past its ends.
+    fn dot(&mut self, center: Point<f32>) {
+        match self.style.cap {
+            LineCap::Butt => {}
+            LineCap::Round => self.fan(center, point(1., 0.), 2. * PI),
+            LineCap::Square => {
+                let half_width = self.half_width;
+                self.quad([
+                    center + point(-half_width, -half_width),
+                    center + point(half_width, -half_width),
+                    center + point(half_width, half_width),
+                    center + point(-half_width, half_width),
+                ]);
+            }
+        }
+    }
+
+    /// Draws the slice of a circle as wide as the stroke that starts in the given
+    /// direction from its center and sweeps clockwise through the given angle.
+    fn fan(&mut self, center: Point<f32>, start: Point<f32>, sweep: f32) {
+        let radius = self.half_width;
+        let max_step = if radius > TOLERANCE {
+            2. * (1. - TOLERANCE / radius).acos()
+        } else {
+            FRAC_PI_2
+        };
+        // An even number of steps puts a vertex halfway through the sweep, which is
+        // where a cap reaches furthest.
+        let steps = (sweep.abs() / max_step / 2.).ceil().clamp(1., 32.) as usize * 2;
+        let start_angle = angle(start);
+        let mut previous = center + start * radius;
+        for step in 1..=steps {
+            let next =
+                center + direction(start_angle + sweep * step as f32 / steps as f32) * radius;
+            self.triangle(center, previous, next);
+            previous = next;
+        }
+    }
+
+    fn quad(&mut self, [a, b, c, d]: [Point<f32>; 4]) {
+        self.triangle(a, b, c);
+        self.triangle(a, c, d);
+    }
+
+    fn triangle(&mut self, a: Point<f32>, b: Point<f32>, c: Point<f32>) {
+        self.path
+            .push_triangle((pixels(a), pixels(b), pixels(c)), SOLID);
+    }
+}
+
+/// Approximates a subpath with straight lines, returning its points and whether it's
+/// closed.
+fn flatten(subpath: &Subpath) -> (Vec<Point<f32>>, bool) {
+    let mut points = vec![unitless(subpath.start)];
+    let mut current = points[0];
+    for segment in &subpath.segments {
+        match *segment {
+            Segment::Line(to) => points.push(unitless(to)),
+            Segment::Quadratic { ctrl, to } => {
+                let [ctrl, to] = [ctrl, to].map(unitless);
+                let pieces = curve_pieces(length(current - ctrl * 2. + to) / (4. * TOLERANCE));
+                points.extend((1..=pieces).map(|piece| {
+                    let t = piece as f32 / pieces as f32;
+                    current * ((1. - t) * (1. - t)) + ctrl * (2. * t * (1. - t)) + to * (t * t)
+                }));
+            }
+            Segment::Cubic { ctrl_a, ctrl_b, to } => {
+                let cubic = [current, unitless(ctrl_a), unitless(ctrl_b), unitless(to)];
+                let [from, ctrl_a, ctrl_b, to] = cubic;
+                let bend =
+                    length(from - ctrl_a * 2. + ctrl_b).max(length(ctrl_a - ctrl_b * 2. + to));
+                let pieces = curve_pieces(3. * bend / (4. * TOLERANCE));
+                points.extend(
+                    (1..=pieces).map(|piece| cubic_point(&cubic, piece as f32 / pieces as f32)),
+                );
+            }
+        }
+        current = unitless(segment.to());
+    }
+    (points, subpath.closed)
+}
+
+/// The number of pieces needed to approximate a curve, given the square of that number
+/// as a fraction.
+fn curve_pieces(squared: f32) -> usize {
+    (squared.sqrt().ceil() as usize).clamp(1, MAX_CURVE_PIECES)
+}
+
+/// Splits a cubic curve into quadratic curves that stay close to it.
+fn cubic_to_quadratics(cubic: [Point<f32>; 4]) -> Vec<[Point<f32>; 3]> {
+    let [from, ctrl_a, ctrl_b, to] = cubic;
+    let error = length(to - ctrl_b * 3. + ctrl_a * 3. - from) * 3f32.sqrt() / 36.;
+    let pieces = ((error / TOLERANCE).cbrt().ceil() as usize).clamp(1, MAX_CURVE_PIECES);
+    (0..pieces)
+        .map(|piece| {
+            let (t0, t1) = (
+                piece as f32 / pieces as f32,
+                (piece + 1) as f32 / pieces as f32,
+            );
+            let (start, end) = (cubic_point(&cubic, t0), cubic_point(&cubic, t1));
+            let span = (t1 - t0) / 3.;
+            let ctrl_a = start + cubic_derivative(&cubic, t0) * span;
+            let ctrl_b = end - cubic_derivative(&cubic, t1) * span;
+            [start, (ctrl_a * 3. + ctrl_b * 3. - start - end) * 0.25, end]
+        })
+        .collect()
+}
+
+fn cubic_point([a, b, c, d]: &[Point<f32>; 4], t: f32) -> Point<f32> {
+    let s = 1. - t;
+    *a * (s * s * s) + *b * (3. * s * s * t) + *c * (3. * s * t * t) + *d * (t * t * t)
+}
+
+fn cubic_derivative([a, b, c, d]: &[Point<f32>; 4], t: f32) -> Point<f32> {
+    let s = 1. - t;
+    (*b - *a) * (3. * s * s) + (*c - *b) * (6. * s * t) + (*d - *c) * (3. * t * t)
+}
+
+/// The lengths of the dashes and gaps to stroke with, or `None` to stroke without gaps.
+fn dash_pattern(style: &StrokeStyle) -> Option<Vec<f32>> {
+    let mut pattern = style
+        .dashes
+        .iter()
+        .map(|length| length.0)
+        .collect::<Vec<_>>();
+    let total = pattern.iter().sum::<f32>();
+    if pattern
+        .iter()
+        .any(|length| *length < 0. || !length.is_finite())
+        || total <= 0.
+    {
+        return None;
+    }
+    // Like SVG, a pattern with an odd number of lengths is repeated to make it even.
+    if pattern.len() % 2 == 1 {
+        pattern.extend_from_slice(&pattern.clone());
+    }
+    Some(pattern)
+}
+
+/// Splits a polyline into the open polylines of its dashes.
+fn dash_polyline(
+    points: &[Point<f32>],
+    closed: bool,
+    pattern: &[f32],
+    offset: f32,
+) -> Vec<Vec<Point<f32>>> {
+    let total = pattern.iter().sum::<f32>();
+    let mut ix = 0;
+    let mut remaining = offset.rem_euclid(total);
+    while remaining >= pattern[ix] && pattern[ix] > 0. {
+        remaining -= pattern[ix];
+        ix = (ix + 1) % pattern.len();
+    }
+    remaining = pattern[ix] - remaining;
+
+    let mut dashes = Vec::new();
+    let mut dash = Vec::new();
+    if ix % 2 == 0 {
+        dash.push(points[0]);
+    }
+    let closing_segment = closed.then(|| (points[points.len() - 1], points[0]));
+    let segments = points
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .chain(closing_segment);
+    for (from, to) in segments {
+        let segment_length = length(to - from);
+        if segment_length == 0. {
+            continue;
+        }
+        let direction = (to - from) * (1. / segment_length);
+        let mut position = 0.;
+        loop {
+            let step = remaining.min(segment_length - position);
+            position += step;
+            remaining -= step;
+            if ix % 2 == 0 {
+                dash.push(from + direction * position);
+            }
+            if remaining > 0. {
+                break;
+            }
+
+            if ix % 2 == 0 {
+                dashes.push(std::mem::take(&mut dash));
+            }
+            ix = (ix + 1) % pattern.len();
+            remaining = pattern[ix];
+            if ix % 2 == 0 {
+                dash.push(from + direction * position);
+            }
+        }
+    }
+    if !dash.is_empty() {
+        dashes.push(dash);
+    }
+    dashes
+}
+
+fn unitless(point: Point<Pixels>) -> Point<f32> {
+    point.map(|coordinate| coordinate.0)
+}
+
+fn pixels(point: Point<f32>) -> Point<Pixels> {
+    point.map(px)
+}
+
+fn length(vector: Point<f32>) -> f32 {
+    vector.x.hypot(vector.y)
+}
+
+fn normalize(vector: Point<f32>) -> Point<f32> {
+    let length = length(vector);
+    if length > 0. {
+        vector * (1. / length)
+    } else {
+        vector
+    }
+}
+
+/// The vector a quarter turn clockwise from the given one.
+fn perpendicular(vector: Point<f32>) -> Point<f32> {
+    point(-vector.y, vector.x)
+}
+
+fn dot(a: Point<f32>, b: Point<f32>) -> f32 {
+    a.x * b.x + a.y * b.y
+}
+
+fn cross(a: Point<f32>, b: Point<f32>) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+fn angle(vector: Point<f32>) -> f32 {
+    vector.y.atan2(vector.x)
+}
+
+fn direction(angle: f32) -> Point<f32> {
+    point(angle.cos(), angle.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Bounds;
+
+    fn assert_bounds_eq(actual: &Bounds<Pixels>, expected: [f32; 4]) {
+        let actual = [
+            actual.origin.x.0,
+            actual.origin.y.0,
+            actual.size.width.0,
+            actual.size.height.0,
+        ];
+        for (actual_value, expected_value) in actual.iter().zip(expected) {
+            assert!(
+                (actual_value - expected_value).abs() < 0.01,
+                "{actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    fn corner() -> PathBuilder {
+        let mut builder = PathBuilder::new();
+        builder.move_to(point(px(0.), px(0.)));
+        builder.line_to(point(px(10.), px(0.)));
+        builder.line_to(point(px(10.), px(10.)));
+        builder
+    }
+
+    #[test]
+    fn test_stroke_joins_and_caps() {
+        let path = corner().stroke(&StrokeStyle::new(px(2.)));
+        assert_eq!(path.coverage, PathCoverage::Union);
+        assert_bounds_eq(&path.bounds, [0., -1., 11., 11.]);
+
+        let path = corner().stroke(&StrokeStyle::new(px(2.)).miter_limit(1.));
+        let bevel = corner().stroke(&StrokeStyle::new(px(2.)).join(LineJoin::Bevel));
+        assert_eq!(path.vertices.len(), bevel.vertices.len());
+
+        let path = corner().stroke(&StrokeStyle::new(px(2.)).cap(LineCap::Square));
+        assert_bounds_eq(&path.bounds, [-1., -1., 12., 12.]);
+
+        let path = corner().stroke(
+            &StrokeStyle::new(px(2.))
+                .cap(LineCap::Round)
+                .join(LineJoin::Round),
+        );
+        assert_bounds_eq(&path.bounds, [-1., -1., 12., 12.]);
+    }
+
+    #[test]
+    fn test_dashes() {
+        let mut builder = PathBuilder::new();
+        builder.move_to(point(px(0.), px(0.)));
+        builder.line_to(point(px(10.), px(0.)));
+
+        // Dashes cover 0..2 and 5..7, and the one starting at 10 has no length.
+        let style = StrokeStyle::new(px(2.)).dashes([px(2.), px(3.)]);
+        assert_eq!(builder.stroke(&style).vertices.len(), 2 * 6);
+
+        // Shifting the pattern splits the first dash across the start of the line.
+        let style = style.dash_offset(px(1.));
+        assert_eq!(builder.stroke(&style).vertices.len(), 3 * 6);
+
+        // Dashes with no length are drawn as dots when their caps stick out.
+        let style = StrokeStyle::new(px(2.))
+            .dashes([px(0.), px(5.)])
+            .cap(LineCap::Square);
+        assert_eq!(builder.stroke(&style).vertices.len(), 3 * 6);
+    }
+
+    #[test]
+    fn test_arcs_and_curves() {
+        // A clockwise half circle from the left to the right passes over its center.
+        let mut builder = PathBuilder::new();
+        builder.move_to(point(px(0.), px(10.)));
+        builder.arc_to(point(px(20.), px(10.)), px(10.), false, true);
+        builder.close();
+        let path = builder.fill();
+        assert_eq!(path.coverage, PathCoverage::EvenOdd);
+        assert_bounds_eq(&path.bounds, [0., 0., 20., 10.]);
+
+        // The large arc between the same points with a smaller radius bulges further.
+        let mut builder = PathBuilder::new();
+        builder.move_to(point(px(0.), px(10.)));
+        builder.arc_to(point(px(10.), px(0.)), px(10.), true, false);
+        let bounds = builder.stroke(&StrokeStyle::new(px(0.1))).bounds;
+        assert_bounds_eq(&bounds, [-0.05, -0.05, 20.1, 20.1]);
+
+        // Strokes follow curves closely enough to stay within their control points.
+        let mut builder = PathBuilder::new();
+        builder.move_to(point(px(0.), px(0.)));
+        builder.cubic_bezier_to(
+            point(px(30.), px(0.)),
+            point(px(0.), px(20.)),
+            point(px(30.), px(20.)),
+        );
+        let bounds = builder.stroke(&StrokeStyle::new(px(0.1))).bounds;
+        assert_bounds_eq(&bounds, [-0.05, 0., 30.1, 15.05]);
+    }
+}
@@ -1,17 +1,29 @@
 mod app_menu;
+#[cfg(any(not(target_os = "macos"), test, feature = "test-support"))]
+mod cosmic_text_system;
+// On macOS, the software renderer and its atlas are only used to replay frame dumps.
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+mod cpu_atlas;
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+mod cpu_renderer;
+#[cfg(target_os = "linux")]
+mod headless;
 mod keystroke;
 #[cfg(target_os = "macos")]
 mod mac;
 #[cfg(any(test, feature = "test-support"))]
 mod test;
+#[cfg(any(not(target_os = "macos"), test, feature = "test-support"))]
+mod thread_pool_dispatcher;
 #[cfg(target_os = "windows")]
 mod windows;
 
 use crate::{
     Action, AnyWindowHandle, AsyncWindowContext, BackgroundExecutor, Bounds, DevicePixels, Font,
-    FontId, FontMetrics, FontRun, ForegroundExecutor, GlobalPixels, GlyphId, Keymap, LineLayout,
-    Pixels, PlatformInput, Point, RenderGlyphParams, RenderImageParams, RenderSvgParams, Result,
-    Scene, SharedString, Size, Task, TaskLabel, WindowContext,
+    FontId, FontMetrics, FontRun, ForegroundExecutor, GlobalPixels, GlyphId, ImageData, Keymap,
+    LineLayout, Pixels, PlatformInput, Point, RenderGlyphParams, RenderImageParams,
+    RenderSvgParams, Result, Scene, SharedString, Size, SvgColorMode, Task, TaskLabel,
+    WindowContext,
 };
 use anyhow::anyhow;
 use async_task::Runnable;
@@ -21,7 +33,7 @@ use seahash::SeaHasher;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::hash::{Hash, Hasher};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{
     any::Any,
     fmt::{self, Debug, Display},
@@ -34,11 +46,21 @@ use std::{
 use uuid::Uuid;
 
 pub use app_menu::*;
+#[cfg(any(not(target_os = "macos"), test, feature = "test-support"))]
+pub(crate) use cosmic_text_system::*;
+pub(crate) use cpu_atlas::*;
+pub(crate) use cpu_renderer::*;
+#[cfg(all(target_os = "linux", any(test, feature = "test-support")))]
+pub use headless::HeadlessWindow;
+#[cfg(target_os = "linux")]
+pub(crate) use headless::*;
 pub use keystroke::*;
 #[cfg(target_os = "macos")]
 pub(crate) use mac::*;
 #[cfg(any(test, feature = "test-support"))]
 pub(crate) use test::*;
+#[cfg(any(not(target_os = "macos"), test, feature = "test-support"))]
+pub(crate) use thread_pool_dispatcher::*;
 use time::UtcOffset;
 #[cfg(target_os = "windows")]
 pub(crate) use windows::*;
@@ -53,6 +75,11 @@ pub(crate) fn current_platform() -> Rc<dyn Platform> {
     Rc::new(WindowsPlatform::new())
 }
 
+#[cfg(target_os = "linux")]
+pub(crate) fn current_platform() -> Rc<dyn Platform> {
+    HeadlessPlatform::new()
+}
+
 pub(crate) trait Platform: 'static {
     fn background_executor(&self) -> BackgroundExecutor;
     fn foreground_executor(&self) -> ForegroundExecutor;
@@ -188,10 +215,21 @@ pub(crate) trait PlatformWindow {
 
     fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas>;
 
+    /// Whether the renderer behind this window can paint stacking contexts into intermediate
+    /// layers, which filters, clip paths and masks need.
+    fn supports_layers(&self) -> bool {
+        true
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     fn as_test(&mut self) -> Option<&mut TestWindow> {
         None
     }
+
+    #[cfg(all(target_os = "linux", any(test, feature = "test-support")))]
+    fn as_headless(&self) -> Option<HeadlessWindow> {
+        None
+    }
 }
 
 /// This type is public so that our test macro can generate and use it, but it should not
@@ -206,6 +244,10 @@ pub trait PlatformDispatcher: Send + Sync {
     fn park(&self);
     fn unparker(&self) -> Unparker;
 
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
     #[cfg(any(test, feature = "test-support"))]
     fn as_test(&self) -> Option<&TestDispatcher> {
         None
@@ -267,7 +309,10 @@ impl AtlasKey {
                     AtlasTextureKind::Monochrome
                 }
             }
-            AtlasKey::Svg(_) => AtlasTextureKind::Monochrome,
+            AtlasKey::Svg(params) => match params.color_mode {
+                SvgColorMode::Monochrome => AtlasTextureKind::Monochrome,
+                SvgColorMode::FullColor => AtlasTextureKind::Polychrome,
+            },
             AtlasKey::Image(_) => AtlasTextureKind::Polychrome,
         }
     }
@@ -297,6 +342,11 @@ pub(crate) trait PlatformAtlas: Send + Sync {
         key: &AtlasKey,
         build: &mut dyn FnMut() -> Result<(Size<DevicePixels>, Cow<'a, [u8]>)>,
     ) -> Result<AtlasTile>;
+
+    /// Gives read access to the atlas' pixels, if they can be read back.
+    fn texture_source(&self) -> Option<&dyn CpuTextureSource> {
+        None
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -315,7 +365,7 @@ pub(crate) struct AtlasTextureId {
     pub(crate) kind: AtlasTextureKind,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(C)]
 pub(crate) enum AtlasTextureKind {
     Monochrome = 0,
@@ -323,7 +373,7 @@ pub(crate) enum AtlasTextureKind {
     Path = 2,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(C)]
 pub(crate) struct TileId(pub(crate) u32);
 
@@ -744,11 +794,45 @@ impl Display for SemanticVersion {
     }
 }
 
-/// A clipboard item that should be copied to the clipboard
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// A clipboard item that should be copied to the clipboard.
+///
+/// Besides its text, an item can carry richer representations of the same content: HTML,
+/// an image, or a list of file paths. Platforms offer every format an item holds, so the
+/// application pasting it can pick the one it understands best.
+#[derive(Clone, Debug)]
 pub struct ClipboardItem {
     pub(crate) text: String,
     pub(crate) metadata: Option<String>,
+    pub(crate) html: Option<String>,
+    pub(crate) image: Option<Arc<ImageData>>,
+    pub(crate) paths: Option<Vec<PathBuf>>,
+}
+
+/// A format a [`ClipboardItem`] can hold.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ClipboardFormat {
+    /// Plain text
+    Text,
+    /// An HTML fragment
+    Html,
+    /// A bitmap image
+    Image,
+    /// A list of file paths
+    Paths,
+}
+
+/// The contents of a [`ClipboardItem`] in a single format, as returned by
+/// [`ClipboardItem::best_content`].
+#[derive(Clone, Copy, Debug)]
+pub enum ClipboardContent<'a> {
+    /// Plain text
+    Text(&'a str),
+    /// An HTML fragment
+    Html(&'a str),
+    /// A bitmap image
+    Image(&'a Arc<ImageData>),
+    /// A list of file paths
+    Paths(&'a [PathBuf]),
 }
 
 impl ClipboardItem {
@@ -757,15 +841,52 @@ impl ClipboardItem {
         Self {
             text,
             metadata: None,
+            html: None,
+            image: None,
+            paths: None,
         }
     }
 
+    /// Create a new clipboard item holding an image. The item has no text.
+    pub fn new_image(image: Arc<ImageData>) -> Self {
+        Self::new(String::new()).with_image(image)
+    }
+
+    /// Create a new clipboard item holding a list of file paths. The item's text lists
+    /// the paths, one per line, for applications that only accept text.
+    pub fn new_paths(paths: Vec<PathBuf>) -> Self {
+        let text = paths
+            .iter()
+            .map(|path| path.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Self::new(text).with_paths(paths)
+    }
+
     /// Create a new clipboard item with the given text and metadata
     pub fn with_metadata<T: Serialize>(mut self, metadata: T) -> Self {
         self.metadata = Some(serde_json::to_string(&metadata).unwrap());
         self
     }
 
+    /// Attach an HTML representation of the item's text
+    pub fn with_html(mut self, html: impl Into<String>) -> Self {
+        self.html = Some(html.into());
+        self
+    }
+
+    /// Attach an image to the clipboard item
+    pub fn with_image(mut self, image: Arc<ImageData>) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Attach a list of file paths to the clipboard item
+    pub fn with_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.paths = Some(paths);
+        self
+    }
+
     /// Get the text of the clipboard item
     pub fn text(&self) -> &String {
         &self.text
@@ -781,9 +902,74 @@ impl ClipboardItem {
             .and_then(|m| serde_json::from_str(m).ok())
     }
 
+    /// Get the HTML representation of the clipboard item, if it has one
+    pub fn html(&self) -> Option<&str> {
+        self.html.as_deref()
+    }
+
+    /// Get the image held by the clipboard item, if it has one
+    pub fn image(&self) -> Option<&Arc<ImageData>> {
+        self.image.as_ref()
+    }
+
+    /// Get the file paths held by the clipboard item, if it has any
+    pub fn paths(&self) -> Option<&[PathBuf]> {
+        self.paths.as_deref()
+    }
+
+    /// Returns every format this item holds, richest first.
+    pub fn formats(&self) -> Vec<ClipboardFormat> {
+        let mut formats = Vec::new();
+        if self.paths.is_some() {
+            formats.push(ClipboardFormat::Paths);
+        }
+        if self.image.is_some() {
+            formats.push(ClipboardFormat::Image);
+        }
+        if self.html.is_some() {
+            formats.push(ClipboardFormat::Html);
+        }
+        if !self.text.is_empty() || formats.is_empty() {
+            formats.push(ClipboardFormat::Text);
+        }
+        formats
+    }
+
+    /// Returns the item's contents in the first of the `accepted` formats it holds, so
+    /// callers should list the formats they accept from most to least preferred.
+    pub fn best_content(&self, accepted: &[ClipboardFormat]) -> Option<ClipboardContent<'_>> {
+        accepted.iter().find_map(|format| self.content(*format))
+    }
+
+    /// Returns the item's contents in the given format, if it holds it.
+    pub fn content(&self, format: ClipboardFormat) -> Option<ClipboardContent<'_>> {
+        match format {
+            ClipboardFormat::Text => self
+                .formats()
+                .contains(&ClipboardFormat::Text)
+                .then_some(ClipboardContent::Text(&self.text)),
+            ClipboardFormat::Html => self.html().map(ClipboardContent::Html),
+            ClipboardFormat::Image => self.image().map(ClipboardContent::Image),
+            ClipboardFormat::Paths => self.paths().map(ClipboardContent::Paths),
+        }
+    }
+
     pub(crate) fn text_hash(text: &str) -> u64 {
         let mut hasher = SeaHasher::new();
         text.hash(&mut hasher);
         hasher.finish()
     }
 }
+
+impl PartialEq for ClipboardItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+            && self.metadata == other.metadata
+            && self.html == other.html
+            && self.image.as_ref().map(|image| image.id)
+                == other.image.as_ref().map(|image| image.id)
+            && self.paths == other.paths
+    }
+}
+
+impl Eq for ClipboardItem {}
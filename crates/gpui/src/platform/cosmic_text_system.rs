@@ -0,0 +1,542 @@
+use crate::{
+    point, px, size, Bounds, DevicePixels, Font, FontId, FontMetrics, FontRun, FontStyle,
+    FontWeight, GlyphId, LineLayout, Pixels, PlatformTextSystem, RenderGlyphParams, Result,
+    ShapedGlyph, ShapedRun, Size, SUBPIXEL_VARIANTS,
+};
+use anyhow::anyhow;
+use collections::{BTreeSet, HashMap};
+use cosmic_text::{
+    fontdb, rustybuzz::ttf_parser, Attrs, AttrsList, CacheKey, Family, FontSystem, LayoutLine,
+    ShapeBuffer, ShapeLine, Shaping, SwashCache, SwashContent, SwashImage, Wrap,
+};
+use parking_lot::{RwLock, RwLockUpgradableReadGuard};
+use smallvec::SmallVec;
+use std::sync::Arc;
+
+/// A text system implemented in pure Rust on top of `cosmic-text`. Fonts are discovered
+/// and matched with `fontdb`, shaped with `rustybuzz` and rasterized with `swash`, so it
+/// works on any platform that lacks a native text system.
+pub(crate) struct CosmicTextSystem(RwLock<CosmicTextSystemState>);
+
+struct CosmicTextSystemState {
+    font_system: FontSystem,
+    swash_cache: SwashCache,
+    scratch: ShapeBuffer,
+    fonts: Vec<LoadedFont>,
+    font_selections: HashMap<Font, FontId>,
+    font_ids_by_cosmic_id: HashMap<fontdb::ID, FontId>,
+}
+
+struct LoadedFont {
+    font: Arc<cosmic_text::Font>,
+    family: String,
+    weight: fontdb::Weight,
+    style: fontdb::Style,
+    stretch: fontdb::Stretch,
+    is_emoji: bool,
+}
+
+impl CosmicTextSystem {
+    pub(crate) fn new() -> Self {
+        Self(RwLock::new(CosmicTextSystemState {
+            font_system: FontSystem::new(),
+            swash_cache: SwashCache::new(),
+            scratch: ShapeBuffer::default(),
+            fonts: Vec::new(),
+            font_selections: HashMap::default(),
+            font_ids_by_cosmic_id: HashMap::default(),
+        }))
+    }
+}
+
+impl Default for CosmicTextSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlatformTextSystem for CosmicTextSystem {
+    fn add_fonts(&self, fonts: &[Arc<Vec<u8>>]) -> Result<()> {
+        self.0.write().add_fonts(fonts)
+    }
+
+    fn all_font_names(&self) -> Vec<String> {
+        let lock = self.0.read();
+        let mut names = BTreeSet::new();
+        for face in lock.font_system.db().faces() {
+            names.extend(face.families.iter().map(|(name, _)| name.clone()));
+            names.insert(face.post_script_name.clone());
+        }
+        names.into_iter().collect()
+    }
+
+    fn all_font_families(&self) -> Vec<String> {
+        let lock = self.0.read();
+        let mut families = BTreeSet::new();
+        for face in lock.font_system.db().faces() {
+            families.extend(face.families.first().map(|(name, _)| name.clone()));
+        }
+        families.into_iter().collect()
+    }
+
+    fn font_id(&self, font: &Font) -> Result<FontId> {
+        let lock = self.0.upgradable_read();
+        if let Some(font_id) = lock.font_selections.get(font) {
+            Ok(*font_id)
+        } else {
+            let mut lock = RwLockUpgradableReadGuard::upgrade(lock);
+            let font_id = lock.select_font(font)?;
+            lock.font_selections.insert(font.clone(), font_id);
+            Ok(font_id)
+        }
+    }
+
+    fn font_metrics(&self, font_id: FontId) -> FontMetrics {
+        let lock = self.0.read();
+        let face = lock.fonts[font_id.0].font.rustybuzz();
+        let units_per_em = face.units_per_em();
+        let underline = face.underline_metrics();
+        let bounding_box = face.global_bounding_box();
+        FontMetrics {
+            units_per_em: units_per_em as u32,
+            ascent: face.ascender() as f32,
+            descent: face.descender() as f32,
+            line_gap: face.line_gap() as f32,
+            underline_position: underline.map_or(0., |metrics| metrics.position as f32),
+            underline_thickness: underline.map_or(units_per_em as f32 / 14., |metrics| {
+                metrics.thickness as f32
+            }),
+            cap_height: face.capital_height().unwrap_or(face.ascender()) as f32,
+            x_height: face.x_height().unwrap_or(face.ascender() / 2) as f32,
+            bounding_box: bounds_from_rect(bounding_box),
+        }
+    }
+
+    fn typographic_bounds(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Bounds<f32>> {
+        let lock = self.0.read();
+        let rect = lock.fonts[font_id.0]
+            .font
+            .rustybuzz()
+            .glyph_bounding_box(ttf_parser::GlyphId(glyph_id.0 as u16))
+            .ok_or_else(|| anyhow!("glyph {} has no bounding box", glyph_id.0))?;
+        Ok(bounds_from_rect(rect))
+    }
+
+    fn advance(&self, font_id: FontId, glyph_id: GlyphId) -> Result<Size<f32>> {
+        let lock = self.0.read();
+        let advance = lock.fonts[font_id.0]
+            .font
+            .rustybuzz()
+            .glyph_hor_advance(ttf_parser::GlyphId(glyph_id.0 as u16))
+            .ok_or_else(|| anyhow!("glyph {} has no advance", glyph_id.0))?;
+        Ok(size(advance as f32, 0.))
+    }
+
+    fn glyph_for_char(&self, font_id: FontId, ch: char) -> Option<GlyphId> {
+        let lock = self.0.read();
+        let glyph_id = lock.fonts[font_id.0].font.rustybuzz().glyph_index(ch)?;
+        Some(GlyphId(glyph_id.0 as u32))
+    }
+
+    fn glyph_raster_bounds(&self, params: &RenderGlyphParams) -> Result<Bounds<DevicePixels>> {
+        let mut lock = self.0.write();
+        let image = lock.render_glyph(params)?;
+        Ok(Bounds {
+            origin: point(
+                DevicePixels(image.placement.left),
+                DevicePixels(-image.placement.top),
+            ),
+            size: size(
+                DevicePixels(image.placement.width as i32),
+                DevicePixels(image.placement.height as i32),
+            ),
+        })
+    }
+
+    fn rasterize_glyph(
+        &self,
+        params: &RenderGlyphParams,
+        raster_bounds: Bounds<DevicePixels>,
+    ) -> Result<(Size<DevicePixels>, Vec<u8>)> {
+        if raster_bounds.size.width.0 == 0 || raster_bounds.size.height.0 == 0 {
+            return Err(anyhow!("glyph bounds are empty"));
+        }
+
+        let mut lock = self.0.write();
+        let image = lock.render_glyph(params)?;
+        let bytes = match (&image.content, params.is_emoji) {
+            (SwashContent::Mask, false) => image.data.clone(),
+            (SwashContent::Mask, true) => image
+                .data
+                .iter()
+                .flat_map(|alpha| [0xff, 0xff, 0xff, *alpha])
+                .collect(),
+            // Polychrome sprites are stored as BGRA.
+            (SwashContent::Color, true) => {
+                let mut bytes = image.data.clone();
+                for pixel in bytes.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+                bytes
+            }
+            (SwashContent::Color, false) => {
+                image.data.chunks_exact(4).map(|pixel| pixel[3]).collect()
+            }
+            (SwashContent::SubpixelMask, _) => {
+                return Err(anyhow!("subpixel glyph masks are not supported"))
+            }
+        };
+        Ok((raster_bounds.size, bytes))
+    }
+
+    fn layout_line(&self, text: &str, font_size: Pixels, font_runs: &[FontRun]) -> LineLayout {
+        self.0.write().layout_line(text, font_size, font_runs)
+    }
+
+    fn wrap_line(
+        &self,
+        text: &str,
+        font_id: FontId,
+        font_size: Pixels,
+        width: Pixels,
+    ) -> Vec<usize> {
+        let text = &text[..text.find(['\n', '\r']).unwrap_or(text.len())];
+        let font_runs = [FontRun {
+            font_id,
+            len: text.len(),
+        }];
+        self.0
+            .write()
+            .shape_line(text, font_size, &font_runs, Some(width))
+            .iter()
+            .skip(1)
+            .filter_map(|layout_line| layout_line.glyphs.iter().map(|glyph| glyph.start).min())
+            .collect()
+    }
+}
+
+impl CosmicTextSystemState {
+    fn add_fonts(&mut self, fonts: &[Arc<Vec<u8>>]) -> Result<()> {
+        let db = self.font_system.db_mut();
+        for bytes in fonts {
+            let ids = db.load_font_source(fontdb::Source::Binary(bytes.clone()));
+            if ids.is_empty() {
+                return Err(anyhow!("font data contains no usable faces"));
+            }
+        }
+        // New faces may be better matches for fonts that were already selected.
+        self.font_selections.clear();
+        Ok(())
+    }
+
+    fn select_font(&mut self, font: &Font) -> Result<FontId> {
+        let mut families = SmallVec::<[Family; 8]>::new();
+        families.push(match font.family.as_ref() {
+            "serif" => Family::Serif,
+            "sans-serif" => Family::SansSerif,
+            "monospace" => Family::Monospace,
+            "cursive" => Family::Cursive,
+            "fantasy" => Family::Fantasy,
+            name => Family::Name(name),
+        });
+        families.extend(substitute_families(&font.family).iter().cloned());
+
+        let cosmic_id = self
+            .font_system
+            .db()
+            .query(&fontdb::Query {
+                families: &families,
+                weight: font.weight.into(),
+                stretch: fontdb::Stretch::Normal,
+                style: font.style.into(),
+            })
+            .ok_or_else(|| anyhow!("could not find font family {:?}", font.family))?;
+        self.font_id_for_cosmic_id(cosmic_id)
+    }
+
+    fn font_id_for_cosmic_id(&mut self, cosmic_id: fontdb::ID) -> Result<FontId> {
+        if let Some(font_id) = self.font_ids_by_cosmic_id.get(&cosmic_id) {
+            return Ok(*font_id);
+        }
+
+        let face = self
+            .font_system
+            .db()
+            .face(cosmic_id)
+            .ok_or_else(|| anyhow!("font face {cosmic_id:?} is not loaded"))?;
+        let family = face
+            .families
+            .first()
+            .map(|(name, _)| name.clone())
+            .unwrap_or_default();
+        let (weight, style, stretch) = (face.weight, face.style, face.stretch);
+        let font = self
+            .font_system
+            .get_font(cosmic_id)
+            .ok_or_else(|| anyhow!("failed to load font {family:?}"))?;
+        let tables = font.rustybuzz().tables();
+        let is_emoji = tables.colr.is_some()
+            || tables.sbix.is_some()
+            || tables.cbdt.is_some()
+            || tables.svg.is_some();
+
+        let font_id = FontId(self.fonts.len());
+        self.fonts.push(LoadedFont {
+            font,
+            family,
+            weight,
+            style,
+            stretch,
+            is_emoji,
+        });
+        self.font_ids_by_cosmic_id.insert(cosmic_id, font_id);
+        Ok(font_id)
+    }
+
+    fn render_glyph(&mut self, params: &RenderGlyphParams) -> Result<&SwashImage> {
+        let font = &self.fonts[params.font_id.0];
+        let subpixel_shift = params
+            .subpixel_variant
+            .map(|variant| variant as f32 / SUBPIXEL_VARIANTS as f32);
+        let (cache_key, _, _) = CacheKey::new(
+            font.font.id(),
+            params.glyph_id.0 as u16,
+            params.font_size.0 * params.scale_factor,
+            (subpixel_shift.x, subpixel_shift.y),
+        );
+        self.swash_cache
+            .get_image(&mut self.font_system, cache_key)
+            .as_ref()
+            .ok_or_else(|| anyhow!("failed to rasterize glyph {}", params.glyph_id.0))
+    }
+
+    fn attrs_list(&self, font_runs: &[FontRun]) -> AttrsList {
+        let mut attrs_list = AttrsList::new(Attrs::new());
+        let mut offset = 0;
+        for run in font_runs {
+            let font = &self.fonts[run.font_id.0];
+            attrs_list.add_span(
+                offset..offset + run.len,
+                Attrs::new()
+                    .family(Family::Name(&font.family))
+                    .weight(font.weight)
+                    .style(font.style)
+                    .stretch(font.stretch),
+            );
+            offset += run.len;
+        }
+        attrs_list
+    }
+
+    fn shape_line(
+        &mut self,
+        text: &str,
+        font_size: Pixels,
+        font_runs: &[FontRun],
+        width: Option<Pixels>,
+    ) -> Vec<LayoutLine> {
+        let attrs_list = self.attrs_list(font_runs);
+        let line = ShapeLine::new_in_buffer(
+            &mut self.scratch,
+            &mut self.font_system,
+            text,
+            &attrs_list,
+            Shaping::Advanced,
+        );
+        let mut layout_lines = Vec::with_capacity(1);
+        line.layout_to_buffer(
+            &mut self.scratch,
+            font_size.0,
+            width.map_or(f32::MAX, |width| width.0),
+            if width.is_some() {
+                Wrap::Word
+            } else {
+                Wrap::None
+            },
+            None,
+            &mut layout_lines,
+        );
+        layout_lines
+    }
+
+    fn layout_line(&mut self, text: &str, font_size: Pixels, font_runs: &[FontRun]) -> LineLayout {
+        // Shaping only supports a single paragraph, so stop at the first line break.
+        let len = text.len();
+        let text = &text[..text.find(['\n', '\r']).unwrap_or(len)];
+        let layout_lines = self.shape_line(text, font_size, font_runs, None);
+
+        let mut runs: Vec<ShapedRun> = Vec::new();
+        let mut width = px(0.);
+        for layout_line in &layout_lines {
+            width = width.max(px(layout_line.w));
+            for glyph in &layout_line.glyphs {
+                let Ok(font_id) = self.font_id_for_cosmic_id(glyph.font_id) else {
+                    continue;
+                };
+                let shaped_glyph = ShapedGlyph {
+                    id: GlyphId(glyph.glyph_id as u32),
+                    position: point(
+                        px(glyph.x + glyph.x_offset * glyph.font_size),
+                        px(glyph.y - glyph.y_offset * glyph.font_size),
+                    ),
+                    index: glyph.start,
+                    is_emoji: self.fonts[font_id.0].is_emoji,
+                };
+                match runs.last_mut() {
+                    Some(run) if run.font_id == font_id => run.glyphs.push(shaped_glyph),
+                    _ => runs.push(ShapedRun {
+                        font_id,
+                        glyphs: SmallVec::from_elem(shaped_glyph, 1),
+                    }),
+                }
+            }
+        }
+
+        let (ascent, descent) = font_runs
+            .iter()
+            .map(|run| run.font_id)
+            .chain(runs.iter().map(|run| run.font_id))
+            .fold((px(0.), px(0.)), |(ascent, descent), font_id| {
+                let face = self.fonts[font_id.0].font.rustybuzz();
+                let scale = font_size / face.units_per_em() as f32;
+                (
+                    ascent.max(scale * face.ascender() as f32),
+                    descent.max(scale * -(face.descender() as f32)),
+                )
+            });
+
+        LineLayout {
+            font_size,
+            width,
+            ascent,
+            descent,
+            runs,
+            len,
+        }
+    }
+}
+
+/// Families to try, in order, when a font that is commonly installed on macOS or Windows
+/// is requested but not available. Each list starts with metric-compatible replacements.
+fn substitute_families(family: &str) -> &'static [Family<'static>] {
+    const SANS_SERIF: &[Family] = &[
+        Family::Name("Liberation Sans"),
+        Family::Name("Arimo"),
+        Family::Name("DejaVu Sans"),
+        Family::Name("Noto Sans"),
+        Family::SansSerif,
+    ];
+    const SERIF: &[Family] = &[
+        Family::Name("Liberation Serif"),
+        Family::Name("Tinos"),
+        Family::Name("DejaVu Serif"),
+        Family::Name("Noto Serif"),
+        Family::Serif,
+    ];
+    const MONOSPACE: &[Family] = &[
+        Family::Name("Liberation Mono"),
+        Family::Name("Cousine"),
+        Family::Name("DejaVu Sans Mono"),
+        Family::Name("Noto Sans Mono"),
+        Family::Monospace,
+    ];
+
+    match family {
+        "Helvetica" | "Helvetica Neue" | "Arial" | "Segoe UI" | "sans-serif" => SANS_SERIF,
+        "Times" | "Times New Roman" | "Georgia" | "serif" => SERIF,
+        "Courier" | "Courier New" | "Menlo" | "Monaco" | "Consolas" | "monospace" => MONOSPACE,
+        _ => &[],
+    }
+}
+
+fn bounds_from_rect(rect: ttf_parser::Rect) -> Bounds<f32> {
+    Bounds {
+        origin: point(rect.x_min as f32, rect.y_min as f32),
+        size: size(rect.width() as f32, rect.height() as f32),
+    }
+}
+
+impl From<FontWeight> for fontdb::Weight {
+    fn from(weight: FontWeight) -> Self {
+        fontdb::Weight(weight.0.round().clamp(1., 1000.) as u16)
+    }
+}
+
+impl From<FontStyle> for fontdb::Style {
+    fn from(style: FontStyle) -> Self {
+        match style {
+            FontStyle::Normal => fontdb::Style::Normal,
+            FontStyle::Italic => fontdb::Style::Italic,
+            FontStyle::Oblique => fontdb::Style::Oblique,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{font, px, CosmicTextSystem, FontRun, FontWeight, PlatformTextSystem};
+
+    /// A generic family, which resolves to whichever monospace font the host has installed, so
+    /// that the tests don't depend on a particular one.
+    const FAMILY: &str = "monospace";
+
+    #[test]
+    fn test_font_id_matches_weight() {
+        let fonts = CosmicTextSystem::new();
+        let regular = fonts.font_id(&font(FAMILY)).unwrap();
+        let bold = fonts.font_id(&font(FAMILY).bold()).unwrap();
+        assert_ne!(regular, bold);
+        assert_eq!(fonts.font_id(&font(FAMILY)).unwrap(), regular);
+        assert_eq!(
+            fonts
+                .font_id(&crate::Font {
+                    weight: FontWeight::EXTRA_BOLD,
+                    ..font(FAMILY)
+                })
+                .unwrap(),
+            bold
+        );
+    }
+
+    #[test]
+    fn test_wrap_line() {
+        let fonts = CosmicTextSystem::new();
+        let font_id = fonts.font_id(&font(FAMILY)).unwrap();
+
+        let line = "aa bbb cccc ddddd eeee\n";
+        let wrap_boundaries = fonts.wrap_line(line, font_id, px(16.), px(72.));
+        assert_eq!(
+            wrap_boundaries,
+            &[
+                "aa bbb ".len(),
+                "aa bbb cccc ".len(),
+                "aa bbb cccc ddddd ".len()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_line() {
+        let fonts = CosmicTextSystem::new();
+        let font_id = fonts.font_id(&font(FAMILY)).unwrap();
+        let line = "a\u{feff}b";
+        let layout = fonts.layout_line(
+            line,
+            px(16.),
+            &[FontRun {
+                font_id,
+                len: line.len(),
+            }],
+        );
+        assert_eq!(layout.len, line.len());
+        assert_eq!(layout.runs.len(), 1);
+
+        let glyphs = &layout.runs[0].glyphs;
+        let a = fonts.glyph_for_char(font_id, 'a').unwrap();
+        let b = fonts.glyph_for_char(font_id, 'b').unwrap();
+        assert_eq!(glyphs.first().unwrap().id, a);
+        assert_eq!(glyphs.last().unwrap().id, b);
+        assert_eq!(glyphs.last().unwrap().index, "a\u{feff}".len());
+        assert!(layout.ascent > px(0.) && layout.descent > px(0.));
+    }
+}
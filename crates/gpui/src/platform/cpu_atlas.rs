@@ -0,0 +1,434 @@
+use crate::{
+    AtlasKey, AtlasTextureId, AtlasTextureKind, AtlasTile, Bounds, DevicePixels, PlatformAtlas,
+    Point, Scene, Size, TileId,
+};
+use anyhow::{bail, Result};
+use collections::FxHashMap;
+use etagere::BucketedAtlasAllocator;
+use parking_lot::Mutex;
+use std::borrow::Cow;
+
+const INITIAL_TEXTURE_SIZE: i32 = 512;
+const MAX_TEXTURE_SIZE: i32 = 4096;
+
+/// A sprite atlas that packs tiles into textures held in main memory, so it can be
+/// shared by any renderer regardless of the graphics API behind it.
+///
+/// When no texture has room for a new tile, tiles that weren't used by the last
+/// drawn frame are evicted first, then the newest texture grows, and only then is
+/// another texture added. Renderers read the pixels through [`CpuTextureSource`].
+pub(crate) struct CpuAtlas(Mutex<CpuAtlasState>);
+
+/// Gives renderers read access to the pixels stored in a sprite atlas.
+pub(crate) trait CpuTextureSource {
+    /// Invokes the given callback with the contents of the texture for the given id,
+    /// if the texture exists.
+    fn with_texture(&self, id: AtlasTextureId, f: &mut dyn FnMut(CpuTexture));
+}
+
+/// A borrowed view of an atlas texture's pixels. Monochrome and path textures store
+/// one byte per pixel, polychrome textures store BGRA pixels.
+#[derive(Clone, Copy)]
+pub(crate) struct CpuTexture<'a> {
+    pub(crate) kind: AtlasTextureKind,
+    pub(crate) size: Size<DevicePixels>,
+    pub(crate) bytes: &'a [u8],
+}
+
+struct CpuAtlasState {
+    initial_texture_size: i32,
+    max_texture_size: i32,
+    monochrome_textures: Vec<CpuAtlasTexture>,
+    polychrome_textures: Vec<CpuAtlasTexture>,
+    path_textures: Vec<CpuAtlasTexture>,
+    tiles_by_key: FxHashMap<AtlasKey, AtlasTile>,
+    current_frame: u64,
+}
+
+struct CpuAtlasTexture {
+    id: AtlasTextureId,
+    allocator: BucketedAtlasAllocator,
+    size: Size<DevicePixels>,
+    bytes: Vec<u8>,
+    tiles: FxHashMap<TileId, TileUsage>,
+}
+
+struct TileUsage {
+    key: AtlasKey,
+    last_used_frame: u64,
+}
+
+impl CpuAtlas {
+    pub(crate) fn new() -> Self {
+        Self::with_texture_sizes(INITIAL_TEXTURE_SIZE, MAX_TEXTURE_SIZE)
+    }
+
+    fn with_texture_sizes(initial_texture_size: i32, max_texture_size: i32) -> Self {
+        CpuAtlas(Mutex::new(CpuAtlasState {
+            initial_texture_size,
+            max_texture_size,
+            monochrome_textures: Vec::new(),
+            polychrome_textures: Vec::new(),
+            path_textures: Vec::new(),
+            tiles_by_key: Default::default(),
+            current_frame: 0,
+        }))
+    }
+
+    /// Records that the tiles referenced by the given scene were used by the frame
+    /// that was just drawn, and starts a new frame. Tiles are only evicted once a
+    /// drawn frame no longer references them, since later frames may reuse the
+    /// primitives of earlier ones without requesting their tiles again.
+    pub(crate) fn finish_frame(&self, scene: &Scene) {
+        let mut lock = self.0.lock();
+        for sprite in &scene.monochrome_sprites {
+            lock.mark_used(&sprite.tile);
+        }
+        for sprite in &scene.polychrome_sprites {
+            lock.mark_used(&sprite.tile);
+        }
+        lock.current_frame += 1;
+    }
+}
+
+impl PlatformAtlas for CpuAtlas {
+    fn get_or_insert_with<'a>(
+        &self,
+        key: &AtlasKey,
+        build: &mut dyn FnMut() -> Result<(Size<DevicePixels>, Cow<'a, [u8]>)>,
+    ) -> Result<AtlasTile> {
+        let mut lock = self.0.lock();
+        if let Some(tile) = lock.tiles_by_key.get(key).cloned() {
+            lock.mark_used(&tile);
+            return Ok(tile);
+        }
+
+        let (size, bytes) = build()?;
+        if size.width.0 <= 0 || size.height.0 <= 0 {
+            bail!("cannot insert an empty tile into the atlas");
+        }
+        let kind = key.texture_kind();
+        let expected_len = size.width.0 as usize * size.height.0 as usize * bytes_per_pixel(kind);
+        if bytes.len() != expected_len {
+            bail!(
+                "tile of size {size:?} has {} bytes, expected {expected_len}",
+                bytes.len()
+            );
+        }
+
+        let tile = lock.allocate(size, kind);
+        let current_frame = lock.current_frame;
+        let texture = lock.texture_mut(tile.texture_id);
+        texture.upload(tile.bounds, &bytes);
+        texture.tiles.insert(
+            tile.tile_id,
+            TileUsage {
+                key: key.clone(),
+                last_used_frame: current_frame,
+            },
+        );
+        lock.tiles_by_key.insert(key.clone(), tile.clone());
+        Ok(tile)
+    }
+
+    fn texture_source(&self) -> Option<&dyn CpuTextureSource> {
+        Some(self)
+    }
+}
+
+impl CpuTextureSource for CpuAtlas {
+    fn with_texture(&self, id: AtlasTextureId, f: &mut dyn FnMut(CpuTexture)) {
+        let lock = self.0.lock();
+        if let Some(texture) = lock.textures(id.kind).get(id.index as usize) {
+            f(CpuTexture {
+                kind: id.kind,
+                size: texture.size,
+                bytes: &texture.bytes,
+            });
+        }
+    }
+}
+
+impl CpuAtlasState {
+    fn allocate(&mut self, size: Size<DevicePixels>, kind: AtlasTextureKind) -> AtlasTile {
+        if let Some(tile) = self.allocate_in_existing_textures(size, kind) {
+            return tile;
+        }
+
+        self.evict_unused_tiles(kind);
+        if let Some(tile) = self.allocate_in_existing_textures(size, kind) {
+            return tile;
+        }
+
+        let max_texture_size = self.max_texture_size;
+        if let Some(texture) = self.textures_mut(kind).last_mut() {
+            while texture.size.width.0 < max_texture_size
+                || texture.size.height.0 < max_texture_size
+            {
+                let new_size = texture
+                    .size
+                    .map(|dimension| DevicePixels((dimension.0 * 2).min(max_texture_size)));
+                texture.grow(new_size);
+                if let Some(tile) = texture.allocate(size) {
+                    return tile;
+                }
+            }
+        }
+
+        self.push_texture(size, kind)
+            .allocate(size)
+            .expect("a new texture should fit the tile it was created for")
+    }
+
+    fn allocate_in_existing_textures(
+        &mut self,
+        size: Size<DevicePixels>,
+        kind: AtlasTextureKind,
+    ) -> Option<AtlasTile> {
+        self.textures_mut(kind)
+            .iter_mut()
+            .rev()
+            .find_map(|texture| texture.allocate(size))
+    }
+
+    /// Frees the tiles of the given kind that weren't used since the last drawn frame.
+    fn evict_unused_tiles(&mut self, kind: AtlasTextureKind) {
+        let current_frame = self.current_frame;
+        let mut evicted_keys = Vec::new();
+        for texture in self.textures_mut(kind) {
+            let CpuAtlasTexture {
+                allocator, tiles, ..
+            } = texture;
+            tiles.retain(|tile_id, usage| {
+                if usage.last_used_frame + 1 < current_frame {
+                    allocator.deallocate((*tile_id).into());
+                    evicted_keys.push(usage.key.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        for key in evicted_keys {
+            self.tiles_by_key.remove(&key);
+        }
+    }
+
+    fn push_texture(
+        &mut self,
+        min_size: Size<DevicePixels>,
+        kind: AtlasTextureKind,
+    ) -> &mut CpuAtlasTexture {
+        let initial_size = DevicePixels(self.initial_texture_size);
+        let size = min_size.max(&Size {
+            width: initial_size,
+            height: initial_size,
+        });
+        let textures = self.textures_mut(kind);
+        textures.push(CpuAtlasTexture {
+            id: AtlasTextureId {
+                index: textures.len() as u32,
+                kind,
+            },
+            allocator: BucketedAtlasAllocator::new(etagere_size(size)),
+            size,
+            bytes: vec![0; byte_len(size, kind)],
+            tiles: Default::default(),
+        });
+        textures.last_mut().unwrap()
+    }
+
+    fn mark_used(&mut self, tile: &AtlasTile) {
+        let current_frame = self.current_frame;
+        if let Some(usage) = self
+            .textures_mut(tile.texture_id.kind)
+            .get_mut(tile.texture_id.index as usize)
+            .and_then(|texture| texture.tiles.get_mut(&tile.tile_id))
+        {
+            usage.last_used_frame = current_frame;
+        }
+    }
+
+    fn textures(&self, kind: AtlasTextureKind) -> &Vec<CpuAtlasTexture> {
+        match kind {
+            AtlasTextureKind::Monochrome => &self.monochrome_textures,
+            AtlasTextureKind::Polychrome => &self.polychrome_textures,
+            AtlasTextureKind::Path => &self.path_textures,
+        }
+    }
+
+    fn textures_mut(&mut self, kind: AtlasTextureKind) -> &mut Vec<CpuAtlasTexture> {
+        match kind {
+            AtlasTextureKind::Monochrome => &mut self.monochrome_textures,
+            AtlasTextureKind::Polychrome => &mut self.polychrome_textures,
+            AtlasTextureKind::Path => &mut self.path_textures,
+        }
+    }
+
+    fn texture_mut(&mut self, id: AtlasTextureId) -> &mut CpuAtlasTexture {
+        &mut self.textures_mut(id.kind)[id.index as usize]
+    }
+}
+
+impl CpuAtlasTexture {
+    fn allocate(&mut self, size: Size<DevicePixels>) -> Option<AtlasTile> {
+        let allocation = self.allocator.allocate(etagere_size(size))?;
+        Some(AtlasTile {
+            texture_id: self.id,
+            tile_id: allocation.id.into(),
+            bounds: Bounds {
+                origin: Point {
+                    x: DevicePixels(allocation.rectangle.min.x),
+                    y: DevicePixels(allocation.rectangle.min.y),
+                },
+                size,
+            },
+        })
+    }
+
+    /// Enlarges the texture, keeping existing tiles at their current positions.
+    fn grow(&mut self, new_size: Size<DevicePixels>) {
+        self.allocator.grow(etagere_size(new_size));
+        let bytes_per_pixel = bytes_per_pixel(self.id.kind);
+        let old_stride = self.size.width.0 as usize * bytes_per_pixel;
+        let new_stride = new_size.width.0 as usize * bytes_per_pixel;
+        let mut bytes = vec![0; byte_len(new_size, self.id.kind)];
+        for (old_row, new_row) in self
+            .bytes
+            .chunks_exact(old_stride)
+            .zip(bytes.chunks_exact_mut(new_stride))
+        {
+            new_row[..old_stride].copy_from_slice(old_row);
+        }
+        self.bytes = bytes;
+        self.size = new_size;
+    }
+
+    fn upload(&mut self, bounds: Bounds<DevicePixels>, bytes: &[u8]) {
+        let bytes_per_pixel = bytes_per_pixel(self.id.kind);
+        let stride = self.size.width.0 as usize * bytes_per_pixel;
+        let row_len = bounds.size.width.0 as usize * bytes_per_pixel;
+        let x_offset = bounds.origin.x.0 as usize * bytes_per_pixel;
+        for (row, tile_row) in bytes.chunks_exact(row_len).enumerate() {
+            let offset = (bounds.origin.y.0 as usize + row) * stride + x_offset;
+            self.bytes[offset..offset + row_len].copy_from_slice(tile_row);
+        }
+    }
+}
+
+/// The number of bytes used to store a single pixel in a texture of the given kind.
+pub(crate) fn bytes_per_pixel(kind: AtlasTextureKind) -> usize {
+    match kind {
+        AtlasTextureKind::Monochrome | AtlasTextureKind::Path => 1,
+        AtlasTextureKind::Polychrome => 4,
+    }
+}
+
+fn byte_len(size: Size<DevicePixels>, kind: AtlasTextureKind) -> usize {
+    size.width.0 as usize * size.height.0 as usize * bytes_per_pixel(kind)
+}
+
+fn etagere_size(size: Size<DevicePixels>) -> etagere::Size {
+    etagere::size2(size.width.0, size.height.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{size, RenderSvgParams};
+
+    fn svg_key(name: &'static str, width: i32, height: i32) -> AtlasKey {
+        AtlasKey::Svg(RenderSvgParams {
+            path: name.into(),
+            size: size(DevicePixels(width), DevicePixels(height)),
+            color_mode: Default::default(),
+        })
+    }
+
+    fn insert(atlas: &CpuAtlas, key: &AtlasKey, fill: u8) -> AtlasTile {
+        let AtlasKey::Svg(params) = key else {
+            unreachable!()
+        };
+        let size = params.size;
+        atlas
+            .get_or_insert_with(key, &mut || {
+                let len = size.width.0 as usize * size.height.0 as usize;
+                Ok((size, Cow::Owned(vec![fill; len])))
+            })
+            .unwrap()
+    }
+
+    fn tile_bytes(atlas: &CpuAtlas, tile: &AtlasTile) -> Vec<u8> {
+        let mut result = Vec::new();
+        atlas.with_texture(tile.texture_id, &mut |texture| {
+            let width = texture.size.width.0 as usize;
+            for y in 0..tile.bounds.size.height.0 as usize {
+                let row = (tile.bounds.origin.y.0 as usize + y) * width;
+                let start = row + tile.bounds.origin.x.0 as usize;
+                result.extend_from_slice(
+                    &texture.bytes[start..start + tile.bounds.size.width.0 as usize],
+                );
+            }
+        });
+        result
+    }
+
+    #[test]
+    fn test_tiles_share_textures() {
+        let atlas = CpuAtlas::new();
+        let a = insert(&atlas, &svg_key("a", 4, 3), 1);
+        let b = insert(&atlas, &svg_key("b", 5, 2), 2);
+        assert_eq!(a.texture_id, b.texture_id);
+        assert_ne!(a.bounds.origin, b.bounds.origin);
+        assert_eq!(tile_bytes(&atlas, &a), vec![1; 12]);
+        assert_eq!(tile_bytes(&atlas, &b), vec![2; 10]);
+
+        let mut rebuilt = false;
+        let cached = atlas
+            .get_or_insert_with(&svg_key("a", 4, 3), &mut || {
+                rebuilt = true;
+                Ok((
+                    size(DevicePixels(4), DevicePixels(3)),
+                    Cow::Owned(vec![9; 12]),
+                ))
+            })
+            .unwrap();
+        assert_eq!(cached, a);
+        assert!(!rebuilt);
+    }
+
+    #[test]
+    fn test_textures_grow_when_full() {
+        let atlas = CpuAtlas::with_texture_sizes(32, 64);
+        let first = insert(&atlas, &svg_key("first", 32, 32), 1);
+        let second = insert(&atlas, &svg_key("second", 32, 32), 2);
+        assert_eq!(first.texture_id, second.texture_id);
+        atlas.with_texture(first.texture_id, &mut |texture| {
+            assert_eq!(texture.size, size(DevicePixels(64), DevicePixels(64)));
+        });
+        assert_eq!(tile_bytes(&atlas, &first), vec![1; 32 * 32]);
+        assert_eq!(tile_bytes(&atlas, &second), vec![2; 32 * 32]);
+
+        let oversized = insert(&atlas, &svg_key("oversized", 100, 10), 3);
+        assert_ne!(oversized.texture_id, first.texture_id);
+        assert_eq!(tile_bytes(&atlas, &oversized), vec![3; 1000]);
+    }
+
+    #[test]
+    fn test_evicts_tiles_unused_by_the_last_frame() {
+        let atlas = CpuAtlas::with_texture_sizes(32, 32);
+        let stale = insert(&atlas, &svg_key("stale", 32, 32), 1);
+        atlas.finish_frame(&Scene::default());
+        atlas.finish_frame(&Scene::default());
+
+        let fresh = insert(&atlas, &svg_key("fresh", 32, 32), 2);
+        assert_eq!(fresh.texture_id, stale.texture_id);
+        assert_eq!(tile_bytes(&atlas, &fresh), vec![2; 32 * 32]);
+
+        // The fresh tile was inserted during the current frame, so it can't be evicted
+        // and the stale key has to be rebuilt in a new texture.
+        let rebuilt = insert(&atlas, &svg_key("stale", 32, 32), 3);
+        assert_ne!(rebuilt.texture_id, fresh.texture_id);
+        assert_eq!(tile_bytes(&atlas, &rebuilt), vec![3; 32 * 32]);
+    }
+}
@@ -0,0 +1,1589 @@
+use crate::{
+    bytes_per_pixel, point, AtlasTextureKind, AtlasTile, Background, BackgroundTag, BorderStyle,
+    Bounds, ColorSpace, ContentMask, Corners, CpuTexture, CpuTextureSource, DevicePixels,
+    FilterEffect, Hsla, LayerMask, MonochromeSprite, Path, PathCoverage, PolychromeSprite,
+    PrimitiveBatch, Quad, Rgba, ScaledPixels, Scene, Shadow, Size, TransformationMatrix, Underline,
+};
+use std::{f32::consts::PI, mem, ops::Range};
+
+/// Rasterizes scenes into an RGBA framebuffer held in main memory.
+///
+/// Coverage is computed per pixel center with the same math as the Metal shaders,
+/// so the output can serve as a reference for the GPU renderers on machines that
+/// have neither Metal nor Vulkan.
+pub(crate) struct CpuRenderer {
+    size: Size<DevicePixels>,
+    pixels: Vec<u8>,
+    /// The framebuffers beneath each filter layer that is being painted.
+    layers: Vec<Vec<u8>>,
+}
+
+impl CpuRenderer {
+    pub fn new(size: Size<DevicePixels>) -> Self {
+        Self {
+            size,
+            pixels: vec![0; byte_len(size)],
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn update_drawable_size(&mut self, size: Size<DevicePixels>) {
+        if size != self.size {
+            self.size = size;
+            self.pixels = vec![0; byte_len(size)];
+        }
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    /// The size of the framebuffer in device pixels.
+    pub fn size(&self) -> Size<DevicePixels> {
+        self.size
+    }
+
+    /// The contents of the framebuffer, as rows of RGBA pixels with straight alpha.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Clears the framebuffer and paints every batch of the scene in order.
+    pub fn draw(&mut self, scene: &Scene, textures: &dyn CpuTextureSource) {
+        self.pixels.fill(0);
+        for batch in scene.batches() {
+            match batch {
+                PrimitiveBatch::Shadows(shadows) => {
+                    for shadow in shadows {
+                        self.draw_shadow(shadow);
+                    }
+                }
+                PrimitiveBatch::Quads(quads) => {
+                    for quad in quads {
+                        self.draw_quad(quad);
+                    }
+                }
+                PrimitiveBatch::Paths(paths) => {
+                    for path in paths {
+                        self.draw_path(path);
+                    }
+                }
+                PrimitiveBatch::Underlines(underlines) => {
+                    for underline in underlines {
+                        self.draw_underline(underline);
+                    }
+                }
+                PrimitiveBatch::MonochromeSprites {
+                    texture_id,
+                    sprites,
+                } => textures.with_texture(texture_id, &mut |texture| {
+                    for sprite in sprites {
+                        self.draw_monochrome_sprite(sprite, texture);
+                    }
+                }),
+                PrimitiveBatch::PolychromeSprites {
+                    texture_id,
+                    sprites,
+                } => textures.with_texture(texture_id, &mut |texture| {
+                    for sprite in sprites {
+                        self.draw_polychrome_sprite(sprite, texture);
+                    }
+                }),
+                // Surfaces wrap platform video frames, which have no CPU representation.
+                PrimitiveBatch::Surfaces(_) => {}
+                PrimitiveBatch::BeginFilter(_) => {
+                    let layer = vec![0; self.pixels.len()];
+                    self.layers.push(mem::replace(&mut self.pixels, layer));
+                }
+                PrimitiveBatch::EndFilter(filter) => {
+                    let beneath = self.layers.pop().expect("filter layer was never begun");
+                    let layer = mem::replace(&mut self.pixels, beneath);
+                    let mask = filter
+                        .mask
+                        .as_ref()
+                        .map(|mask| self.paint_mask(mask, textures));
+                    self.composite_filter_layer(&layer, &filter.effect, mask.as_deref());
+                }
+            }
+        }
+    }
+
+    fn draw_quad(&mut self, quad: &Quad) {
+        let solid_background = quad.background.as_solid().map(Rgba::from);
+        let edge = |width: ScaledPixels, color: Hsla, style: BorderStyle| {
+            (width.0, Rgba::from(color), style)
+        };
+        let widths = &quad.border_widths;
+        let colors = &quad.border_colors;
+        let styles = &quad.border_styles;
+        let top_edge = edge(widths.top, colors.top, styles.top);
+        let right_edge = edge(widths.right, colors.right, styles.right);
+        let bottom_edge = edge(widths.bottom, colors.bottom, styles.bottom);
+        let left_edge = edge(widths.left, colors.left, styles.left);
+        let is_plain = quad.border_widths.top.0 == 0.
+            && quad.border_widths.right.0 == 0.
+            && quad.border_widths.bottom.0 == 0.
+            && quad.border_widths.left.0 == 0.
+            && corner_radii_are_zero(&quad.corner_radii);
+        let half_size = half_size(&quad.bounds);
+        let center = center(&quad.bounds);
+
+        self.fill(&quad.bounds, &quad.transform, &quad.content_mask, |x, y| {
+            let background = solid_background
+                .unwrap_or_else(|| gradient_color(&quad.background, &quad.bounds, (x, y)));
+            if is_plain {
+                return background;
+            }
+
+            let center_to_point = (x - center.0, y - center.1);
+            let corner_radius = corner_radius(&quad.corner_radii, center_to_point);
+            let distance = rounded_rect_distance(center_to_point, half_size, corner_radius);
+
+            let vertical_edge = if center_to_point.0 <= 0. {
+                left_edge
+            } else {
+                right_edge
+            };
+            let horizontal_edge = if center_to_point.1 <= 0. {
+                top_edge
+            } else {
+                bottom_edge
+            };
+            let point_to_inset_corner = (
+                center_to_point.0.abs() - (half_size.0 - corner_radius - vertical_edge.0),
+                center_to_point.1.abs() - (half_size.1 - corner_radius - horizontal_edge.0),
+            );
+
+            // Dashes and dots are laid out along the middle of the border, starting from the
+            // middle of the corner where the edge begins.
+            let (border_width, border_color, border_style, along_edge, edge_length) =
+                if point_to_inset_corner.0 < 0. && point_to_inset_corner.1 < 0. {
+                    (0., background, BorderStyle::Solid, 0., 0.)
+                } else if point_to_inset_corner.1 > point_to_inset_corner.0 {
+                    let (width, color, style) = horizontal_edge;
+                    let start = quad.bounds.origin.x.0 + left_edge.0 / 2.;
+                    let end = quad.bounds.origin.x.0 + 2. * half_size.0 - right_edge.0 / 2.;
+                    (width, color, style, x - start, end - start)
+                } else {
+                    let (width, color, style) = vertical_edge;
+                    let start = quad.bounds.origin.y.0 + top_edge.0 / 2.;
+                    let end = quad.bounds.origin.y.0 + 2. * half_size.1 - bottom_edge.0 / 2.;
+                    (width, color, style, y - start, end - start)
+                };
+
+            let color = if border_width == 0. {
+                background
+            } else {
+                let pattern_alpha = border_pattern_alpha(
+                    border_style,
+                    border_width,
+                    along_edge,
+                    edge_length,
+                    distance + border_width / 2.,
+                );
+                // Blend the border on top of the background and then linearly interpolate
+                // between the two as we slide inside the background.
+                let blended_border = over(
+                    background,
+                    with_alpha(border_color, border_color.a * pattern_alpha),
+                );
+                mix(
+                    blended_border,
+                    background,
+                    saturate(0.5 - (distance + border_width)),
+                )
+            };
+            with_alpha(color, color.a * saturate(0.5 - distance))
+        });
+    }
+
+    fn draw_shadow(&mut self, shadow: &Shadow) {
+        let color = Rgba::from(shadow.color);
+        let blur_radius = shadow.blur_radius.0;
+
+        if shadow.inset {
+            // The shadow is cast by a hole in an infinite plane, painted inside the element.
+            let spread = shadow.spread_radius.0;
+            let hole = Bounds {
+                origin: point(
+                    ScaledPixels(shadow.bounds.origin.x.0 + shadow.offset.x.0 + spread),
+                    ScaledPixels(shadow.bounds.origin.y.0 + shadow.offset.y.0 + spread),
+                ),
+                size: shadow
+                    .bounds
+                    .size
+                    .map(|size| ScaledPixels((size.0 - 2. * spread).max(0.))),
+            };
+            let hole_radii = shadow
+                .corner_radii
+                .map(|radius| ScaledPixels((radius.0 - spread).max(0.)));
+            let half_size = half_size(&shadow.bounds);
+            let center = center(&shadow.bounds);
+            self.fill(
+                &shadow.bounds,
+                &shadow.transform,
+                &shadow.content_mask,
+                |x, y| {
+                    let center_to_point = (x - center.0, y - center.1);
+                    let corner_radius = corner_radius(&shadow.corner_radii, center_to_point);
+                    let distance = rounded_rect_distance(center_to_point, half_size, corner_radius);
+                    let alpha = 1. - shadow_alpha(&hole, &hole_radii, blur_radius, (x, y));
+                    with_alpha(color, color.a * alpha * saturate(0.5 - distance))
+                },
+            );
+            return;
+        }
+
+        let margin = 3. * blur_radius;
+        let bounds = Bounds {
+            origin: shadow
+                .bounds
+                .origin
+                .map(|origin| ScaledPixels(origin.0 - margin)),
+            size: shadow
+                .bounds
+                .size
+                .map(|size| ScaledPixels(size.0 + 2. * margin)),
+        };
+        self.fill(&bounds, &shadow.transform, &shadow.content_mask, |x, y| {
+            let alpha = shadow_alpha(&shadow.bounds, &shadow.corner_radii, blur_radius, (x, y));
+            with_alpha(color, color.a * alpha)
+        });
+    }
+
+    fn draw_underline(&mut self, underline: &Underline) {
+        let color = Rgba::from(underline.color);
+        let bounds = &underline.bounds;
+        let thickness = underline.thickness.0;
+        let height = bounds.size.height.0;
+
+        self.fill(
+            bounds,
+            &underline.transform,
+            &underline.content_mask,
+            |x, y| {
+                if !underline.wavy {
+                    return color;
+                }
+
+                let half_thickness = thickness * 0.5;
+                let st = (
+                    (x - bounds.origin.x.0) / height,
+                    (y - bounds.origin.y.0) / height - 0.5,
+                );
+                let frequency = (PI * (3. * thickness)) / 8.;
+                let amplitude = 1. / (2. * thickness);
+                let sine = (st.0 * frequency).sin() * amplitude;
+                let d_sine = (st.0 * frequency).cos() * amplitude * frequency;
+                let distance = (st.1 - sine) / (1. + d_sine * d_sine).sqrt();
+                let distance_in_pixels = distance * height;
+                let distance_from_top_border = distance_in_pixels - half_thickness;
+                let distance_from_bottom_border = distance_in_pixels + half_thickness;
+                let alpha =
+                    saturate(0.5 - (-distance_from_bottom_border).max(distance_from_top_border));
+                with_alpha(color, color.a * alpha)
+            },
+        );
+    }
+
+    fn draw_monochrome_sprite(&mut self, sprite: &MonochromeSprite, texture: CpuTexture) {
+        let color = Rgba::from(sprite.color);
+        self.fill(
+            &sprite.bounds,
+            &sprite.transform,
+            &sprite.content_mask,
+            |x, y| {
+                let sample = texture.sample(&sprite.tile, &sprite.bounds, x, y);
+                with_alpha(color, color.a * sample.a)
+            },
+        );
+    }
+
+    fn draw_polychrome_sprite(&mut self, sprite: &PolychromeSprite, texture: CpuTexture) {
+        let half_size = half_size(&sprite.bounds);
+        let center = center(&sprite.bounds);
+        self.fill(
+            &sprite.bounds,
+            &sprite.transform,
+            &sprite.content_mask,
+            |x, y| {
+                let mut color = texture.sample(&sprite.tile, &sprite.bounds, x, y);
+                if sprite.grayscale {
+                    let grayscale = 0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b;
+                    color.r = grayscale;
+                    color.g = grayscale;
+                    color.b = grayscale;
+                }
+                let center_to_point = (x - center.0, y - center.1);
+                let corner_radius = corner_radius(&sprite.corner_radii, center_to_point);
+                let distance = rounded_rect_distance(center_to_point, half_size, corner_radius);
+                with_alpha(color, color.a * sprite.opacity * saturate(0.5 - distance))
+            },
+        );
+    }
+
+    /// Accumulates the coverage of the path's triangles, then paints the path's color
+    /// wherever that coverage has an odd winding, or any coverage at all for strokes,
+    /// like the GPU renderers' path atlas.
+    fn draw_path(&mut self, path: &Path<ScaledPixels>) {
+        let Some((x_range, y_range)) = self.pixel_ranges(&path.bounds, &path.content_mask.bounds)
+        else {
+            return;
+        };
+        let width = x_range.len();
+        let mut coverage = vec![0f32; width * y_range.len()];
+
+        for triangle in path.vertices.chunks_exact(3) {
+            let mut vertices = [
+                (triangle[0].xy_position, triangle[0].st_position),
+                (triangle[1].xy_position, triangle[1].st_position),
+                (triangle[2].xy_position, triangle[2].st_position),
+            ]
+            .map(|(xy, st)| ((xy.x.0, xy.y.0), (st.x, st.y)));
+            let mut area = edge_function(vertices[0].0, vertices[1].0, vertices[2].0);
+            if area == 0. {
+                continue;
+            }
+            if area < 0. {
+                vertices.swap(1, 2);
+                area = -area;
+            }
+            let [(a, st_a), (b, st_b), (c, st_c)] = vertices;
+
+            // The st coordinates are an affine function of the position, so their screen
+            // space derivatives are constant across the triangle.
+            let ds = (
+                ((b.1 - c.1) * st_a.0 + (c.1 - a.1) * st_b.0 + (a.1 - b.1) * st_c.0) / area,
+                ((c.0 - b.0) * st_a.0 + (a.0 - c.0) * st_b.0 + (b.0 - a.0) * st_c.0) / area,
+            );
+            let dt = (
+                ((b.1 - c.1) * st_a.1 + (c.1 - a.1) * st_b.1 + (a.1 - b.1) * st_c.1) / area,
+                ((c.0 - b.0) * st_a.1 + (a.0 - c.0) * st_b.1 + (b.0 - a.0) * st_c.1) / area,
+            );
+
+            let min_x = a.0.min(b.0).min(c.0).floor().max(x_range.start as f32) as usize;
+            let max_x = a.0.max(b.0).max(c.0).ceil().min(x_range.end as f32) as usize;
+            let min_y = a.1.min(b.1).min(c.1).floor().max(y_range.start as f32) as usize;
+            let max_y = a.1.max(b.1).max(c.1).ceil().min(y_range.end as f32) as usize;
+            for y in min_y..max_y {
+                for x in min_x..max_x {
+                    let p = (x as f32 + 0.5, y as f32 + 0.5);
+                    let w_a = edge_function(b, c, p);
+                    let w_b = edge_function(c, a, p);
+                    let w_c = edge_function(a, b, p);
+                    if !covers_edge(w_a, b, c) || !covers_edge(w_b, c, a) || !covers_edge(w_c, a, b)
+                    {
+                        continue;
+                    }
+
+                    let s = (w_a * st_a.0 + w_b * st_b.0 + w_c * st_c.0) / area;
+                    let t = (w_a * st_a.1 + w_b * st_b.1 + w_c * st_c.1) / area;
+                    let gradient = (2. * s * ds.0 - dt.0, 2. * s * ds.1 - dt.1);
+                    let gradient_length = gradient.0.hypot(gradient.1);
+                    let f = s * s - t;
+                    let alpha = if gradient_length > 0. {
+                        saturate(0.5 - f / gradient_length)
+                    } else if f <= 0. {
+                        1.
+                    } else {
+                        0.
+                    };
+                    coverage[(y - y_range.start) * width + (x - x_range.start)] += alpha;
+                }
+            }
+        }
+
+        let color = Rgba::from(path.color);
+        for (row, y) in y_range.enumerate() {
+            for (column, x) in x_range.clone().enumerate() {
+                let coverage = coverage[row * width + column];
+                let mask = match path.coverage {
+                    PathCoverage::EvenOdd => 1. - (1. - coverage.rem_euclid(2.)).abs(),
+                    PathCoverage::Union => saturate(coverage),
+                };
+                let mask = mask * content_mask_alpha(&path.content_mask, x, y);
+                if mask > 0. {
+                    self.blend_pixel(x, y, with_alpha(color, color.a * mask));
+                }
+            }
+        }
+    }
+
+    /// Blends the color computed for each pixel whose center lies within both the
+    /// transformed primitive and its content mask. Colors are computed at the point in the
+    /// primitive's own space that the pixel center maps back to.
+    fn fill(
+        &mut self,
+        bounds: &Bounds<ScaledPixels>,
+        transform: &TransformationMatrix,
+        content_mask: &ContentMask<ScaledPixels>,
+        mut shade: impl FnMut(f32, f32) -> Rgba,
+    ) {
+        let Some(inverse) = transform.inverse() else {
+            return;
+        };
+        let Some((x_range, y_range)) = self.pixel_ranges(
+            &transform.apply_to_scaled_bounds(bounds),
+            &content_mask.bounds,
+        ) else {
+            return;
+        };
+        let lower_right = bounds.lower_right();
+        for y in y_range {
+            for x in x_range.clone() {
+                let center = point(ScaledPixels(x as f32 + 0.5), ScaledPixels(y as f32 + 0.5));
+                let local = inverse.apply_scaled(center);
+                if local.x.0 < bounds.origin.x.0
+                    || local.x.0 >= lower_right.x.0
+                    || local.y.0 < bounds.origin.y.0
+                    || local.y.0 >= lower_right.y.0
+                {
+                    continue;
+                }
+
+                let color = shade(local.x.0, local.y.0);
+                let color = with_alpha(color, color.a * content_mask_alpha(content_mask, x, y));
+                if color.a > 0. {
+                    self.blend_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    /// Blurs the layer, applies the effect's color matrix to it and blends it onto the
+    /// framebuffer within the effect's content mask.
+    /// Paints a layer's mask into a framebuffer of its own and returns it.
+    fn paint_mask(&mut self, mask: &LayerMask, textures: &dyn CpuTextureSource) -> Vec<u8> {
+        let framebuffer = vec![0; self.pixels.len()];
+        let beneath = mem::replace(&mut self.pixels, framebuffer);
+        match mask {
+            LayerMask::Path(path) => self.draw_path(path),
+            LayerMask::Quad(quad) => self.draw_quad(quad),
+            LayerMask::Sprite(sprite) => textures
+                .with_texture(sprite.tile.texture_id, &mut |texture| {
+                    self.draw_polychrome_sprite(sprite, texture)
+                }),
+        }
+        mem::replace(&mut self.pixels, beneath)
+    }
+
+    /// Blurs, color-filters and masks a finished filter layer, then blends it onto the
+    /// framebuffer. The mask's alpha scales the layer's.
+    fn composite_filter_layer(&mut self, layer: &[u8], effect: &FilterEffect, mask: Option<&[u8]>) {
+        let content_bounds = &effect.content_mask.bounds;
+        let Some((x_range, y_range)) = self.pixel_ranges(content_bounds, content_bounds) else {
+            return;
+        };
+
+        // Blur with premultiplied alpha, so that transparent pixels don't darken their
+        // neighbours.
+        let width = self.size.width.0 as usize;
+        let height = self.size.height.0 as usize;
+        let mut premultiplied = layer
+            .chunks_exact(4)
+            .map(|pixel| {
+                let a = pixel[3] as f32 / 255.;
+                let channel = |ix: usize| pixel[ix] as f32 / 255. * a;
+                [channel(0), channel(1), channel(2), a]
+            })
+            .collect::<Vec<_>>();
+        if effect.blur_radius.0 > 0. {
+            let sigma = effect.blur_radius.0;
+            premultiplied = blur_pixels(&premultiplied, width, height, sigma, (1, 0));
+            premultiplied = blur_pixels(&premultiplied, width, height, sigma, (0, 1));
+        }
+
+        for y in y_range {
+            for x in x_range.clone() {
+                let [r, g, b, a] = premultiplied[y * width + x];
+                if a <= 0. {
+                    continue;
+                }
+                let [r, g, b] = effect.color_matrix.apply([r / a, g / a, b / a]);
+                let mask_alpha =
+                    mask.map_or(1., |mask| mask[(y * width + x) * 4 + 3] as f32 / 255.);
+                let a = a * mask_alpha * content_mask_alpha(&effect.content_mask, x, y);
+                self.blend_pixel(x, y, Rgba { r, g, b, a });
+            }
+        }
+    }
+
+    fn pixel_ranges(
+        &self,
+        bounds: &Bounds<ScaledPixels>,
+        clip_bounds: &Bounds<ScaledPixels>,
+    ) -> Option<(Range<usize>, Range<usize>)> {
+        let bounds = bounds.intersect(clip_bounds);
+        let lower_right = bounds.lower_right();
+        // A pixel is covered when its center lies in [min, max).
+        let to_pixel = |value: f32, max: i32| (value - 0.5).ceil().clamp(0., max as f32) as usize;
+        let x_range = to_pixel(bounds.origin.x.0, self.size.width.0)
+            ..to_pixel(lower_right.x.0, self.size.width.0);
+        let y_range = to_pixel(bounds.origin.y.0, self.size.height.0)
+            ..to_pixel(lower_right.y.0, self.size.height.0);
+        if x_range.is_empty() || y_range.is_empty() {
+            None
+        } else {
+            Some((x_range, y_range))
+        }
+    }
+
+    fn blend_pixel(&mut self, x: usize, y: usize, color: Rgba) {
+        let offset = (y * self.size.width.0 as usize + x) * 4;
+        let pixel = &mut self.pixels[offset..offset + 4];
+        let src_a = saturate(color.a);
+        let dst_a = pixel[3] as f32 / 255.;
+        let out_a = src_a + dst_a * (1. - src_a);
+        if out_a <= 0. {
+            return;
+        }
+        for (channel, src) in pixel.iter_mut().zip([color.r, color.g, color.b]) {
+            let dst = *channel as f32 / 255.;
+            let out = (saturate(src) * src_a + dst * dst_a * (1. - src_a)) / out_a;
+            *channel = (out * 255.).round() as u8;
+        }
+        pixel[3] = (out_a * 255.).round() as u8;
+    }
+}
+
+impl CpuTexture<'_> {
+    /// Bilinearly samples the given tile at the given device position, mapping the
+    /// tile onto the given bounds. Texels outside of the tile are never read, so
+    /// transformed sprites don't pick up their neighbours in the atlas.
+    fn sample(&self, tile: &AtlasTile, bounds: &Bounds<ScaledPixels>, x: f32, y: f32) -> Rgba {
+        let u = tile.bounds.origin.x.0 as f32
+            + (x - bounds.origin.x.0) / bounds.size.width.0 * tile.bounds.size.width.0 as f32;
+        let v = tile.bounds.origin.y.0 as f32
+            + (y - bounds.origin.y.0) / bounds.size.height.0 * tile.bounds.size.height.0 as f32;
+        let (u, v) = (u - 0.5, v - 0.5);
+        let (x0, y0) = (u.floor(), v.floor());
+        let (fx, fy) = (u - x0, v - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+
+        let min_x = tile.bounds.origin.x.0;
+        let max_x = (min_x + tile.bounds.size.width.0 - 1).max(min_x);
+        let min_y = tile.bounds.origin.y.0;
+        let max_y = (min_y + tile.bounds.size.height.0 - 1).max(min_y);
+        let texel = |x: i32, y: i32| self.texel(x.clamp(min_x, max_x), y.clamp(min_y, max_y));
+
+        let top = mix(texel(x0, y0), texel(x0 + 1, y0), fx);
+        let bottom = mix(texel(x0, y0 + 1), texel(x0 + 1, y0 + 1), fx);
+        mix(top, bottom, fy)
+    }
+
+    /// Reads a single texel, clamping the coordinates to the edges of the texture.
+    fn texel(&self, x: i32, y: i32) -> Rgba {
+        let width = self.size.width.0;
+        let height = self.size.height.0;
+        if width <= 0 || height <= 0 {
+            return Rgba::default();
+        }
+        let x = x.clamp(0, width - 1) as usize;
+        let y = y.clamp(0, height - 1) as usize;
+        let bytes_per_pixel = bytes_per_pixel(self.kind);
+        let offset = (y * width as usize + x) * bytes_per_pixel;
+        let Some(texel) = self.bytes.get(offset..offset + bytes_per_pixel) else {
+            return Rgba::default();
+        };
+        match self.kind {
+            AtlasTextureKind::Monochrome | AtlasTextureKind::Path => Rgba {
+                r: 0.,
+                g: 0.,
+                b: 0.,
+                a: texel[0] as f32 / 255.,
+            },
+            AtlasTextureKind::Polychrome => Rgba {
+                r: texel[2] as f32 / 255.,
+                g: texel[1] as f32 / 255.,
+                b: texel[0] as f32 / 255.,
+                a: texel[3] as f32 / 255.,
+            },
+        }
+    }
+}
+
+fn byte_len(size: Size<DevicePixels>) -> usize {
+    size.width.0.max(0) as usize * size.height.0.max(0) as usize * 4
+}
+
+fn half_size(bounds: &Bounds<ScaledPixels>) -> (f32, f32) {
+    (bounds.size.width.0 / 2., bounds.size.height.0 / 2.)
+}
+
+fn center(bounds: &Bounds<ScaledPixels>) -> (f32, f32) {
+    let half_size = half_size(bounds);
+    (
+        bounds.origin.x.0 + half_size.0,
+        bounds.origin.y.0 + half_size.1,
+    )
+}
+
+fn corner_radii_are_zero(corner_radii: &Corners<ScaledPixels>) -> bool {
+    corner_radii.top_left.0 == 0.
+        && corner_radii.top_right.0 == 0.
+        && corner_radii.bottom_right.0 == 0.
+        && corner_radii.bottom_left.0 == 0.
+}
+
+/// Picks the radius of the corner in the quadrant of the given offset from the center.
+/// The coverage of the pixel at the given device coordinates by the rounded corners of
+/// the content mask. Its straight edges are already clipped by [`CpuRenderer::pixel_ranges`].
+fn content_mask_alpha(content_mask: &ContentMask<ScaledPixels>, x: usize, y: usize) -> f32 {
+    if corner_radii_are_zero(&content_mask.corner_radii) {
+        return 1.;
+    }
+    let center = center(&content_mask.bounds);
+    let center_to_point = (x as f32 + 0.5 - center.0, y as f32 + 0.5 - center.1);
+    let corner_radius = corner_radius(&content_mask.corner_radii, center_to_point);
+    let distance = rounded_rect_distance(
+        center_to_point,
+        half_size(&content_mask.bounds),
+        corner_radius,
+    );
+    saturate(0.5 - distance)
+}
+
+fn corner_radius(corner_radii: &Corners<ScaledPixels>, center_to_point: (f32, f32)) -> f32 {
+    match (center_to_point.0 < 0., center_to_point.1 < 0.) {
+        (true, true) => corner_radii.top_left.0,
+        (true, false) => corner_radii.bottom_left.0,
+        (false, true) => corner_radii.top_right.0,
+        (false, false) => corner_radii.bottom_right.0,
+    }
+}
+
+/// The signed distance from a point to the edge of a rounded rectangle centered on the
+/// origin, negative inside.
+fn rounded_rect_distance(center_to_point: (f32, f32), half_size: (f32, f32), radius: f32) -> f32 {
+    let rounded_edge_to_point = (
+        center_to_point.0.abs() - half_size.0 + radius,
+        center_to_point.1.abs() - half_size.1 + radius,
+    );
+    rounded_edge_to_point
+        .0
+        .max(0.)
+        .hypot(rounded_edge_to_point.1.max(0.))
+        + rounded_edge_to_point.0.max(rounded_edge_to_point.1).min(0.)
+        - radius
+}
+
+/// Samples a gradient background at the given point.
+/// The coverage of a dashed or dotted border at a point `along_edge` of an edge whose dashes
+/// span `edge_length`, and `across_edge` from the middle of the border.
+fn border_pattern_alpha(
+    style: BorderStyle,
+    width: f32,
+    along_edge: f32,
+    edge_length: f32,
+    across_edge: f32,
+) -> f32 {
+    let period = match style {
+        BorderStyle::Solid => return 1.,
+        BorderStyle::Dashed => 6. * width,
+        BorderStyle::Dotted => 2. * width,
+    };
+    // Stretch the pattern so that a whole number of periods fits, leaving a dash or dot
+    // centered on each corner.
+    let period = edge_length.max(width) / (edge_length / period).round().max(1.);
+    let along = along_edge - (along_edge / period).round() * period;
+    match style {
+        BorderStyle::Dashed => saturate(0.5 - (along.abs() - period / 4.)),
+        _ => saturate(0.5 - ((along * along + across_edge * across_edge).sqrt() - width / 2.)),
+    }
+}
+
+/// Convolves premultiplied pixels with a gaussian along the given direction, treating
+/// everything outside of the framebuffer as transparent.
+fn blur_pixels(
+    pixels: &[[f32; 4]],
+    width: usize,
+    height: usize,
+    sigma: f32,
+    (dx, dy): (isize, isize),
+) -> Vec<[f32; 4]> {
+    let radius = (3. * sigma).ceil() as isize;
+    let weights = (-radius..=radius)
+        .map(|offset| gaussian(offset as f32, sigma))
+        .collect::<Vec<_>>();
+    let total_weight = weights.iter().sum::<f32>();
+
+    let mut blurred = vec![[0.; 4]; pixels.len()];
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let mut sum = [0.; 4];
+            for (offset, weight) in (-radius..=radius).zip(&weights) {
+                let (sx, sy) = (x + offset * dx, y + offset * dy);
+                if sx < 0 || sy < 0 || sx >= width as isize || sy >= height as isize {
+                    continue;
+                }
+                let pixel = pixels[sy as usize * width + sx as usize];
+                for (channel, value) in sum.iter_mut().zip(pixel) {
+                    *channel += value * weight;
+                }
+            }
+            blurred[y as usize * width + x as usize] = sum.map(|channel| channel / total_weight);
+        }
+    }
+    blurred
+}
+
+fn gradient_color(
+    background: &Background,
+    bounds: &Bounds<ScaledPixels>,
+    point: (f32, f32),
+) -> Rgba {
+    let half_size = half_size(bounds);
+    let center = center(bounds);
+    let position = match background.tag {
+        BackgroundTag::Solid => return Rgba::from(background.solid),
+        BackgroundTag::LinearGradient => {
+            // The gradient line passes through the center, and is long enough for the
+            // corners to lie on the lines perpendicular to its ends.
+            let direction = background.direction;
+            let length =
+                (2. * half_size.0 * direction.x).abs() + (2. * half_size.1 * direction.y).abs();
+            let projection =
+                (point.0 - center.0) * direction.x + (point.1 - center.1) * direction.y;
+            if length > 0. {
+                projection / length + 0.5
+            } else {
+                0.
+            }
+        }
+        BackgroundTag::RadialGradient => {
+            let gradient_center = (
+                bounds.origin.x.0 + background.center.x * bounds.size.width.0,
+                bounds.origin.y.0 + background.center.y * bounds.size.height.0,
+            );
+            let farthest_corner = (
+                (gradient_center.0 - bounds.origin.x.0)
+                    .abs()
+                    .max((bounds.origin.x.0 + bounds.size.width.0 - gradient_center.0).abs()),
+                (gradient_center.1 - bounds.origin.y.0)
+                    .abs()
+                    .max((bounds.origin.y.0 + bounds.size.height.0 - gradient_center.1).abs()),
+            );
+            let radius = farthest_corner.0.hypot(farthest_corner.1);
+            let distance = (point.0 - gradient_center.0).hypot(point.1 - gradient_center.1);
+            if radius > 0. {
+                distance / radius
+            } else {
+                0.
+            }
+        }
+    };
+
+    let stops = &background.stops[..background.stop_count as usize];
+    let mut color = Rgba::from(stops[0].color);
+    for pair in stops.windows(2) {
+        if position > pair[0].position {
+            let span = (pair[1].position - pair[0].position).max(f32::EPSILON);
+            color = interpolate(
+                pair[0].color,
+                pair[1].color,
+                saturate((position - pair[0].position) / span),
+                background.color_space,
+            );
+        }
+    }
+    color
+}
+
+/// Blends two colors in the given color space, with premultiplied alpha so that fading
+/// to a transparent stop doesn't darken the other color.
+fn interpolate(from: Hsla, to: Hsla, amount: f32, color_space: ColorSpace) -> Rgba {
+    let encode = |color: Hsla| {
+        let rgba = Rgba::from(color);
+        let [x, y, z] = match color_space {
+            ColorSpace::Srgb => [rgba.r, rgba.g, rgba.b],
+            ColorSpace::LinearSrgb => [rgba.r, rgba.g, rgba.b].map(srgb_to_linear),
+            ColorSpace::Oklab => linear_srgb_to_oklab([rgba.r, rgba.g, rgba.b].map(srgb_to_linear)),
+        };
+        [x * rgba.a, y * rgba.a, z * rgba.a, rgba.a]
+    };
+    let from = encode(from);
+    let to = encode(to);
+    let [x, y, z, a] = [0, 1, 2, 3].map(|ix| from[ix] + (to[ix] - from[ix]) * amount);
+    if a <= 0. {
+        return Rgba::default();
+    }
+    let components = [x / a, y / a, z / a];
+    let [r, g, b] = match color_space {
+        ColorSpace::Srgb => components,
+        ColorSpace::LinearSrgb => components.map(linear_to_srgb),
+        ColorSpace::Oklab => oklab_to_linear_srgb(components).map(linear_to_srgb),
+    };
+    Rgba {
+        r: saturate(r),
+        g: saturate(g),
+        b: saturate(b),
+        a,
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    let value = value.max(0.);
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    }
+}
+
+#[allow(clippy::excessive_precision)]
+fn linear_srgb_to_oklab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+#[allow(clippy::excessive_precision)]
+fn oklab_to_linear_srgb([lightness, a, b]: [f32; 3]) -> [f32; 3] {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ]
+}
+
+/// A standard gaussian function, used for weighting samples.
+/// The coverage of a point by the given rounded rectangle blurred with a gaussian.
+fn shadow_alpha(
+    bounds: &Bounds<ScaledPixels>,
+    corner_radii: &Corners<ScaledPixels>,
+    blur_radius: f32,
+    (x, y): (f32, f32),
+) -> f32 {
+    let half_size = half_size(bounds);
+    let center = center(bounds);
+    let point = (x - center.0, y - center.1);
+    let corner_radius = corner_radius(corner_radii, point);
+    if blur_radius <= 0. {
+        let distance = rounded_rect_distance(point, half_size, corner_radius);
+        return saturate(0.5 - distance);
+    }
+
+    // The signal is only non-zero in a limited range, so don't waste samples.
+    let low = point.1 - half_size.1;
+    let high = point.1 + half_size.1;
+    let start = (-3. * blur_radius).clamp(low, high);
+    let end = (3. * blur_radius).clamp(low, high);
+
+    let step = (end - start) / 4.;
+    let mut y = start + step * 0.5;
+    let mut alpha = 0.;
+    for _ in 0..4 {
+        alpha += blur_along_x(point.0, point.1 - y, blur_radius, corner_radius, half_size)
+            * gaussian(y, blur_radius)
+            * step;
+        y += step;
+    }
+    alpha
+}
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    (-(x * x) / (2. * sigma * sigma)).exp() / ((2. * PI).sqrt() * sigma)
+}
+
+/// Approximates the error function, needed for the gaussian integral.
+fn erf(x: f32) -> f32 {
+    let sign = if x > 0. {
+        1.
+    } else if x < 0. {
+        -1.
+    } else {
+        0.
+    };
+    let a = x.abs();
+    let mut x = 1. + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    x *= x;
+    sign - sign / (x * x)
+}
+
+fn blur_along_x(x: f32, y: f32, sigma: f32, corner: f32, half_size: (f32, f32)) -> f32 {
+    let delta = (half_size.1 - corner - y.abs()).min(0.);
+    let curved = half_size.0 - corner + (corner * corner - delta * delta).max(0.).sqrt();
+    let scale = 0.5f32.sqrt() / sigma;
+    let integral_low = 0.5 + 0.5 * erf((x - curved) * scale);
+    let integral_high = 0.5 + 0.5 * erf((x + curved) * scale);
+    integral_high - integral_low
+}
+
+/// Twice the signed area of the triangle (a, b, p).
+fn edge_function(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+/// Whether a pixel center with the given edge function value is covered by the edge
+/// from `a` to `b`. Centers lying exactly on an edge belong to only one of the two
+/// triangles sharing it, so shared edges aren't counted twice.
+fn covers_edge(value: f32, a: (f32, f32), b: (f32, f32)) -> bool {
+    value > 0. || (value == 0. && (b.1 > a.1 || (b.1 == a.1 && b.0 < a.0)))
+}
+
+fn saturate(value: f32) -> f32 {
+    value.clamp(0., 1.)
+}
+
+fn with_alpha(color: Rgba, a: f32) -> Rgba {
+    Rgba { a, ..color }
+}
+
+fn over(below: Rgba, above: Rgba) -> Rgba {
+    let alpha = above.a + below.a * (1. - above.a);
+    if alpha <= 0. {
+        return Rgba::default();
+    }
+    let blend = |above_channel: f32, below_channel: f32| {
+        (above_channel * above.a + below_channel * below.a * (1. - above.a)) / alpha
+    };
+    Rgba {
+        r: blend(above.r, below.r),
+        g: blend(above.g, below.g),
+        b: blend(above.b, below.b),
+        a: alpha,
+    }
+}
+
+fn mix(from: Rgba, to: Rgba, amount: f32) -> Rgba {
+    let lerp = |from: f32, to: f32| from + (to - from) * amount;
+    Rgba {
+        r: lerp(from.r, to.r),
+        g: lerp(from.g, to.g),
+        b: lerp(from.b, to.b),
+        a: lerp(from.a, to.a),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color_stop, linear_gradient, point, px, radial_gradient, rgb, rgba, size, AtlasTextureId,
+        ColorMatrix, Edges, Fill, FilterLayer, Hsla, PathBuilder, Pixels, Point, StackingContext,
+        StackingOrder, StrokeStyle, TileId,
+    };
+
+    struct TestTextures(Vec<(AtlasTextureId, Size<DevicePixels>, Vec<u8>)>);
+
+    impl CpuTextureSource for TestTextures {
+        fn with_texture(&self, id: AtlasTextureId, f: &mut dyn FnMut(CpuTexture)) {
+            if let Some((_, size, bytes)) =
+                self.0.iter().find(|(texture_id, _, _)| *texture_id == id)
+            {
+                f(CpuTexture {
+                    kind: id.kind,
+                    size: *size,
+                    bytes,
+                });
+            }
+        }
+    }
+
+    fn stacking_order(z_index: u16) -> StackingOrder {
+        let mut order = StackingOrder::default();
+        order.push(StackingContext { z_index, id: 0 });
+        order
+    }
+
+    fn scaled_bounds(x: f32, y: f32, width: f32, height: f32) -> Bounds<ScaledPixels> {
+        Bounds {
+            origin: point(ScaledPixels(x), ScaledPixels(y)),
+            size: size(ScaledPixels(width), ScaledPixels(height)),
+        }
+    }
+
+    fn render(scene: &mut Scene, width: i32, height: i32, textures: &TestTextures) -> CpuRenderer {
+        scene.finish();
+        let mut renderer = CpuRenderer::new(size(DevicePixels(width), DevicePixels(height)));
+        renderer.draw(scene, textures);
+        renderer
+    }
+
+    fn pixel(renderer: &CpuRenderer, x: usize, y: usize) -> [u8; 4] {
+        let offset = (y * renderer.size().width.0 as usize + x) * 4;
+        renderer.pixels()[offset..offset + 4].try_into().unwrap()
+    }
+
+    fn quad(bounds: Bounds<ScaledPixels>, background: Hsla) -> Quad {
+        Quad {
+            bounds,
+            content_mask: ContentMask {
+                bounds,
+                ..Default::default()
+            },
+            background: background.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_quad_corners_and_borders() {
+        let bounds = scaled_bounds(0., 0., 10., 10.);
+        let mut scene = Scene::default();
+        scene.insert(
+            &stacking_order(0),
+            Quad {
+                border_colors: Edges::all(rgb(0x0000ff).into()),
+                corner_radii: Corners {
+                    top_left: ScaledPixels(4.),
+                    ..Default::default()
+                },
+                border_widths: Edges::all(ScaledPixels(2.)),
+                ..quad(bounds, rgb(0xff0000).into())
+            },
+        );
+        let renderer = render(&mut scene, 10, 10, &TestTextures(Vec::new()));
+
+        assert_eq!(pixel(&renderer, 0, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(&renderer, 9, 0), [0, 0, 255, 255]);
+        assert_eq!(pixel(&renderer, 5, 9), [0, 0, 255, 255]);
+        assert_eq!(pixel(&renderer, 5, 5), [255, 0, 0, 255]);
+        let corner = pixel(&renderer, 1, 1);
+        assert!(corner[3] > 0 && corner[3] < 255, "{corner:?}");
+    }
+
+    #[test]
+    fn test_filter_layers() {
+        let filter_layer =
+            |z_index: u16, blur_radius: f32, color_matrix: ColorMatrix| FilterLayer {
+                view_id: Default::default(),
+                stacking_order: stacking_order(z_index),
+                orders: 0..0,
+                effect: FilterEffect {
+                    content_mask: ContentMask {
+                        bounds: scaled_bounds(0., 0., 20., 10.),
+                        ..Default::default()
+                    },
+                    blur_radius: ScaledPixels(blur_radius),
+                    color_matrix,
+                },
+                mask: None,
+            };
+
+        let mut scene = Scene::default();
+        scene.push_filter_layer(filter_layer(1, 0., ColorMatrix::saturate(0.)));
+        scene.push_filter_layer(filter_layer(2, 1., ColorMatrix::identity()));
+        scene.insert(
+            &stacking_order(1),
+            quad(scaled_bounds(0., 0., 10., 10.), rgb(0xff0000).into()),
+        );
+        scene.insert(
+            &stacking_order(2),
+            quad(scaled_bounds(12., 2., 6., 6.), rgb(0x0000ff).into()),
+        );
+        scene.insert(
+            &stacking_order(3),
+            quad(scaled_bounds(0., 0., 2., 2.), rgb(0x00ff00).into()),
+        );
+        let renderer = render(&mut scene, 20, 10, &TestTextures(Vec::new()));
+
+        // The first layer is desaturated, while what's painted after it is untouched.
+        assert_eq!(pixel(&renderer, 5, 5), [54, 54, 54, 255]);
+        assert_eq!(pixel(&renderer, 1, 1), [0, 255, 0, 255]);
+
+        // The second layer is blurred beyond its edges, without darkening.
+        let center = pixel(&renderer, 15, 5);
+        assert!(center[2] == 255 && center[3] > 240, "{center:?}");
+        let edge = pixel(&renderer, 11, 5);
+        assert!(edge[2] == 255 && edge[3] > 0 && edge[3] < 128, "{edge:?}");
+    }
+
+    #[test]
+    fn test_masked_layers() {
+        let content_mask = ContentMask {
+            bounds: scaled_bounds(0., 0., 20., 10.),
+            ..Default::default()
+        };
+        let masked_layer = |z_index: u16, mask: LayerMask| FilterLayer {
+            view_id: Default::default(),
+            stacking_order: stacking_order(z_index),
+            orders: 0..0,
+            effect: FilterEffect {
+                content_mask: content_mask.clone(),
+                ..Default::default()
+            },
+            mask: Some(mask),
+        };
+
+        // The left layer is clipped to the triangle above its diagonal.
+        let mut triangle = crate::Path::new(point(px(0.), px(0.)));
+        triangle.line_to(point(px(10.), px(0.)));
+        triangle.line_to(point(px(0.), px(10.)));
+        triangle.color = rgb(0x000000).into();
+        let mut triangle = triangle.scale(1.);
+        triangle.content_mask = content_mask.clone();
+
+        // The right layer fades out from left to right.
+        let fade = Quad {
+            bounds: scaled_bounds(10., 0., 10., 10.),
+            content_mask: content_mask.clone(),
+            background: Background::from(&Fill::from(
+                linear_gradient(
+                    90.,
+                    [
+                        color_stop(rgb(0x000000), 0.),
+                        color_stop(rgba(0x00000000), 1.),
+                    ],
+                )
+                .unwrap(),
+            )),
+            ..Default::default()
+        };
+
+        let mut scene = Scene::default();
+        scene.push_filter_layer(masked_layer(1, LayerMask::Path(triangle)));
+        scene.push_filter_layer(masked_layer(2, LayerMask::Quad(fade)));
+        scene.insert(
+            &stacking_order(1),
+            quad(scaled_bounds(0., 0., 10., 10.), rgb(0xff0000).into()),
+        );
+        scene.insert(
+            &stacking_order(2),
+            quad(scaled_bounds(10., 0., 10., 10.), rgb(0x0000ff).into()),
+        );
+        let renderer = render(&mut scene, 20, 10, &TestTextures(Vec::new()));
+
+        assert_eq!(pixel(&renderer, 2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 8, 8), [0, 0, 0, 0]);
+
+        let alphas = (10..20)
+            .map(|x| pixel(&renderer, x, 5)[3])
+            .collect::<Vec<_>>();
+        assert!(alphas[0] > 230 && alphas[9] < 25, "{alphas:?}");
+        assert!(
+            alphas.windows(2).all(|pair| pair[0] > pair[1]),
+            "{alphas:?}"
+        );
+        assert_eq!(pixel(&renderer, 12, 5)[2], 255);
+    }
+
+    #[test]
+    fn test_dashed_and_dotted_borders() {
+        let renderer = render_quad(
+            Quad {
+                border_colors: Edges {
+                    left: rgb(0x00ff00).into(),
+                    ..Edges::all(rgb(0x0000ff).into())
+                },
+                border_widths: Edges::all(ScaledPixels(2.)),
+                border_styles: Edges {
+                    top: BorderStyle::Dashed,
+                    left: BorderStyle::Dotted,
+                    ..Default::default()
+                },
+                ..quad(scaled_bounds(0., 0., 26., 14.), rgb(0xff0000).into())
+            },
+            26,
+            14,
+        );
+
+        // Two 6px dashes fit along the top edge, so the gaps show the background.
+        assert_eq!(pixel(&renderer, 13, 0), [0, 0, 255, 255]);
+        assert_eq!(pixel(&renderer, 6, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 19, 0), [255, 0, 0, 255]);
+
+        // Dots sit every 4px down the left edge.
+        let dot = pixel(&renderer, 1, 5);
+        assert!(dot[1] > dot[0], "{dot:?}");
+        assert_eq!(pixel(&renderer, 1, 7), [255, 0, 0, 255]);
+
+        // The remaining edges stay solid.
+        assert_eq!(pixel(&renderer, 25, 7), [0, 0, 255, 255]);
+        assert_eq!(pixel(&renderer, 6, 13), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_stacking_order_and_content_mask() {
+        let mut scene = Scene::default();
+        let mut clipped = quad(scaled_bounds(0., 0., 4., 4.), rgb(0x00ff00).into());
+        clipped.content_mask.bounds = scaled_bounds(0., 0., 2., 4.);
+        scene.insert(&stacking_order(1), clipped);
+        scene.insert(
+            &stacking_order(0),
+            quad(scaled_bounds(0., 0., 4., 4.), rgb(0xff0000).into()),
+        );
+        let renderer = render(&mut scene, 4, 4, &TestTextures(Vec::new()));
+
+        assert_eq!(pixel(&renderer, 1, 1), [0, 255, 0, 255]);
+        assert_eq!(pixel(&renderer, 2, 1), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_rounded_content_mask() {
+        let mut clipped = quad(scaled_bounds(0., 0., 10., 10.), rgb(0x00ff00).into());
+        clipped.content_mask.corner_radii = Corners::all(ScaledPixels(5.));
+        let renderer = render_quad(clipped, 10, 10);
+
+        assert_eq!(pixel(&renderer, 0, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(&renderer, 9, 9), [0, 0, 0, 0]);
+        assert_eq!(pixel(&renderer, 5, 1), [0, 255, 0, 255]);
+        assert_eq!(pixel(&renderer, 5, 5), [0, 255, 0, 255]);
+        let edge = pixel(&renderer, 0, 2);
+        assert!(edge[3] > 0 && edge[3] < 255, "{edge:?}");
+    }
+
+    fn gradient_quad(bounds: Bounds<ScaledPixels>, fill: impl Into<Fill>) -> Quad {
+        Quad {
+            bounds,
+            content_mask: ContentMask {
+                bounds,
+                ..Default::default()
+            },
+            background: Background::from(&fill.into()),
+            ..Default::default()
+        }
+    }
+
+    fn render_quad(quad: Quad, width: i32, height: i32) -> CpuRenderer {
+        let mut scene = Scene::default();
+        scene.insert(&stacking_order(0), quad);
+        render(&mut scene, width, height, &TestTextures(Vec::new()))
+    }
+
+    #[test]
+    fn test_transformed_quad() {
+        // A 4x8 quad turned a quarter around its center covers an 8x4 area.
+        let transform = TransformationMatrix::unit()
+            .translate(point(px(4.), px(4.)))
+            .rotate(PI / 2.)
+            .translate(point(px(-4.), px(-4.)));
+        let renderer = render_quad(
+            Quad {
+                transform,
+                content_mask: ContentMask {
+                    bounds: scaled_bounds(0., 0., 8., 8.),
+                    ..Default::default()
+                },
+                ..quad(scaled_bounds(2., 0., 4., 8.), rgb(0xff0000).into())
+            },
+            8,
+            8,
+        );
+
+        assert_eq!(pixel(&renderer, 0, 3), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 7, 4), [255, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 3, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(&renderer, 4, 7), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_linear_gradient() {
+        // Stops are sorted by position, whatever order they're given in.
+        let horizontal = render_quad(
+            gradient_quad(
+                scaled_bounds(0., 0., 10., 2.),
+                linear_gradient(
+                    90.,
+                    [color_stop(rgb(0xffffff), 1.), color_stop(rgb(0x000000), 0.)],
+                )
+                .unwrap(),
+            ),
+            10,
+            2,
+        );
+        let row = (0..10)
+            .map(|x| pixel(&horizontal, x, 1)[0])
+            .collect::<Vec<_>>();
+        assert!(row.windows(2).all(|pair| pair[0] < pair[1]), "{row:?}");
+        assert!(row[0] < 20 && row[9] > 235, "{row:?}");
+        assert_eq!(pixel(&horizontal, 4, 0), pixel(&horizontal, 4, 1));
+
+        // An angle of zero runs from the bottom to the top.
+        let vertical = render_quad(
+            gradient_quad(
+                scaled_bounds(0., 0., 2., 10.),
+                linear_gradient(
+                    0.,
+                    [color_stop(rgb(0xff0000), 0.), color_stop(rgb(0x0000ff), 1.)],
+                )
+                .unwrap(),
+            ),
+            2,
+            10,
+        );
+        assert!(pixel(&vertical, 0, 0)[2] > 235);
+        assert!(pixel(&vertical, 0, 9)[0] > 235);
+    }
+
+    #[test]
+    fn test_gradient_color_spaces() {
+        let midpoint = |color_space| {
+            let renderer = render_quad(
+                gradient_quad(
+                    scaled_bounds(0., 0., 11., 1.),
+                    linear_gradient(
+                        90.,
+                        [color_stop(rgb(0xff0000), 0.), color_stop(rgb(0x0000ff), 1.)],
+                    )
+                    .unwrap()
+                    .color_space(color_space),
+                ),
+                11,
+                1,
+            );
+            pixel(&renderer, 5, 0)
+        };
+
+        assert_eq!(midpoint(ColorSpace::Srgb), [128, 0, 128, 255]);
+        assert_eq!(midpoint(ColorSpace::LinearSrgb), [188, 0, 188, 255]);
+        let oklab = midpoint(ColorSpace::Oklab);
+        assert_ne!(oklab, midpoint(ColorSpace::Srgb));
+        assert!(oklab[0] > 128 && oklab[2] > 128, "{oklab:?}");
+
+        // Fading to a transparent stop keeps the opaque stop's color.
+        let renderer = render_quad(
+            gradient_quad(
+                scaled_bounds(0., 0., 11., 1.),
+                linear_gradient(
+                    90.,
+                    [
+                        color_stop(rgb(0xff0000), 0.),
+                        color_stop(rgba(0x0000ff00), 1.),
+                    ],
+                )
+                .unwrap(),
+            ),
+            11,
+            1,
+        );
+        assert_eq!(pixel(&renderer, 5, 0), [255, 0, 0, 128]);
+    }
+
+    #[test]
+    fn test_radial_gradient_with_corners_and_borders() {
+        let renderer = render_quad(
+            Quad {
+                border_colors: Edges::all(rgb(0x0000ff).into()),
+                corner_radii: Corners {
+                    top_left: ScaledPixels(4.),
+                    ..Default::default()
+                },
+                border_widths: Edges {
+                    bottom: ScaledPixels(1.),
+                    ..Default::default()
+                },
+                ..gradient_quad(
+                    scaled_bounds(0., 0., 9., 9.),
+                    radial_gradient(
+                        point(0.5, 0.5),
+                        [color_stop(rgb(0xffffff), 0.), color_stop(rgb(0x000000), 1.)],
+                    )
+                    .unwrap(),
+                )
+            },
+            9,
+            9,
+        );
+
+        assert_eq!(pixel(&renderer, 4, 4), [255, 255, 255, 255]);
+        let edge = pixel(&renderer, 8, 4);
+        assert!(edge[0] < 128 && edge[3] == 255, "{edge:?}");
+        assert_eq!(pixel(&renderer, 4, 8), [0, 0, 255, 255]);
+        assert_eq!(pixel(&renderer, 0, 0), [0, 0, 0, 0]);
+        let corner = pixel(&renderer, 1, 1);
+        assert!(corner[3] > 0 && corner[3] < 255, "{corner:?}");
+    }
+
+    #[test]
+    fn test_path_fill() {
+        let mut path = Path::new(point(px(1.), px(1.)));
+        for vertex in [(7., 1.), (7., 7.), (1., 7.), (1., 1.)] {
+            path.line_to(point(px(vertex.0), px(vertex.1)));
+        }
+        path.content_mask = ContentMask {
+            bounds: Bounds {
+                origin: Point::default(),
+                size: size(Pixels(8.), Pixels(8.)),
+            },
+            ..Default::default()
+        };
+        path.color = crate::black();
+        let mut scene = Scene::default();
+        scene.insert(&stacking_order(0), path.scale(1.));
+        let renderer = render(&mut scene, 8, 8, &TestTextures(Vec::new()));
+
+        for y in 0..8 {
+            for x in 0..8 {
+                let inside = (1..7).contains(&x) && (1..7).contains(&y);
+                let expected = if inside { [0, 0, 0, 255] } else { [0, 0, 0, 0] };
+                assert_eq!(pixel(&renderer, x, y), expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn test_path_stroke() {
+        let mut builder = PathBuilder::new();
+        builder.move_to(point(px(1.), px(1.)));
+        builder.line_to(point(px(6.), px(1.)));
+        builder.line_to(point(px(6.), px(6.)));
+        let mut path = builder.stroke(&StrokeStyle::new(px(2.)));
+        path.content_mask = ContentMask {
+            bounds: Bounds {
+                origin: Point::default(),
+                size: size(Pixels(8.), Pixels(8.)),
+            },
+            ..Default::default()
+        };
+        path.color = crate::black();
+        let mut scene = Scene::default();
+        scene.insert(&stacking_order(0), path.scale(1.));
+        let renderer = render(&mut scene, 8, 8, &TestTextures(Vec::new()));
+
+        // Where the segments overlap at the corner is painted once, not cancelled out.
+        assert_eq!(pixel(&renderer, 5, 1), [0, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 6, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 1, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 6, 5), [0, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 0, 1), [0, 0, 0, 0]);
+        assert_eq!(pixel(&renderer, 2, 4), [0, 0, 0, 0]);
+        assert_eq!(pixel(&renderer, 6, 6), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_sprite_samples_tile_bounds() {
+        let texture_id = AtlasTextureId {
+            index: 0,
+            kind: AtlasTextureKind::Monochrome,
+        };
+        let textures = TestTextures(vec![(
+            texture_id,
+            size(DevicePixels(4), DevicePixels(2)),
+            vec![0, 0, 255, 255, 0, 0, 255, 255],
+        )]);
+        let bounds = scaled_bounds(0., 0., 2., 2.);
+        let mut scene = Scene::default();
+        scene.insert(
+            &stacking_order(0),
+            MonochromeSprite {
+                view_id: Default::default(),
+                layer_id: 0,
+                order: 0,
+                bounds,
+                content_mask: ContentMask {
+                    bounds,
+                    ..Default::default()
+                },
+                transform: Default::default(),
+                color: crate::white(),
+                tile: AtlasTile {
+                    texture_id,
+                    tile_id: TileId(0),
+                    bounds: Bounds {
+                        origin: point(DevicePixels(2), DevicePixels(0)),
+                        size: size(DevicePixels(2), DevicePixels(2)),
+                    },
+                },
+            },
+        );
+        let renderer = render(&mut scene, 2, 2, &textures);
+
+        assert_eq!(renderer.pixels(), &[255; 16]);
+    }
+
+    #[test]
+    fn test_blurred_shadow_and_wavy_underline() {
+        let mut scene = Scene::default();
+        let bounds = scaled_bounds(4., 4., 4., 4.);
+        scene.insert(
+            &stacking_order(0),
+            Shadow {
+                view_id: Default::default(),
+                layer_id: 0,
+                order: 0,
+                bounds,
+                corner_radii: Default::default(),
+                content_mask: ContentMask {
+                    bounds: scaled_bounds(0., 0., 12., 12.),
+                    ..Default::default()
+                },
+                transform: Default::default(),
+                color: crate::black(),
+                blur_radius: ScaledPixels(1.),
+                offset: Default::default(),
+                spread_radius: ScaledPixels(0.),
+                inset: false,
+            },
+        );
+        let underline_bounds = scaled_bounds(0., 12., 12., 6.);
+        scene.insert(
+            &stacking_order(0),
+            Underline {
+                view_id: Default::default(),
+                layer_id: 0,
+                order: 0,
+                bounds: underline_bounds,
+                content_mask: ContentMask {
+                    bounds: underline_bounds,
+                    ..Default::default()
+                },
+                transform: Default::default(),
+                thickness: ScaledPixels(2.),
+                color: crate::black(),
+                wavy: true,
+            },
+        );
+        let renderer = render(&mut scene, 12, 18, &TestTextures(Vec::new()));
+
+        let inside = pixel(&renderer, 5, 5)[3];
+        let edge = pixel(&renderer, 3, 5)[3];
+        let outside = pixel(&renderer, 0, 5)[3];
+        assert!(inside > edge && edge > outside, "{inside} {edge} {outside}");
+
+        let underline_alphas = (12..18)
+            .flat_map(|y| (0..12).map(move |x| (x, y)))
+            .map(|(x, y)| pixel(&renderer, x, y)[3])
+            .collect::<Vec<_>>();
+        assert!(underline_alphas.contains(&0));
+        assert!(underline_alphas.iter().any(|alpha| *alpha > 128));
+    }
+
+    #[test]
+    fn test_inset_shadow() {
+        let mut scene = Scene::default();
+        let bounds = scaled_bounds(0., 0., 10., 10.);
+        scene.insert(
+            &stacking_order(0),
+            Shadow {
+                view_id: Default::default(),
+                layer_id: 0,
+                order: 0,
+                bounds,
+                corner_radii: Default::default(),
+                content_mask: ContentMask {
+                    bounds,
+                    ..Default::default()
+                },
+                transform: Default::default(),
+                color: crate::black(),
+                blur_radius: ScaledPixels(0.),
+                offset: point(ScaledPixels(2.), ScaledPixels(0.)),
+                spread_radius: ScaledPixels(1.),
+                inset: true,
+            },
+        );
+        let renderer = render(&mut scene, 12, 12, &TestTextures(Vec::new()));
+
+        // The hole casting the shadow spans 3..11 horizontally and 1..9 vertically.
+        assert_eq!(pixel(&renderer, 5, 5), [0, 0, 0, 0]);
+        assert_eq!(pixel(&renderer, 9, 5), [0, 0, 0, 0]);
+        assert_eq!(pixel(&renderer, 2, 5), [0, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 5, 0), [0, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 5, 9), [0, 0, 0, 255]);
+        assert_eq!(pixel(&renderer, 11, 5), [0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,7 @@
+mod display;
+mod platform;
+mod window;
+
+pub(crate) use display::*;
+pub(crate) use platform::*;
+pub use window::*;
@@ -0,0 +1,38 @@
+use crate::{point, Bounds, DisplayId, GlobalPixels, PlatformDisplay, Size};
+use anyhow::Result;
+use uuid::Uuid;
+
+/// A virtual display with a fixed size, used when there is no window system.
+#[derive(Debug)]
+pub(crate) struct HeadlessDisplay {
+    id: DisplayId,
+    uuid: Uuid,
+    bounds: Bounds<GlobalPixels>,
+}
+
+impl HeadlessDisplay {
+    pub fn new(id: DisplayId, size: Size<GlobalPixels>) -> Self {
+        Self {
+            id,
+            uuid: Uuid::from_u128(id.0 as u128),
+            bounds: Bounds {
+                origin: point(GlobalPixels(0.), GlobalPixels(0.)),
+                size,
+            },
+        }
+    }
+}
+
+impl PlatformDisplay for HeadlessDisplay {
+    fn id(&self) -> DisplayId {
+        self.id
+    }
+
+    fn uuid(&self) -> Result<Uuid> {
+        Ok(self.uuid)
+    }
+
+    fn bounds(&self) -> Bounds<GlobalPixels> {
+        self.bounds
+    }
+}
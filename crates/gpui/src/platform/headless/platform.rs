@@ -0,0 +1,465 @@
+use crate::{
+    size, Action, AnyWindowHandle, BackgroundExecutor, ClipboardItem, CosmicTextSystem,
+    CursorStyle, DisplayId, ForegroundExecutor, GlobalPixels, HeadlessDisplay, HeadlessWindow,
+    Keymap, MainThreadQueue, Menu, PathPromptOptions, Platform, PlatformDisplay, PlatformInput,
+    PlatformTextSystem, PlatformWindow, Result, SemanticVersion, Task, ThreadPoolDispatcher,
+    WindowOptions,
+};
+use anyhow::anyhow;
+use collections::HashMap;
+use futures::channel::oneshot;
+use parking_lot::Mutex;
+use std::{
+    path::{Path, PathBuf},
+    rc::{Rc, Weak},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use time::UtcOffset;
+
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// A platform that runs without a window system. Windows are never shown on screen;
+/// their frames are rendered into in-memory framebuffers on every tick of the event loop.
+pub(crate) struct HeadlessPlatform {
+    background_executor: BackgroundExecutor,
+    foreground_executor: ForegroundExecutor,
+    text_system: Arc<CosmicTextSystem>,
+    main_thread_queue: Arc<MainThreadQueue>,
+    display: Rc<HeadlessDisplay>,
+    state: Mutex<HeadlessPlatformState>,
+    weak: Weak<Self>,
+}
+
+struct HeadlessPlatformState {
+    should_quit: bool,
+    windows: Vec<HeadlessWindow>,
+    active_window: Option<AnyWindowHandle>,
+    display_links: HashMap<DisplayId, DisplayLink>,
+    cursor_style: CursorStyle,
+    clipboard: Option<ClipboardItem>,
+    credentials: HashMap<String, (String, Vec<u8>)>,
+    quit: Option<Box<dyn FnMut()>>,
+}
+
+struct DisplayLink {
+    callback: Option<Box<dyn FnMut() + Send>>,
+    running: bool,
+}
+
+impl HeadlessPlatform {
+    pub(crate) fn new() -> Rc<Self> {
+        // The event loop blocks on the main thread queue itself, so it needs no waker.
+        let dispatcher = Arc::new(ThreadPoolDispatcher::new(|| {}));
+        let main_thread_queue = dispatcher.main_thread_queue();
+        Rc::new_cyclic(|weak| Self {
+            background_executor: BackgroundExecutor::new(dispatcher.clone()),
+            foreground_executor: ForegroundExecutor::new(dispatcher),
+            text_system: Arc::new(CosmicTextSystem::new()),
+            main_thread_queue,
+            display: Rc::new(HeadlessDisplay::new(
+                DisplayId(1),
+                size(GlobalPixels(1920.), GlobalPixels(1080.)),
+            )),
+            state: Mutex::new(HeadlessPlatformState {
+                should_quit: false,
+                windows: Vec::new(),
+                active_window: None,
+                display_links: HashMap::default(),
+                cursor_style: CursorStyle::default(),
+                clipboard: None,
+                credentials: HashMap::default(),
+                quit: None,
+            }),
+            weak: weak.clone(),
+        })
+    }
+
+    pub(crate) fn set_active_window(&self, window: Option<HeadlessWindow>) {
+        let handle = window.as_ref().map(|window| window.handle());
+        let previous_handle = std::mem::replace(&mut self.state.lock().active_window, handle);
+        if previous_handle == handle {
+            return;
+        }
+
+        let previous_window = previous_handle.and_then(|previous_handle| {
+            self.state
+                .lock()
+                .windows
+                .iter()
+                .find(|window| window.handle() == previous_handle)
+                .cloned()
+        });
+
+        // Activation callbacks update the app, which may currently be borrowed by the caller.
+        self.foreground_executor
+            .spawn(async move {
+                if let Some(previous_window) = previous_window {
+                    previous_window.simulate_active_status_change(false);
+                }
+                if let Some(window) = window {
+                    window.simulate_active_status_change(true);
+                }
+            })
+            .detach();
+    }
+
+    fn frame(&self) {
+        let display_links = self
+            .state
+            .lock()
+            .display_links
+            .iter_mut()
+            .filter(|(_, link)| link.running)
+            .filter_map(|(display_id, link)| Some((*display_id, link.callback.take()?)))
+            .collect::<Vec<_>>();
+        for (display_id, mut callback) in display_links {
+            callback();
+            if let Some(link) = self.state.lock().display_links.get_mut(&display_id) {
+                link.callback.get_or_insert(callback);
+            }
+        }
+
+        let windows = self.state.lock().windows.clone();
+        for window in windows {
+            window.request_frame_if_needed();
+        }
+    }
+}
+
+impl Platform for HeadlessPlatform {
+    fn background_executor(&self) -> BackgroundExecutor {
+        self.background_executor.clone()
+    }
+
+    fn foreground_executor(&self) -> ForegroundExecutor {
+        self.foreground_executor.clone()
+    }
+
+    fn text_system(&self) -> Arc<dyn PlatformTextSystem> {
+        self.text_system.clone()
+    }
+
+    fn run(&self, on_finish_launching: Box<dyn 'static + FnOnce()>) {
+        on_finish_launching();
+
+        let mut next_frame = Instant::now();
+        while !self.state.lock().should_quit {
+            let now = Instant::now();
+            if now >= next_frame {
+                self.frame();
+                next_frame = now + FRAME_INTERVAL;
+            }
+
+            if let Some(runnable) = self
+                .main_thread_queue
+                .pop_timeout(next_frame.saturating_duration_since(Instant::now()))
+            {
+                runnable.run();
+            }
+        }
+
+        let callback = self.state.lock().quit.take();
+        if let Some(mut callback) = callback {
+            callback();
+        }
+    }
+
+    fn quit(&self) {
+        self.state.lock().should_quit = true;
+    }
+
+    fn restart(&self) {
+        log::warn!("restart is not supported on the headless platform");
+    }
+
+    fn activate(&self, _ignoring_other_apps: bool) {}
+
+    fn hide(&self) {}
+
+    fn hide_other_apps(&self) {}
+
+    fn unhide_other_apps(&self) {}
+
+    fn displays(&self) -> Vec<Rc<dyn PlatformDisplay>> {
+        vec![self.display.clone()]
+    }
+
+    fn display(&self, id: DisplayId) -> Option<Rc<dyn PlatformDisplay>> {
+        self.displays()
+            .into_iter()
+            .find(|display| display.id() == id)
+    }
+
+    fn active_window(&self) -> Option<AnyWindowHandle> {
+        self.state.lock().active_window
+    }
+
+    fn open_window(
+        &self,
+        handle: AnyWindowHandle,
+        options: WindowOptions,
+    ) -> Box<dyn PlatformWindow> {
+        let focus = options.focus;
+        let display = options
+            .display_id
+            .and_then(|display_id| self.display(display_id))
+            .unwrap_or_else(|| self.display.clone());
+        let window = HeadlessWindow::new(handle, options, self.weak.clone(), display, 1.0);
+
+        let mut state = self.state.lock();
+        state
+            .windows
+            .retain(|window| Rc::strong_count(&window.0) > 1);
+        state.windows.push(window.clone());
+        drop(state);
+
+        if focus {
+            self.set_active_window(Some(window.clone()));
+        }
+        Box::new(window)
+    }
+
+    fn set_display_link_output_callback(
+        &self,
+        display_id: DisplayId,
+        callback: Box<dyn FnMut() + Send>,
+    ) {
+        self.state.lock().display_links.insert(
+            display_id,
+            DisplayLink {
+                callback: Some(callback),
+                running: false,
+            },
+        );
+    }
+
+    fn start_display_link(&self, display_id: DisplayId) {
+        if let Some(link) = self.state.lock().display_links.get_mut(&display_id) {
+            link.running = true;
+        }
+    }
+
+    fn stop_display_link(&self, display_id: DisplayId) {
+        if let Some(link) = self.state.lock().display_links.get_mut(&display_id) {
+            link.running = false;
+        }
+    }
+
+    fn open_url(&self, url: &str) {
+        log::info!("headless platform cannot open url {url}");
+    }
+
+    fn on_open_urls(&self, _callback: Box<dyn FnMut(Vec<String>)>) {}
+
+    fn prompt_for_paths(
+        &self,
+        _options: PathPromptOptions,
+    ) -> oneshot::Receiver<Option<Vec<PathBuf>>> {
+        let (tx, rx) = oneshot::channel();
+        tx.send(None).ok();
+        rx
+    }
+
+    fn prompt_for_new_path(&self, _directory: &Path) -> oneshot::Receiver<Option<PathBuf>> {
+        let (tx, rx) = oneshot::channel();
+        tx.send(None).ok();
+        rx
+    }
+
+    fn reveal_path(&self, path: &Path) {
+        log::info!("headless platform cannot reveal path {path:?}");
+    }
+
+    fn on_become_active(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn on_resign_active(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn on_quit(&self, callback: Box<dyn FnMut()>) {
+        self.state.lock().quit = Some(callback);
+    }
+
+    fn on_reopen(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn on_event(&self, _callback: Box<dyn FnMut(PlatformInput) -> bool>) {}
+
+    fn set_menus(&self, _menus: Vec<Menu>, _keymap: &Keymap) {}
+
+    fn on_app_menu_action(&self, _callback: Box<dyn FnMut(&dyn Action)>) {}
+
+    fn on_will_open_app_menu(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn on_validate_app_menu_command(&self, _callback: Box<dyn FnMut(&dyn Action) -> bool>) {}
+
+    fn os_name(&self) -> &'static str {
+        "Linux"
+    }
+
+    fn os_version(&self) -> Result<SemanticVersion> {
+        let release = std::fs::read_to_string("/proc/sys/kernel/osrelease")?;
+        let version = release
+            .trim()
+            .split('.')
+            .take(3)
+            .map(|component| {
+                component
+                    .chars()
+                    .take_while(|ch| ch.is_ascii_digit())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join(".");
+        version.parse()
+    }
+
+    fn app_version(&self) -> Result<SemanticVersion> {
+        Err(anyhow!("app version is unknown on the headless platform"))
+    }
+
+    fn app_path(&self) -> Result<PathBuf> {
+        Ok(std::env::current_exe()?)
+    }
+
+    fn local_timezone(&self) -> UtcOffset {
+        UtcOffset::UTC
+    }
+
+    fn double_click_interval(&self) -> Duration {
+        Duration::from_millis(500)
+    }
+
+    fn path_for_auxiliary_executable(&self, name: &str) -> Result<PathBuf> {
+        let app_path = self.app_path()?;
+        let directory = app_path
+            .parent()
+            .ok_or_else(|| anyhow!("app path {app_path:?} has no parent directory"))?;
+        Ok(directory.join(name))
+    }
+
+    fn set_cursor_style(&self, style: CursorStyle) {
+        self.state.lock().cursor_style = style;
+    }
+
+    fn should_auto_hide_scrollbars(&self) -> bool {
+        false
+    }
+
+    fn write_to_clipboard(&self, item: ClipboardItem) {
+        self.state.lock().clipboard = Some(item);
+    }
+
+    fn read_from_clipboard(&self) -> Option<ClipboardItem> {
+        self.state.lock().clipboard.clone()
+    }
+
+    fn write_credentials(&self, url: &str, username: &str, password: &[u8]) -> Task<Result<()>> {
+        self.state
+            .lock()
+            .credentials
+            .insert(url.to_string(), (username.to_string(), password.to_vec()));
+        Task::ready(Ok(()))
+    }
+
+    fn read_credentials(&self, url: &str) -> Task<Result<Option<(String, Vec<u8>)>>> {
+        Task::ready(Ok(self.state.lock().credentials.get(url).cloned()))
+    }
+
+    fn delete_credentials(&self, url: &str) -> Task<Result<()>> {
+        self.state.lock().credentials.remove(url);
+        Task::ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        div, point, px, red, AppContext, Bounds, DevicePixels, IntoElement, Modifiers,
+        MouseMoveEvent, Render, Size, Styled, ViewContext, VisualContext, WindowBounds,
+    };
+    use util::http;
+
+    struct Fill;
+
+    impl Render for Fill {
+        fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
+            div().size_full().bg(red())
+        }
+    }
+
+    fn open_fill_window(cx: &mut AppContext, size: Size<GlobalPixels>) {
+        cx.open_window(
+            WindowOptions {
+                bounds: WindowBounds::Fixed(Bounds {
+                    origin: point(GlobalPixels(0.), GlobalPixels(0.)),
+                    size,
+                }),
+                ..Default::default()
+            },
+            |cx| cx.new_view(|_| Fill),
+        );
+    }
+
+    #[test]
+    fn test_render_to_framebuffer() {
+        let platform = HeadlessPlatform::new();
+        let app = AppContext::new(platform.clone(), Arc::new(()), http::client());
+
+        let headless_platform = platform.clone();
+        platform.run(Box::new(move || {
+            let cx = &mut *app.borrow_mut();
+            open_fill_window(cx, size(GlobalPixels(8.), GlobalPixels(4.)));
+
+            cx.spawn(|cx| async move {
+                // Draw the first frame now, unless the event loop already has.
+                let window = headless_platform.state.lock().windows[0].clone();
+                window.request_frame_if_needed();
+
+                let (framebuffer_size, pixels) = window.framebuffer();
+                assert_eq!(framebuffer_size, size(DevicePixels(8), DevicePixels(4)));
+                assert!(pixels
+                    .chunks(4)
+                    .all(|pixel| pixel == [0xff, 0x00, 0x00, 0xff]));
+
+                cx.update(|cx| cx.quit()).unwrap();
+            })
+            .detach();
+        }));
+    }
+
+    #[test]
+    fn test_simulate_input_and_resize() {
+        let platform = HeadlessPlatform::new();
+        let app = AppContext::new(platform.clone(), Arc::new(()), http::client());
+
+        let headless_platform = platform.clone();
+        platform.run(Box::new(move || {
+            let cx = &mut *app.borrow_mut();
+            open_fill_window(cx, size(GlobalPixels(8.), GlobalPixels(4.)));
+
+            cx.spawn(|cx| async move {
+                let window = headless_platform.state.lock().windows[0].clone();
+                window.request_frame_if_needed();
+
+                let position = point(px(3.), px(2.));
+                window.simulate_input(PlatformInput::MouseMove(MouseMoveEvent {
+                    position,
+                    pressed_button: None,
+                    modifiers: Modifiers::default(),
+                }));
+                assert_eq!(window.mouse_position(), position);
+
+                window.simulate_resize(size(px(6.), px(6.)));
+                window.request_frame_if_needed();
+                let (framebuffer_size, pixels) = window.framebuffer();
+                assert_eq!(framebuffer_size, size(DevicePixels(6), DevicePixels(6)));
+                assert!(pixels
+                    .chunks(4)
+                    .all(|pixel| pixel == [0xff, 0x00, 0x00, 0xff]));
+
+                cx.update(|cx| cx.quit()).unwrap();
+            })
+            .detach();
+        }));
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{
+    px, AnyWindowHandle, Bounds, CpuAtlas, CpuRenderer, DevicePixels, GlobalPixels,
+    HeadlessPlatform, Modifiers, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
+    PlatformInputHandler, PlatformWindow, Point, PromptLevel, Scene, Size, WindowAppearance,
+    WindowBounds, WindowOptions,
+};
+use futures::channel::oneshot;
+use std::{
+    any::Any,
+    cell::RefCell,
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+pub(crate) struct HeadlessWindowState {
+    handle: AnyWindowHandle,
+    platform: Weak<HeadlessPlatform>,
+    display: Rc<dyn PlatformDisplay>,
+    bounds: Bounds<GlobalPixels>,
+    scale_factor: f32,
+    fullscreen: bool,
+    pub(crate) title: Option<String>,
+    pub(crate) edited: bool,
+    pub(crate) minimized: bool,
+    mouse_position: Point<Pixels>,
+    modifiers: Modifiers,
+    needs_frame: bool,
+    renderer: CpuRenderer,
+    sprite_atlas: Arc<CpuAtlas>,
+    input_handler: Option<PlatformInputHandler>,
+    request_frame_callback: Option<Box<dyn FnMut()>>,
+    input_callback: Option<Box<dyn FnMut(PlatformInput) -> bool>>,
+    active_status_change_callback: Option<Box<dyn FnMut(bool)>>,
+    resize_callback: Option<Box<dyn FnMut(Size<Pixels>, f32)>>,
+    fullscreen_callback: Option<Box<dyn FnMut(bool)>>,
+}
+
+/// A window that is never shown on screen. Frames are painted into an in-memory
+/// framebuffer, and input is delivered through [`HeadlessWindow::simulate_input`].
+#[derive(Clone)]
+pub struct HeadlessWindow(pub(crate) Rc<RefCell<HeadlessWindowState>>);
+
+impl HeadlessWindow {
+    pub(crate) fn new(
+        handle: AnyWindowHandle,
+        options: WindowOptions,
+        platform: Weak<HeadlessPlatform>,
+        display: Rc<dyn PlatformDisplay>,
+        scale_factor: f32,
+    ) -> Self {
+        let (bounds, fullscreen) = match options.bounds {
+            WindowBounds::Fixed(bounds) => (bounds, false),
+            WindowBounds::Maximized => (display.bounds(), false),
+            WindowBounds::Fullscreen => (display.bounds(), true),
+        };
+        let drawable_size = drawable_size(bounds.size, scale_factor);
+
+        Self(Rc::new(RefCell::new(HeadlessWindowState {
+            handle,
+            platform,
+            display,
+            bounds,
+            scale_factor,
+            fullscreen,
+            title: options
+                .titlebar
+                .and_then(|titlebar| titlebar.title)
+                .map(|title| title.to_string()),
+            edited: false,
+            minimized: false,
+            mouse_position: Point::default(),
+            modifiers: Modifiers::default(),
+            needs_frame: true,
+            renderer: CpuRenderer::new(drawable_size),
+            sprite_atlas: Arc::new(CpuAtlas::new()),
+            input_handler: None,
+            request_frame_callback: None,
+            input_callback: None,
+            active_status_change_callback: None,
+            resize_callback: None,
+            fullscreen_callback: None,
+        })))
+    }
+
+    pub(crate) fn handle(&self) -> AnyWindowHandle {
+        self.0.borrow().handle
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    /// Delivers the given input event to the window, as if it came from the platform.
+    /// Returns whether the event was handled.
+    pub fn simulate_input(&self, event: PlatformInput) -> bool {
+        {
+            let mut state = self.0.borrow_mut();
+            match &event {
+                PlatformInput::MouseMove(event) => state.mouse_position = event.position,
+                PlatformInput::MouseDown(event) => state.mouse_position = event.position,
+                PlatformInput::MouseUp(event) => state.mouse_position = event.position,
+                PlatformInput::ModifiersChanged(event) => state.modifiers = event.modifiers,
+                PlatformInput::KeyDown(event) => state.modifiers = event.keystroke.modifiers,
+                _ => {}
+            }
+        }
+
+        let callback = self.0.borrow_mut().input_callback.take();
+        if let Some(mut callback) = callback {
+            let handled = callback(event);
+            self.0.borrow_mut().input_callback = Some(callback);
+            handled
+        } else {
+            false
+        }
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    /// Resizes the window's content area, notifying GPUI of the new size.
+    pub fn simulate_resize(&self, new_size: Size<Pixels>) {
+        let scale_factor = {
+            let mut state = self.0.borrow_mut();
+            state.bounds.size = new_size.map(|pixels| GlobalPixels(pixels.0));
+            let drawable_size = drawable_size(state.bounds.size, state.scale_factor);
+            state.renderer.update_drawable_size(drawable_size);
+            state.scale_factor
+        };
+
+        let callback = self.0.borrow_mut().resize_callback.take();
+        if let Some(mut callback) = callback {
+            callback(new_size, scale_factor);
+            self.0.borrow_mut().resize_callback = Some(callback);
+        }
+    }
+
+    pub(crate) fn simulate_active_status_change(&self, active: bool) {
+        let callback = self.0.borrow_mut().active_status_change_callback.take();
+        if let Some(mut callback) = callback {
+            callback(active);
+            self.0.borrow_mut().active_status_change_callback = Some(callback);
+        }
+    }
+
+    /// Runs the window's frame callback if the window was invalidated since the last frame.
+    /// The event loop does this on every tick; tests can call it to draw a frame right away.
+    pub fn request_frame_if_needed(&self) {
+        let callback = {
+            let mut state = self.0.borrow_mut();
+            if !state.needs_frame || state.minimized {
+                return;
+            }
+            state.needs_frame = false;
+            state.request_frame_callback.take()
+        };
+
+        if let Some(mut callback) = callback {
+            callback();
+            self.0.borrow_mut().request_frame_callback = Some(callback);
+        }
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    /// Reads back the pixels painted during the last frame, as rows of RGBA pixels
+    /// with straight alpha.
+    pub fn framebuffer(&self) -> (Size<DevicePixels>, Vec<u8>) {
+        let state = self.0.borrow();
+        (state.renderer.size(), state.renderer.pixels().to_vec())
+    }
+}
+
+fn drawable_size(size: Size<GlobalPixels>, scale_factor: f32) -> Size<DevicePixels> {
+    size.map(|pixels| DevicePixels((pixels.0 * scale_factor).ceil() as i32))
+}
+
+impl PlatformWindow for HeadlessWindow {
+    fn bounds(&self) -> WindowBounds {
+        let state = self.0.borrow();
+        if state.fullscreen {
+            WindowBounds::Fullscreen
+        } else {
+            WindowBounds::Fixed(state.bounds)
+        }
+    }
+
+    fn content_size(&self) -> Size<Pixels> {
+        self.0.borrow().bounds.size.map(|pixels| px(pixels.0))
+    }
+
+    fn scale_factor(&self) -> f32 {
+        self.0.borrow().scale_factor
+    }
+
+    fn titlebar_height(&self) -> Pixels {
+        px(0.)
+    }
+
+    fn appearance(&self) -> WindowAppearance {
+        WindowAppearance::Light
+    }
+
+    fn display(&self) -> Rc<dyn PlatformDisplay> {
+        self.0.borrow().display.clone()
+    }
+
+    fn mouse_position(&self) -> Point<Pixels> {
+        self.0.borrow().mouse_position
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        self.0.borrow().modifiers
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn set_input_handler(&mut self, input_handler: PlatformInputHandler) {
+        self.0.borrow_mut().input_handler = Some(input_handler);
+    }
+
+    fn take_input_handler(&mut self) -> Option<PlatformInputHandler> {
+        self.0.borrow_mut().input_handler.take()
+    }
+
+    fn prompt(
+        &self,
+        _level: PromptLevel,
+        msg: &str,
+        _detail: Option<&str>,
+        answers: &[&str],
+    ) -> oneshot::Receiver<usize> {
+        log::info!(
+            "headless window prompt {msg:?} answered with {:?}",
+            answers.first()
+        );
+        let (tx, rx) = oneshot::channel();
+        tx.send(0).ok();
+        rx
+    }
+
+    fn activate(&self) {
+        let platform = self.0.borrow().platform.upgrade();
+        if let Some(platform) = platform {
+            platform.set_active_window(Some(self.clone()));
+        }
+    }
+
+    fn set_title(&mut self, title: &str) {
+        self.0.borrow_mut().title = Some(title.to_owned());
+    }
+
+    fn set_edited(&mut self, edited: bool) {
+        self.0.borrow_mut().edited = edited;
+    }
+
+    fn show_character_palette(&self) {}
+
+    fn minimize(&self) {
+        self.0.borrow_mut().minimized = true;
+    }
+
+    fn zoom(&self) {
+        let mut state = self.0.borrow_mut();
+        state.minimized = false;
+        state.bounds = state.display.bounds();
+        let drawable_size = drawable_size(state.bounds.size, state.scale_factor);
+        state.renderer.update_drawable_size(drawable_size);
+        let content_size = state.bounds.size.map(|pixels| px(pixels.0));
+        let scale_factor = state.scale_factor;
+        let callback = state.resize_callback.take();
+        drop(state);
+
+        if let Some(mut callback) = callback {
+            callback(content_size, scale_factor);
+            self.0.borrow_mut().resize_callback = Some(callback);
+        }
+    }
+
+    fn toggle_full_screen(&self) {
+        let fullscreen = {
+            let mut state = self.0.borrow_mut();
+            state.fullscreen = !state.fullscreen;
+            state.fullscreen
+        };
+
+        let callback = self.0.borrow_mut().fullscreen_callback.take();
+        if let Some(mut callback) = callback {
+            callback(fullscreen);
+            self.0.borrow_mut().fullscreen_callback = Some(callback);
+        }
+    }
+
+    fn on_request_frame(&self, callback: Box<dyn FnMut()>) {
+        self.0.borrow_mut().request_frame_callback = Some(callback);
+    }
+
+    fn on_input(&self, callback: Box<dyn FnMut(PlatformInput) -> bool>) {
+        self.0.borrow_mut().input_callback = Some(callback);
+    }
+
+    fn on_active_status_change(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0.borrow_mut().active_status_change_callback = Some(callback);
+    }
+
+    fn on_resize(&self, callback: Box<dyn FnMut(Size<Pixels>, f32)>) {
+        self.0.borrow_mut().resize_callback = Some(callback);
+    }
+
+    fn on_fullscreen(&self, callback: Box<dyn FnMut(bool)>) {
+        self.0.borrow_mut().fullscreen_callback = Some(callback);
+    }
+
+    fn on_moved(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn on_should_close(&self, _callback: Box<dyn FnMut() -> bool>) {}
+
+    fn on_close(&self, _callback: Box<dyn FnOnce()>) {}
+
+    fn on_appearance_changed(&self, _callback: Box<dyn FnMut()>) {}
+
+    fn is_topmost_for_position(&self, position: Point<Pixels>) -> bool {
+        let state = self.0.borrow();
+        let bounds = Bounds {
+            origin: Point::default(),
+            size: state.bounds.size.map(|pixels| px(pixels.0)),
+        };
+        bounds.contains(&position)
+    }
+
+    fn invalidate(&self) {
+        self.0.borrow_mut().needs_frame = true;
+    }
+
+    fn draw(&self, scene: &Scene) {
+        let mut state = self.0.borrow_mut();
+        let state = &mut *state;
+        state.renderer.draw(scene, state.sprite_atlas.as_ref());
+        state.sprite_atlas.finish_frame(scene);
+    }
+
+    fn sprite_atlas(&self) -> Arc<dyn PlatformAtlas> {
+        self.0.borrow().sprite_atlas.clone()
+    }
+
+    #[cfg(any(test, feature = "test-support"))]
+    fn as_headless(&self) -> Option<HeadlessWindow> {
+        Some(self.clone())
+    }
+}
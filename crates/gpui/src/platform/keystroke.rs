@@ -1,10 +1,10 @@
 use anyhow::anyhow;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use std::fmt::Write;
 
 /// A keystroke and associated metadata generated by the platform
-#[derive(Clone, Debug, Eq, PartialEq, Default, Deserialize, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize, Hash)]
 pub struct Keystroke {
     /// the state of the modifier keys at the time the keystroke was generated
     pub modifiers: Modifiers,
@@ -145,7 +145,7 @@ impl std::fmt::Display for Keystroke {
 }
 
 /// The state of the modifier keys at some point in time
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Default, Deserialize, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Default, Serialize, Deserialize, Hash)]
 pub struct Modifiers {
     /// The control key
     pub control: bool,
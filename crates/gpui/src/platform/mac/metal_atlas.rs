@@ -1,6 +1,6 @@
 use crate::{
-    AtlasKey, AtlasTextureId, AtlasTextureKind, AtlasTile, Bounds, DevicePixels, PlatformAtlas,
-    Point, Size,
+    size, AtlasKey, AtlasTextureId, AtlasTextureKind, AtlasTile, Bounds, CpuTexture,
+    CpuTextureSource, DevicePixels, PlatformAtlas, Point, Size,
 };
 use anyhow::Result;
 use collections::FxHashMap;
@@ -74,6 +74,52 @@ impl PlatformAtlas for MetalAtlas {
             Ok(tile)
         }
     }
+
+    fn texture_source(&self) -> Option<&dyn CpuTextureSource> {
+        Some(self)
+    }
+}
+
+impl CpuTextureSource for MetalAtlas {
+    /// Copies the texture's pixels out of video memory. Path textures are rendered on the GPU
+    /// every frame rather than uploaded, so they can't be read back.
+    fn with_texture(&self, id: AtlasTextureId, f: &mut dyn FnMut(CpuTexture)) {
+        if id.kind == AtlasTextureKind::Path {
+            return;
+        }
+        let lock = self.0.lock();
+        let textures = match id.kind {
+            AtlasTextureKind::Monochrome => &lock.monochrome_textures,
+            AtlasTextureKind::Polychrome => &lock.polychrome_textures,
+            AtlasTextureKind::Path => &lock.path_textures,
+        };
+        let Some(texture) = textures.get(id.index as usize) else {
+            return;
+        };
+
+        let texture_size = size(
+            DevicePixels(texture.metal_texture.width() as i32),
+            DevicePixels(texture.metal_texture.height() as i32),
+        );
+        let bytes_per_row = texture_size.width.to_bytes(texture.bytes_per_pixel()) as usize;
+        let mut bytes = vec![0; bytes_per_row * texture_size.height.0 as usize];
+        texture.metal_texture.get_bytes(
+            bytes.as_mut_ptr() as *mut _,
+            bytes_per_row as u64,
+            metal::MTLRegion::new_2d(
+                0,
+                0,
+                texture.metal_texture.width(),
+                texture.metal_texture.height(),
+            ),
+            0,
+        );
+        f(CpuTexture {
+            kind: id.kind,
+            size: texture_size,
+            bytes: &bytes,
+        });
+    }
 }
 
 impl MetalAtlasState {
@@ -1,7 +1,8 @@
 use crate::{
     point, size, AtlasTextureId, AtlasTextureKind, AtlasTile, Bounds, ContentMask, DevicePixels,
-    Hsla, MetalAtlas, MonochromeSprite, Path, PathId, PathVertex, PolychromeSprite, PrimitiveBatch,
-    Quad, ScaledPixels, Scene, Shadow, Size, Surface, Underline,
+    FilterEffect, FilterLayer, Hsla, LayerMask, MetalAtlas, MonochromeSprite, Path, PathCoverage,
+    PathId, PathVertex, PolychromeSprite, PrimitiveBatch, Quad, ScaledPixels, Scene, Shadow, Size,
+    Surface, Underline,
 };
 use cocoa::{
     base::{NO, YES},
@@ -15,12 +16,13 @@ use media::core_video::CVMetalTextureCache;
 use metal::{CommandQueue, MTLPixelFormat, MTLResourceOptions, NSRange};
 use objc::{self, msg_send, sel, sel_impl};
 use smallvec::SmallVec;
-use std::{ffi::c_void, mem, ptr, sync::Arc};
+use std::{ffi::c_void, mem, ptr, slice, sync::Arc};
 
 const SHADERS_METALLIB: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/shaders.metallib"));
 const INSTANCE_BUFFER_SIZE: usize = 32 * 1024 * 1024; // This is an arbitrary decision. There's probably a more optimal value (maybe even we could adjust dynamically...)
 
 pub(crate) struct MetalRenderer {
+    device: metal::Device,
     layer: metal::MetalLayer,
     command_queue: CommandQueue,
     paths_rasterization_pipeline_state: metal::RenderPipelineState,
@@ -31,6 +33,11 @@ pub(crate) struct MetalRenderer {
     monochrome_sprites_pipeline_state: metal::RenderPipelineState,
     polychrome_sprites_pipeline_state: metal::RenderPipelineState,
     surfaces_pipeline_state: metal::RenderPipelineState,
+    filter_blur_pipeline_state: metal::RenderPipelineState,
+    filter_composite_pipeline_state: metal::RenderPipelineState,
+    /// Offscreen textures for filter layers, three per level of nesting: one that the layer
+    /// is painted into, one that holds it blurred horizontally and one for its mask.
+    filter_textures: Vec<metal::Texture>,
     unit_vertices: metal::Buffer,
     instances: metal::Buffer,
     sprite_atlas: Arc<MetalAtlas>,
@@ -154,11 +161,30 @@ impl MetalRenderer {
             "surface_fragment",
             MTLPixelFormat::BGRA8Unorm,
         );
+        let filter_blur_pipeline_state = build_filter_blur_pipeline_state(
+            &device,
+            &library,
+            "filter_blur",
+            "filter_layer_vertex",
+            "filter_blur_fragment",
+            MTLPixelFormat::BGRA8Unorm,
+        );
+        let filter_composite_pipeline_state = build_pipeline_state(
+            &device,
+            &library,
+            "filter_composite",
+            "filter_composite_vertex",
+            "filter_composite_fragment",
+            MTLPixelFormat::BGRA8Unorm,
+        );
 
         let command_queue = device.new_command_queue();
         let sprite_atlas = Arc::new(MetalAtlas::new(device.clone()));
+        let core_video_texture_cache =
+            unsafe { CVMetalTextureCache::new(device.as_ptr()).unwrap() };
 
         Self {
+            device,
             layer,
             command_queue,
             paths_rasterization_pipeline_state,
@@ -169,10 +195,13 @@ impl MetalRenderer {
             monochrome_sprites_pipeline_state,
             polychrome_sprites_pipeline_state,
             surfaces_pipeline_state,
+            filter_blur_pipeline_state,
+            filter_composite_pipeline_state,
+            filter_textures: Vec::new(),
             unit_vertices,
             instances,
             sprite_atlas,
-            core_video_texture_cache: unsafe { CVMetalTextureCache::new(device.as_ptr()).unwrap() },
+            core_video_texture_cache,
         }
     }
 
@@ -204,9 +233,11 @@ impl MetalRenderer {
         let command_buffer = command_queue.new_command_buffer();
         let mut instance_offset = 0;
 
-        let Some(path_tiles) =
-            self.rasterize_paths(scene.paths(), &mut instance_offset, command_buffer)
-        else {
+        let Some(path_tiles) = self.rasterize_paths(
+            scene.paths().iter().chain(scene.mask_paths()),
+            &mut instance_offset,
+            command_buffer,
+        ) else {
             panic!("failed to rasterize {} paths", scene.paths().len());
         };
 
@@ -221,16 +252,11 @@ impl MetalRenderer {
         color_attachment.set_store_action(metal::MTLStoreAction::Store);
         let alpha = if self.layer.is_opaque() { 1. } else { 0. };
         color_attachment.set_clear_color(metal::MTLClearColor::new(0., 0., 0., alpha));
-        let command_encoder = command_buffer.new_render_command_encoder(render_pass_descriptor);
-
-        command_encoder.set_viewport(metal::MTLViewport {
-            originX: 0.0,
-            originY: 0.0,
-            width: i32::from(viewport_size.width) as f64,
-            height: i32::from(viewport_size.height) as f64,
-            znear: 0.0,
-            zfar: 1.0,
-        });
+        let mut command_encoder = command_buffer.new_render_command_encoder(render_pass_descriptor);
+        set_viewport(command_encoder, viewport_size);
+
+        // The textures of the filter layers being painted, innermost last.
+        let mut filter_layers: Vec<metal::Texture> = Vec::new();
         for batch in scene.batches() {
             let ok = match batch {
                 PrimitiveBatch::Shadows(shadows) => self.draw_shadows(
@@ -281,6 +307,56 @@ impl MetalRenderer {
                     viewport_size,
                     command_encoder,
                 ),
+                PrimitiveBatch::BeginFilter(_) => {
+                    command_encoder.end_encoding();
+                    let texture = self.filter_texture(3 * filter_layers.len(), viewport_size);
+                    command_encoder = begin_render_pass(
+                        command_buffer,
+                        &texture,
+                        metal::MTLLoadAction::Clear,
+                        viewport_size,
+                    );
+                    filter_layers.push(texture);
+                    true
+                }
+                PrimitiveBatch::EndFilter(filter) => {
+                    command_encoder.end_encoding();
+                    let layer = filter_layers.pop().expect("filter layer was never begun");
+                    let depth = filter_layers.len();
+                    let mut ok = true;
+                    let mask = filter.mask.as_ref().map(|mask| {
+                        let texture = self.filter_texture(3 * depth + 2, viewport_size);
+                        let command_encoder = begin_render_pass(
+                            command_buffer,
+                            &texture,
+                            metal::MTLLoadAction::Clear,
+                            viewport_size,
+                        );
+                        ok = self.draw_layer_mask(
+                            mask,
+                            &path_tiles,
+                            &mut instance_offset,
+                            viewport_size,
+                            command_encoder,
+                        );
+                        command_encoder.end_encoding();
+                        texture
+                    });
+                    let target = filter_layers
+                        .last()
+                        .map(|texture| texture.as_ref())
+                        .unwrap_or(drawable.texture());
+                    command_encoder = self.composite_filter_layer(
+                        filter,
+                        &layer,
+                        mask.as_deref(),
+                        3 * depth + 1,
+                        target,
+                        viewport_size,
+                        command_buffer,
+                    );
+                    ok
+                }
             };
 
             if !ok {
@@ -310,9 +386,152 @@ impl MetalRenderer {
         drawable.present();
     }
 
-    fn rasterize_paths(
+    /// Returns an offscreen texture the size of the viewport from the pool of filter textures.
+    fn filter_texture(
         &mut self,
-        paths: &[Path<ScaledPixels>],
+        index: usize,
+        viewport_size: Size<DevicePixels>,
+    ) -> metal::Texture {
+        let width = i32::from(viewport_size.width) as u64;
+        let height = i32::from(viewport_size.height) as u64;
+        if let Some(texture) = self.filter_textures.get(index) {
+            if texture.width() == width && texture.height() == height {
+                return texture.clone();
+            }
+        }
+
+        let descriptor = metal::TextureDescriptor::new();
+        descriptor.set_pixel_format(MTLPixelFormat::BGRA8Unorm);
+        descriptor.set_width(width);
+        descriptor.set_height(height);
+        descriptor.set_storage_mode(metal::MTLStorageMode::Private);
+        descriptor
+            .set_usage(metal::MTLTextureUsage::RenderTarget | metal::MTLTextureUsage::ShaderRead);
+        let texture = self.device.new_texture(&descriptor);
+        if index < self.filter_textures.len() {
+            self.filter_textures[index] = texture.clone();
+        } else {
+            self.filter_textures.push(texture.clone());
+        }
+        texture
+    }
+
+    fn draw_layer_mask(
+        &mut self,
+        mask: &LayerMask,
+        path_tiles: &HashMap<PathId, AtlasTile>,
+        offset: &mut usize,
+        viewport_size: Size<DevicePixels>,
+        command_encoder: &metal::RenderCommandEncoderRef,
+    ) -> bool {
+        match mask {
+            LayerMask::Path(path) => self.draw_paths(
+                slice::from_ref(path),
+                path_tiles,
+                offset,
+                viewport_size,
+                command_encoder,
+            ),
+            LayerMask::Quad(quad) => self.draw_quads(
+                slice::from_ref(quad),
+                offset,
+                viewport_size,
+                command_encoder,
+            ),
+            LayerMask::Sprite(sprite) => self.draw_polychrome_sprites(
+                sprite.tile.texture_id,
+                slice::from_ref(sprite),
+                offset,
+                viewport_size,
+                command_encoder,
+            ),
+        }
+    }
+
+    /// Blurs, color-filters and masks a finished filter layer onto the target beneath it,
+    /// returning an encoder that continues painting into that target.
+    #[allow(clippy::too_many_arguments)]
+    fn composite_filter_layer<'a>(
+        &mut self,
+        filter: &FilterLayer,
+        layer: &metal::TextureRef,
+        mask: Option<&metal::TextureRef>,
+        scratch_index: usize,
+        target: &metal::TextureRef,
+        viewport_size: Size<DevicePixels>,
+        command_buffer: &'a metal::CommandBufferRef,
+    ) -> &'a metal::RenderCommandEncoderRef {
+        let effect = &filter.effect;
+        let effect_size = mem::size_of::<FilterEffect>() as u64;
+        let effect_ptr = effect as *const FilterEffect as *const c_void;
+
+        // Blur horizontally into a scratch texture. The composite pass blurs vertically.
+        let mut source = layer.to_owned();
+        if effect.blur_radius.0 > 0. {
+            let scratch = self.filter_texture(scratch_index, viewport_size);
+            let command_encoder = begin_render_pass(
+                command_buffer,
+                &scratch,
+                metal::MTLLoadAction::Clear,
+                viewport_size,
+            );
+            command_encoder.set_render_pipeline_state(&self.filter_blur_pipeline_state);
+            command_encoder.set_vertex_buffer(
+                FilterInputIndex::Vertices as u64,
+                Some(&self.unit_vertices),
+                0,
+            );
+            command_encoder.set_fragment_bytes(
+                FilterInputIndex::Effect as u64,
+                effect_size,
+                effect_ptr,
+            );
+            command_encoder.set_fragment_texture(FilterInputIndex::Layer as u64, Some(layer));
+            command_encoder.draw_primitives(metal::MTLPrimitiveType::Triangle, 0, 6);
+            command_encoder.end_encoding();
+            source = scratch;
+        }
+
+        let command_encoder = begin_render_pass(
+            command_buffer,
+            target,
+            metal::MTLLoadAction::Load,
+            viewport_size,
+        );
+        command_encoder.set_render_pipeline_state(&self.filter_composite_pipeline_state);
+        command_encoder.set_vertex_buffer(
+            FilterInputIndex::Vertices as u64,
+            Some(&self.unit_vertices),
+            0,
+        );
+        command_encoder.set_vertex_bytes(FilterInputIndex::Effect as u64, effect_size, effect_ptr);
+        command_encoder.set_vertex_bytes(
+            FilterInputIndex::ViewportSize as u64,
+            mem::size_of_val(&viewport_size) as u64,
+            &viewport_size as *const Size<DevicePixels> as *const _,
+        );
+        command_encoder.set_fragment_bytes(
+            FilterInputIndex::Effect as u64,
+            effect_size,
+            effect_ptr,
+        );
+        command_encoder.set_fragment_texture(FilterInputIndex::Layer as u64, Some(&source));
+        // The layer stands in for a missing mask, so that every texture is bound.
+        let masked = mask.is_some() as u32;
+        command_encoder.set_fragment_bytes(
+            FilterInputIndex::Masked as u64,
+            mem::size_of_val(&masked) as u64,
+            &masked as *const u32 as *const _,
+        );
+        command_encoder
+            .set_fragment_texture(FilterInputIndex::Mask as u64, Some(mask.unwrap_or(&source)));
+        command_encoder.draw_primitives(metal::MTLPrimitiveType::Triangle, 0, 6);
+        command_encoder
+    }
+
+    fn rasterize_paths<'a>(
+        &mut self,
+        paths: impl IntoIterator<Item = &'a Path<ScaledPixels>>,
         offset: &mut usize,
         command_buffer: &metal::CommandBufferRef,
     ) -> Option<HashMap<PathId, AtlasTile>> {
@@ -333,6 +552,7 @@ impl MetalRenderer {
                     st_position: vertex.st_position,
                     content_mask: ContentMask {
                         bounds: tile.bounds.map(Into::into),
+                        ..Default::default()
                     },
                 }));
             tiles.insert(path.id, tile);
@@ -556,6 +776,8 @@ impl MetalRenderer {
                         },
                         color: path.color,
                         tile: (*tile).clone(),
+                        coverage: path.coverage,
+                        content_mask: path.content_mask.clone(),
                     });
                     paths_and_tiles.next();
                     continue;
@@ -892,6 +1114,11 @@ impl MetalRenderer {
                 Some(&self.instances),
                 *offset as u64,
             );
+            command_encoder.set_fragment_buffer(
+                SurfaceInputIndex::Surfaces as u64,
+                Some(&self.instances),
+                *offset as u64,
+            );
             command_encoder.set_vertex_bytes(
                 SurfaceInputIndex::TextureSize as u64,
                 mem::size_of_val(&texture_size) as u64,
@@ -925,6 +1152,40 @@ impl MetalRenderer {
     }
 }
 
+fn begin_render_pass<'a>(
+    command_buffer: &'a metal::CommandBufferRef,
+    texture: &metal::TextureRef,
+    load_action: metal::MTLLoadAction,
+    viewport_size: Size<DevicePixels>,
+) -> &'a metal::RenderCommandEncoderRef {
+    let render_pass_descriptor = metal::RenderPassDescriptor::new();
+    let color_attachment = render_pass_descriptor
+        .color_attachments()
+        .object_at(0)
+        .unwrap();
+    color_attachment.set_texture(Some(texture));
+    color_attachment.set_load_action(load_action);
+    color_attachment.set_store_action(metal::MTLStoreAction::Store);
+    color_attachment.set_clear_color(metal::MTLClearColor::new(0., 0., 0., 0.));
+    let command_encoder = command_buffer.new_render_command_encoder(render_pass_descriptor);
+    set_viewport(command_encoder, viewport_size);
+    command_encoder
+}
+
+fn set_viewport(
+    command_encoder: &metal::RenderCommandEncoderRef,
+    viewport_size: Size<DevicePixels>,
+) {
+    command_encoder.set_viewport(metal::MTLViewport {
+        originX: 0.0,
+        originY: 0.0,
+        width: i32::from(viewport_size.width) as f64,
+        height: i32::from(viewport_size.height) as f64,
+        znear: 0.0,
+        zfar: 1.0,
+    });
+}
+
 fn build_pipeline_state(
     device: &metal::DeviceRef,
     library: &metal::LibraryRef,
@@ -952,7 +1213,38 @@ fn build_pipeline_state(
     color_attachment.set_source_rgb_blend_factor(metal::MTLBlendFactor::SourceAlpha);
     color_attachment.set_source_alpha_blend_factor(metal::MTLBlendFactor::One);
     color_attachment.set_destination_rgb_blend_factor(metal::MTLBlendFactor::OneMinusSourceAlpha);
-    color_attachment.set_destination_alpha_blend_factor(metal::MTLBlendFactor::One);
+    // Accumulate coverage the way `over` does, so that filter layers keep the alpha of what
+    // was painted into them.
+    color_attachment.set_destination_alpha_blend_factor(metal::MTLBlendFactor::OneMinusSourceAlpha);
+
+    device
+        .new_render_pipeline_state(&descriptor)
+        .expect("could not create render pipeline state")
+}
+
+/// Builds a pipeline that replaces the target's pixels rather than blending with them.
+fn build_filter_blur_pipeline_state(
+    device: &metal::DeviceRef,
+    library: &metal::LibraryRef,
+    label: &str,
+    vertex_fn_name: &str,
+    fragment_fn_name: &str,
+    pixel_format: metal::MTLPixelFormat,
+) -> metal::RenderPipelineState {
+    let vertex_fn = library
+        .get_function(vertex_fn_name, None)
+        .expect("error locating vertex function");
+    let fragment_fn = library
+        .get_function(fragment_fn_name, None)
+        .expect("error locating fragment function");
+
+    let descriptor = metal::RenderPipelineDescriptor::new();
+    descriptor.set_label(label);
+    descriptor.set_vertex_function(Some(vertex_fn.as_ref()));
+    descriptor.set_fragment_function(Some(fragment_fn.as_ref()));
+    let color_attachment = descriptor.color_attachments().object_at(0).unwrap();
+    color_attachment.set_pixel_format(pixel_format);
+    color_attachment.set_blending_enabled(false);
 
     device
         .new_render_pipeline_state(&descriptor)
@@ -1038,6 +1330,16 @@ enum SurfaceInputIndex {
     CbCrTexture = 5,
 }
 
+#[repr(C)]
+enum FilterInputIndex {
+    Vertices = 0,
+    Effect = 1,
+    ViewportSize = 2,
+    Layer = 3,
+    Masked = 4,
+    Mask = 5,
+}
+
 #[repr(C)]
 enum PathRasterizationInputIndex {
     Vertices = 0,
@@ -1050,6 +1352,8 @@ pub struct PathSprite {
     pub bounds: Bounds<ScaledPixels>,
     pub color: Hsla,
     pub tile: AtlasTile,
+    pub coverage: PathCoverage,
+    pub content_mask: ContentMask<ScaledPixels>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -842,24 +842,13 @@ impl Platform for MacPlatform {
                     .read_from_pasteboard(state.pasteboard, state.metadata_pasteboard_type)
                     .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok());
 
+                let mut item = ClipboardItem::new(text);
                 if let Some((hash, metadata)) = hash_bytes.zip(metadata_bytes) {
-                    if hash == ClipboardItem::text_hash(&text) {
-                        Some(ClipboardItem {
-                            text,
-                            metadata: Some(metadata),
-                        })
-                    } else {
-                        Some(ClipboardItem {
-                            text,
-                            metadata: None,
-                        })
+                    if hash == ClipboardItem::text_hash(&item.text) {
+                        item.metadata = Some(metadata);
                     }
-                } else {
-                    Some(ClipboardItem {
-                        text,
-                        metadata: None,
-                    })
                 }
+                Some(item)
             } else {
                 None
             }
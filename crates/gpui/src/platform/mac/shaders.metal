@@ -6,22 +6,41 @@ using namespace metal;
 float4 hsla_to_rgba(Hsla hsla);
 float4 to_device_position(float2 unit_vertex, Bounds_ScaledPixels bounds,
                           constant Size_DevicePixels *viewport_size);
+float4 to_device_position_transformed(float2 unit_vertex,
+                                      Bounds_ScaledPixels bounds,
+                                      TransformationMatrix transform,
+                                      constant Size_DevicePixels *viewport_size);
+float2 apply_transform(TransformationMatrix transform, float2 position);
+float2 untransform_position(TransformationMatrix transform, float2 position);
 float2 to_tile_position(float2 unit_vertex, AtlasTile tile,
                         constant Size_DevicePixels *atlas_size);
 float4 distance_from_clip_rect(float2 unit_vertex, Bounds_ScaledPixels bounds,
                                Bounds_ScaledPixels clip_bounds);
+float4 distance_from_clip_rect_transformed(float2 unit_vertex,
+                                           Bounds_ScaledPixels bounds,
+                                           TransformationMatrix transform,
+                                           Bounds_ScaledPixels clip_bounds);
 float quad_sdf(float2 point, Bounds_ScaledPixels bounds,
                Corners_ScaledPixels corner_radii);
+float content_mask_alpha(float2 position,
+                         ContentMask_ScaledPixels content_mask);
+float border_pattern_alpha(BorderStyle style, float width, float along_edge,
+                           float edge_length, float across_edge);
 float gaussian(float x, float sigma);
 float2 erf(float2 x);
 float blur_along_x(float x, float y, float sigma, float corner,
                    float2 half_size);
+float shadow_alpha(float2 position, Bounds_ScaledPixels bounds,
+                   Corners_ScaledPixels corner_radii, float blur_radius);
 float4 over(float4 below, float4 above);
+float4 gradient_color(Background background, Bounds_ScaledPixels bounds,
+                      float2 point);
+float4 blur_layer(texture2d<float> layer, float2 position, float sigma,
+                  float2 direction);
 
 struct QuadVertexOutput {
   float4 position [[position]];
   float4 background_color [[flat]];
-  float4 border_color [[flat]];
   uint quad_id [[flat]];
   float clip_distance [[clip_distance]][4];
 };
@@ -29,7 +48,6 @@ struct QuadVertexOutput {
 struct QuadFragmentInput {
   float4 position [[position]];
   float4 background_color [[flat]];
-  float4 border_color [[flat]];
   uint quad_id [[flat]];
 };
 
@@ -43,16 +61,14 @@ vertex QuadVertexOutput quad_vertex(uint unit_vertex_id [[vertex_id]],
                                     [[buffer(QuadInputIndex_ViewportSize)]]) {
   float2 unit_vertex = unit_vertices[unit_vertex_id];
   Quad quad = quads[quad_id];
-  float4 device_position =
-      to_device_position(unit_vertex, quad.bounds, viewport_size);
-  float4 clip_distance = distance_from_clip_rect(unit_vertex, quad.bounds,
-                                                 quad.content_mask.bounds);
-  float4 background_color = hsla_to_rgba(quad.background);
-  float4 border_color = hsla_to_rgba(quad.border_color);
+  float4 device_position = to_device_position_transformed(
+      unit_vertex, quad.bounds, quad.transform, viewport_size);
+  float4 clip_distance = distance_from_clip_rect_transformed(
+      unit_vertex, quad.bounds, quad.transform, quad.content_mask.bounds);
+  float4 background_color = hsla_to_rgba(quad.background.solid);
   return QuadVertexOutput{
       device_position,
       background_color,
-      border_color,
       quad_id,
       {clip_distance.x, clip_distance.y, clip_distance.z, clip_distance.w}};
 }
@@ -61,11 +77,17 @@ fragment float4 quad_fragment(QuadFragmentInput input [[stage_in]],
                               constant Quad *quads
                               [[buffer(QuadInputIndex_Quads)]]) {
   Quad quad = quads[input.quad_id];
+  float2 position = untransform_position(quad.transform, input.position.xy);
+  float4 background_color = input.background_color;
+  if (quad.background.tag != BackgroundTag_Solid) {
+    background_color = gradient_color(quad.background, quad.bounds, position);
+  }
+
   float2 half_size =
       float2(quad.bounds.size.width, quad.bounds.size.height) / 2.;
   float2 center =
       float2(quad.bounds.origin.x, quad.bounds.origin.y) + half_size;
-  float2 center_to_point = input.position.xy - center;
+  float2 center_to_point = position - center;
   float corner_radius;
   if (center_to_point.x < 0.) {
     if (center_to_point.y < 0.) {
@@ -88,35 +110,64 @@ fragment float4 quad_fragment(QuadFragmentInput input [[stage_in]],
       min(0., max(rounded_edge_to_point.x, rounded_edge_to_point.y)) -
       corner_radius;
 
-  float vertical_border = center_to_point.x <= 0. ? quad.border_widths.left
-                                                  : quad.border_widths.right;
-  float horizontal_border = center_to_point.y <= 0. ? quad.border_widths.top
-                                                    : quad.border_widths.bottom;
+  bool is_left = center_to_point.x <= 0.;
+  bool is_top = center_to_point.y <= 0.;
+  float vertical_border =
+      is_left ? quad.border_widths.left : quad.border_widths.right;
+  float horizontal_border =
+      is_top ? quad.border_widths.top : quad.border_widths.bottom;
   float2 inset_size =
       half_size - corner_radius - float2(vertical_border, horizontal_border);
   float2 point_to_inset_corner = fabs(center_to_point) - inset_size;
   float border_width;
+  Hsla border_color;
+  BorderStyle border_style;
+  float along_edge;
+  float edge_length;
+  // Dashes and dots are laid out along the middle of the border, starting from
+  // the middle of the corner where the edge begins.
   if (point_to_inset_corner.x < 0. && point_to_inset_corner.y < 0.) {
     border_width = 0.;
   } else if (point_to_inset_corner.y > point_to_inset_corner.x) {
     border_width = horizontal_border;
+    border_color = is_top ? quad.border_colors.top : quad.border_colors.bottom;
+    border_style = is_top ? quad.border_styles.top : quad.border_styles.bottom;
+    float start = quad.bounds.origin.x + quad.border_widths.left / 2.;
+    float end = quad.bounds.origin.x + quad.bounds.size.width -
+                quad.border_widths.right / 2.;
+    along_edge = position.x - start;
+    edge_length = end - start;
   } else {
     border_width = vertical_border;
+    border_color = is_left ? quad.border_colors.left : quad.border_colors.right;
+    border_style = is_left ? quad.border_styles.left : quad.border_styles.right;
+    float start = quad.bounds.origin.y + quad.border_widths.top / 2.;
+    float end = quad.bounds.origin.y + quad.bounds.size.height -
+                quad.border_widths.bottom / 2.;
+    along_edge = position.y - start;
+    edge_length = end - start;
   }
 
   float4 color;
   if (border_width == 0.) {
-    color = input.background_color;
+    color = background_color;
   } else {
     float inset_distance = distance + border_width;
+    float4 border_rgba = hsla_to_rgba(border_color);
+    border_rgba.a *=
+        border_pattern_alpha(border_style, border_width, along_edge,
+                             edge_length, distance + border_width / 2.);
     // Blend the border on top of the background and then linearly interpolate
     // between the two as we slide inside the background.
-    float4 blended_border = over(input.background_color, input.border_color);
-    color = mix(blended_border, input.background_color,
+    float4 blended_border = over(background_color, border_rgba);
+    color = mix(blended_border, background_color,
                 saturate(0.5 - inset_distance));
   }
 
-  return color * float4(1., 1., 1., saturate(0.5 - distance));
+  return color * float4(1., 1., 1.,
+                        saturate(0.5 - distance) *
+                            content_mask_alpha(input.position.xy,
+                                               quad.content_mask));
 }
 
 struct ShadowVertexOutput {
@@ -141,7 +192,9 @@ vertex ShadowVertexOutput shadow_vertex(
   float2 unit_vertex = unit_vertices[unit_vertex_id];
   Shadow shadow = shadows[shadow_id];
 
-  float margin = 3. * shadow.blur_radius;
+  // Inset shadows are painted inside the element, outer ones extend past it by
+  // the blur.
+  float margin = shadow.inset ? 0. : 3. * shadow.blur_radius;
   // Set the bounds of the shadow and adjust its size based on the shadow's
   // spread radius to achieve the spreading effect
   Bounds_ScaledPixels bounds = shadow.bounds;
@@ -150,10 +203,10 @@ vertex ShadowVertexOutput shadow_vertex(
   bounds.size.width += 2. * margin;
   bounds.size.height += 2. * margin;
 
-  float4 device_position =
-      to_device_position(unit_vertex, bounds, viewport_size);
-  float4 clip_distance =
-      distance_from_clip_rect(unit_vertex, bounds, shadow.content_mask.bounds);
+  float4 device_position = to_device_position_transformed(
+      unit_vertex, bounds, shadow.transform, viewport_size);
+  float4 clip_distance = distance_from_clip_rect_transformed(
+      unit_vertex, bounds, shadow.transform, shadow.content_mask.bounds);
   float4 color = hsla_to_rgba(shadow.color);
 
   return ShadowVertexOutput{
@@ -167,45 +220,37 @@ fragment float4 shadow_fragment(ShadowFragmentInput input [[stage_in]],
                                 constant Shadow *shadows
                                 [[buffer(ShadowInputIndex_Shadows)]]) {
   Shadow shadow = shadows[input.shadow_id];
+  float2 position = untransform_position(shadow.transform, input.position.xy);
 
-  float2 origin = float2(shadow.bounds.origin.x, shadow.bounds.origin.y);
-  float2 size = float2(shadow.bounds.size.width, shadow.bounds.size.height);
-  float2 half_size = size / 2.;
-  float2 center = origin + half_size;
-  float2 point = input.position.xy - center;
-  float corner_radius;
-  if (point.x < 0.) {
-    if (point.y < 0.) {
-      corner_radius = shadow.corner_radii.top_left;
-    } else {
-      corner_radius = shadow.corner_radii.bottom_left;
-    }
-  } else {
-    if (point.y < 0.) {
-      corner_radius = shadow.corner_radii.top_right;
-    } else {
-      corner_radius = shadow.corner_radii.bottom_right;
-    }
-  }
-
-  // The signal is only non-zero in a limited range, so don't waste samples
-  float low = point.y - half_size.y;
-  float high = point.y + half_size.y;
-  float start = clamp(-3. * shadow.blur_radius, low, high);
-  float end = clamp(3. * shadow.blur_radius, low, high);
+  float alpha;
+  if (shadow.inset) {
+    // The shadow is cast by a hole in an infinite plane, painted inside the
+    // element.
+    float spread = shadow.spread_radius;
+    Bounds_ScaledPixels hole = shadow.bounds;
+    hole.origin.x += shadow.offset.x + spread;
+    hole.origin.y += shadow.offset.y + spread;
+    hole.size.width = max(0., hole.size.width - 2. * spread);
+    hole.size.height = max(0., hole.size.height - 2. * spread);
+    Corners_ScaledPixels hole_radii = shadow.corner_radii;
+    hole_radii.top_left = max(0., hole_radii.top_left - spread);
+    hole_radii.top_right = max(0., hole_radii.top_right - spread);
+    hole_radii.bottom_right = max(0., hole_radii.bottom_right - spread);
+    hole_radii.bottom_left = max(0., hole_radii.bottom_left - spread);
 
-  // Accumulate samples (we can get away with surprisingly few samples)
-  float step = (end - start) / 4.;
-  float y = start + step * 0.5;
-  float alpha = 0.;
-  for (int i = 0; i < 4; i++) {
-    alpha += blur_along_x(point.x, point.y - y, shadow.blur_radius,
-                          corner_radius, half_size) *
-             gaussian(y, shadow.blur_radius) * step;
-    y += step;
+    float element_alpha = saturate(
+        0.5 - quad_sdf(position, shadow.bounds, shadow.corner_radii));
+    alpha = (1. - shadow_alpha(position, hole, hole_radii, shadow.blur_radius)) *
+            element_alpha;
+  } else {
+    alpha = shadow_alpha(position, shadow.bounds, shadow.corner_radii,
+                         shadow.blur_radius);
   }
 
-  return input.color * float4(1., 1., 1., alpha);
+  return input.color *
+         float4(1., 1., 1.,
+                alpha * content_mask_alpha(input.position.xy,
+                                           shadow.content_mask));
 }
 
 struct UnderlineVertexOutput {
@@ -229,10 +274,11 @@ vertex UnderlineVertexOutput underline_vertex(
     [[buffer(ShadowInputIndex_ViewportSize)]]) {
   float2 unit_vertex = unit_vertices[unit_vertex_id];
   Underline underline = underlines[underline_id];
-  float4 device_position =
-      to_device_position(unit_vertex, underline.bounds, viewport_size);
-  float4 clip_distance = distance_from_clip_rect(unit_vertex, underline.bounds,
-                                                 underline.content_mask.bounds);
+  float4 device_position = to_device_position_transformed(
+      unit_vertex, underline.bounds, underline.transform, viewport_size);
+  float4 clip_distance = distance_from_clip_rect_transformed(
+      unit_vertex, underline.bounds, underline.transform,
+      underline.content_mask.bounds);
   float4 color = hsla_to_rgba(underline.color);
   return UnderlineVertexOutput{
       device_position,
@@ -245,12 +291,16 @@ fragment float4 underline_fragment(UnderlineFragmentInput input [[stage_in]],
                                    constant Underline *underlines
                                    [[buffer(UnderlineInputIndex_Underlines)]]) {
   Underline underline = underlines[input.underline_id];
+  float mask_alpha =
+      content_mask_alpha(input.position.xy, underline.content_mask);
   if (underline.wavy) {
     float half_thickness = underline.thickness * 0.5;
     float2 origin =
         float2(underline.bounds.origin.x, underline.bounds.origin.y);
-    float2 st = ((input.position.xy - origin) / underline.bounds.size.height) -
-                float2(0., 0.5);
+    float2 position =
+        untransform_position(underline.transform, input.position.xy);
+    float2 st =
+        ((position - origin) / underline.bounds.size.height) - float2(0., 0.5);
     float frequency = (M_PI_F * (3. * underline.thickness)) / 8.;
     float amplitude = 1. / (2. * underline.thickness);
     float sine = sin(st.x * frequency) * amplitude;
@@ -261,9 +311,9 @@ fragment float4 underline_fragment(UnderlineFragmentInput input [[stage_in]],
     float distance_from_bottom_border = distance_in_pixels + half_thickness;
     float alpha = saturate(
         0.5 - max(-distance_from_bottom_border, distance_from_top_border));
-    return input.color * float4(1., 1., 1., alpha);
+    return input.color * float4(1., 1., 1., alpha * mask_alpha);
   } else {
-    return input.color;
+    return input.color * float4(1., 1., 1., mask_alpha);
   }
 }
 
@@ -271,6 +321,7 @@ struct MonochromeSpriteVertexOutput {
   float4 position [[position]];
   float2 tile_position;
   float4 color [[flat]];
+  uint sprite_id [[flat]];
   float clip_distance [[clip_distance]][4];
 };
 
@@ -278,6 +329,7 @@ struct MonochromeSpriteFragmentInput {
   float4 position [[position]];
   float2 tile_position;
   float4 color [[flat]];
+  uint sprite_id [[flat]];
 };
 
 vertex MonochromeSpriteVertexOutput monochrome_sprite_vertex(
@@ -290,16 +342,17 @@ vertex MonochromeSpriteVertexOutput monochrome_sprite_vertex(
     [[buffer(SpriteInputIndex_AtlasTextureSize)]]) {
   float2 unit_vertex = unit_vertices[unit_vertex_id];
   MonochromeSprite sprite = sprites[sprite_id];
-  float4 device_position =
-      to_device_position(unit_vertex, sprite.bounds, viewport_size);
-  float4 clip_distance = distance_from_clip_rect(unit_vertex, sprite.bounds,
-                                                 sprite.content_mask.bounds);
+  float4 device_position = to_device_position_transformed(
+      unit_vertex, sprite.bounds, sprite.transform, viewport_size);
+  float4 clip_distance = distance_from_clip_rect_transformed(
+      unit_vertex, sprite.bounds, sprite.transform, sprite.content_mask.bounds);
   float2 tile_position = to_tile_position(unit_vertex, sprite.tile, atlas_size);
   float4 color = hsla_to_rgba(sprite.color);
   return MonochromeSpriteVertexOutput{
       device_position,
       tile_position,
       color,
+      sprite_id,
       {clip_distance.x, clip_distance.y, clip_distance.z, clip_distance.w}};
 }
 
@@ -311,8 +364,10 @@ fragment float4 monochrome_sprite_fragment(
                                           min_filter::linear);
   float4 sample =
       atlas_texture.sample(atlas_texture_sampler, input.tile_position);
+  MonochromeSprite sprite = sprites[input.sprite_id];
   float4 color = input.color;
-  color.a *= sample.a;
+  color.a *= sample.a *
+             content_mask_alpha(input.position.xy, sprite.content_mask);
   return color;
 }
 
@@ -340,10 +395,10 @@ vertex PolychromeSpriteVertexOutput polychrome_sprite_vertex(
 
   float2 unit_vertex = unit_vertices[unit_vertex_id];
   PolychromeSprite sprite = sprites[sprite_id];
-  float4 device_position =
-      to_device_position(unit_vertex, sprite.bounds, viewport_size);
-  float4 clip_distance = distance_from_clip_rect(unit_vertex, sprite.bounds,
-                                                 sprite.content_mask.bounds);
+  float4 device_position = to_device_position_transformed(
+      unit_vertex, sprite.bounds, sprite.transform, viewport_size);
+  float4 clip_distance = distance_from_clip_rect_transformed(
+      unit_vertex, sprite.bounds, sprite.transform, sprite.content_mask.bounds);
   float2 tile_position = to_tile_position(unit_vertex, sprite.tile, atlas_size);
   return PolychromeSpriteVertexOutput{
       device_position,
@@ -362,7 +417,8 @@ fragment float4 polychrome_sprite_fragment(
   float4 sample =
       atlas_texture.sample(atlas_texture_sampler, input.tile_position);
   float distance =
-      quad_sdf(input.position.xy, sprite.bounds, sprite.corner_radii);
+      quad_sdf(untransform_position(sprite.transform, input.position.xy),
+               sprite.bounds, sprite.corner_radii);
 
   float4 color = sample;
   if (sprite.grayscale) {
@@ -371,7 +427,8 @@ fragment float4 polychrome_sprite_fragment(
     color.g = grayscale;
     color.b = grayscale;
   }
-  color.a *= saturate(0.5 - distance);
+  color.a *= sprite.opacity * saturate(0.5 - distance) *
+             content_mask_alpha(input.position.xy, sprite.content_mask);
   return color;
 }
 
@@ -424,6 +481,8 @@ struct PathSpriteVertexOutput {
   float4 position [[position]];
   float2 tile_position;
   float4 color [[flat]];
+  PathCoverage coverage [[flat]];
+  uint sprite_id [[flat]];
 };
 
 vertex PathSpriteVertexOutput path_sprite_vertex(
@@ -437,13 +496,15 @@ vertex PathSpriteVertexOutput path_sprite_vertex(
 
   float2 unit_vertex = unit_vertices[unit_vertex_id];
   PathSprite sprite = sprites[sprite_id];
-  // Don't apply content mask because it was already accounted for when
-  // rasterizing the path.
+  // Don't clip to the content mask's bounds because they were already
+  // accounted for when rasterizing the path. Rounded corners are applied in
+  // the fragment shader.
   float4 device_position =
       to_device_position(unit_vertex, sprite.bounds, viewport_size);
   float2 tile_position = to_tile_position(unit_vertex, sprite.tile, atlas_size);
   float4 color = hsla_to_rgba(sprite.color);
-  return PathSpriteVertexOutput{device_position, tile_position, color};
+  return PathSpriteVertexOutput{device_position, tile_position, color,
+                                sprite.coverage, sprite_id};
 }
 
 fragment float4 path_sprite_fragment(
@@ -454,21 +515,29 @@ fragment float4 path_sprite_fragment(
                                           min_filter::linear);
   float4 sample =
       atlas_texture.sample(atlas_texture_sampler, input.tile_position);
-  float mask = 1. - abs(1. - fmod(sample.r, 2.));
+  float mask;
+  if (input.coverage == PathCoverage_Union) {
+    mask = saturate(sample.r);
+  } else {
+    mask = 1. - abs(1. - fmod(sample.r, 2.));
+  }
+  PathSprite sprite = sprites[input.sprite_id];
   float4 color = input.color;
-  color.a *= mask;
+  color.a *= mask * content_mask_alpha(input.position.xy, sprite.content_mask);
   return color;
 }
 
 struct SurfaceVertexOutput {
   float4 position [[position]];
   float2 texture_position;
+  uint surface_id [[flat]];
   float clip_distance [[clip_distance]][4];
 };
 
 struct SurfaceFragmentInput {
   float4 position [[position]];
   float2 texture_position;
+  uint surface_id [[flat]];
 };
 
 vertex SurfaceVertexOutput surface_vertex(
@@ -491,10 +560,13 @@ vertex SurfaceVertexOutput surface_vertex(
   return SurfaceVertexOutput{
       device_position,
       texture_position,
+      surface_id,
       {clip_distance.x, clip_distance.y, clip_distance.z, clip_distance.w}};
 }
 
 fragment float4 surface_fragment(SurfaceFragmentInput input [[stage_in]],
+                                 constant SurfaceBounds *surfaces
+                                 [[buffer(SurfaceInputIndex_Surfaces)]],
                                  texture2d<float> y_texture
                                  [[texture(SurfaceInputIndex_YTexture)]],
                                  texture2d<float> cb_cr_texture
@@ -509,7 +581,70 @@ fragment float4 surface_fragment(SurfaceFragmentInput input [[stage_in]],
       y_texture.sample(texture_sampler, input.texture_position).r,
       cb_cr_texture.sample(texture_sampler, input.texture_position).rg, 1.0);
 
-  return ycbcrToRGBTransform * ycbcr;
+  float4 color = ycbcrToRGBTransform * ycbcr;
+  color.a *= content_mask_alpha(input.position.xy,
+                                surfaces[input.surface_id].content_mask);
+  return color;
+}
+
+struct FilterVertexOutput {
+  float4 position [[position]];
+};
+
+// Covers the whole viewport, so the horizontal blur pass also fills the rows
+// that the vertical pass reads from outside the content mask.
+vertex FilterVertexOutput filter_layer_vertex(
+    uint unit_vertex_id [[vertex_id]],
+    constant float2 *unit_vertices [[buffer(FilterInputIndex_Vertices)]]) {
+  float2 unit_vertex = unit_vertices[unit_vertex_id];
+  return FilterVertexOutput{
+      float4(unit_vertex * float2(2., -2.) + float2(-1., 1.), 0., 1.)};
+}
+
+vertex FilterVertexOutput filter_composite_vertex(
+    uint unit_vertex_id [[vertex_id]],
+    constant float2 *unit_vertices [[buffer(FilterInputIndex_Vertices)]],
+    constant FilterEffect *effect [[buffer(FilterInputIndex_Effect)]],
+    constant Size_DevicePixels *viewport_size
+    [[buffer(FilterInputIndex_ViewportSize)]]) {
+  float2 unit_vertex = unit_vertices[unit_vertex_id];
+  return FilterVertexOutput{to_device_position(
+      unit_vertex, effect->content_mask.bounds, viewport_size)};
+}
+
+fragment float4 filter_blur_fragment(
+    FilterVertexOutput input [[stage_in]],
+    constant FilterEffect *effect [[buffer(FilterInputIndex_Effect)]],
+    texture2d<float> layer [[texture(FilterInputIndex_Layer)]]) {
+  return blur_layer(layer, input.position.xy, effect->blur_radius,
+                    float2(1., 0.));
+}
+
+fragment float4 filter_composite_fragment(
+    FilterVertexOutput input [[stage_in]],
+    constant FilterEffect *effect [[buffer(FilterInputIndex_Effect)]],
+    texture2d<float> layer [[texture(FilterInputIndex_Layer)]],
+    constant uint *masked [[buffer(FilterInputIndex_Masked)]],
+    texture2d<float> mask [[texture(FilterInputIndex_Mask)]]) {
+  float4 premultiplied = blur_layer(layer, input.position.xy,
+                                    effect->blur_radius, float2(0., 1.));
+  if (premultiplied.a <= 0.) {
+    return float4(0.);
+  }
+
+  float4 color = float4(premultiplied.rgb / premultiplied.a, 1.);
+  float4 filtered;
+  for (int row = 0; row < 3; row++) {
+    constant float *weights = effect->color_matrix.rows[row];
+    filtered[row] = saturate(
+        dot(color, float4(weights[0], weights[1], weights[2], weights[3])));
+  }
+  filtered.a = premultiplied.a *
+               content_mask_alpha(input.position.xy, effect->content_mask);
+  if (*masked) {
+    filtered.a *= mask.read(uint2(input.position.xy)).a;
+  }
+  return filtered;
 }
 
 float4 hsla_to_rgba(Hsla hsla) {
@@ -560,6 +695,27 @@ float4 hsla_to_rgba(Hsla hsla) {
   return rgba;
 }
 
+// Blurs a layer holding premultiplied colors along one axis. Pixels outside the
+// layer count as transparent.
+float4 blur_layer(texture2d<float> layer, float2 position, float sigma,
+                  float2 direction) {
+  constexpr sampler layer_sampler(coord::pixel, address::clamp_to_zero,
+                                  filter::nearest);
+  if (sigma <= 0.) {
+    return layer.sample(layer_sampler, position);
+  }
+
+  int radius = int(ceil(3. * sigma));
+  float4 sum = float4(0.);
+  float total_weight = 0.;
+  for (int i = -radius; i <= radius; i++) {
+    float weight = gaussian(float(i), sigma);
+    sum += weight * layer.sample(layer_sampler, position + float(i) * direction);
+    total_weight += weight;
+  }
+  return sum / total_weight;
+}
+
 float4 to_device_position(float2 unit_vertex, Bounds_ScaledPixels bounds,
                           constant Size_DevicePixels *input_viewport_size) {
   float2 position =
@@ -572,6 +728,44 @@ float4 to_device_position(float2 unit_vertex, Bounds_ScaledPixels bounds,
   return float4(device_position, 0., 1.);
 }
 
+float4 to_device_position_transformed(
+    float2 unit_vertex, Bounds_ScaledPixels bounds,
+    TransformationMatrix transform,
+    constant Size_DevicePixels *input_viewport_size) {
+  float2 position =
+      unit_vertex * float2(bounds.size.width, bounds.size.height) +
+      float2(bounds.origin.x, bounds.origin.y);
+  position = apply_transform(transform, position);
+  float2 viewport_size = float2((float)input_viewport_size->width,
+                                (float)input_viewport_size->height);
+  float2 device_position =
+      position / viewport_size * float2(2., -2.) + float2(-1., 1.);
+  return float4(device_position, 0., 1.);
+}
+
+float2 apply_transform(TransformationMatrix transform, float2 position) {
+  return float2(transform.rotation_scale[0][0] * position.x +
+                    transform.rotation_scale[0][1] * position.y +
+                    transform.translation[0],
+                transform.rotation_scale[1][0] * position.x +
+                    transform.rotation_scale[1][1] * position.y +
+                    transform.translation[1]);
+}
+
+// Maps a position in the window back to where it was before the transformation
+// was applied, so that fragments can be shaded in the space of the primitive
+// they belong to.
+float2 untransform_position(TransformationMatrix transform, float2 position) {
+  float a = transform.rotation_scale[0][0];
+  float b = transform.rotation_scale[0][1];
+  float c = transform.rotation_scale[1][0];
+  float d = transform.rotation_scale[1][1];
+  float2 offset =
+      position - float2(transform.translation[0], transform.translation[1]);
+  return float2(d * offset.x - b * offset.y, a * offset.y - c * offset.x) /
+         (a * d - b * c);
+}
+
 float2 to_tile_position(float2 unit_vertex, AtlasTile tile,
                         constant Size_DevicePixels *atlas_size) {
   float2 tile_origin = float2(tile.bounds.origin.x, tile.bounds.origin.y);
@@ -610,6 +804,39 @@ float quad_sdf(float2 point, Bounds_ScaledPixels bounds,
   return distance;
 }
 
+// The coverage of a window position by the rounded corners of a content mask.
+// Its straight edges are already clipped by the vertex shaders' clip distances.
+float content_mask_alpha(float2 position,
+                         ContentMask_ScaledPixels content_mask) {
+  Corners_ScaledPixels radii = content_mask.corner_radii;
+  if (radii.top_left == 0. && radii.top_right == 0. &&
+      radii.bottom_right == 0. && radii.bottom_left == 0.) {
+    return 1.;
+  }
+  return saturate(0.5 - quad_sdf(position, content_mask.bounds, radii));
+}
+
+// The coverage of a dashed or dotted border at a point `along_edge` of an edge
+// whose dashes span `edge_length`, and `across_edge` from the middle of the
+// border.
+float border_pattern_alpha(BorderStyle style, float width, float along_edge,
+                           float edge_length, float across_edge) {
+  if (style == BorderStyle_Solid) {
+    return 1.;
+  }
+
+  float period = style == BorderStyle_Dashed ? 6. * width : 2. * width;
+  // Stretch the pattern so that a whole number of periods fits, leaving a dash
+  // or dot centered on each corner.
+  period = max(edge_length, width) / max(round(edge_length / period), 1.);
+  float along = along_edge - round(along_edge / period) * period;
+  if (style == BorderStyle_Dashed) {
+    return saturate(0.5 - (fabs(along) - period / 4.));
+  } else {
+    return saturate(0.5 - (length(float2(along, across_edge)) - width / 2.));
+  }
+}
+
 // A standard gaussian function, used for weighting samples
 float gaussian(float x, float sigma) {
   return exp(-(x * x) / (2. * sigma * sigma)) / (sqrt(2. * M_PI_F) * sigma);
@@ -634,6 +861,53 @@ float blur_along_x(float x, float y, float sigma, float corner,
   return integral.y - integral.x;
 }
 
+// The coverage of a point by a rounded rectangle blurred with a gaussian.
+float shadow_alpha(float2 position, Bounds_ScaledPixels bounds,
+                   Corners_ScaledPixels corner_radii, float blur_radius) {
+  float2 origin = float2(bounds.origin.x, bounds.origin.y);
+  float2 size = float2(bounds.size.width, bounds.size.height);
+  float2 half_size = size / 2.;
+  float2 center = origin + half_size;
+  float2 point = position - center;
+  float corner_radius;
+  if (point.x < 0.) {
+    if (point.y < 0.) {
+      corner_radius = corner_radii.top_left;
+    } else {
+      corner_radius = corner_radii.bottom_left;
+    }
+  } else {
+    if (point.y < 0.) {
+      corner_radius = corner_radii.top_right;
+    } else {
+      corner_radius = corner_radii.bottom_right;
+    }
+  }
+
+  if (blur_radius <= 0.) {
+    return saturate(0.5 - quad_sdf(position, bounds, corner_radii));
+  }
+
+  // The signal is only non-zero in a limited range, so don't waste samples
+  float low = point.y - half_size.y;
+  float high = point.y + half_size.y;
+  float start = clamp(-3. * blur_radius, low, high);
+  float end = clamp(3. * blur_radius, low, high);
+
+  // Accumulate samples (we can get away with surprisingly few samples)
+  float step = (end - start) / 4.;
+  float y = start + step * 0.5;
+  float alpha = 0.;
+  for (int i = 0; i < 4; i++) {
+    alpha += blur_along_x(point.x, point.y - y, blur_radius, corner_radius,
+                          half_size) *
+             gaussian(y, blur_radius) * step;
+    y += step;
+  }
+
+  return alpha;
+}
+
 float4 distance_from_clip_rect(float2 unit_vertex, Bounds_ScaledPixels bounds,
                                Bounds_ScaledPixels clip_bounds) {
   float2 position =
@@ -645,6 +919,20 @@ float4 distance_from_clip_rect(float2 unit_vertex, Bounds_ScaledPixels bounds,
                 clip_bounds.origin.y + clip_bounds.size.height - position.y);
 }
 
+float4 distance_from_clip_rect_transformed(float2 unit_vertex,
+                                           Bounds_ScaledPixels bounds,
+                                           TransformationMatrix transform,
+                                           Bounds_ScaledPixels clip_bounds) {
+  float2 position =
+      unit_vertex * float2(bounds.size.width, bounds.size.height) +
+      float2(bounds.origin.x, bounds.origin.y);
+  position = apply_transform(transform, position);
+  return float4(position.x - clip_bounds.origin.x,
+                clip_bounds.origin.x + clip_bounds.size.width - position.x,
+                position.y - clip_bounds.origin.y,
+                clip_bounds.origin.y + clip_bounds.size.height - position.y);
+}
+
 float4 over(float4 below, float4 above) {
   float4 result;
   float alpha = above.a + below.a * (1.0 - above.a);
@@ -653,3 +941,113 @@ float4 over(float4 below, float4 above) {
   result.a = alpha;
   return result;
 }
+
+float srgb_to_linear(float value) {
+  return value <= 0.04045 ? value / 12.92 : pow((value + 0.055) / 1.055, 2.4);
+}
+
+float linear_to_srgb(float value) {
+  value = max(value, 0.);
+  return value <= 0.0031308 ? value * 12.92
+                            : 1.055 * pow(value, 1. / 2.4) - 0.055;
+}
+
+float3 linear_srgb_to_oklab(float3 color) {
+  float3 lms = float3(
+      0.4122214708 * color.r + 0.5363325363 * color.g + 0.0514459929 * color.b,
+      0.2119034982 * color.r + 0.6806995451 * color.g + 0.1073969566 * color.b,
+      0.0883024619 * color.r + 0.2817188376 * color.g + 0.6299787005 * color.b);
+  lms = sign(lms) * pow(fabs(lms), float3(1. / 3.));
+  return float3(
+      0.2104542553 * lms.x + 0.7936177850 * lms.y - 0.0040720468 * lms.z,
+      1.9779984951 * lms.x - 2.4285922050 * lms.y + 0.4505937099 * lms.z,
+      0.0259040371 * lms.x + 0.7827717662 * lms.y - 0.8086757660 * lms.z);
+}
+
+float3 oklab_to_linear_srgb(float3 color) {
+  float3 lms =
+      float3(color.x + 0.3963377774 * color.y + 0.2158037573 * color.z,
+             color.x - 0.1055613458 * color.y - 0.0638541728 * color.z,
+             color.x - 0.0894841775 * color.y - 1.2914855480 * color.z);
+  lms = lms * lms * lms;
+  return float3(
+      4.0767416621 * lms.x - 3.3077115913 * lms.y + 0.2309699292 * lms.z,
+      -1.2684380046 * lms.x + 2.6097574011 * lms.y - 0.3413193965 * lms.z,
+      -0.0041960863 * lms.x - 0.7034186147 * lms.y + 1.7076147010 * lms.z);
+}
+
+// Converts a color into the space a gradient blends in, with premultiplied
+// alpha.
+float4 to_gradient_space(Hsla hsla, uint color_space) {
+  float4 rgba = hsla_to_rgba(hsla);
+  float3 color = rgba.rgb;
+  if (color_space == ColorSpace_LinearSrgb) {
+    color = float3(srgb_to_linear(color.r), srgb_to_linear(color.g),
+                   srgb_to_linear(color.b));
+  } else if (color_space == ColorSpace_Oklab) {
+    color = linear_srgb_to_oklab(float3(srgb_to_linear(color.r),
+                                        srgb_to_linear(color.g),
+                                        srgb_to_linear(color.b)));
+  }
+  return float4(color * rgba.a, rgba.a);
+}
+
+float4 from_gradient_space(float4 color, uint color_space) {
+  if (color.a <= 0.) {
+    return float4(0.);
+  }
+  float3 result = color.rgb / color.a;
+  if (color_space == ColorSpace_LinearSrgb) {
+    result = float3(linear_to_srgb(result.r), linear_to_srgb(result.g),
+                    linear_to_srgb(result.b));
+  } else if (color_space == ColorSpace_Oklab) {
+    result = oklab_to_linear_srgb(result);
+    result = float3(linear_to_srgb(result.r), linear_to_srgb(result.g),
+                    linear_to_srgb(result.b));
+  }
+  return float4(saturate(result), color.a);
+}
+
+float4 gradient_color(Background background, Bounds_ScaledPixels bounds,
+                      float2 point) {
+  float2 origin = float2(bounds.origin.x, bounds.origin.y);
+  float2 size = float2(bounds.size.width, bounds.size.height);
+  float position = 0.;
+  if (background.tag == BackgroundTag_LinearGradient) {
+    // The gradient line passes through the center, and is long enough for the
+    // corners to lie on the lines perpendicular to its ends.
+    float2 direction = float2(background.direction.x, background.direction.y);
+    float line_length =
+        fabs(size.x * direction.x) + fabs(size.y * direction.y);
+    if (line_length > 0.) {
+      position =
+          dot(point - (origin + size / 2.), direction) / line_length + 0.5;
+    }
+  } else {
+    float2 center =
+        origin + float2(background.center.x, background.center.y) * size;
+    float2 farthest_corner =
+        max(fabs(center - origin), fabs(origin + size - center));
+    float radius = length(farthest_corner);
+    if (radius > 0.) {
+      position = length(point - center) / radius;
+    }
+  }
+
+  float4 color = hsla_to_rgba(background.stops[0].color);
+  for (uint i = 1; i < background.stop_count; i++) {
+    GradientStop from_stop = background.stops[i - 1];
+    GradientStop to_stop = background.stops[i];
+    if (position > from_stop.position) {
+      float span = max(to_stop.position - from_stop.position, 1e-6);
+      float amount =
+          saturate((position - from_stop.position) / span);
+      float4 blended =
+          mix(to_gradient_space(from_stop.color, background.color_space),
+              to_gradient_space(to_stop.color, background.color_space),
+              amount);
+      color = from_gradient_space(blended, background.color_space);
+    }
+  }
+  return color;
+}
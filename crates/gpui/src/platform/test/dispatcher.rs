@@ -11,7 +11,7 @@ use std::{
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use util::post_inc;
 
@@ -32,6 +32,7 @@ struct TestDispatcherState {
     background: Vec<Runnable>,
     deprioritized_background: Vec<Runnable>,
     delayed: Vec<(Duration, Runnable)>,
+    start_time: Instant,
     time: Duration,
     is_main_thread: bool,
     next_id: TestDispatcherId,
@@ -50,6 +51,7 @@ impl TestDispatcher {
             background: Vec::new(),
             deprioritized_background: Vec::new(),
             delayed: Vec::new(),
+            start_time: Instant::now(),
             time: Duration::ZERO,
             is_main_thread: true,
             next_id: TestDispatcherId(1),
@@ -278,6 +280,11 @@ impl PlatformDispatcher for TestDispatcher {
         self.unparker.clone()
     }
 
+    fn now(&self) -> Instant {
+        let state = self.state.lock();
+        state.start_time + state.time
+    }
+
     fn as_test(&self) -> Option<&TestDispatcher> {
         Some(self)
     }
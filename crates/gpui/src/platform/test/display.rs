@@ -7,19 +7,40 @@ pub(crate) struct TestDisplay {
     id: DisplayId,
     uuid: uuid::Uuid,
     bounds: Bounds<GlobalPixels>,
+    scale_factor: f32,
 }
 
 impl TestDisplay {
     pub fn new() -> Self {
-        TestDisplay {
-            id: DisplayId(1),
-            uuid: uuid::Uuid::new_v4(),
-            bounds: Bounds::from_corners(
+        TestDisplay::with_options(
+            DisplayId(1),
+            uuid::Uuid::new_v4(),
+            Bounds::from_corners(
                 Point::default(),
                 Point::new(GlobalPixels(1920.), GlobalPixels(1080.)),
             ),
+            2.0,
+        )
+    }
+
+    pub fn with_options(
+        id: DisplayId,
+        uuid: uuid::Uuid,
+        bounds: Bounds<GlobalPixels>,
+        scale_factor: f32,
+    ) -> Self {
+        TestDisplay {
+            id,
+            uuid,
+            bounds,
+            scale_factor,
         }
     }
+
+    /// The scale factor of windows shown on this display.
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
 }
 
 impl PlatformDisplay for TestDisplay {
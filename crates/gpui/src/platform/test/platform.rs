@@ -1,7 +1,7 @@
 use crate::{
-    AnyWindowHandle, BackgroundExecutor, ClipboardItem, CursorStyle, DisplayId, ForegroundExecutor,
-    Keymap, Platform, PlatformDisplay, PlatformTextSystem, Task, TestDisplay, TestWindow,
-    WindowOptions,
+    AnyWindowHandle, BackgroundExecutor, Bounds, ClipboardItem, CursorStyle, DisplayId,
+    ForegroundExecutor, GlobalPixels, Keymap, Platform, PlatformDisplay, PlatformTextSystem, Task,
+    TestDisplay, TestWindow, WindowOptions,
 };
 use anyhow::{anyhow, Result};
 use collections::VecDeque;
@@ -21,7 +21,7 @@ pub(crate) struct TestPlatform {
     foreground_executor: ForegroundExecutor,
 
     pub(crate) active_window: RefCell<Option<TestWindow>>,
-    active_display: Rc<dyn PlatformDisplay>,
+    displays: RefCell<Vec<Rc<TestDisplay>>>,
     active_cursor: Mutex<CursorStyle>,
     current_clipboard_item: Mutex<Option<ClipboardItem>>,
     pub(crate) prompts: RefCell<TestPrompts>,
@@ -41,13 +41,50 @@ impl TestPlatform {
             foreground_executor,
             prompts: Default::default(),
             active_cursor: Default::default(),
-            active_display: Rc::new(TestDisplay::new()),
+            displays: RefCell::new(vec![Rc::new(TestDisplay::new())]),
             active_window: Default::default(),
             current_clipboard_item: Mutex::new(None),
             weak: weak.clone(),
         })
     }
 
+    /// Adds a display with the given bounds and scale factor, returning its id.
+    pub(crate) fn add_display(
+        &self,
+        uuid: uuid::Uuid,
+        bounds: Bounds<GlobalPixels>,
+        scale_factor: f32,
+    ) -> DisplayId {
+        let mut displays = self.displays.borrow_mut();
+        let id = DisplayId(
+            displays
+                .iter()
+                .map(|display| display.id().0)
+                .max()
+                .unwrap_or(0)
+                + 1,
+        );
+        displays.push(Rc::new(TestDisplay::with_options(
+            id,
+            uuid,
+            bounds,
+            scale_factor,
+        )));
+        id
+    }
+
+    pub(crate) fn test_display(&self, id: DisplayId) -> Option<Rc<TestDisplay>> {
+        self.displays
+            .borrow()
+            .iter()
+            .find(|display| display.id() == id)
+            .cloned()
+    }
+
+    fn primary_display(&self) -> Rc<TestDisplay> {
+        self.displays.borrow()[0].clone()
+    }
+
     pub(crate) fn simulate_new_path_selection(
         &self,
         select_path: impl FnOnce(&std::path::Path) -> Option<std::path::PathBuf>,
@@ -118,7 +155,7 @@ impl Platform for TestPlatform {
     }
 
     fn text_system(&self) -> Arc<dyn PlatformTextSystem> {
-        Arc::new(crate::platform::mac::MacTextSystem::new())
+        Arc::new(crate::platform::CosmicTextSystem::new())
     }
 
     fn run(&self, _on_finish_launching: Box<dyn FnOnce()>) {
@@ -148,11 +185,16 @@ impl Platform for TestPlatform {
     }
 
     fn displays(&self) -> Vec<std::rc::Rc<dyn crate::PlatformDisplay>> {
-        vec![self.active_display.clone()]
+        self.displays
+            .borrow()
+            .iter()
+            .map(|display| display.clone() as Rc<dyn PlatformDisplay>)
+            .collect()
     }
 
     fn display(&self, id: DisplayId) -> Option<std::rc::Rc<dyn crate::PlatformDisplay>> {
-        self.displays().iter().find(|d| d.id() == id).cloned()
+        self.test_display(id)
+            .map(|display| display as Rc<dyn PlatformDisplay>)
     }
 
     fn active_window(&self) -> Option<crate::AnyWindowHandle> {
@@ -167,12 +209,11 @@ impl Platform for TestPlatform {
         handle: AnyWindowHandle,
         options: WindowOptions,
     ) -> Box<dyn crate::PlatformWindow> {
-        let window = TestWindow::new(
-            options,
-            handle,
-            self.weak.clone(),
-            self.active_display.clone(),
-        );
+        let display = options
+            .display_id
+            .and_then(|id| self.test_display(id))
+            .unwrap_or_else(|| self.primary_display());
+        let window = TestWindow::new(options, handle, self.weak.clone(), display);
         Box::new(window)
     }
 
@@ -297,3 +338,65 @@ impl Platform for TestPlatform {
         Duration::from_millis(500)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{ClipboardContent, ClipboardFormat, ClipboardItem, ImageData, TestAppContext};
+    use image::{Bgra, ImageBuffer};
+    use std::{path::PathBuf, sync::Arc};
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    #[crate::test]
+    fn test_clipboard_keeps_every_format(cx: &mut TestAppContext) {
+        let item = ClipboardItem::new("bold".into())
+            .with_html("<b>bold</b>")
+            .with_metadata(vec![1, 2]);
+        cx.write_to_clipboard(item.clone());
+
+        let pasted = cx.read_from_clipboard().unwrap();
+        assert_eq!(pasted, item);
+        assert_eq!(
+            pasted.formats(),
+            [ClipboardFormat::Html, ClipboardFormat::Text]
+        );
+        assert_eq!(pasted.metadata::<Vec<u8>>(), Some(vec![1, 2]));
+        assert!(matches!(
+            pasted.best_content(&[ClipboardFormat::Image, ClipboardFormat::Html]),
+            Some(ClipboardContent::Html("<b>bold</b>"))
+        ));
+        assert!(matches!(
+            pasted.best_content(&[ClipboardFormat::Text]),
+            Some(ClipboardContent::Text("bold"))
+        ));
+        assert!(pasted.best_content(&[ClipboardFormat::Paths]).is_none());
+    }
+
+    #[crate::test]
+    fn test_clipboard_images_and_paths(cx: &mut TestAppContext) {
+        let image = Arc::new(ImageData::new(ImageBuffer::from_pixel(
+            2,
+            2,
+            Bgra([0, 0, 255, 255]),
+        )));
+        cx.write_to_clipboard(ClipboardItem::new_image(image.clone()));
+        let pasted = cx.read_from_clipboard().unwrap();
+        assert_eq!(pasted.formats(), [ClipboardFormat::Image]);
+        assert!(pasted.content(ClipboardFormat::Text).is_none());
+        match pasted.best_content(&[ClipboardFormat::Image, ClipboardFormat::Text]) {
+            Some(ClipboardContent::Image(pasted_image)) => assert_eq!(pasted_image.id, image.id),
+            content => panic!("expected an image, got {content:?}"),
+        }
+
+        let paths = vec![PathBuf::from("/a/one.txt"), PathBuf::from("/b/two.txt")];
+        cx.write_to_clipboard(ClipboardItem::new_paths(paths.clone()));
+        let pasted = cx.read_from_clipboard().unwrap();
+        assert_eq!(pasted.paths(), Some(paths.as_slice()));
+        assert_eq!(pasted.text(), "/a/one.txt\n/b/two.txt");
+        assert_eq!(
+            pasted.formats(),
+            [ClipboardFormat::Paths, ClipboardFormat::Text]
+        );
+    }
+}
@@ -1,9 +1,8 @@
 use crate::{
-    px, AnyWindowHandle, AtlasKey, AtlasTextureId, AtlasTile, Bounds, KeyDownEvent, Keystroke,
-    Pixels, PlatformAtlas, PlatformDisplay, PlatformInput, PlatformInputHandler, PlatformWindow,
-    Point, Size, TestPlatform, TileId, WindowAppearance, WindowBounds, WindowOptions,
+    px, AnyWindowHandle, Bounds, CpuAtlas, KeyDownEvent, Keystroke, Pixels, PlatformDisplay,
+    PlatformInput, PlatformInputHandler, PlatformWindow, Point, Size, TestDisplay, TestPlatform,
+    WindowAppearance, WindowBounds, WindowOptions,
 };
-use collections::HashMap;
 use parking_lot::Mutex;
 use std::{
     rc::{Rc, Weak},
@@ -13,11 +12,12 @@ use std::{
 pub(crate) struct TestWindowState {
     pub(crate) bounds: WindowBounds,
     pub(crate) handle: AnyWindowHandle,
-    display: Rc<dyn PlatformDisplay>,
+    display: Rc<TestDisplay>,
     pub(crate) title: Option<String>,
     pub(crate) edited: bool,
     platform: Weak<TestPlatform>,
-    sprite_atlas: Arc<dyn PlatformAtlas>,
+    pub(crate) sprite_atlas: Arc<CpuAtlas>,
+    scale_factor: f32,
     pub(crate) should_close_handler: Option<Box<dyn FnMut() -> bool>>,
     input_callback: Option<Box<dyn FnMut(PlatformInput) -> bool>>,
     active_status_change_callback: Option<Box<dyn FnMut(bool)>>,
@@ -34,14 +34,15 @@ impl TestWindow {
         options: WindowOptions,
         handle: AnyWindowHandle,
         platform: Weak<TestPlatform>,
-        display: Rc<dyn PlatformDisplay>,
+        display: Rc<TestDisplay>,
     ) -> Self {
         Self(Arc::new(Mutex::new(TestWindowState {
             bounds: options.bounds,
+            scale_factor: display.scale_factor(),
             display,
             platform,
             handle,
-            sprite_atlas: Arc::new(TestAtlas::new()),
+            sprite_atlas: Arc::new(CpuAtlas::new()),
             title: Default::default(),
             edited: false,
             should_close_handler: None,
@@ -62,7 +63,7 @@ impl TestWindow {
         match &mut lock.bounds {
             WindowBounds::Fullscreen | WindowBounds::Maximized => {
                 lock.bounds = WindowBounds::Fixed(Bounds {
-                    origin: Point::default(),
+                    origin: lock.display.bounds().origin,
                     size: size.map(|pixels| f64::from(pixels).into()),
                 });
             }
@@ -75,6 +76,52 @@ impl TestWindow {
         self.0.lock().resize_callback = Some(callback);
     }
 
+    /// Changes the window's scale factor, notifying GPUI as if the window moved to a
+    /// display with a different density.
+    pub fn simulate_scale_factor_change(&mut self, scale_factor: f32) {
+        self.0.lock().scale_factor = scale_factor;
+        let content_size = self.content_size();
+        let mut lock = self.0.lock();
+        let Some(mut callback) = lock.resize_callback.take() else {
+            return;
+        };
+        drop(lock);
+        callback(content_size, scale_factor);
+        self.0.lock().resize_callback = Some(callback);
+    }
+
+    /// Moves the window to another display, keeping its position relative to the
+    /// display's origin. GPUI is notified that the window moved and, as the new display
+    /// may have a different density, that it was resized.
+    pub fn simulate_move_to_display(&mut self, display: Rc<TestDisplay>) {
+        let mut lock = self.0.lock();
+        let old_origin = lock.display.bounds().origin;
+        let new_origin = display.bounds().origin;
+        if let WindowBounds::Fixed(bounds) = &mut lock.bounds {
+            bounds.origin = Point {
+                x: new_origin.x + (bounds.origin.x - old_origin.x),
+                y: new_origin.y + (bounds.origin.y - old_origin.y),
+            };
+        }
+        lock.scale_factor = display.scale_factor();
+        lock.display = display;
+        let moved_callback = lock.moved_callback.take();
+        drop(lock);
+
+        if let Some(mut callback) = moved_callback {
+            callback();
+            self.0.lock().moved_callback = Some(callback);
+        }
+
+        let content_size = self.content_size();
+        let scale_factor = self.scale_factor();
+        let Some(mut callback) = self.0.lock().resize_callback.take() else {
+            return;
+        };
+        callback(content_size, scale_factor);
+        self.0.lock().resize_callback = Some(callback);
+    }
+
     pub(crate) fn simulate_active_status_change(&self, active: bool) {
         let mut lock = self.0.lock();
         let Some(mut callback) = lock.active_status_change_callback.take() else {
@@ -146,7 +193,7 @@ impl PlatformWindow for TestWindow {
     }
 
     fn scale_factor(&self) -> f32 {
-        2.0
+        self.0.lock().scale_factor
     }
 
     fn titlebar_height(&self) -> Pixels {
@@ -269,7 +316,9 @@ impl PlatformWindow for TestWindow {
 
     fn invalidate(&self) {}
 
-    fn draw(&self, _scene: &crate::Scene) {}
+    fn draw(&self, scene: &crate::Scene) {
+        self.0.lock().sprite_atlas.finish_frame(scene);
+    }
 
     fn sprite_atlas(&self) -> sync::Arc<dyn crate::PlatformAtlas> {
         self.0.lock().sprite_atlas.clone()
@@ -280,60 +329,110 @@ impl PlatformWindow for TestWindow {
     }
 }
 
-pub(crate) struct TestAtlasState {
-    next_id: u32,
-    tiles: HashMap<AtlasKey, AtlasTile>,
-}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        div, point, px, size, Bounds, GlobalPixels, IntoElement, Render, TestAppContext,
+        ViewContext, VisualContext as _, VisualTestContext, WindowBounds, WindowOptions,
+    };
+    use std::ops::Deref;
 
-pub(crate) struct TestAtlas(Mutex<TestAtlasState>);
+    // For compatibility with the test macro
+    use crate as gpui;
 
-impl TestAtlas {
-    pub fn new() -> Self {
-        TestAtlas(Mutex::new(TestAtlasState {
-            next_id: 0,
-            tiles: HashMap::default(),
-        }))
+    #[derive(Default)]
+    struct BoundsLog {
+        scale_factors: Vec<f32>,
     }
-}
 
-impl PlatformAtlas for TestAtlas {
-    fn get_or_insert_with<'a>(
-        &self,
-        key: &crate::AtlasKey,
-        build: &mut dyn FnMut() -> anyhow::Result<(
-            Size<crate::DevicePixels>,
-            std::borrow::Cow<'a, [u8]>,
-        )>,
-    ) -> anyhow::Result<crate::AtlasTile> {
-        let mut state = self.0.lock();
-        if let Some(tile) = state.tiles.get(key) {
-            return Ok(tile.clone());
+    impl Render for BoundsLog {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div()
         }
+    }
 
-        state.next_id += 1;
-        let texture_id = state.next_id;
-        state.next_id += 1;
-        let tile_id = state.next_id;
-
-        drop(state);
-        let (size, _) = build()?;
-        let mut state = self.0.lock();
-
-        state.tiles.insert(
-            key.clone(),
-            crate::AtlasTile {
-                texture_id: AtlasTextureId {
-                    index: texture_id,
-                    kind: crate::AtlasTextureKind::Path,
-                },
-                tile_id: TileId(tile_id),
-                bounds: crate::Bounds {
-                    origin: Point::default(),
-                    size,
-                },
-            },
+    fn global_bounds(x: f32, y: f32, width: f32, height: f32) -> Bounds<GlobalPixels> {
+        Bounds {
+            origin: point(GlobalPixels(x), GlobalPixels(y)),
+            size: size(GlobalPixels(width), GlobalPixels(height)),
+        }
+    }
+
+    #[crate::test]
+    fn test_maximized_window_uses_chosen_display(cx: &mut TestAppContext) {
+        let display_id = cx.add_display(
+            uuid::Uuid::new_v4(),
+            global_bounds(1920., 0., 1280., 800.),
+            1.0,
         );
 
-        Ok(state.tiles[key].clone())
+        let window = cx.update(|cx| {
+            cx.open_window(
+                WindowOptions {
+                    bounds: WindowBounds::Maximized,
+                    display_id: Some(display_id),
+                    ..Default::default()
+                },
+                |cx| cx.new_view(|_| BoundsLog::default()),
+            )
+        });
+        let cx = VisualTestContext::from_window(*window.deref(), cx).as_mut();
+        cx.update(|cx| {
+            assert_eq!(cx.display().unwrap().id(), display_id);
+            assert_eq!(cx.scale_factor(), 1.0);
+            assert_eq!(cx.viewport_size(), size(px(1280.), px(800.)));
+        });
+    }
+
+    #[crate::test]
+    fn test_move_window_between_displays(cx: &mut TestAppContext) {
+        let primary_uuid = cx.update(|cx| cx.displays()[0].uuid().unwrap());
+        let external_uuid = uuid::Uuid::new_v4();
+        let external_id =
+            cx.add_display(external_uuid, global_bounds(-2560., 0., 2560., 1440.), 1.5);
+        cx.update(|cx| {
+            let uuids = cx
+                .displays()
+                .iter()
+                .map(|display| display.uuid().unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(uuids, [primary_uuid, external_uuid]);
+        });
+
+        let window = cx.update(|cx| {
+            cx.open_window(
+                WindowOptions {
+                    bounds: WindowBounds::Fixed(global_bounds(100., 50., 400., 300.)),
+                    ..Default::default()
+                },
+                |cx| {
+                    cx.new_view(|cx| {
+                        cx.observe_window_bounds(|this: &mut BoundsLog, cx| {
+                            this.scale_factors.push(cx.scale_factor())
+                        })
+                        .detach();
+                        BoundsLog::default()
+                    })
+                },
+            )
+        });
+        let cx = VisualTestContext::from_window(*window.deref(), cx).as_mut();
+        assert_eq!(cx.update(|cx| cx.scale_factor()), 2.0);
+
+        cx.simulate_move_to_display(external_id);
+        cx.update(|cx| {
+            assert_eq!(cx.display().unwrap().id(), external_id);
+            assert_eq!(cx.scale_factor(), 1.5);
+            assert_eq!(cx.viewport_size(), size(px(400.), px(300.)));
+            assert_eq!(
+                cx.window_bounds(),
+                WindowBounds::Fixed(global_bounds(-2460., 50., 400., 300.))
+            );
+        });
+        let scale_factors = window
+            .update(cx, |view, _| view.scale_factors.clone())
+            .unwrap();
+        // Bounds observers hear about both the move and the resulting resize.
+        assert_eq!(scale_factors, [1.5, 1.5]);
     }
 }
@@ -0,0 +1,562 @@
+use crate::{PlatformDispatcher, TaskLabel};
+use async_task::Runnable;
+use parking::{Parker, Unparker};
+use parking_lot::{Condvar, Mutex, MutexGuard};
+use std::{
+    collections::VecDeque,
+    mem,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The resolution of timers scheduled with `dispatch_after`.
+const TIMER_TICK: Duration = Duration::from_millis(1);
+
+/// The number of slots in the timer wheel. Timers further than this many ticks
+/// away share slots with nearer ones and stay put until their tick comes around.
+const TIMER_SLOT_COUNT: usize = 256;
+
+/// How many unlabeled runnables a worker may run in a row while labeled ones are
+/// waiting, so that labeled work is deprioritized but never starved.
+const MAX_UNLABELED_STREAK: usize = 16;
+
+/// A dispatcher for platforms without a system thread pool.
+///
+/// Background runnables run on one worker thread per CPU. Runnables spawned with a
+/// [`TaskLabel`] are treated as low priority: workers prefer unlabeled runnables
+/// when both are queued. Main thread runnables are queued on a [`MainThreadQueue`],
+/// which the platform's event loop is responsible for draining.
+pub(crate) struct ThreadPoolDispatcher {
+    main_thread_id: thread::ThreadId,
+    main_thread_queue: Arc<MainThreadQueue>,
+    background_queue: Arc<BackgroundQueue>,
+    timers: Arc<TimerWheel>,
+    parker: Mutex<Parker>,
+}
+
+/// Runnables waiting to be run by the platform's event loop on the main thread.
+pub(crate) struct MainThreadQueue {
+    runnables: Mutex<VecDeque<Runnable>>,
+    condvar: Condvar,
+    wake_main_thread: Box<dyn Fn() + Send + Sync>,
+}
+
+struct BackgroundQueue {
+    state: Mutex<BackgroundQueueState>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct BackgroundQueueState {
+    unlabeled: VecDeque<Runnable>,
+    labeled: VecDeque<Runnable>,
+    unlabeled_streak: usize,
+    closed: bool,
+}
+
+struct TimerWheel {
+    state: Mutex<TimerWheelState>,
+    condvar: Condvar,
+}
+
+/// A hashed timer wheel. Each timer is stored in the slot for its deadline tick
+/// modulo the number of slots, and fires once the wheel has advanced past it.
+struct TimerWheelState {
+    start: Instant,
+    current_tick: u64,
+    slots: Vec<Vec<Timer>>,
+    len: usize,
+    closed: bool,
+}
+
+struct Timer {
+    tick: u64,
+    runnable: Runnable,
+}
+
+impl ThreadPoolDispatcher {
+    /// Creates a dispatcher whose main thread is the calling thread.
+    ///
+    /// `wake_main_thread` is called after a runnable is queued for the main thread,
+    /// so event loops that block on something other than the [`MainThreadQueue`]
+    /// can wake up and drain it.
+    pub fn new(wake_main_thread: impl 'static + Fn() + Send + Sync) -> Self {
+        Self::with_worker_count(num_cpus::get(), wake_main_thread)
+    }
+
+    fn with_worker_count(
+        worker_count: usize,
+        wake_main_thread: impl 'static + Fn() + Send + Sync,
+    ) -> Self {
+        let background_queue = Arc::new(BackgroundQueue {
+            state: Mutex::new(BackgroundQueueState::default()),
+            condvar: Condvar::new(),
+        });
+        for ix in 0..worker_count.max(1) {
+            let background_queue = background_queue.clone();
+            thread::Builder::new()
+                .name(format!("gpui-worker-{ix}"))
+                .spawn(move || {
+                    while let Some(runnable) = background_queue.pop() {
+                        runnable.run();
+                    }
+                })
+                .expect("failed to spawn background thread");
+        }
+
+        let timers = Arc::new(TimerWheel {
+            state: Mutex::new(TimerWheelState::new(Instant::now())),
+            condvar: Condvar::new(),
+        });
+        thread::Builder::new()
+            .name("gpui-timer".into())
+            .spawn({
+                let timers = timers.clone();
+                let background_queue = background_queue.clone();
+                move || timers.run(&background_queue)
+            })
+            .expect("failed to spawn timer thread");
+
+        Self {
+            main_thread_id: thread::current().id(),
+            main_thread_queue: Arc::new(MainThreadQueue {
+                runnables: Mutex::new(VecDeque::new()),
+                condvar: Condvar::new(),
+                wake_main_thread: Box::new(wake_main_thread),
+            }),
+            background_queue,
+            timers,
+            parker: Mutex::new(Parker::new()),
+        }
+    }
+
+    /// The queue of runnables the platform's event loop must run on the main thread.
+    pub fn main_thread_queue(&self) -> Arc<MainThreadQueue> {
+        self.main_thread_queue.clone()
+    }
+}
+
+impl Drop for ThreadPoolDispatcher {
+    fn drop(&mut self) {
+        self.background_queue.close();
+        self.timers.close();
+    }
+}
+
+impl PlatformDispatcher for ThreadPoolDispatcher {
+    fn is_main_thread(&self) -> bool {
+        thread::current().id() == self.main_thread_id
+    }
+
+    fn dispatch(&self, runnable: Runnable, label: Option<TaskLabel>) {
+        self.background_queue.push(runnable, label);
+    }
+
+    fn dispatch_on_main_thread(&self, runnable: Runnable) {
+        self.main_thread_queue.push(runnable);
+    }
+
+    fn dispatch_after(&self, duration: Duration, runnable: Runnable) {
+        self.timers.insert(Instant::now() + duration, runnable);
+    }
+
+    fn tick(&self, _background_only: bool) -> bool {
+        false
+    }
+
+    fn park(&self) {
+        self.parker.lock().park()
+    }
+
+    fn unparker(&self) -> Unparker {
+        self.parker.lock().unparker()
+    }
+}
+
+impl MainThreadQueue {
+    fn push(&self, runnable: Runnable) {
+        self.runnables.lock().push_back(runnable);
+        self.condvar.notify_one();
+        (self.wake_main_thread)();
+    }
+
+    /// Pops the next runnable, waiting up to `timeout` for one to be queued.
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<Runnable> {
+        let deadline = Instant::now() + timeout;
+        let mut runnables = self.runnables.lock();
+        loop {
+            if let Some(runnable) = runnables.pop_front() {
+                return Some(runnable);
+            }
+            if self
+                .condvar
+                .wait_until(&mut runnables, deadline)
+                .timed_out()
+            {
+                return runnables.pop_front();
+            }
+        }
+    }
+}
+
+impl BackgroundQueue {
+    fn push(&self, runnable: Runnable, label: Option<TaskLabel>) {
+        let mut state = self.state.lock();
+        if state.closed {
+            return;
+        }
+        if label.is_some() {
+            state.labeled.push_back(runnable);
+        } else {
+            state.unlabeled.push_back(runnable);
+        }
+        drop(state);
+        self.condvar.notify_one();
+    }
+
+    /// Blocks until a runnable is available, returning `None` once the queue is closed.
+    fn pop(&self) -> Option<Runnable> {
+        let mut state = self.state.lock();
+        loop {
+            if state.closed {
+                return None;
+            }
+            if let Some(runnable) = state.pop() {
+                return Some(runnable);
+            }
+            self.condvar.wait(&mut state);
+        }
+    }
+
+    fn close(&self) {
+        let mut state = self.state.lock();
+        state.closed = true;
+        let unlabeled = mem::take(&mut state.unlabeled);
+        let labeled = mem::take(&mut state.labeled);
+        drop(state);
+        self.condvar.notify_all();
+        drop((unlabeled, labeled));
+    }
+}
+
+impl BackgroundQueueState {
+    fn pop(&mut self) -> Option<Runnable> {
+        if !self.labeled.is_empty()
+            && (self.unlabeled.is_empty() || self.unlabeled_streak >= MAX_UNLABELED_STREAK)
+        {
+            self.unlabeled_streak = 0;
+            self.labeled.pop_front()
+        } else {
+            let runnable = self.unlabeled.pop_front()?;
+            self.unlabeled_streak += 1;
+            Some(runnable)
+        }
+    }
+}
+
+impl TimerWheel {
+    fn insert(&self, deadline: Instant, runnable: Runnable) {
+        let mut state = self.state.lock();
+        if state.closed {
+            return;
+        }
+        let tick = state.deadline_tick(deadline);
+        state.insert(tick, runnable);
+        drop(state);
+        self.condvar.notify_one();
+    }
+
+    fn run(&self, background_queue: &BackgroundQueue) {
+        let mut state = self.state.lock();
+        while !state.closed {
+            let now_tick = state.elapsed_ticks(Instant::now());
+            let expired = state.advance(now_tick);
+            if !expired.is_empty() {
+                MutexGuard::unlocked(&mut state, || {
+                    for runnable in expired {
+                        background_queue.push(runnable, None);
+                    }
+                });
+                continue;
+            }
+
+            if let Some(tick) = state.next_tick() {
+                let deadline = state.tick_instant(tick);
+                self.condvar.wait_until(&mut state, deadline);
+            } else {
+                self.condvar.wait(&mut state);
+            }
+        }
+    }
+
+    fn close(&self) {
+        let mut state = self.state.lock();
+        state.closed = true;
+        let slots = mem::take(&mut state.slots);
+        drop(state);
+        self.condvar.notify_all();
+        drop(slots);
+    }
+}
+
+impl TimerWheelState {
+    fn new(start: Instant) -> Self {
+        Self {
+            start,
+            current_tick: 0,
+            slots: (0..TIMER_SLOT_COUNT).map(|_| Vec::new()).collect(),
+            len: 0,
+            closed: false,
+        }
+    }
+
+    /// The first tick at or after the given deadline, so timers never fire early.
+    fn deadline_tick(&self, deadline: Instant) -> u64 {
+        let elapsed = deadline.saturating_duration_since(self.start).as_nanos();
+        elapsed.div_ceil(TIMER_TICK.as_nanos()) as u64
+    }
+
+    /// The number of whole ticks that have passed by the given instant.
+    fn elapsed_ticks(&self, now: Instant) -> u64 {
+        let elapsed = now.saturating_duration_since(self.start).as_nanos();
+        (elapsed / TIMER_TICK.as_nanos()) as u64
+    }
+
+    fn tick_instant(&self, tick: u64) -> Instant {
+        self.start + TIMER_TICK * u32::try_from(tick).unwrap_or(u32::MAX)
+    }
+
+    fn insert(&mut self, tick: u64, runnable: Runnable) {
+        // Timers that are already due fire on the next advance.
+        let tick = tick.max(self.current_tick + 1);
+        self.slots[tick as usize % TIMER_SLOT_COUNT].push(Timer { tick, runnable });
+        self.len += 1;
+    }
+
+    /// Advances the wheel to `now_tick`, returning the runnables of expired timers
+    /// ordered by deadline.
+    fn advance(&mut self, now_tick: u64) -> Vec<Runnable> {
+        let mut expired = Vec::new();
+        if now_tick <= self.current_tick {
+            return Vec::new();
+        }
+
+        // Every pending timer is due after the current tick, so visiting the slots
+        // of the elapsed ticks (or every slot, if a whole revolution has elapsed)
+        // finds all of the expired ones.
+        let steps = (now_tick - self.current_tick).min(TIMER_SLOT_COUNT as u64);
+        if self.len > 0 {
+            for tick in self.current_tick + 1..=self.current_tick + steps {
+                let slot = &mut self.slots[tick as usize % TIMER_SLOT_COUNT];
+                let (due, pending) = mem::take(slot)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|timer| timer.tick <= now_tick);
+                *slot = pending;
+                expired.extend(due);
+            }
+        }
+        self.current_tick = now_tick;
+        self.len -= expired.len();
+
+        expired.sort_by_key(|timer| timer.tick);
+        expired.into_iter().map(|timer| timer.runnable).collect()
+    }
+
+    fn next_tick(&self) -> Option<u64> {
+        if self.len == 0 {
+            return None;
+        }
+        self.slots.iter().flatten().map(|timer| timer.tick).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        mpsc,
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn runnable(f: impl 'static + FnOnce() + Send) -> Runnable {
+        let (runnable, task) = async_task::spawn(async move { f() }, |_| {});
+        task.detach();
+        runnable
+    }
+
+    #[test]
+    fn test_background_dispatch_prefers_unlabeled_runnables() {
+        let dispatcher = ThreadPoolDispatcher::with_worker_count(1, || {});
+        let (events_tx, events_rx) = mpsc::channel();
+
+        // Block the only worker so the queue fills up before anything else runs.
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        dispatcher.dispatch(
+            runnable(move || {
+                release_rx.recv().ok();
+            }),
+            None,
+        );
+
+        let label = TaskLabel::new();
+        for name in ["labeled-1", "labeled-2"] {
+            let events_tx = events_tx.clone();
+            dispatcher.dispatch(runnable(move || events_tx.send(name).unwrap()), Some(label));
+        }
+        for name in ["unlabeled-1", "unlabeled-2"] {
+            let events_tx = events_tx.clone();
+            dispatcher.dispatch(runnable(move || events_tx.send(name).unwrap()), None);
+        }
+        release_tx.send(()).unwrap();
+
+        let events = (0..4)
+            .map(|_| events_rx.recv_timeout(TIMEOUT).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            ["unlabeled-1", "unlabeled-2", "labeled-1", "labeled-2"]
+        );
+    }
+
+    #[test]
+    fn test_labeled_runnables_are_not_starved() {
+        let mut state = BackgroundQueueState::default();
+        let (ran_tx, ran_rx) = mpsc::channel();
+        state.labeled.push_back(runnable({
+            let ran_tx = ran_tx.clone();
+            move || ran_tx.send("labeled").unwrap()
+        }));
+        for _ in 0..MAX_UNLABELED_STREAK * 2 {
+            let ran_tx = ran_tx.clone();
+            state
+                .unlabeled
+                .push_back(runnable(move || ran_tx.send("unlabeled").unwrap()));
+        }
+
+        while let Some(runnable) = state.pop() {
+            runnable.run();
+        }
+        let order = ran_rx.try_iter().collect::<Vec<_>>();
+        assert_eq!(
+            order.iter().position(|name| *name == "labeled"),
+            Some(MAX_UNLABELED_STREAK)
+        );
+    }
+
+    #[test]
+    fn test_main_thread_queue() {
+        let wake_count = Arc::new(AtomicUsize::new(0));
+        let dispatcher = Arc::new(ThreadPoolDispatcher::with_worker_count(2, {
+            let wake_count = wake_count.clone();
+            move || {
+                wake_count.fetch_add(1, SeqCst);
+            }
+        }));
+        let queue = dispatcher.main_thread_queue();
+        assert!(dispatcher.is_main_thread());
+        assert!(queue.pop_timeout(Duration::ZERO).is_none());
+
+        // Dispatch onto the main thread from a background thread.
+        let (ran_tx, ran_rx) = mpsc::channel();
+        dispatcher.dispatch(
+            runnable({
+                let dispatcher = dispatcher.clone();
+                move || {
+                    assert!(!dispatcher.is_main_thread());
+                    let main_dispatcher = dispatcher.clone();
+                    dispatcher.dispatch_on_main_thread(runnable(move || {
+                        ran_tx.send(main_dispatcher.is_main_thread()).unwrap();
+                    }));
+                }
+            }),
+            None,
+        );
+
+        queue.pop_timeout(TIMEOUT).unwrap().run();
+        assert_eq!(ran_rx.try_recv(), Ok(true));
+        assert_eq!(wake_count.load(SeqCst), 1);
+    }
+
+    #[test]
+    fn test_dispatch_after() {
+        let dispatcher = ThreadPoolDispatcher::with_worker_count(1, || {});
+        let (fired_tx, fired_rx) = mpsc::channel();
+        let start = Instant::now();
+        for millis in [30, 10, 20] {
+            let fired_tx = fired_tx.clone();
+            dispatcher.dispatch_after(
+                Duration::from_millis(millis),
+                runnable(move || fired_tx.send((millis, Instant::now())).unwrap()),
+            );
+        }
+
+        let fired = (0..3)
+            .map(|_| fired_rx.recv_timeout(TIMEOUT).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fired.iter().map(|(millis, _)| *millis).collect::<Vec<_>>(),
+            [10, 20, 30]
+        );
+        for (millis, fired_at) in fired {
+            assert!(fired_at - start >= Duration::from_millis(millis));
+        }
+    }
+
+    #[test]
+    fn test_timer_wheel_advance() {
+        let mut wheel = TimerWheelState::new(Instant::now());
+        let (fired_tx, fired_rx) = mpsc::channel();
+        let far_tick = 5 + TIMER_SLOT_COUNT as u64;
+        for tick in [far_tick, 5, 3] {
+            let fired_tx = fired_tx.clone();
+            wheel.insert(tick, runnable(move || fired_tx.send(tick).unwrap()));
+        }
+        assert_eq!(wheel.next_tick(), Some(3));
+
+        let run = |runnables: Vec<Runnable>| {
+            for runnable in runnables {
+                runnable.run();
+            }
+            fired_rx.try_iter().collect::<Vec<_>>()
+        };
+
+        // The far timer shares a slot with the one at tick 5 but isn't due yet.
+        assert_eq!(run(wheel.advance(5)), [3, 5]);
+
+        // Timers inserted in the past fire on the next advance.
+        {
+            let fired_tx = fired_tx.clone();
+            wheel.insert(0, runnable(move || fired_tx.send(0).unwrap()));
+        }
+        assert_eq!(run(wheel.advance(far_tick - 1)), [0]);
+        assert_eq!(wheel.next_tick(), Some(far_tick));
+
+        // Advancing more than a whole revolution still finds every expired timer.
+        assert_eq!(
+            run(wheel.advance(far_tick + 10 * TIMER_SLOT_COUNT as u64)),
+            [far_tick]
+        );
+        assert_eq!(wheel.next_tick(), None);
+    }
+
+    #[test]
+    fn test_park_and_unpark() {
+        let dispatcher = ThreadPoolDispatcher::with_worker_count(1, || {});
+        let unparker = dispatcher.unparker();
+        let (parked_tx, parked_rx) = mpsc::channel();
+        thread::spawn(move || {
+            parked_rx.recv().unwrap();
+            thread::sleep(Duration::from_millis(10));
+            unparker.unpark();
+        });
+        parked_tx.send(()).unwrap();
+        dispatcher.park();
+
+        // An unpark that happens before parking isn't lost.
+        dispatcher.unparker().unpark();
+        dispatcher.park();
+    }
+}
@@ -1,8 +1,5 @@
-mod dispatcher;
 mod display;
 mod platform;
-mod text_system;
-mod vulkan_atlas;
 mod vulkan_renderer;
 mod window;
 
@@ -1,20 +1,29 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use parking_lot::Mutex;
-use windows::Win32::UI::WindowsAndMessaging::{DispatchMessageA, GetMessageA};
+use windows::Win32::{
+    Foundation::{LPARAM, WPARAM},
+    System::Threading::GetCurrentThreadId,
+    UI::WindowsAndMessaging::{DispatchMessageA, GetMessageA, PostThreadMessageA, WM_USER},
+};
 
 use crate::{
-    Action, BackgroundExecutor, ForegroundExecutor, Platform, PlatformInput, SemanticVersion,
+    Action, BackgroundExecutor, CosmicTextSystem, ForegroundExecutor, MainThreadQueue, Platform,
+    PlatformInput, SemanticVersion, ThreadPoolDispatcher,
 };
 
-use super::{dispatcher::WindowsDispatcher, text_system::WindowsTextSystem, window::WindowsWindow};
+use super::window::WindowsWindow;
+
+/// Posted to the main thread to wake its message loop when runnables are dispatched to it.
+const WM_GPUI_DISPATCH: u32 = WM_USER;
 
 pub(crate) struct WindowsPlatform(Mutex<WindowsPlatformState>);
 
 pub(crate) struct WindowsPlatformState {
     background_executor: BackgroundExecutor,
     foreground_executor: ForegroundExecutor,
-    text_system: Arc<WindowsTextSystem>,
+    text_system: Arc<CosmicTextSystem>,
+    main_thread_queue: Arc<MainThreadQueue>,
     become_active: Option<Box<dyn FnMut()>>,
     resign_active: Option<Box<dyn FnMut()>>,
     reopen: Option<Box<dyn FnMut()>>,
@@ -30,11 +39,16 @@ pub(crate) struct WindowsPlatformState {
 
 impl WindowsPlatform {
     pub(crate) fn new() -> Self {
-        let dispatcher = Arc::new(WindowsDispatcher::new());
+        let main_thread_id = unsafe { GetCurrentThreadId() };
+        let dispatcher = Arc::new(ThreadPoolDispatcher::new(move || unsafe {
+            PostThreadMessageA(main_thread_id, WM_GPUI_DISPATCH, WPARAM(0), LPARAM(0)).ok();
+        }));
+        let main_thread_queue = dispatcher.main_thread_queue();
         Self(Mutex::new(WindowsPlatformState {
             background_executor: BackgroundExecutor::new(dispatcher.clone()),
             foreground_executor: ForegroundExecutor::new(dispatcher),
-            text_system: Arc::new(WindowsTextSystem::new()),
+            text_system: Arc::new(CosmicTextSystem::new()),
+            main_thread_queue,
             become_active: None,
             resign_active: None,
             reopen: None,
@@ -67,11 +81,22 @@ impl Platform for WindowsPlatform {
         // self.0.lock().finish_launching = Some(on_finish_launching);
         on_finish_launching();
 
+        // Wake-up messages posted before the thread had a message queue are lost, so
+        // drain the queue once before waiting for messages.
+        let main_thread_queue = self.0.lock().main_thread_queue.clone();
+        let run_main_thread_runnables = || {
+            while let Some(runnable) = main_thread_queue.pop_timeout(Duration::ZERO) {
+                runnable.run();
+            }
+        };
+        run_main_thread_runnables();
+
         unsafe {
             let mut message = std::mem::zeroed();
 
             while GetMessageA(&mut message, None, 0, 0).into() {
                 DispatchMessageA(&message);
+                run_main_thread_runnables();
             }
         }
     }
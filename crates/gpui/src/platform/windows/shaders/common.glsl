@@ -16,6 +16,20 @@ const uint SurfaceInputIndex_TextureSize = 3;
 const uint SurfaceInputIndex_YTexture = 4;
 const uint SurfaceInputIndex_CbCrTexture = 5;
 
+const uint BackgroundTag_Solid = 0;
+const uint BackgroundTag_LinearGradient = 1;
+const uint BackgroundTag_RadialGradient = 2;
+
+const uint ColorSpace_Srgb = 0;
+const uint ColorSpace_LinearSrgb = 1;
+const uint ColorSpace_Oklab = 2;
+
+const uint BorderStyle_Solid = 0;
+const uint BorderStyle_Dashed = 1;
+const uint BorderStyle_Dotted = 2;
+
+#define MAX_GRADIENT_STOPS 4
+
 const vec2 unit_vertices[6] = vec2[6](
     vec2(0.0, 0.0),
     vec2(1.0, 0.0),
@@ -86,8 +100,21 @@ struct Bounds_ScaledPixels {
     Size_ScaledPixels size;
 };
 
+struct Corners_ScaledPixels {
+    ScaledPixels top_left;
+    ScaledPixels top_right;
+    ScaledPixels bottom_right;
+    ScaledPixels bottom_left;
+};
+
 struct ContentMask_ScaledPixels {
     Bounds_ScaledPixels bounds;
+    Corners_ScaledPixels corner_radii;
+};
+
+struct TransformationMatrix {
+    float rotation_scale[2][2];
+    float translation[2];
 };
 
 struct PathVertex_ScaledPixels {
@@ -105,13 +132,6 @@ struct ViewId {
 
 #define DrawOrder uint
 
-struct Corners_ScaledPixels {
-    ScaledPixels top_left;
-    ScaledPixels top_right;
-    ScaledPixels bottom_right;
-    ScaledPixels bottom_left;
-};
-
 struct Shadow {
     ViewId view_id;
     LayerId layer_id;
@@ -119,8 +139,12 @@ struct Shadow {
     Bounds_ScaledPixels bounds;
     Corners_ScaledPixels corner_radii;
     ContentMask_ScaledPixels content_mask;
+    TransformationMatrix transform;
     Hsla color;
     ScaledPixels blur_radius;
+    Point_ScaledPixels offset;
+    ScaledPixels spread_radius;
+    bool inset;
 };
 
 struct Underline {
@@ -129,6 +153,7 @@ struct Underline {
     DrawOrder order;
     Bounds_ScaledPixels bounds;
     ContentMask_ScaledPixels content_mask;
+    TransformationMatrix transform;
     ScaledPixels thickness;
     Hsla color;
     bool wavy;
@@ -141,16 +166,47 @@ struct Edges_ScaledPixels {
     ScaledPixels left;
 };
 
+struct GradientStop {
+    Hsla color;
+    float position;
+};
+
+struct Background {
+    uint tag;
+    uint color_space;
+    Hsla solid;
+    Point_f32 direction;
+    Point_f32 center;
+    uint stop_count;
+    GradientStop stops[MAX_GRADIENT_STOPS];
+};
+
+struct Edges_Hsla {
+    Hsla top;
+    Hsla right;
+    Hsla bottom;
+    Hsla left;
+};
+
+struct Edges_BorderStyle {
+    uint top;
+    uint right;
+    uint bottom;
+    uint left;
+};
+
 struct Quad {
     ViewId view_id;
     LayerId layer_id;
     DrawOrder order;
     Bounds_ScaledPixels bounds;
     ContentMask_ScaledPixels content_mask;
-    Hsla background;
-    Hsla border_color;
+    TransformationMatrix transform;
+    Background background;
+    Edges_Hsla border_colors;
     Corners_ScaledPixels corner_radii;
     Edges_ScaledPixels border_widths;
+    Edges_BorderStyle border_styles;
 };
 
 struct MonochromeSprite {
@@ -159,6 +215,7 @@ struct MonochromeSprite {
     DrawOrder order;
     Bounds_ScaledPixels bounds;
     ContentMask_ScaledPixels content_mask;
+    TransformationMatrix transform;
     Hsla color;
     AtlasTile tile;
 };
@@ -169,15 +226,19 @@ struct PolychromeSprite {
     DrawOrder order;
     Bounds_ScaledPixels bounds;
     ContentMask_ScaledPixels content_mask;
+    TransformationMatrix transform;
     Corners_ScaledPixels corner_radii;
     AtlasTile tile;
     bool grayscale;
+    float opacity;
 };
 
 struct PathSprite {
     Bounds_ScaledPixels bounds;
     Hsla color;
     AtlasTile tile;
+    uint coverage;
+    ContentMask_ScaledPixels content_mask;
 };
 
 struct SurfaceBounds {
@@ -234,8 +295,28 @@ vec4 hsla_to_rgba(Hsla hsla) {
     return rgba;
 }
 
-vec4 to_device_position(vec2 unit_vertex, Bounds_ScaledPixels bounds, Size_DevicePixels input_viewport_size) {
+vec2 apply_transform(TransformationMatrix transform, vec2 position) {
+    return vec2(
+        transform.rotation_scale[0][0] * position.x + transform.rotation_scale[0][1] * position.y + transform.translation[0],
+        transform.rotation_scale[1][0] * position.x + transform.rotation_scale[1][1] * position.y + transform.translation[1]
+    );
+}
+
+// Maps a position in the window back to where it was before the transformation was applied,
+// so that fragments can be shaded in the space of the primitive they belong to.
+vec2 untransform_position(TransformationMatrix transform, vec2 position) {
+    float a = transform.rotation_scale[0][0];
+    float b = transform.rotation_scale[0][1];
+    float c = transform.rotation_scale[1][0];
+    float d = transform.rotation_scale[1][1];
+    vec2 offset = position - vec2(transform.translation[0], transform.translation[1]);
+
+    return vec2(d * offset.x - b * offset.y, a * offset.y - c * offset.x) / (a * d - b * c);
+}
+
+vec4 to_device_position(vec2 unit_vertex, Bounds_ScaledPixels bounds, TransformationMatrix transform, Size_DevicePixels input_viewport_size) {
     vec2 position = unit_vertex * vec2(bounds.size.width, bounds.size.height) + vec2(bounds.origin.x, bounds.origin.y);
+    position = apply_transform(transform, position);
     vec2 viewport_size = vec2(float(input_viewport_size.width), float(input_viewport_size.height));
     vec2 device_position = position / viewport_size * vec2(2.0, -2.0) + vec2(-1.0, 1.0);
     device_position.y = -device_position.y;
@@ -276,6 +357,35 @@ float quad_sdf(vec2 point, Bounds_ScaledPixels bounds, Corners_ScaledPixels corn
     return distance;
 }
 
+// The coverage of the given window position by the rounded corners of the content mask.
+// Its straight edges are already clipped by the vertex shaders' clip distances.
+float content_mask_alpha(vec2 position, ContentMask_ScaledPixels content_mask) {
+    Corners_ScaledPixels radii = content_mask.corner_radii;
+    if (radii.top_left == 0.0 && radii.top_right == 0.0 && radii.bottom_right == 0.0 && radii.bottom_left == 0.0) {
+        return 1.0;
+    }
+    return clamp(0.5 - quad_sdf(position, content_mask.bounds, radii), 0.0, 1.0);
+}
+
+// The coverage of a dashed or dotted border at a point `along_edge` of an edge whose dashes
+// span `edge_length`, and `across_edge` from the middle of the border.
+float border_pattern_alpha(uint style, float width, float along_edge, float edge_length, float across_edge) {
+    if (style == BorderStyle_Solid) {
+        return 1.0;
+    }
+
+    float period = style == BorderStyle_Dashed ? 6.0 * width : 2.0 * width;
+    // Stretch the pattern so that a whole number of periods fits, leaving a dash or dot
+    // centered on each corner.
+    period = max(edge_length, width) / max(round(edge_length / period), 1.0);
+    float along = along_edge - round(along_edge / period) * period;
+    if (style == BorderStyle_Dashed) {
+        return clamp(0.5 - (abs(along) - period / 4.0), 0.0, 1.0);
+    } else {
+        return clamp(0.5 - (length(vec2(along, across_edge)) - width / 2.0), 0.0, 1.0);
+    }
+}
+
 float gaussian(float x, float sigma) {
     return exp(-(x * x) / (2.0 * sigma * sigma)) / (sqrt(2.0 * PI) * sigma);
 }
@@ -297,8 +407,54 @@ float blur_along_x(float x, float y, float sigma, float corner, vec2 half_size)
     return integral.y - integral.x;
 }
 
-vec4 distance_from_clip_rect(vec2 unit_vertex, Bounds_ScaledPixels bounds, Bounds_ScaledPixels clip_bounds) {
+// The coverage of a point by the given rounded rectangle blurred with a gaussian.
+float shadow_alpha(vec2 position, Bounds_ScaledPixels bounds, Corners_ScaledPixels corner_radii, float blur_radius) {
+    vec2 origin = vec2(bounds.origin.x, bounds.origin.y);
+    vec2 size = vec2(bounds.size.width, bounds.size.height);
+    vec2 half_size = size / 2.0;
+    vec2 center = origin + half_size;
+    vec2 point = position - center;
+    float corner_radius = 0.0;
+
+    if (point.x < 0.0) {
+        if (point.y < 0.0) {
+            corner_radius = corner_radii.top_left;
+        } else {
+            corner_radius = corner_radii.bottom_left;
+        }
+    } else {
+        if (point.y < 0.0) {
+            corner_radius = corner_radii.top_right;
+        } else {
+            corner_radius = corner_radii.bottom_right;
+        }
+    }
+
+    if (blur_radius <= 0.0) {
+        return clamp(0.5 - quad_sdf(position, bounds, corner_radii), 0.0, 1.0);
+    }
+
+    // The signal is only non-zero in a limited range, so don't waste samples
+    float low = point.y - half_size.y;
+    float high = point.y + half_size.y;
+    float start = clamp(-3.0 * blur_radius, low, high);
+    float end = clamp(3.0 * blur_radius, low, high);
+
+    // Accumulate samples (we can get away with surprisingly few samples)
+    float step = (end - start) / 4.0;
+    float y = start + step * 0.5;
+    float alpha = 0.0;
+    for (int i = 0; i < 4; i++) {
+        alpha += blur_along_x(point.x, point.y - y, blur_radius, corner_radius, half_size) * gaussian(y, blur_radius) * step;
+        y += step;
+    }
+
+    return alpha;
+}
+
+vec4 distance_from_clip_rect(vec2 unit_vertex, Bounds_ScaledPixels bounds, TransformationMatrix transform, Bounds_ScaledPixels clip_bounds) {
     vec2 position = unit_vertex * vec2(bounds.size.width, bounds.size.height) + vec2(bounds.origin.x, bounds.origin.y);
+    position = apply_transform(transform, position);
 
     return vec4(
         position.x - clip_bounds.origin.x,
@@ -316,3 +472,112 @@ vec4 over(vec4 below, vec4 above) {
 
     return result;
 }
+
+float srgb_to_linear(float value) {
+    return value <= 0.04045 ? value / 12.92 : pow((value + 0.055) / 1.055, 2.4);
+}
+
+float linear_to_srgb(float value) {
+    value = max(value, 0.0);
+    return value <= 0.0031308 ? value * 12.92 : 1.055 * pow(value, 1.0 / 2.4) - 0.055;
+}
+
+vec3 linear_srgb_to_oklab(vec3 color) {
+    vec3 lms = vec3(
+        0.4122214708 * color.r + 0.5363325363 * color.g + 0.0514459929 * color.b,
+        0.2119034982 * color.r + 0.6806995451 * color.g + 0.1073969566 * color.b,
+        0.0883024619 * color.r + 0.2817188376 * color.g + 0.6299787005 * color.b
+    );
+    lms = sign(lms) * pow(abs(lms), vec3(1.0 / 3.0));
+
+    return vec3(
+        0.2104542553 * lms.x + 0.7936177850 * lms.y - 0.0040720468 * lms.z,
+        1.9779984951 * lms.x - 2.4285922050 * lms.y + 0.4505937099 * lms.z,
+        0.0259040371 * lms.x + 0.7827717662 * lms.y - 0.8086757660 * lms.z
+    );
+}
+
+vec3 oklab_to_linear_srgb(vec3 color) {
+    vec3 lms = vec3(
+        color.x + 0.3963377774 * color.y + 0.2158037573 * color.z,
+        color.x - 0.1055613458 * color.y - 0.0638541728 * color.z,
+        color.x - 0.0894841775 * color.y - 1.2914855480 * color.z
+    );
+    lms = lms * lms * lms;
+
+    return vec3(
+        4.0767416621 * lms.x - 3.3077115913 * lms.y + 0.2309699292 * lms.z,
+        -1.2684380046 * lms.x + 2.6097574011 * lms.y - 0.3413193965 * lms.z,
+        -0.0041960863 * lms.x - 0.7034186147 * lms.y + 1.7076147010 * lms.z
+    );
+}
+
+// Converts a color into the space a gradient blends in, with premultiplied alpha.
+vec4 to_gradient_space(Hsla hsla, uint color_space) {
+    vec4 rgba = hsla_to_rgba(hsla);
+    vec3 color = rgba.rgb;
+    if (color_space == ColorSpace_LinearSrgb) {
+        color = vec3(srgb_to_linear(color.r), srgb_to_linear(color.g), srgb_to_linear(color.b));
+    } else if (color_space == ColorSpace_Oklab) {
+        color = linear_srgb_to_oklab(vec3(srgb_to_linear(color.r), srgb_to_linear(color.g), srgb_to_linear(color.b)));
+    }
+
+    return vec4(color * rgba.a, rgba.a);
+}
+
+vec4 from_gradient_space(vec4 color, uint color_space) {
+    if (color.a <= 0.0) {
+        return vec4(0.0);
+    }
+
+    vec3 result = color.rgb / color.a;
+    if (color_space == ColorSpace_LinearSrgb) {
+        result = vec3(linear_to_srgb(result.r), linear_to_srgb(result.g), linear_to_srgb(result.b));
+    } else if (color_space == ColorSpace_Oklab) {
+        result = oklab_to_linear_srgb(result);
+        result = vec3(linear_to_srgb(result.r), linear_to_srgb(result.g), linear_to_srgb(result.b));
+    }
+
+    return vec4(clamp(result, 0.0, 1.0), color.a);
+}
+
+vec4 gradient_color(Background background, Bounds_ScaledPixels bounds, vec2 point) {
+    vec2 origin = vec2(bounds.origin.x, bounds.origin.y);
+    vec2 size = vec2(bounds.size.width, bounds.size.height);
+    float position = 0.0;
+
+    if (background.tag == BackgroundTag_LinearGradient) {
+        // The gradient line passes through the center, and is long enough for the
+        // corners to lie on the lines perpendicular to its ends.
+        vec2 direction = vec2(background.direction.x, background.direction.y);
+        float line_length = abs(size.x * direction.x) + abs(size.y * direction.y);
+        if (line_length > 0.0) {
+            position = dot(point - (origin + size / 2.0), direction) / line_length + 0.5;
+        }
+    } else {
+        vec2 center = origin + vec2(background.center.x, background.center.y) * size;
+        vec2 farthest_corner = max(abs(center - origin), abs(origin + size - center));
+        float radius = length(farthest_corner);
+        if (radius > 0.0) {
+            position = length(point - center) / radius;
+        }
+    }
+
+    vec4 color = hsla_to_rgba(background.stops[0].color);
+    for (uint i = 1u; i < background.stop_count; i++) {
+        GradientStop from_stop = background.stops[i - 1u];
+        GradientStop to_stop = background.stops[i];
+        if (position > from_stop.position) {
+            float span = max(to_stop.position - from_stop.position, 1e-6);
+            float amount = clamp((position - from_stop.position) / span, 0.0, 1.0);
+            vec4 blended = mix(
+                to_gradient_space(from_stop.color, background.color_space),
+                to_gradient_space(to_stop.color, background.color_space),
+                amount
+            );
+            color = from_gradient_space(blended, background.color_space);
+        }
+    }
+
+    return color;
+}
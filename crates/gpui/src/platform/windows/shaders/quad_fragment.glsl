@@ -4,18 +4,22 @@
 
 layout (location = 0) out vec4 out_color;
 
-layout (location = 0) flat in vec4 background_color;
-layout (location = 1) flat in vec4 border_color;
-layout (location = 2) flat in uint quad_id;
+layout (location = 0) flat in vec4 solid_background_color;
+layout (location = 1) flat in uint quad_id;
 
 layout (binding = 0) readonly buffer RenderBuffer {
     Quad quads[];
 };
 
 void main() {
-    vec4 position = gl_FragCoord;
-
     Quad quad = quads[quad_id];
+    vec2 position = untransform_position(quad.transform, gl_FragCoord.xy);
+
+    vec4 background_color = solid_background_color;
+    if (quad.background.tag != BackgroundTag_Solid) {
+        background_color = gradient_color(quad.background, quad.bounds, position.xy);
+    }
+
     vec2 half_size = vec2(quad.bounds.size.width, quad.bounds.size.height) / 2.0;
     vec2 center = vec2(quad.bounds.origin.x, quad.bounds.origin.y) + half_size;
     vec2 center_to_point = position.xy - center;
@@ -38,18 +42,38 @@ void main() {
     vec2 rounded_edge_to_point = abs(center_to_point) - half_size + corner_radius;
     float distance = length(max(vec2(0.0), rounded_edge_to_point)) + min(0.0, max(rounded_edge_to_point.x, rounded_edge_to_point.y)) - corner_radius;
 
-    float vertical_border = center_to_point.x <= 0.0 ? quad.border_widths.left : quad.border_widths.right;
-    float horizontal_border = center_to_point.y <= 0.0 ? quad.border_widths.top : quad.border_widths.bottom;
+    bool is_left = center_to_point.x <= 0.0;
+    bool is_top = center_to_point.y <= 0.0;
+    float vertical_border = is_left ? quad.border_widths.left : quad.border_widths.right;
+    float horizontal_border = is_top ? quad.border_widths.top : quad.border_widths.bottom;
     vec2 inset_size = half_size - corner_radius - vec2(vertical_border, horizontal_border);
     vec2 point_to_inset_corner = abs(center_to_point) - inset_size;
     float border_width = 0.0;
+    Hsla border_color;
+    uint border_style;
+    float along_edge;
+    float edge_length;
 
+    // Dashes and dots are laid out along the middle of the border, starting from the
+    // middle of the corner where the edge begins.
     if (point_to_inset_corner.x < 0.0 && point_to_inset_corner.y < 0.0) {
         border_width = 0.0;
     } else if (point_to_inset_corner.y > point_to_inset_corner.x) {
         border_width = horizontal_border;
+        border_color = is_top ? quad.border_colors.top : quad.border_colors.bottom;
+        border_style = is_top ? quad.border_styles.top : quad.border_styles.bottom;
+        float start = quad.bounds.origin.x + quad.border_widths.left / 2.0;
+        float end = quad.bounds.origin.x + quad.bounds.size.width - quad.border_widths.right / 2.0;
+        along_edge = position.x - start;
+        edge_length = end - start;
     } else {
         border_width = vertical_border;
+        border_color = is_left ? quad.border_colors.left : quad.border_colors.right;
+        border_style = is_left ? quad.border_styles.left : quad.border_styles.right;
+        float start = quad.bounds.origin.y + quad.border_widths.top / 2.0;
+        float end = quad.bounds.origin.y + quad.bounds.size.height - quad.border_widths.bottom / 2.0;
+        along_edge = position.y - start;
+        edge_length = end - start;
     }
 
     vec4 color = vec4(0.0);
@@ -58,11 +82,14 @@ void main() {
         color = background_color;
     } else {
         float inset_distance = distance + border_width;
+        vec4 border_rgba = hsla_to_rgba(border_color);
+        border_rgba.a *= border_pattern_alpha(border_style, border_width, along_edge, edge_length, distance + border_width / 2.0);
         // Blend the border on top of the background and then linearly interpolate
         // between the two as we slide inside the background.
-        vec4 blended_border = over(background_color, border_color);
+        vec4 blended_border = over(background_color, border_rgba);
         color = mix(blended_border, background_color, clamp(0.5 - inset_distance, 0.0, 1.0));
     }
 
-    out_color = color * vec4(1.0, 1.0, 1.0, clamp(0.5 - distance, 0.0, 1.0));
+    float mask_alpha = content_mask_alpha(gl_FragCoord.xy, quad.content_mask);
+    out_color = color * vec4(1.0, 1.0, 1.0, clamp(0.5 - distance, 0.0, 1.0) * mask_alpha);
 }
\ No newline at end of file
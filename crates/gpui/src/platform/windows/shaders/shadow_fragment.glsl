@@ -12,44 +12,28 @@ layout (binding = 0) readonly buffer RenderBuffer {
 };
 
 void main() {
-    vec4 position = gl_FragCoord;
-
     Shadow shadow = shadows[shadow_id];
-    vec2 origin = vec2(shadow.bounds.origin.x, shadow.bounds.origin.y);
-    vec2 size = vec2(shadow.bounds.size.width, shadow.bounds.size.height);
-    vec2 half_size = size / 2.0;
-    vec2 center = origin + half_size;
-    vec2 point = position.xy - center;
-    float corner_radius = 0.0;
-
-    if (point.x < 0.0) {
-        if (point.y < 0.0) {
-            corner_radius = shadow.corner_radii.top_left;
-        } else {
-            corner_radius = shadow.corner_radii.bottom_left;
-        }
-    } else {
-        if (point.y < 0.0) {
-            corner_radius = shadow.corner_radii.top_right;
-        } else {
-            corner_radius = shadow.corner_radii.bottom_right;
-        }
-    }
+    vec2 position = untransform_position(shadow.transform, gl_FragCoord.xy);
 
-    // The signal is only non-zero in a limited range, so don't waste samples
-    float low = point.y - half_size.y;
-    float high = point.y + half_size.y;
-    float start = clamp(-3.0 * shadow.blur_radius, low, high);
-    float end = clamp(3.0 * shadow.blur_radius, low, high);
-
-    // Accumulate samples (we can get away with surprisingly few samples)
-    float step = (end - start) / 4.0;
-    float y = start + step * 0.5;
     float alpha = 0.0;
-    for (int i = 0; i < 4; i++) {
-        alpha += blur_along_x(point.x, point.y - y, shadow.blur_radius, corner_radius, half_size) * gaussian(y, shadow.blur_radius) * step;
-        y += step;
+    if (shadow.inset) {
+        // The shadow is cast by a hole in an infinite plane, painted inside the element.
+        Bounds_ScaledPixels hole = shadow.bounds;
+        hole.origin.x += shadow.offset.x + shadow.spread_radius;
+        hole.origin.y += shadow.offset.y + shadow.spread_radius;
+        hole.size.width = max(0.0, hole.size.width - 2.0 * shadow.spread_radius);
+        hole.size.height = max(0.0, hole.size.height - 2.0 * shadow.spread_radius);
+        Corners_ScaledPixels hole_radii = shadow.corner_radii;
+        hole_radii.top_left = max(0.0, hole_radii.top_left - shadow.spread_radius);
+        hole_radii.top_right = max(0.0, hole_radii.top_right - shadow.spread_radius);
+        hole_radii.bottom_right = max(0.0, hole_radii.bottom_right - shadow.spread_radius);
+        hole_radii.bottom_left = max(0.0, hole_radii.bottom_left - shadow.spread_radius);
+
+        float element_alpha = clamp(0.5 - quad_sdf(position, shadow.bounds, shadow.corner_radii), 0.0, 1.0);
+        alpha = (1.0 - shadow_alpha(position, hole, hole_radii, shadow.blur_radius)) * element_alpha;
+    } else {
+        alpha = shadow_alpha(position, shadow.bounds, shadow.corner_radii, shadow.blur_radius);
     }
 
-    out_color = color * vec4(1.0, 1.0, 1.0, alpha);
-}
\ No newline at end of file
+    out_color = color * vec4(1.0, 1.0, 1.0, alpha * content_mask_alpha(gl_FragCoord.xy, shadow.content_mask));
+}
@@ -20,7 +20,8 @@ void main() {
     vec2 unit_vertex = unit_vertices[unit_vertex_id];
     Shadow shadow = shadows[shadow_id];
 
-    float margin = 3.0 * shadow.blur_radius;
+    // Inset shadows are painted inside the element, outer ones extend past it by the blur
+    float margin = shadow.inset ? 0.0 : 3.0 * shadow.blur_radius;
     // Set the bounds of the shadow and adjust its size based on the shadow's
     // spread radius to achieve the spreading effect
     Bounds_ScaledPixels bounds = shadow.bounds;
@@ -29,8 +30,8 @@ void main() {
     bounds.size.width += 2.0 * margin;
     bounds.size.height += 2.0 * margin;
 
-    vec4 device_position = to_device_position(unit_vertex, bounds, viewport_size);
-    vec4 clip_distance = distance_from_clip_rect(unit_vertex, bounds, shadow.content_mask.bounds);
+    vec4 device_position = to_device_position(unit_vertex, bounds, shadow.transform, viewport_size);
+    vec4 clip_distance = distance_from_clip_rect(unit_vertex, bounds, shadow.transform, shadow.content_mask.bounds);
     vec4 color = hsla_to_rgba(shadow.color);
 
     gl_Position = device_position;
@@ -12,9 +12,9 @@ layout (binding = 0) readonly buffer RenderBuffer {
 };
 
 void main() {
-    vec4 position = gl_FragCoord;
-
     Underline underline = underlines[underline_id];
+    vec2 position = untransform_position(underline.transform, gl_FragCoord.xy);
+
     if (underline.wavy) {
         float half_thickness = underline.thickness * 0.5;
         vec2 origin = vec2(underline.bounds.origin.x, underline.bounds.origin.y);
@@ -32,4 +32,5 @@ void main() {
     } else {
         out_color = color;
     }
+    out_color.a *= content_mask_alpha(gl_FragCoord.xy, underline.content_mask);
 }
\ No newline at end of file
@@ -19,8 +19,8 @@ void main() {
 
     vec2 unit_vertex = unit_vertices[unit_vertex_id];
     Underline underline = underlines[underline_id];
-    vec4 device_position = to_device_position(unit_vertex, underline.bounds, viewport_size);
-    vec4 clip_distance = distance_from_clip_rect(unit_vertex, underline.bounds, underline.content_mask.bounds);
+    vec4 device_position = to_device_position(unit_vertex, underline.bounds, underline.transform, viewport_size);
+    vec4 clip_distance = distance_from_clip_rect(unit_vertex, underline.bounds, underline.transform, underline.content_mask.bounds);
     vec4 color = hsla_to_rgba(underline.color);
 
     gl_Position = device_position;
@@ -5,17 +5,15 @@ use std::{ffi::c_void, sync::Arc};
 use ash::*;
 use inline_spirv::include_spirv;
 
-use crate::{PrimitiveBatch, Scene};
+use crate::{CpuAtlas, PrimitiveBatch, Scene};
 
 use self::pipeline::Pipeline;
 
-use super::vulkan_atlas::VulkanAtlas;
-
 #[allow(unused)]
 pub(crate) struct VulkanRenderer {
     width: i32,
     height: i32,
-    sprite_atlas: Arc<VulkanAtlas>,
+    sprite_atlas: Arc<CpuAtlas>,
     entry: Entry,
     instance: Instance,
     surface: vk::SurfaceKHR,
@@ -203,7 +201,7 @@ impl VulkanRenderer {
         Self {
             width,
             height,
-            sprite_atlas: Arc::new(VulkanAtlas::new()),
+            sprite_atlas: Arc::new(CpuAtlas::new()),
             entry,
             instance,
             surface,
@@ -227,7 +225,7 @@ impl VulkanRenderer {
         }
     }
 
-    pub fn sprite_atlas(&self) -> &Arc<VulkanAtlas> {
+    pub fn sprite_atlas(&self) -> &Arc<CpuAtlas> {
         &self.sprite_atlas
     }
 
@@ -364,6 +362,8 @@ impl VulkanRenderer {
 
                         offset += underline_bytes_len;
                     }
+                    // Layers need intermediate render targets, which this renderer doesn't have
+                    // yet. Windows report that they don't support layers, so none are painted.
                     _ => {}
                 }
             }
@@ -400,6 +400,8 @@ impl VulkanRenderer {
                 )
                 .unwrap();
         }
+
+        self.sprite_atlas.finish_frame(scene);
     }
 
     fn create_renderpass(device: &Device) -> vk::RenderPass {
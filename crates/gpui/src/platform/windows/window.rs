@@ -267,6 +267,11 @@ impl PlatformWindow for WindowsWindow {
         this.renderer.draw(scene);
     }
 
+    // The Vulkan renderer doesn't paint into intermediate layers yet.
+    fn supports_layers(&self) -> bool {
+        false
+    }
+
     fn sprite_atlas(&self) -> Arc<dyn crate::PlatformAtlas> {
         self.0.lock().renderer.sprite_atlas().clone()
     }
@@ -1,9 +1,15 @@
 use crate::{
-    point, AtlasTextureId, AtlasTile, Bounds, ContentMask, Corners, Edges, EntityId, Hsla, Pixels,
-    Point, ScaledPixels, StackingOrder,
+    point, size, transparent_black, AtlasTextureId, AtlasTile, BorderStyle, Bounds, ColorSpace,
+    ColorStop, ContentMask, Corners, Edges, EntityId, Fill, Hsla, Pixels, Point, ScaledPixels,
+    Size, StackingOrder, MAX_GRADIENT_STOPS,
 };
 use collections::{BTreeMap, FxHashSet};
-use std::{fmt::Debug, iter::Peekable, slice};
+use smallvec::SmallVec;
+use std::{fmt::Debug, iter::Peekable, ops::Range, slice};
+
+mod dump;
+
+pub use dump::*;
 
 // Exported to metal
 pub(crate) type PointF = Point<f32>;
@@ -37,11 +43,314 @@ impl From<ViewId> for EntityId {
     }
 }
 
+/// A 2D affine transformation, which maps a point `p` to `rotation_scale * p + translation`.
+///
+/// Primitives carry the transformation that positions them in the window. Renderers apply
+/// it to each vertex and undo it for each pixel, so that coverage is still computed in
+/// the primitive's own coordinate space.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub struct TransformationMatrix {
+    /// The linear part of the transformation, as the rows of a 2x2 matrix.
+    pub rotation_scale: [[f32; 2]; 2],
+    /// The offset added after the linear part has been applied.
+    pub translation: [f32; 2],
+}
+
+impl Eq for TransformationMatrix {}
+
+impl Default for TransformationMatrix {
+    fn default() -> Self {
+        Self::unit()
+    }
+}
+
+impl TransformationMatrix {
+    /// The transformation that leaves every point where it is.
+    pub fn unit() -> Self {
+        Self {
+            rotation_scale: [[1., 0.], [0., 1.]],
+            translation: [0., 0.],
+        }
+    }
+
+    /// Returns true if this transformation leaves every point where it is.
+    pub fn is_unit(&self) -> bool {
+        *self == Self::unit()
+    }
+
+    /// Translates by the given offset after applying this transformation's other parts.
+    pub fn translate(self, offset: Point<Pixels>) -> Self {
+        self.compose(Self {
+            rotation_scale: [[1., 0.], [0., 1.]],
+            translation: [offset.x.0, offset.y.0],
+        })
+    }
+
+    /// Rotates clockwise by the given angle, in radians, around the origin.
+    pub fn rotate(self, angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        self.compose(Self {
+            rotation_scale: [[cos, -sin], [sin, cos]],
+            translation: [0., 0.],
+        })
+    }
+
+    /// Scales by the given factors along each axis, relative to the origin.
+    pub fn scale(self, factors: Size<f32>) -> Self {
+        self.compose(Self {
+            rotation_scale: [[factors.width, 0.], [0., factors.height]],
+            translation: [0., 0.],
+        })
+    }
+
+    /// Skews by the given angles, in radians, along the x and y axes.
+    pub fn skew(self, angles: Point<f32>) -> Self {
+        self.compose(Self {
+            rotation_scale: [[1., angles.x.tan()], [angles.y.tan(), 1.]],
+            translation: [0., 0.],
+        })
+    }
+
+    /// Combines this transformation with another one, which is applied first.
+    pub fn compose(self, other: Self) -> Self {
+        if other.is_unit() {
+            return self;
+        }
+        if self.is_unit() {
+            return other;
+        }
+
+        let [[a, b], [c, d]] = self.rotation_scale;
+        let [[e, f], [g, h]] = other.rotation_scale;
+        let [tx, ty] = other.translation;
+        Self {
+            rotation_scale: [
+                [a * e + b * g, a * f + b * h],
+                [c * e + d * g, c * f + d * h],
+            ],
+            translation: [
+                a * tx + b * ty + self.translation[0],
+                c * tx + d * ty + self.translation[1],
+            ],
+        }
+    }
+
+    /// The transformation that undoes this one, if it doesn't collapse the plane onto a
+    /// line or a point.
+    pub fn inverse(&self) -> Option<Self> {
+        let [[a, b], [c, d]] = self.rotation_scale;
+        let determinant = a * d - b * c;
+        if determinant.abs() <= f32::EPSILON {
+            return None;
+        }
+
+        let rotation_scale = [
+            [d / determinant, -b / determinant],
+            [-c / determinant, a / determinant],
+        ];
+        let [tx, ty] = self.translation;
+        Some(Self {
+            rotation_scale,
+            translation: [
+                -(rotation_scale[0][0] * tx + rotation_scale[0][1] * ty),
+                -(rotation_scale[1][0] * tx + rotation_scale[1][1] * ty),
+            ],
+        })
+    }
+
+    /// Maps the given point through this transformation.
+    pub fn apply(&self, point: Point<Pixels>) -> Point<Pixels> {
+        let [x, y] = self.apply_raw([point.x.0, point.y.0]);
+        Point::new(Pixels(x), Pixels(y))
+    }
+
+    /// Maps a transformed point back to where it was before this transformation was
+    /// applied, if the transformation can be undone.
+    pub fn untransform(&self, point: Point<Pixels>) -> Option<Point<Pixels>> {
+        Some(self.inverse()?.apply(point))
+    }
+
+    /// The smallest axis-aligned bounds containing the given bounds once transformed.
+    pub fn apply_to_bounds(&self, bounds: &Bounds<Pixels>) -> Bounds<Pixels> {
+        let [x, y, width, height] = self.apply_to_rect([
+            bounds.origin.x.0,
+            bounds.origin.y.0,
+            bounds.size.width.0,
+            bounds.size.height.0,
+        ]);
+        Bounds {
+            origin: point(Pixels(x), Pixels(y)),
+            size: size(Pixels(width), Pixels(height)),
+        }
+    }
+
+    /// How much the transformation scales lengths on average, which is exact for uniform scales.
+    pub(crate) fn average_scale(&self) -> f32 {
+        let [[a, b], [c, d]] = self.rotation_scale;
+        (a * d - b * c).abs().sqrt()
+    }
+
+    /// Converts a transformation of logical pixels into one of scaled pixels.
+    pub(crate) fn scale_translation(self, factor: f32) -> Self {
+        Self {
+            rotation_scale: self.rotation_scale,
+            translation: self.translation.map(|component| component * factor),
+        }
+    }
+
+    pub(crate) fn apply_scaled(&self, point: Point<ScaledPixels>) -> Point<ScaledPixels> {
+        let [x, y] = self.apply_raw([point.x.0, point.y.0]);
+        Point::new(ScaledPixels(x), ScaledPixels(y))
+    }
+
+    pub(crate) fn apply_to_scaled_bounds(
+        &self,
+        bounds: &Bounds<ScaledPixels>,
+    ) -> Bounds<ScaledPixels> {
+        let [x, y, width, height] = self.apply_to_rect([
+            bounds.origin.x.0,
+            bounds.origin.y.0,
+            bounds.size.width.0,
+            bounds.size.height.0,
+        ]);
+        Bounds {
+            origin: point(ScaledPixels(x), ScaledPixels(y)),
+            size: size(ScaledPixels(width), ScaledPixels(height)),
+        }
+    }
+
+    fn apply_raw(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let [[a, b], [c, d]] = self.rotation_scale;
+        [
+            a * x + b * y + self.translation[0],
+            c * x + d * y + self.translation[1],
+        ]
+    }
+
+    fn apply_to_rect(&self, [x, y, width, height]: [f32; 4]) -> [f32; 4] {
+        if self.is_unit() {
+            return [x, y, width, height];
+        }
+
+        let corners = [
+            [x, y],
+            [x + width, y],
+            [x, y + height],
+            [x + width, y + height],
+        ]
+        .map(|corner| self.apply_raw(corner));
+        let (mut min, mut max) = ([f32::MAX; 2], [f32::MIN; 2]);
+        for [x, y] in corners {
+            min = [min[0].min(x), min[1].min(y)];
+            max = [max[0].max(x), max[1].max(y)];
+        }
+        [min[0], min[1], max[0] - min[0], max[1] - min[1]]
+    }
+}
+
+/// An affine transformation of a color's red, green and blue channels. Each row maps
+/// `[r, g, b, 1]` to one output channel.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub(crate) struct ColorMatrix {
+    pub rows: [[f32; 4]; 3],
+}
+
+impl Default for ColorMatrix {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+// Channel weights for luminance, as used by the CSS filter functions.
+const LUMINANCE: [f32; 3] = [0.213, 0.715, 0.072];
+
+impl ColorMatrix {
+    /// The matrix that leaves every color unchanged.
+    pub fn identity() -> Self {
+        Self::scale(1., 0.)
+    }
+
+    /// Multiplies every channel by `factor` and then adds `offset` to it.
+    pub fn scale(factor: f32, offset: f32) -> Self {
+        Self {
+            rows: [
+                [factor, 0., 0., offset],
+                [0., factor, 0., offset],
+                [0., 0., factor, offset],
+            ],
+        }
+    }
+
+    /// Scales the saturation, where 0 is fully desaturated and 1 is unchanged.
+    pub fn saturate(amount: f32) -> Self {
+        let mut rows = [[0.; 4]; 3];
+        for (ix, row) in rows.iter_mut().enumerate() {
+            for (jx, weight) in LUMINANCE.iter().enumerate() {
+                let identity = if ix == jx { 1. } else { 0. };
+                row[jx] = weight + (identity - weight) * amount;
+            }
+        }
+        Self { rows }
+    }
+
+    /// Rotates hues by the given angle, in radians.
+    pub fn hue_rotate(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        let [r, g, b] = LUMINANCE;
+        Self {
+            rows: [
+                [
+                    r + cos * (1. - r) - sin * r,
+                    g - cos * g - sin * g,
+                    b - cos * b + sin * (1. - b),
+                    0.,
+                ],
+                [
+                    r - cos * r + sin * 0.143,
+                    g + cos * (1. - g) + sin * 0.140,
+                    b - cos * b - sin * 0.283,
+                    0.,
+                ],
+                [
+                    r - cos * r - sin * (1. - r),
+                    g - cos * g + sin * g,
+                    b + cos * (1. - b) + sin * b,
+                    0.,
+                ],
+            ],
+        }
+    }
+
+    /// Combines this matrix with another one, which is applied first.
+    pub fn compose(self, other: Self) -> Self {
+        let mut rows = [[0.; 4]; 3];
+        for (ix, row) in rows.iter_mut().enumerate() {
+            for (jx, value) in row.iter_mut().enumerate() {
+                *value = (0..3)
+                    .map(|kx| self.rows[ix][kx] * other.rows[kx][jx])
+                    .sum::<f32>();
+            }
+            row[3] += self.rows[ix][3];
+        }
+        Self { rows }
+    }
+
+    /// Applies the matrix to a color with straight alpha, clamping the result.
+    pub fn apply(&self, [r, g, b]: [f32; 3]) -> [f32; 3] {
+        self.rows
+            .map(|[x, y, z, w]| (x * r + y * g + z * b + w).clamp(0., 1.))
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct Scene {
     last_layer: Option<(StackingOrder, LayerId)>,
     layers_by_order: BTreeMap<StackingOrder, LayerId>,
     orders_by_layer: BTreeMap<LayerId, StackingOrder>,
+    pub(crate) filters: Vec<FilterLayer>,
+    filter_boundaries: Vec<(DrawOrder, FilterBoundary)>,
     pub(crate) shadows: Vec<Shadow>,
     pub(crate) quads: Vec<Quad>,
     pub(crate) paths: Vec<Path<ScaledPixels>>,
@@ -56,6 +365,8 @@ impl Scene {
         self.last_layer = None;
         self.layers_by_order.clear();
         self.orders_by_layer.clear();
+        self.filters.clear();
+        self.filter_boundaries.clear();
         self.shadows.clear();
         self.quads.clear();
         self.paths.clear();
@@ -69,6 +380,14 @@ impl Scene {
         &self.paths
     }
 
+    /// The paths that mask filter layers, which are rasterized along with the scene's own.
+    pub fn mask_paths(&self) -> impl Iterator<Item = &Path<ScaledPixels>> {
+        self.filters.iter().filter_map(|filter| match &filter.mask {
+            Some(LayerMask::Path(path)) => Some(path),
+            _ => None,
+        })
+    }
+
     pub(crate) fn batches(&self) -> impl Iterator<Item = PrimitiveBatch> {
         BatchIterator {
             shadows: &self.shadows,
@@ -92,9 +411,17 @@ impl Scene {
             surfaces: &self.surfaces,
             surfaces_start: 0,
             surfaces_iter: self.surfaces.iter().peekable(),
+            filters: &self.filters,
+            filter_boundaries_iter: self.filter_boundaries.iter().peekable(),
         }
     }
 
+    /// Paints every primitive whose stacking order starts with the layer's stacking order
+    /// into an intermediate layer, which is filtered before being composited.
+    pub(crate) fn push_filter_layer(&mut self, layer: FilterLayer) {
+        self.filters.push(layer);
+    }
+
     pub(crate) fn insert(&mut self, order: &StackingOrder, primitive: impl Into<Primitive>) {
         let primitive = primitive.into();
         let clipped_bounds = primitive
@@ -160,6 +487,12 @@ impl Scene {
     }
 
     pub fn reuse_views(&mut self, views: &FxHashSet<EntityId>, prev_scene: &mut Self) {
+        for filter in prev_scene.filters.drain(..) {
+            if views.contains(&filter.view_id.into()) {
+                self.push_filter_layer(filter);
+            }
+        }
+
         for shadow in prev_scene.shadows.drain(..) {
             if views.contains(&shadow.view_id.into()) {
                 let order = &prev_scene.orders_by_layer[&shadow.layer_id];
@@ -250,6 +583,45 @@ impl Scene {
             surface.order = orders[surface.layer_id as usize];
         }
         self.surfaces.sort_by_key(|surface| surface.order);
+
+        // The stacking orders that start with a filter layer's order are adjacent, so its
+        // primitives span a contiguous range of draw orders.
+        for filter in &mut self.filters {
+            let start = self.layers_by_order.range(..&filter.stacking_order).count();
+            let len = self
+                .layers_by_order
+                .range(&filter.stacking_order..)
+                .take_while(|(order, _)| order.starts_with(&filter.stacking_order))
+                .count();
+            filter.orders = start as DrawOrder..(start + len) as DrawOrder;
+        }
+        self.filters.retain(|filter| !filter.orders.is_empty());
+        // Mask paths are rasterized with the scene's paths, so their ids follow on.
+        let mut next_path_id = self.paths.len();
+        for filter in &mut self.filters {
+            if let Some(LayerMask::Path(path)) = &mut filter.mask {
+                path.id = PathId(next_path_id);
+                next_path_id += 1;
+            }
+        }
+
+        // At each draw order, layers end before others begin. Outer layers begin first
+        // and end last.
+        self.filter_boundaries.clear();
+        for (ix, filter) in self.filters.iter().enumerate() {
+            self.filter_boundaries
+                .push((filter.orders.start, FilterBoundary::Begin(ix)));
+            self.filter_boundaries
+                .push((filter.orders.end, FilterBoundary::End(ix)));
+        }
+        let filters = &self.filters;
+        self.filter_boundaries.sort_by_key(|(order, boundary)| {
+            let depth = match boundary {
+                FilterBoundary::End(ix) => -(filters[*ix].stacking_order.len() as isize),
+                FilterBoundary::Begin(ix) => filters[*ix].stacking_order.len() as isize,
+            };
+            (*order, matches!(boundary, FilterBoundary::Begin(_)), depth)
+        });
     }
 }
 
@@ -275,6 +647,8 @@ struct BatchIterator<'a> {
     surfaces: &'a [Surface],
     surfaces_start: usize,
     surfaces_iter: Peekable<slice::Iter<'a, Surface>>,
+    filters: &'a [FilterLayer],
+    filter_boundaries_iter: Peekable<slice::Iter<'a, (DrawOrder, FilterBoundary)>>,
 }
 
 impl<'a> Iterator for BatchIterator<'a> {
@@ -307,13 +681,28 @@ impl<'a> Iterator for BatchIterator<'a> {
         ];
         orders_and_kinds.sort_by_key(|(order, kind)| (order.unwrap_or(u32::MAX), *kind));
 
+        // Filter layers begin before, and end after, every primitive they contain, and
+        // split any batch that would straddle them.
         let first = orders_and_kinds[0];
+        if let Some(&&(boundary_order, boundary)) = self.filter_boundaries_iter.peek() {
+            if !matches!(first.0, Some(order) if order < boundary_order) {
+                self.filter_boundaries_iter.next();
+                return Some(match boundary {
+                    FilterBoundary::Begin(ix) => PrimitiveBatch::BeginFilter(&self.filters[ix]),
+                    FilterBoundary::End(ix) => PrimitiveBatch::EndFilter(&self.filters[ix]),
+                });
+            }
+        }
+
         let second = orders_and_kinds[1];
-        let (batch_kind, max_order_and_kind) = if first.0.is_some() {
+        let (batch_kind, mut max_order_and_kind) = if first.0.is_some() {
             (first.1, (second.0.unwrap_or(u32::MAX), second.1))
         } else {
             return None;
         };
+        if let Some((boundary_order, _)) = self.filter_boundaries_iter.peek() {
+            max_order_and_kind = max_order_and_kind.min((*boundary_order, PrimitiveKind::Shadow));
+        }
 
         match batch_kind {
             PrimitiveKind::Shadow => {
@@ -461,15 +850,22 @@ pub(crate) enum Primitive {
 }
 
 impl Primitive {
-    pub fn bounds(&self) -> &Bounds<ScaledPixels> {
+    /// The bounds the primitive covers in the window, once it has been transformed.
+    pub fn bounds(&self) -> Bounds<ScaledPixels> {
         match self {
-            Primitive::Shadow(shadow) => &shadow.bounds,
-            Primitive::Quad(quad) => &quad.bounds,
-            Primitive::Path(path) => &path.bounds,
-            Primitive::Underline(underline) => &underline.bounds,
-            Primitive::MonochromeSprite(sprite) => &sprite.bounds,
-            Primitive::PolychromeSprite(sprite) => &sprite.bounds,
-            Primitive::Surface(surface) => &surface.bounds,
+            Primitive::Shadow(shadow) => shadow.transform.apply_to_scaled_bounds(&shadow.bounds),
+            Primitive::Quad(quad) => quad.transform.apply_to_scaled_bounds(&quad.bounds),
+            Primitive::Path(path) => path.bounds,
+            Primitive::Underline(underline) => underline
+                .transform
+                .apply_to_scaled_bounds(&underline.bounds),
+            Primitive::MonochromeSprite(sprite) => {
+                sprite.transform.apply_to_scaled_bounds(&sprite.bounds)
+            }
+            Primitive::PolychromeSprite(sprite) => {
+                sprite.transform.apply_to_scaled_bounds(&sprite.bounds)
+            }
+            Primitive::Surface(surface) => surface.bounds,
         }
     }
 
@@ -501,6 +897,10 @@ pub(crate) enum PrimitiveBatch<'a> {
         sprites: &'a [PolychromeSprite],
     },
     Surfaces(&'a [Surface]),
+    /// Subsequent batches are painted into a new, transparent intermediate layer.
+    BeginFilter(&'a FilterLayer),
+    /// The intermediate layer is filtered and composited onto the one beneath it.
+    EndFilter(&'a FilterLayer),
 }
 
 #[derive(Default, Debug, Clone, Eq, PartialEq)]
@@ -511,10 +911,12 @@ pub(crate) struct Quad {
     pub order: DrawOrder,
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
-    pub background: Hsla,
-    pub border_color: Hsla,
+    pub transform: TransformationMatrix,
+    pub background: Background,
+    pub border_colors: Edges<Hsla>,
     pub corner_radii: Corners<ScaledPixels>,
     pub border_widths: Edges<ScaledPixels>,
+    pub border_styles: Edges<BorderStyle>,
 }
 
 impl Ord for Quad {
@@ -535,6 +937,122 @@ impl From<Quad> for Primitive {
     }
 }
 
+#[derive(Default, Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub(crate) enum BackgroundTag {
+    #[default]
+    Solid,
+    LinearGradient,
+    RadialGradient,
+}
+
+/// How a quad's background is painted: a solid color, or a gradient that renderers
+/// evaluate for every pixel.
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub(crate) struct Background {
+    pub tag: BackgroundTag,
+    pub color_space: ColorSpace,
+    pub solid: Hsla,
+    /// The unit vector a linear gradient runs along, with y pointing down.
+    pub direction: PointF,
+    /// The center of a radial gradient, relative to the quad's bounds.
+    pub center: PointF,
+    pub stop_count: u32,
+    /// The gradient's stops, sorted by position. Only the first `stop_count` are used.
+    pub stops: [GradientStop; MAX_GRADIENT_STOPS],
+}
+
+impl Eq for Background {}
+
+#[derive(Default, Copy, Clone, Debug, PartialEq)]
+#[repr(C)]
+pub(crate) struct GradientStop {
+    pub color: Hsla,
+    pub position: f32,
+}
+
+impl Background {
+    pub fn solid(color: Hsla) -> Self {
+        Self {
+            solid: color,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a copy of the background with the alpha of each of its colors multiplied
+    /// by the given factor.
+    pub fn opacity(mut self, factor: f32) -> Self {
+        self.solid = self.solid.opacity(factor);
+        for stop in &mut self.stops {
+            stop.color = stop.color.opacity(factor);
+        }
+        self
+    }
+
+    /// Returns the color of the background, if it is solid.
+    pub fn as_solid(&self) -> Option<Hsla> {
+        (self.tag == BackgroundTag::Solid).then_some(self.solid)
+    }
+
+    fn gradient(tag: BackgroundTag, stops: &[ColorStop], color_space: ColorSpace) -> Self {
+        let mut sorted_stops = stops.iter().copied().collect::<SmallVec<[ColorStop; 8]>>();
+        sorted_stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        match sorted_stops.as_slice() {
+            [] => Self::solid(transparent_black()),
+            [stop] => Self::solid(stop.color),
+            _ => {
+                let mut background = Self {
+                    tag,
+                    color_space,
+                    stop_count: sorted_stops.len() as u32,
+                    ..Default::default()
+                };
+                for (slot, stop) in background.stops.iter_mut().zip(&sorted_stops) {
+                    *slot = GradientStop {
+                        color: stop.color,
+                        position: stop.position.clamp(0., 1.),
+                    };
+                }
+                background
+            }
+        }
+    }
+}
+
+impl From<Hsla> for Background {
+    fn from(color: Hsla) -> Self {
+        Self::solid(color)
+    }
+}
+
+impl From<&Fill> for Background {
+    fn from(fill: &Fill) -> Self {
+        match fill {
+            Fill::Color(color) => Self::solid(*color),
+            Fill::LinearGradient(gradient) => {
+                let angle = gradient.angle.to_radians();
+                Self {
+                    direction: point(angle.sin(), -angle.cos()),
+                    ..Self::gradient(
+                        BackgroundTag::LinearGradient,
+                        gradient.stops(),
+                        gradient.color_space,
+                    )
+                }
+            }
+            Fill::RadialGradient(gradient) => Self {
+                center: gradient.center,
+                ..Self::gradient(
+                    BackgroundTag::RadialGradient,
+                    gradient.stops(),
+                    gradient.color_space,
+                )
+            },
+        }
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[repr(C)]
 pub(crate) struct Underline {
@@ -543,6 +1061,7 @@ pub(crate) struct Underline {
     pub order: DrawOrder,
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
+    pub transform: TransformationMatrix,
     pub thickness: ScaledPixels,
     pub color: Hsla,
     pub wavy: bool,
@@ -575,8 +1094,15 @@ pub(crate) struct Shadow {
     pub bounds: Bounds<ScaledPixels>,
     pub corner_radii: Corners<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
+    pub transform: TransformationMatrix,
     pub color: Hsla,
     pub blur_radius: ScaledPixels,
+    /// For inset shadows, `bounds` and `corner_radii` describe the element the shadow is
+    /// painted inside of, and the shadow is cast by the element's edges moved by `offset`
+    /// and pulled inward by `spread_radius`. Outer shadows bake both into `bounds`.
+    pub offset: Point<ScaledPixels>,
+    pub spread_radius: ScaledPixels,
+    pub inset: bool,
 }
 
 impl Ord for Shadow {
@@ -605,6 +1131,7 @@ pub(crate) struct MonochromeSprite {
     pub order: DrawOrder,
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
+    pub transform: TransformationMatrix,
     pub color: Hsla,
     pub tile: AtlasTile,
 }
@@ -630,7 +1157,7 @@ impl From<MonochromeSprite> for Primitive {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 #[repr(C)]
 pub(crate) struct PolychromeSprite {
     pub view_id: ViewId,
@@ -638,11 +1165,15 @@ pub(crate) struct PolychromeSprite {
     pub order: DrawOrder,
     pub bounds: Bounds<ScaledPixels>,
     pub content_mask: ContentMask<ScaledPixels>,
+    pub transform: TransformationMatrix,
     pub corner_radii: Corners<ScaledPixels>,
     pub tile: AtlasTile,
     pub grayscale: bool,
+    pub opacity: f32,
 }
 
+impl Eq for PolychromeSprite {}
+
 impl Ord for PolychromeSprite {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         match self.order.cmp(&other.order) {
@@ -692,11 +1223,55 @@ impl From<Surface> for Primitive {
     }
 }
 
+/// A part of the scene that is painted into an intermediate layer, which is then
+/// filtered, masked and composited onto the layer beneath it.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct FilterLayer {
+    pub view_id: ViewId,
+    /// Every primitive whose stacking order starts with this one is part of the layer.
+    pub stacking_order: StackingOrder,
+    /// The draw orders of the layer's primitives, assigned when the scene is finished.
+    pub orders: Range<DrawOrder>,
+    pub effect: FilterEffect,
+    /// Scales the alpha of the filtered layer by the alpha this primitive is painted with.
+    pub mask: Option<LayerMask>,
+}
+
+/// A primitive that is painted on its own to mask a filter layer, rather than as part
+/// of the scene.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum LayerMask {
+    /// Keeps the parts of the layer the path covers.
+    Path(Path<ScaledPixels>),
+    /// Keeps the parts of the layer the quad covers, fading them with its background.
+    Quad(Quad),
+    /// Keeps the parts of the layer the sprite covers, fading them with its image.
+    Sprite(PolychromeSprite),
+}
+
+/// How a filter layer is filtered and composited.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[repr(C)]
+pub(crate) struct FilterEffect {
+    /// Clips the filtered layer, including anything blurred beyond its primitives.
+    pub content_mask: ContentMask<ScaledPixels>,
+    /// The standard deviation of the gaussian blur applied to the layer.
+    pub blur_radius: ScaledPixels,
+    /// Applied to the blurred layer's colors, with straight alpha.
+    pub color_matrix: ColorMatrix,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum FilterBoundary {
+    Begin(usize),
+    End(usize),
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct PathId(pub(crate) usize);
 
 /// A line made up of a series of vertices and control points.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Path<P: Clone + Default + Debug> {
     pub(crate) id: PathId,
     pub(crate) view_id: ViewId,
@@ -706,6 +1281,7 @@ pub struct Path<P: Clone + Default + Debug> {
     pub(crate) content_mask: ContentMask<P>,
     pub(crate) vertices: Vec<PathVertex<P>>,
     pub(crate) color: Hsla,
+    pub(crate) coverage: PathCoverage,
     start: Point<P>,
     current: Point<P>,
     contour_count: usize,
@@ -728,6 +1304,7 @@ impl Path<Pixels> {
             },
             content_mask: Default::default(),
             color: Default::default(),
+            coverage: PathCoverage::default(),
             contour_count: 0,
         }
     }
@@ -750,7 +1327,22 @@ impl Path<Pixels> {
             current: self.current.scale(factor),
             contour_count: self.contour_count,
             color: self.color,
+            coverage: self.coverage,
+        }
+    }
+
+    /// Maps the path's points through the given transformation.
+    pub(crate) fn transform(&mut self, transform: &TransformationMatrix) {
+        if transform.is_unit() {
+            return;
         }
+
+        for vertex in &mut self.vertices {
+            vertex.xy_position = transform.apply(vertex.xy_position);
+        }
+        self.start = transform.apply(self.start);
+        self.current = transform.apply(self.current);
+        self.bounds = transform.apply_to_bounds(&self.bounds);
     }
 
     /// Draw a straight line from the current point to the given point.
@@ -782,7 +1374,7 @@ impl Path<Pixels> {
         self.current = to;
     }
 
-    fn push_triangle(
+    pub(crate) fn push_triangle(
         &mut self,
         xy: (Point<Pixels>, Point<Pixels>, Point<Pixels>),
         st: (Point<f32>, Point<f32>, Point<f32>),
@@ -820,6 +1412,19 @@ impl Path<Pixels> {
     }
 }
 
+/// How the coverage of a path's overlapping triangles is combined when it's drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub(crate) enum PathCoverage {
+    /// Pixels covered an odd number of times are painted, so that a contour can be
+    /// filled with a fan of triangles around its start.
+    #[default]
+    EvenOdd,
+    /// Pixels covered at least once are painted, so that a stroke can be made of
+    /// segments, joins and caps that overlap each other.
+    Union,
+}
+
 impl Eq for Path<ScaledPixels> {}
 
 impl PartialEq for Path<ScaledPixels> {
@@ -866,3 +1471,124 @@ impl PathVertex<Pixels> {
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct AtlasId(pub(crate) usize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{black, blue, green, px, red, StackingContext};
+
+    #[test]
+    fn test_transformation_matrix() {
+        let transform = TransformationMatrix::unit()
+            .translate(point(px(10.), px(0.)))
+            .scale(size(2., 3.));
+        assert_eq!(
+            transform.apply(point(px(1.), px(1.))),
+            point(px(12.), px(3.))
+        );
+        assert_eq!(
+            transform.untransform(point(px(12.), px(3.))),
+            Some(point(px(1.), px(1.)))
+        );
+        assert_eq!(
+            transform.apply_to_bounds(&Bounds {
+                origin: point(px(-1.), px(0.)),
+                size: size(px(2.), px(1.)),
+            }),
+            Bounds {
+                origin: point(px(8.), px(0.)),
+                size: size(px(4.), px(3.)),
+            }
+        );
+        assert!(TransformationMatrix::unit()
+            .scale(size(0., 1.))
+            .inverse()
+            .is_none());
+    }
+
+    #[test]
+    fn test_gradient_sorts_stops_by_position() {
+        let colors = [red(), green(), blue(), black()];
+        let stops = [3, 0, 2, 1].map(|ix| ColorStop {
+            color: colors[ix],
+            position: ix as f32 / 3.,
+        });
+        let background =
+            Background::gradient(BackgroundTag::LinearGradient, &stops, ColorSpace::default());
+        assert_eq!(background.stop_count, MAX_GRADIENT_STOPS as u32);
+        assert_eq!(
+            background.stops.map(|stop| (stop.color, stop.position)),
+            [
+                (red(), 0.),
+                (green(), 1. / 3.),
+                (blue(), 2. / 3.),
+                (black(), 1.)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_layers_wrap_their_batches() {
+        let order = |z_indices: &[u16]| {
+            let mut order = StackingOrder::default();
+            order.extend(z_indices.iter().map(|z_index| StackingContext {
+                z_index: *z_index,
+                id: 0,
+            }));
+            order
+        };
+        let filter = |z_indices: &[u16], blur_radius: f32| FilterLayer {
+            view_id: Default::default(),
+            stacking_order: order(z_indices),
+            orders: 0..0,
+            effect: FilterEffect {
+                blur_radius: ScaledPixels(blur_radius),
+                ..Default::default()
+            },
+            mask: None,
+        };
+
+        let mut scene = Scene::default();
+        for z_indices in [&[0][..], &[1], &[1, 2], &[1, 3], &[4]] {
+            let bounds = Bounds {
+                origin: point(ScaledPixels(0.), ScaledPixels(0.)),
+                size: size(ScaledPixels(10.), ScaledPixels(10.)),
+            };
+            scene.insert(
+                &order(z_indices),
+                Quad {
+                    bounds,
+                    content_mask: ContentMask {
+                        bounds,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            );
+        }
+        scene.push_filter_layer(filter(&[1], 1.));
+        scene.push_filter_layer(filter(&[1, 2], 2.));
+        // A filter with nothing painted inside it is dropped.
+        scene.push_filter_layer(filter(&[5], 3.));
+        scene.finish();
+
+        let batches = scene
+            .batches()
+            .map(|batch| match batch {
+                PrimitiveBatch::Quads(quads) => format!("quads {}", quads.len()),
+                PrimitiveBatch::BeginFilter(filter) => {
+                    format!("begin {}", filter.effect.blur_radius.0)
+                }
+                PrimitiveBatch::EndFilter(filter) => format!("end {}", filter.effect.blur_radius.0),
+                _ => "other".into(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            batches,
+            [
+                "quads 1", "begin 1", "quads 1", "begin 2", "quads 1", "end 2", "quads 1", "end 1",
+                "quads 1",
+            ]
+        );
+    }
+}
@@ -0,0 +1,1581 @@
+use super::{
+    Background, BackgroundTag, ColorMatrix, DrawOrder, FilterEffect, FilterLayer, GradientStop,
+    LayerId, LayerMask, MonochromeSprite, Path, PathCoverage, PathId, PathVertex, PolychromeSprite,
+    Quad, Scene, Shadow, Surface, TransformationMatrix, Underline,
+};
+use crate::{
+    bytes_per_pixel, point, size, AtlasTextureId, AtlasTextureKind, AtlasTile, BorderStyle, Bounds,
+    ColorSpace, ContentMask, Corners, CpuRenderer, CpuTexture, CpuTextureSource, DevicePixels,
+    Edges, Hsla, Point, ScaledPixels, Size, StackingContext, StackingOrder, TileId,
+    MAX_GRADIENT_STOPS,
+};
+use anyhow::{anyhow, bail, Context as _, Result};
+use collections::{FxHashMap, HashMap};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write as _},
+    path::Path as FsPath,
+};
+
+/// The version of the scene dump format. Bump it whenever the format changes in a
+/// way older loaders can't read.
+pub(crate) const SCENE_DUMP_VERSION: u32 = 10;
+
+/// A self-contained snapshot of a finished [`Scene`], holding every primitive along
+/// with its stacking order and content mask, and the pixels of every atlas tile its
+/// sprites sample from. Dumps are stored as JSON and can be replayed without the
+/// application state that produced them.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneDump {
+    version: u32,
+    /// The size of the window's drawable area, as `[width, height]` in device pixels.
+    viewport_size: [i32; 2],
+    /// The stacking order of each layer, as `(z_index, id)` pairs.
+    layers: Vec<Vec<(u16, u16)>>,
+    shadows: Vec<ShadowDump>,
+    quads: Vec<QuadDump>,
+    paths: Vec<PathDump>,
+    underlines: Vec<UnderlineDump>,
+    monochrome_sprites: Vec<MonochromeSpriteDump>,
+    polychrome_sprites: Vec<PolychromeSpriteDump>,
+    surfaces: Vec<SurfaceDump>,
+    /// Missing from dumps written before elements could be filtered.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    filters: Vec<FilterDump>,
+    tiles: Vec<TileDump>,
+}
+
+/// Bounds are stored as `[x, y, width, height]`, corners as `[top_left, top_right,
+/// bottom_right, bottom_left]`, edges as `[top, right, bottom, left]` and colors as
+/// `[h, s, l, a]`.
+type RectDump = [f32; 4];
+type ColorDump = [f32; 4];
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PrimitiveHeader {
+    view_id: u64,
+    layer: u32,
+    bounds: RectDump,
+    content_mask: RectDump,
+    /// The radii of the content mask's corners. Omitted when they're all square.
+    #[serde(default, skip_serializing_if = "is_square")]
+    content_mask_corner_radii: [f32; 4],
+    /// The transformation applied to the bounds, as `[a, b, c, d, x, y]` for the matrix
+    /// `[[a, b], [c, d]]` and the translation `(x, y)`. Omitted when it is the identity.
+    #[serde(default = "unit_transform", skip_serializing_if = "is_unit_transform")]
+    transform: TransformDump,
+}
+
+type TransformDump = [f32; 6];
+
+fn unit_transform() -> TransformDump {
+    dump_transform(&TransformationMatrix::unit())
+}
+
+fn is_unit_transform(transform: &TransformDump) -> bool {
+    *transform == unit_transform()
+}
+
+fn is_square(corner_radii: &[f32; 4]) -> bool {
+    corner_radii.iter().all(|radius| *radius == 0.)
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ShadowDump {
+    #[serde(flatten)]
+    header: PrimitiveHeader,
+    corner_radii: [f32; 4],
+    color: ColorDump,
+    blur_radius: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inset: Option<InsetDump>,
+}
+
+/// How an inset shadow's hole is moved and shrunk relative to the element's bounds.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct InsetDump {
+    offset: [f32; 2],
+    spread_radius: f32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct QuadDump {
+    #[serde(flatten)]
+    header: PrimitiveHeader,
+    background: BackgroundDump,
+    /// The color of the top edge, which is every edge's color unless `border_colors` is set.
+    border_color: ColorDump,
+    corner_radii: [f32; 4],
+    border_widths: [f32; 4],
+    /// Each edge's color as `[top, right, bottom, left]`, only stored when they differ.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    border_colors: Option<[ColorDump; 4]>,
+    /// Each edge's style as `[top, right, bottom, left]`, only stored when any isn't solid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    border_styles: Option<[BorderStyleDump; 4]>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BorderStyleDump {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+/// Solid backgrounds are stored as a bare color, as they were before gradients existed.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+enum BackgroundDump {
+    Solid(ColorDump),
+    Gradient(GradientDump),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct GradientDump {
+    kind: GradientKindDump,
+    color_space: ColorSpaceDump,
+    /// Each stop as `(color, position)`.
+    stops: Vec<(ColorDump, f32)>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum GradientKindDump {
+    /// The unit vector the gradient runs along, as `[x, y]`.
+    Linear { direction: [f32; 2] },
+    /// The center of the gradient relative to the quad's bounds, as `[x, y]`.
+    Radial { center: [f32; 2] },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ColorSpaceDump {
+    Srgb,
+    LinearSrgb,
+    Oklab,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PathDump {
+    #[serde(flatten)]
+    header: PrimitiveHeader,
+    color: ColorDump,
+    /// Each vertex as `[x, y, s, t]`.
+    vertices: Vec<[f32; 4]>,
+    vertex_content_masks: Vec<RectDump>,
+    /// Whether any coverage of the path's triangles is painted, rather than only odd
+    /// coverage. Missing from dumps written before paths could be stroked.
+    #[serde(default)]
+    union_coverage: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct UnderlineDump {
+    #[serde(flatten)]
+    header: PrimitiveHeader,
+    thickness: f32,
+    color: ColorDump,
+    wavy: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct MonochromeSpriteDump {
+    #[serde(flatten)]
+    header: PrimitiveHeader,
+    color: ColorDump,
+    /// An index into [`SceneDump::tiles`].
+    tile: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct PolychromeSpriteDump {
+    #[serde(flatten)]
+    header: PrimitiveHeader,
+    corner_radii: [f32; 4],
+    /// An index into [`SceneDump::tiles`].
+    tile: usize,
+    grayscale: bool,
+    /// Missing from dumps written before sprites had an opacity.
+    #[serde(default = "full_opacity")]
+    opacity: f32,
+}
+
+fn full_opacity() -> f32 {
+    1.
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct SurfaceDump {
+    #[serde(flatten)]
+    header: PrimitiveHeader,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct FilterDump {
+    view_id: u64,
+    /// The stacking order whose primitives are filtered, as `(z_index, id)` pairs.
+    stacking_order: Vec<(u16, u16)>,
+    content_mask: RectDump,
+    #[serde(default, skip_serializing_if = "is_square")]
+    content_mask_corner_radii: [f32; 4],
+    blur_radius: f32,
+    /// Each row maps `[r, g, b, 1]` to one output channel.
+    color_matrix: [[f32; 4]; 3],
+    /// Missing from dumps written before layers could be masked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mask: Option<MaskDump>,
+}
+
+/// The primitive a filter layer is masked with. Masks aren't painted as part of any
+/// layer, so the layer in their headers is always 0.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MaskDump {
+    Path(PathDump),
+    Quad(QuadDump),
+    Sprite(PolychromeSpriteDump),
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct TileDump {
+    kind: AtlasTextureKind,
+    texture_index: u32,
+    tile_id: u32,
+    /// The tile's bounds within its texture, as `[x, y, width, height]`.
+    bounds: [i32; 4],
+    /// The tile's pixels, in the layout of its texture kind.
+    bytes: Vec<u8>,
+}
+
+/// The atlas textures of a replayed scene, rebuilt from the tiles in its dump. Tiles
+/// keep their original texture ids and bounds, so sprites can sample them unchanged.
+pub(crate) struct SceneDumpTextures {
+    textures: FxHashMap<AtlasTextureId, (Size<DevicePixels>, Vec<u8>)>,
+}
+
+impl SceneDump {
+    /// Captures a finished scene drawn to a viewport of the given size, reading the
+    /// pixels of its sprites' tiles from the given atlas.
+    pub(crate) fn capture(
+        scene: &Scene,
+        viewport_size: Size<DevicePixels>,
+        atlas: &dyn CpuTextureSource,
+    ) -> Result<Self> {
+        let mut layer_indices = HashMap::default();
+        let layers = scene
+            .orders_by_layer
+            .iter()
+            .enumerate()
+            .map(|(ix, (layer_id, order))| {
+                layer_indices.insert(*layer_id, ix as u32);
+                order
+                    .iter()
+                    .map(|context| (context.z_index, context.id))
+                    .collect()
+            })
+            .collect();
+        let header = |view_id: super::ViewId,
+                      layer_id: LayerId,
+                      bounds: &Bounds<ScaledPixels>,
+                      content_mask: &ContentMask<ScaledPixels>,
+                      transform: &TransformationMatrix| {
+            let layer = *layer_indices
+                .get(&layer_id)
+                .ok_or_else(|| anyhow!("primitive refers to unknown layer {layer_id}"))?;
+            Ok::<_, anyhow::Error>(PrimitiveHeader::new(
+                view_id,
+                layer,
+                bounds,
+                content_mask,
+                transform,
+            ))
+        };
+
+        let mut tiles = TileCollector::new(atlas);
+        Ok(Self {
+            version: SCENE_DUMP_VERSION,
+            viewport_size: [viewport_size.width.0, viewport_size.height.0],
+            layers,
+            shadows: scene
+                .shadows
+                .iter()
+                .map(|shadow| {
+                    Ok(ShadowDump {
+                        header: header(
+                            shadow.view_id,
+                            shadow.layer_id,
+                            &shadow.bounds,
+                            &shadow.content_mask,
+                            &shadow.transform,
+                        )?,
+                        corner_radii: dump_corners(&shadow.corner_radii),
+                        color: dump_color(shadow.color),
+                        blur_radius: shadow.blur_radius.0,
+                        inset: shadow.inset.then_some(InsetDump {
+                            offset: [shadow.offset.x.0, shadow.offset.y.0],
+                            spread_radius: shadow.spread_radius.0,
+                        }),
+                    })
+                })
+                .collect::<Result<_>>()?,
+            quads: scene
+                .quads
+                .iter()
+                .map(|quad| {
+                    let header = header(
+                        quad.view_id,
+                        quad.layer_id,
+                        &quad.bounds,
+                        &quad.content_mask,
+                        &quad.transform,
+                    )?;
+                    Ok(QuadDump::new(quad, header))
+                })
+                .collect::<Result<_>>()?,
+            paths: scene
+                .paths
+                .iter()
+                .map(|path| {
+                    let header = header(
+                        path.view_id,
+                        path.layer_id,
+                        &path.bounds,
+                        &path.content_mask,
+                        &TransformationMatrix::unit(),
+                    )?;
+                    Ok(PathDump::new(path, header))
+                })
+                .collect::<Result<_>>()?,
+            underlines: scene
+                .underlines
+                .iter()
+                .map(|underline| {
+                    Ok(UnderlineDump {
+                        header: header(
+                            underline.view_id,
+                            underline.layer_id,
+                            &underline.bounds,
+                            &underline.content_mask,
+                            &underline.transform,
+                        )?,
+                        thickness: underline.thickness.0,
+                        color: dump_color(underline.color),
+                        wavy: underline.wavy,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            monochrome_sprites: scene
+                .monochrome_sprites
+                .iter()
+                .map(|sprite| {
+                    Ok(MonochromeSpriteDump {
+                        header: header(
+                            sprite.view_id,
+                            sprite.layer_id,
+                            &sprite.bounds,
+                            &sprite.content_mask,
+                            &sprite.transform,
+                        )?,
+                        color: dump_color(sprite.color),
+                        tile: tiles.collect(&sprite.tile)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            polychrome_sprites: scene
+                .polychrome_sprites
+                .iter()
+                .map(|sprite| {
+                    let header = header(
+                        sprite.view_id,
+                        sprite.layer_id,
+                        &sprite.bounds,
+                        &sprite.content_mask,
+                        &sprite.transform,
+                    )?;
+                    Ok(PolychromeSpriteDump::new(
+                        sprite,
+                        header,
+                        tiles.collect(&sprite.tile)?,
+                    ))
+                })
+                .collect::<Result<_>>()?,
+            surfaces: scene
+                .surfaces
+                .iter()
+                .map(|surface| {
+                    Ok(SurfaceDump {
+                        header: header(
+                            surface.view_id,
+                            surface.layer_id,
+                            &surface.bounds,
+                            &surface.content_mask,
+                            &TransformationMatrix::unit(),
+                        )?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            filters: scene
+                .filters
+                .iter()
+                .map(|filter| {
+                    Ok(FilterDump {
+                        view_id: dump_view_id(filter.view_id),
+                        stacking_order: filter
+                            .stacking_order
+                            .iter()
+                            .map(|context| (context.z_index, context.id))
+                            .collect(),
+                        content_mask: dump_bounds(&filter.effect.content_mask.bounds),
+                        content_mask_corner_radii: dump_corners(
+                            &filter.effect.content_mask.corner_radii,
+                        ),
+                        blur_radius: filter.effect.blur_radius.0,
+                        color_matrix: filter.effect.color_matrix.rows,
+                        mask: filter
+                            .mask
+                            .as_ref()
+                            .map(|mask| MaskDump::new(mask, &mut tiles))
+                            .transpose()?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            tiles: tiles.tiles,
+        })
+    }
+
+    /// Parses a dump from JSON, rejecting dumps written in a newer format.
+    pub fn from_json(json: &[u8]) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        let Version { version } =
+            serde_json::from_slice(json).context("failed to read scene dump version")?;
+        if version > SCENE_DUMP_VERSION {
+            bail!(
+                "scene dump has version {version}, but only versions up to {SCENE_DUMP_VERSION} are supported"
+            );
+        }
+        serde_json::from_slice(json).context("failed to parse scene dump")
+    }
+
+    /// Reads a dump written by [`SceneDump::save`].
+    pub fn load(path: &FsPath) -> Result<Self> {
+        let json = fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+        Self::from_json(&json)
+    }
+
+    /// Writes the dump to a file as JSON.
+    pub fn save(&self, path: &FsPath) -> Result<()> {
+        let mut writer = BufWriter::new(
+            File::create(path).with_context(|| format!("failed to create {path:?}"))?,
+        );
+        serde_json::to_writer(&mut writer, self).context("failed to serialize scene dump")?;
+        writer
+            .flush()
+            .with_context(|| format!("failed to write {path:?}"))
+    }
+
+    /// The size of the drawable area of the window the scene was drawn in.
+    pub fn viewport_size(&self) -> Size<DevicePixels> {
+        let [width, height] = self.viewport_size;
+        size(DevicePixels(width.max(0)), DevicePixels(height.max(0)))
+    }
+
+    /// Rebuilds the scene, ready to be drawn by a renderer that samples sprites from
+    /// the returned textures.
+    pub(crate) fn replay(&self) -> Result<(Scene, SceneDumpTextures)> {
+        let load_order = |contexts: &[(u16, u16)]| {
+            let mut order = StackingOrder::default();
+            order.extend(contexts.iter().map(|(z_index, id)| StackingContext {
+                z_index: *z_index,
+                id: *id,
+            }));
+            order
+        };
+        let orders = self
+            .layers
+            .iter()
+            .map(|contexts| load_order(contexts))
+            .collect::<Vec<_>>();
+        let order = |header: &PrimitiveHeader| {
+            orders
+                .get(header.layer as usize)
+                .ok_or_else(|| anyhow!("primitive refers to unknown layer {}", header.layer))
+        };
+        let tile = |ix: usize| {
+            self.tiles
+                .get(ix)
+                .map(TileDump::atlas_tile)
+                .ok_or_else(|| anyhow!("sprite refers to unknown tile {ix}"))
+        };
+
+        let mut scene = Scene::default();
+        for shadow in &self.shadows {
+            scene.insert(
+                order(&shadow.header)?,
+                Shadow {
+                    view_id: shadow.header.view_id(),
+                    layer_id: 0,
+                    order: 0,
+                    bounds: load_bounds(shadow.header.bounds),
+                    corner_radii: load_corners(shadow.corner_radii),
+                    content_mask: shadow.header.content_mask(),
+                    transform: shadow.header.transform(),
+                    color: load_color(shadow.color),
+                    blur_radius: ScaledPixels(shadow.blur_radius),
+                    offset: shadow.inset.as_ref().map_or_else(Point::default, |inset| {
+                        point(ScaledPixels(inset.offset[0]), ScaledPixels(inset.offset[1]))
+                    }),
+                    spread_radius: ScaledPixels(
+                        shadow
+                            .inset
+                            .as_ref()
+                            .map_or(0., |inset| inset.spread_radius),
+                    ),
+                    inset: shadow.inset.is_some(),
+                },
+            );
+        }
+        for quad in &self.quads {
+            scene.insert(order(&quad.header)?, quad.quad()?);
+        }
+        for path in &self.paths {
+            scene.insert(order(&path.header)?, path.path()?);
+        }
+        for underline in &self.underlines {
+            scene.insert(
+                order(&underline.header)?,
+                Underline {
+                    view_id: underline.header.view_id(),
+                    layer_id: 0,
+                    order: 0,
+                    bounds: load_bounds(underline.header.bounds),
+                    content_mask: underline.header.content_mask(),
+                    transform: underline.header.transform(),
+                    thickness: ScaledPixels(underline.thickness),
+                    color: load_color(underline.color),
+                    wavy: underline.wavy,
+                },
+            );
+        }
+        for sprite in &self.monochrome_sprites {
+            scene.insert(
+                order(&sprite.header)?,
+                MonochromeSprite {
+                    view_id: sprite.header.view_id(),
+                    layer_id: 0,
+                    order: 0,
+                    bounds: load_bounds(sprite.header.bounds),
+                    content_mask: sprite.header.content_mask(),
+                    transform: sprite.header.transform(),
+                    color: load_color(sprite.color),
+                    tile: tile(sprite.tile)?,
+                },
+            );
+        }
+        for sprite in &self.polychrome_sprites {
+            scene.insert(order(&sprite.header)?, sprite.sprite(tile(sprite.tile)?));
+        }
+        for surface in &self.surfaces {
+            scene.insert(
+                order(&surface.header)?,
+                Surface {
+                    view_id: surface.header.view_id(),
+                    layer_id: 0,
+                    order: 0,
+                    bounds: load_bounds(surface.header.bounds),
+                    content_mask: surface.header.content_mask(),
+                },
+            );
+        }
+        for filter in &self.filters {
+            scene.push_filter_layer(FilterLayer {
+                view_id: load_view_id(filter.view_id),
+                stacking_order: load_order(&filter.stacking_order),
+                orders: 0..0,
+                effect: FilterEffect {
+                    content_mask: ContentMask {
+                        bounds: load_bounds(filter.content_mask),
+                        corner_radii: load_corners(filter.content_mask_corner_radii),
+                    },
+                    blur_radius: ScaledPixels(filter.blur_radius),
+                    color_matrix: ColorMatrix {
+                        rows: filter.color_matrix,
+                    },
+                },
+                mask: match &filter.mask {
+                    Some(MaskDump::Path(path)) => Some(LayerMask::Path(path.path()?)),
+                    Some(MaskDump::Quad(quad)) => Some(LayerMask::Quad(quad.quad()?)),
+                    Some(MaskDump::Sprite(sprite)) => {
+                        Some(LayerMask::Sprite(sprite.sprite(tile(sprite.tile)?)))
+                    }
+                    None => None,
+                },
+            });
+        }
+        scene.finish();
+
+        Ok((scene, SceneDumpTextures::new(&self.tiles)?))
+    }
+}
+
+impl PrimitiveHeader {
+    fn new(
+        view_id: super::ViewId,
+        layer: u32,
+        bounds: &Bounds<ScaledPixels>,
+        content_mask: &ContentMask<ScaledPixels>,
+        transform: &TransformationMatrix,
+    ) -> Self {
+        Self {
+            view_id: dump_view_id(view_id),
+            layer,
+            bounds: dump_bounds(bounds),
+            content_mask: dump_bounds(&content_mask.bounds),
+            content_mask_corner_radii: dump_corners(&content_mask.corner_radii),
+            transform: dump_transform(transform),
+        }
+    }
+
+    fn view_id(&self) -> super::ViewId {
+        load_view_id(self.view_id)
+    }
+
+    fn content_mask(&self) -> ContentMask<ScaledPixels> {
+        ContentMask {
+            bounds: load_bounds(self.content_mask),
+            corner_radii: load_corners(self.content_mask_corner_radii),
+        }
+    }
+
+    fn transform(&self) -> TransformationMatrix {
+        let [a, b, c, d, x, y] = self.transform;
+        TransformationMatrix {
+            rotation_scale: [[a, b], [c, d]],
+            translation: [x, y],
+        }
+    }
+}
+
+impl QuadDump {
+    fn new(quad: &Quad, header: PrimitiveHeader) -> Self {
+        Self {
+            header,
+            background: dump_background(&quad.background),
+            border_color: dump_color(quad.border_colors.top),
+            corner_radii: dump_corners(&quad.corner_radii),
+            border_widths: dump_edges(&quad.border_widths),
+            border_colors: (quad.border_colors != Edges::all(quad.border_colors.top)).then(|| {
+                let colors = &quad.border_colors;
+                [colors.top, colors.right, colors.bottom, colors.left].map(dump_color)
+            }),
+            border_styles: (quad.border_styles != Edges::default()).then(|| {
+                let styles = &quad.border_styles;
+                [styles.top, styles.right, styles.bottom, styles.left].map(dump_border_style)
+            }),
+        }
+    }
+
+    fn quad(&self) -> Result<Quad> {
+        Ok(Quad {
+            view_id: self.header.view_id(),
+            layer_id: 0,
+            order: 0,
+            bounds: load_bounds(self.header.bounds),
+            content_mask: self.header.content_mask(),
+            transform: self.header.transform(),
+            background: load_background(&self.background)?,
+            border_colors: match self.border_colors {
+                Some([top, right, bottom, left]) => Edges {
+                    top: load_color(top),
+                    right: load_color(right),
+                    bottom: load_color(bottom),
+                    left: load_color(left),
+                },
+                None => Edges::all(load_color(self.border_color)),
+            },
+            corner_radii: load_corners(self.corner_radii),
+            border_widths: load_edges(self.border_widths),
+            border_styles: match &self.border_styles {
+                Some([top, right, bottom, left]) => Edges {
+                    top: load_border_style(top),
+                    right: load_border_style(right),
+                    bottom: load_border_style(bottom),
+                    left: load_border_style(left),
+                },
+                None => Edges::default(),
+            },
+        })
+    }
+}
+
+impl PathDump {
+    fn new(path: &Path<ScaledPixels>, header: PrimitiveHeader) -> Self {
+        Self {
+            header,
+            color: dump_color(path.color),
+            vertices: path
+                .vertices
+                .iter()
+                .map(|vertex| {
+                    [
+                        vertex.xy_position.x.0,
+                        vertex.xy_position.y.0,
+                        vertex.st_position.x,
+                        vertex.st_position.y,
+                    ]
+                })
+                .collect(),
+            vertex_content_masks: path
+                .vertices
+                .iter()
+                .map(|vertex| dump_bounds(&vertex.content_mask.bounds))
+                .collect(),
+            union_coverage: path.coverage == PathCoverage::Union,
+        }
+    }
+
+    fn path(&self) -> Result<Path<ScaledPixels>> {
+        if self.vertices.len() != self.vertex_content_masks.len() {
+            bail!("path has mismatched vertices and content masks");
+        }
+        let vertices = self
+            .vertices
+            .iter()
+            .zip(&self.vertex_content_masks)
+            .map(|([x, y, s, t], content_mask)| PathVertex {
+                xy_position: point(ScaledPixels(*x), ScaledPixels(*y)),
+                st_position: point(*s, *t),
+                content_mask: ContentMask {
+                    bounds: load_bounds(*content_mask),
+                    ..Default::default()
+                },
+            })
+            .collect();
+        let bounds = load_bounds(self.header.bounds);
+        Ok(Path {
+            id: PathId(0),
+            view_id: self.header.view_id(),
+            layer_id: 0,
+            order: DrawOrder::default(),
+            start: bounds.origin,
+            current: bounds.origin,
+            bounds,
+            content_mask: self.header.content_mask(),
+            vertices,
+            color: load_color(self.color),
+            coverage: if self.union_coverage {
+                PathCoverage::Union
+            } else {
+                PathCoverage::EvenOdd
+            },
+            contour_count: 0,
+        })
+    }
+}
+
+impl PolychromeSpriteDump {
+    fn new(sprite: &PolychromeSprite, header: PrimitiveHeader, tile: usize) -> Self {
+        Self {
+            header,
+            corner_radii: dump_corners(&sprite.corner_radii),
+            tile,
+            grayscale: sprite.grayscale,
+            opacity: sprite.opacity,
+        }
+    }
+
+    fn sprite(&self, tile: AtlasTile) -> PolychromeSprite {
+        PolychromeSprite {
+            view_id: self.header.view_id(),
+            layer_id: 0,
+            order: 0,
+            bounds: load_bounds(self.header.bounds),
+            content_mask: self.header.content_mask(),
+            transform: self.header.transform(),
+            corner_radii: load_corners(self.corner_radii),
+            tile,
+            grayscale: self.grayscale,
+            opacity: self.opacity,
+        }
+    }
+}
+
+impl MaskDump {
+    fn new(mask: &LayerMask, tiles: &mut TileCollector<'_>) -> Result<Self> {
+        Ok(match mask {
+            LayerMask::Path(path) => {
+                let header = PrimitiveHeader::new(
+                    path.view_id,
+                    0,
+                    &path.bounds,
+                    &path.content_mask,
+                    &TransformationMatrix::unit(),
+                );
+                MaskDump::Path(PathDump::new(path, header))
+            }
+            LayerMask::Quad(quad) => {
+                let header = PrimitiveHeader::new(
+                    quad.view_id,
+                    0,
+                    &quad.bounds,
+                    &quad.content_mask,
+                    &quad.transform,
+                );
+                MaskDump::Quad(QuadDump::new(quad, header))
+            }
+            LayerMask::Sprite(sprite) => {
+                let header = PrimitiveHeader::new(
+                    sprite.view_id,
+                    0,
+                    &sprite.bounds,
+                    &sprite.content_mask,
+                    &sprite.transform,
+                );
+                MaskDump::Sprite(PolychromeSpriteDump::new(
+                    sprite,
+                    header,
+                    tiles.collect(&sprite.tile)?,
+                ))
+            }
+        })
+    }
+}
+
+impl TileDump {
+    fn atlas_tile(&self) -> AtlasTile {
+        let [x, y, width, height] = self.bounds;
+        AtlasTile {
+            texture_id: AtlasTextureId {
+                index: self.texture_index,
+                kind: self.kind,
+            },
+            tile_id: TileId(self.tile_id),
+            bounds: Bounds {
+                origin: point(DevicePixels(x), DevicePixels(y)),
+                size: size(DevicePixels(width), DevicePixels(height)),
+            },
+        }
+    }
+}
+
+impl SceneDumpTextures {
+    fn new(tiles: &[TileDump]) -> Result<Self> {
+        let mut sizes = FxHashMap::<AtlasTextureId, Size<DevicePixels>>::default();
+        for tile in tiles {
+            let atlas_tile = tile.atlas_tile();
+            let bounds = atlas_tile.bounds;
+            if bounds.origin.x.0 < 0
+                || bounds.origin.y.0 < 0
+                || bounds.size.width.0 < 0
+                || bounds.size.height.0 < 0
+            {
+                bail!("tile {} has invalid bounds {bounds:?}", tile.tile_id);
+            }
+            let expected_len = bounds.size.width.0 as usize
+                * bounds.size.height.0 as usize
+                * bytes_per_pixel(tile.kind);
+            if tile.bytes.len() != expected_len {
+                bail!(
+                    "tile {} has {} bytes, expected {expected_len}",
+                    tile.tile_id,
+                    tile.bytes.len()
+                );
+            }
+            let texture_size = sizes.entry(atlas_tile.texture_id).or_default();
+            texture_size.width = texture_size.width.max(bounds.lower_right().x);
+            texture_size.height = texture_size.height.max(bounds.lower_right().y);
+        }
+
+        let mut textures = sizes
+            .into_iter()
+            .map(|(id, size)| {
+                let len = size.width.0 as usize * size.height.0 as usize * bytes_per_pixel(id.kind);
+                (id, (size, vec![0; len]))
+            })
+            .collect::<FxHashMap<_, _>>();
+        for tile in tiles {
+            let atlas_tile = tile.atlas_tile();
+            let (texture_size, bytes) = textures
+                .get_mut(&atlas_tile.texture_id)
+                .expect("a texture was sized for every tile");
+            let bytes_per_pixel = bytes_per_pixel(tile.kind);
+            let row_len = atlas_tile.bounds.size.width.0 as usize * bytes_per_pixel;
+            for (row, tile_row) in tile.bytes.chunks_exact(row_len.max(1)).enumerate() {
+                let y = atlas_tile.bounds.origin.y.0 as usize + row;
+                let start = (y * texture_size.width.0 as usize
+                    + atlas_tile.bounds.origin.x.0 as usize)
+                    * bytes_per_pixel;
+                bytes[start..start + row_len].copy_from_slice(tile_row);
+            }
+        }
+
+        Ok(Self { textures })
+    }
+}
+
+impl CpuTextureSource for SceneDumpTextures {
+    fn with_texture(&self, id: AtlasTextureId, f: &mut dyn FnMut(CpuTexture)) {
+        if let Some((size, bytes)) = self.textures.get(&id) {
+            f(CpuTexture {
+                kind: id.kind,
+                size: *size,
+                bytes,
+            });
+        }
+    }
+}
+
+/// Replays a frame written by [`WindowContext::dump_frame`](crate::WindowContext::dump_frame)
+/// with the software renderer, and saves the result as a PNG image.
+pub fn render_frame_dump(dump_path: &FsPath, image_path: &FsPath) -> Result<()> {
+    let dump = SceneDump::load(dump_path)?;
+    let (scene, textures) = dump.replay()?;
+    let mut renderer = CpuRenderer::new(dump.viewport_size());
+    renderer.draw(&scene, &textures);
+
+    let viewport_size = dump.viewport_size();
+    image::RgbaImage::from_raw(
+        viewport_size.width.0 as u32,
+        viewport_size.height.0 as u32,
+        renderer.pixels().to_vec(),
+    )
+    .ok_or_else(|| anyhow!("framebuffer size doesn't match the viewport"))?
+    .save(image_path)
+    .with_context(|| format!("failed to write {image_path:?}"))
+}
+
+/// Copies the pixels of each distinct tile out of the atlas as sprites reference them.
+struct TileCollector<'a> {
+    atlas: &'a dyn CpuTextureSource,
+    indices: HashMap<(AtlasTextureId, TileId), usize>,
+    tiles: Vec<TileDump>,
+}
+
+impl<'a> TileCollector<'a> {
+    fn new(atlas: &'a dyn CpuTextureSource) -> Self {
+        Self {
+            atlas,
+            indices: HashMap::default(),
+            tiles: Vec::new(),
+        }
+    }
+
+    fn collect(&mut self, tile: &AtlasTile) -> Result<usize> {
+        if let Some(ix) = self.indices.get(&(tile.texture_id, tile.tile_id)) {
+            return Ok(*ix);
+        }
+
+        let mut bytes = None;
+        self.atlas.with_texture(tile.texture_id, &mut |texture| {
+            bytes = read_tile(&texture, &tile.bounds);
+        });
+        let bytes = bytes.ok_or_else(|| {
+            anyhow!(
+                "tile {:?} of texture {:?} is not in the atlas",
+                tile.tile_id,
+                tile.texture_id
+            )
+        })?;
+
+        let ix = self.tiles.len();
+        self.tiles.push(TileDump {
+            kind: tile.texture_id.kind,
+            texture_index: tile.texture_id.index,
+            tile_id: tile.tile_id.0,
+            bounds: [
+                tile.bounds.origin.x.0,
+                tile.bounds.origin.y.0,
+                tile.bounds.size.width.0,
+                tile.bounds.size.height.0,
+            ],
+            bytes,
+        });
+        self.indices.insert((tile.texture_id, tile.tile_id), ix);
+        Ok(ix)
+    }
+}
+
+fn read_tile(texture: &CpuTexture, bounds: &Bounds<DevicePixels>) -> Option<Vec<u8>> {
+    let lower_right = bounds.lower_right();
+    if bounds.origin.x.0 < 0
+        || bounds.origin.y.0 < 0
+        || lower_right.x > texture.size.width
+        || lower_right.y > texture.size.height
+    {
+        return None;
+    }
+
+    let bytes_per_pixel = bytes_per_pixel(texture.kind);
+    let row_len = bounds.size.width.0.max(0) as usize * bytes_per_pixel;
+    let mut bytes = Vec::with_capacity(row_len * bounds.size.height.0.max(0) as usize);
+    for y in bounds.origin.y.0..lower_right.y.0 {
+        let start = (y as usize * texture.size.width.0 as usize + bounds.origin.x.0 as usize)
+            * bytes_per_pixel;
+        bytes.extend_from_slice(&texture.bytes[start..start + row_len]);
+    }
+    Some(bytes)
+}
+
+fn dump_view_id(view_id: super::ViewId) -> u64 {
+    (view_id.high_bits as u64) << 32 | view_id.low_bits as u64
+}
+
+fn load_view_id(view_id: u64) -> super::ViewId {
+    super::ViewId {
+        low_bits: view_id as u32,
+        high_bits: (view_id >> 32) as u32,
+    }
+}
+
+fn dump_bounds(bounds: &Bounds<ScaledPixels>) -> RectDump {
+    [
+        bounds.origin.x.0,
+        bounds.origin.y.0,
+        bounds.size.width.0,
+        bounds.size.height.0,
+    ]
+}
+
+fn load_bounds([x, y, width, height]: RectDump) -> Bounds<ScaledPixels> {
+    Bounds {
+        origin: point(ScaledPixels(x), ScaledPixels(y)),
+        size: size(ScaledPixels(width), ScaledPixels(height)),
+    }
+}
+
+fn dump_corners(corners: &Corners<ScaledPixels>) -> [f32; 4] {
+    [
+        corners.top_left.0,
+        corners.top_right.0,
+        corners.bottom_right.0,
+        corners.bottom_left.0,
+    ]
+}
+
+fn load_corners(
+    [top_left, top_right, bottom_right, bottom_left]: [f32; 4],
+) -> Corners<ScaledPixels> {
+    Corners {
+        top_left: ScaledPixels(top_left),
+        top_right: ScaledPixels(top_right),
+        bottom_right: ScaledPixels(bottom_right),
+        bottom_left: ScaledPixels(bottom_left),
+    }
+}
+
+fn dump_edges(edges: &Edges<ScaledPixels>) -> [f32; 4] {
+    [edges.top.0, edges.right.0, edges.bottom.0, edges.left.0]
+}
+
+fn load_edges([top, right, bottom, left]: [f32; 4]) -> Edges<ScaledPixels> {
+    Edges {
+        top: ScaledPixels(top),
+        right: ScaledPixels(right),
+        bottom: ScaledPixels(bottom),
+        left: ScaledPixels(left),
+    }
+}
+
+fn dump_transform(transform: &TransformationMatrix) -> TransformDump {
+    let [[a, b], [c, d]] = transform.rotation_scale;
+    let [x, y] = transform.translation;
+    [a, b, c, d, x, y]
+}
+
+fn dump_color(color: Hsla) -> ColorDump {
+    [color.h, color.s, color.l, color.a]
+}
+
+fn load_color([h, s, l, a]: ColorDump) -> Hsla {
+    Hsla { h, s, l, a }
+}
+
+fn dump_border_style(style: BorderStyle) -> BorderStyleDump {
+    match style {
+        BorderStyle::Solid => BorderStyleDump::Solid,
+        BorderStyle::Dashed => BorderStyleDump::Dashed,
+        BorderStyle::Dotted => BorderStyleDump::Dotted,
+    }
+}
+
+fn load_border_style(style: &BorderStyleDump) -> BorderStyle {
+    match style {
+        BorderStyleDump::Solid => BorderStyle::Solid,
+        BorderStyleDump::Dashed => BorderStyle::Dashed,
+        BorderStyleDump::Dotted => BorderStyle::Dotted,
+    }
+}
+
+fn dump_background(background: &Background) -> BackgroundDump {
+    let kind = match background.tag {
+        BackgroundTag::Solid => return BackgroundDump::Solid(dump_color(background.solid)),
+        BackgroundTag::LinearGradient => GradientKindDump::Linear {
+            direction: [background.direction.x, background.direction.y],
+        },
+        BackgroundTag::RadialGradient => GradientKindDump::Radial {
+            center: [background.center.x, background.center.y],
+        },
+    };
+    BackgroundDump::Gradient(GradientDump {
+        kind,
+        color_space: match background.color_space {
+            ColorSpace::Srgb => ColorSpaceDump::Srgb,
+            ColorSpace::LinearSrgb => ColorSpaceDump::LinearSrgb,
+            ColorSpace::Oklab => ColorSpaceDump::Oklab,
+        },
+        stops: background.stops[..background.stop_count as usize]
+            .iter()
+            .map(|stop| (dump_color(stop.color), stop.position))
+            .collect(),
+    })
+}
+
+fn load_background(background: &BackgroundDump) -> Result<Background> {
+    let gradient = match background {
+        BackgroundDump::Solid(color) => return Ok(Background::solid(load_color(*color))),
+        BackgroundDump::Gradient(gradient) => gradient,
+    };
+    if !(2..=MAX_GRADIENT_STOPS).contains(&gradient.stops.len()) {
+        bail!(
+            "gradient has {} stops, but must have between 2 and {MAX_GRADIENT_STOPS}",
+            gradient.stops.len()
+        );
+    }
+
+    let mut loaded = Background {
+        color_space: match gradient.color_space {
+            ColorSpaceDump::Srgb => ColorSpace::Srgb,
+            ColorSpaceDump::LinearSrgb => ColorSpace::LinearSrgb,
+            ColorSpaceDump::Oklab => ColorSpace::Oklab,
+        },
+        stop_count: gradient.stops.len() as u32,
+        ..Default::default()
+    };
+    match gradient.kind {
+        GradientKindDump::Linear { direction: [x, y] } => {
+            loaded.tag = BackgroundTag::LinearGradient;
+            loaded.direction = point(x, y);
+        }
+        GradientKindDump::Radial { center: [x, y] } => {
+            loaded.tag = BackgroundTag::RadialGradient;
+            loaded.center = point(x, y);
+        }
+    }
+    for (slot, (color, position)) in loaded.stops.iter_mut().zip(&gradient.stops) {
+        *slot = GradientStop {
+            color: load_color(*color),
+            position: *position,
+        };
+    }
+    Ok(loaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color_stop, div, linear_gradient, px, radial_gradient, rgb, rgba, AtlasKey, CpuAtlas, Fill,
+        IntoElement, PlatformAtlas, Render, RenderSvgParams, SharedString, Styled, TestAppContext,
+        ViewContext,
+    };
+    use std::borrow::Cow;
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    fn order(z_index: u16) -> StackingOrder {
+        let mut order = StackingOrder::default();
+        order.push(StackingContext { z_index, id: 0 });
+        order
+    }
+
+    fn bounds(x: f32, y: f32, width: f32, height: f32) -> Bounds<ScaledPixels> {
+        load_bounds([x, y, width, height])
+    }
+
+    fn content_mask() -> ContentMask<ScaledPixels> {
+        ContentMask {
+            bounds: bounds(0., 0., 32., 32.),
+            corner_radii: load_corners([8., 8., 8., 8.]),
+        }
+    }
+
+    /// A scene holding one of each primitive kind that the software renderer draws.
+    fn scene(atlas: &CpuAtlas) -> Scene {
+        let key = AtlasKey::Svg(RenderSvgParams {
+            path: SharedString::from("icon.svg"),
+            size: size(DevicePixels(4), DevicePixels(4)),
+            color_mode: Default::default(),
+        });
+        let tile = atlas
+            .get_or_insert_with(&key, &mut || {
+                Ok((
+                    size(DevicePixels(4), DevicePixels(4)),
+                    Cow::Owned((0..16).map(|ix| ix * 16).collect()),
+                ))
+            })
+            .unwrap();
+
+        let mut scene = Scene::default();
+        scene.insert(
+            &order(0),
+            Shadow {
+                view_id: Default::default(),
+                layer_id: 0,
+                order: 0,
+                bounds: bounds(4., 4., 16., 16.),
+                corner_radii: load_corners([2., 2., 2., 2.]),
+                content_mask: content_mask(),
+                transform: Default::default(),
+                color: rgb(0x000000).into(),
+                blur_radius: ScaledPixels(3.),
+                offset: Default::default(),
+                spread_radius: ScaledPixels(0.),
+                inset: false,
+            },
+        );
+        scene.insert(
+            &order(3),
+            Shadow {
+                view_id: Default::default(),
+                layer_id: 0,
+                order: 0,
+                bounds: bounds(2., 2., 20., 12.),
+                corner_radii: load_corners([2., 2., 2., 2.]),
+                content_mask: content_mask(),
+                transform: Default::default(),
+                color: rgb(0x0000ff).into(),
+                blur_radius: ScaledPixels(2.),
+                offset: point(ScaledPixels(1.), ScaledPixels(2.)),
+                spread_radius: ScaledPixels(1.),
+                inset: true,
+            },
+        );
+        scene.insert(
+            &order(1),
+            Quad {
+                bounds: bounds(2., 2., 20., 12.),
+                content_mask: content_mask(),
+                background: Background::solid(rgb(0xff0000).into()),
+                border_colors: Edges {
+                    left: rgb(0x00ff00).into(),
+                    ..Edges::all(rgb(0x0000ff).into())
+                },
+                corner_radii: load_corners([4., 0., 4., 0.]),
+                border_widths: load_edges([1., 2., 1., 2.]),
+                border_styles: Edges {
+                    top: BorderStyle::Dashed,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        scene.insert(
+            &order(1),
+            Quad {
+                bounds: bounds(2., 16., 12., 12.),
+                content_mask: content_mask(),
+                background: Background::from(&Fill::from(
+                    radial_gradient(
+                        point(0.25, 0.5),
+                        [
+                            color_stop(rgb(0xffffff), 0.),
+                            color_stop(rgb(0x00ffff), 0.4),
+                            color_stop(rgba(0x0000ff00), 1.),
+                        ],
+                    )
+                    .unwrap()
+                    .color_space(ColorSpace::Oklab),
+                )),
+                corner_radii: load_corners([6., 6., 6., 6.]),
+                ..Default::default()
+            },
+        );
+        let mut path = crate::Path::new(point(px(16.), px(16.)));
+        path.line_to(point(px(28.), px(18.)));
+        path.line_to(point(px(20.), px(30.)));
+        path.color = rgb(0x00ff00).into();
+        path.content_mask = ContentMask {
+            bounds: Bounds {
+                origin: point(px(0.), px(0.)),
+                size: size(px(32.), px(32.)),
+            },
+            corner_radii: Corners::all(px(8.)),
+        };
+        let mut path = path.scale(1.);
+        for vertex in &mut path.vertices {
+            vertex.content_mask = content_mask();
+        }
+        scene.insert(&order(2), path);
+        scene.insert(
+            &order(2),
+            Underline {
+                view_id: Default::default(),
+                layer_id: 0,
+                order: 0,
+                bounds: bounds(0., 24., 24., 4.),
+                content_mask: content_mask(),
+                transform: Default::default(),
+                thickness: ScaledPixels(1.),
+                color: rgb(0xffff00).into(),
+                wavy: true,
+            },
+        );
+        scene.insert(
+            &order(3),
+            MonochromeSprite {
+                view_id: Default::default(),
+                layer_id: 0,
+                order: 0,
+                bounds: bounds(24., 2., 4., 4.),
+                content_mask: content_mask(),
+                transform: TransformationMatrix::unit().skew(point(0.2, 0.)),
+                color: rgb(0xff00ff).into(),
+                tile,
+            },
+        );
+        scene.push_filter_layer(FilterLayer {
+            view_id: Default::default(),
+            stacking_order: order(2),
+            orders: 0..0,
+            effect: FilterEffect {
+                content_mask: content_mask(),
+                blur_radius: ScaledPixels(1.),
+                color_matrix: ColorMatrix::saturate(0.5).compose(ColorMatrix::hue_rotate(1.)),
+            },
+            mask: None,
+        });
+        let fade = bounds(0., 0., 24., 32.);
+        scene.push_filter_layer(FilterLayer {
+            view_id: Default::default(),
+            stacking_order: order(1),
+            orders: 0..0,
+            effect: FilterEffect {
+                content_mask: content_mask(),
+                ..Default::default()
+            },
+            mask: Some(LayerMask::Quad(Quad {
+                bounds: fade,
+                content_mask: content_mask(),
+                background: Background::from(&Fill::from(
+                    linear_gradient(
+                        90.,
+                        [
+                            color_stop(rgb(0x000000), 0.5),
+                            color_stop(rgba(0x00000000), 1.),
+                        ],
+                    )
+                    .unwrap(),
+                )),
+                ..Default::default()
+            })),
+        });
+        scene.finish();
+        scene
+    }
+
+    #[test]
+    fn test_replayed_scene_renders_identically() {
+        let atlas = CpuAtlas::new();
+        let scene = scene(&atlas);
+        let dump =
+            SceneDump::capture(&scene, size(DevicePixels(32), DevicePixels(32)), &atlas).unwrap();
+        assert_eq!(dump.layers.len(), 4);
+        assert_eq!(dump.tiles.len(), 1);
+
+        let json = serde_json::to_vec(&dump).unwrap();
+        let loaded = SceneDump::from_json(&json).unwrap();
+        assert_eq!(loaded, dump);
+
+        let (replayed, textures) = loaded.replay().unwrap();
+        assert_eq!(replayed.quads, scene.quads);
+        assert_eq!(replayed.shadows, scene.shadows);
+        assert_eq!(replayed.underlines, scene.underlines);
+        assert_eq!(replayed.monochrome_sprites, scene.monochrome_sprites);
+        assert_eq!(replayed.paths.len(), scene.paths.len());
+        assert_eq!(replayed.filters, scene.filters);
+
+        let viewport = loaded.viewport_size();
+        let mut expected = CpuRenderer::new(viewport);
+        expected.draw(&scene, &atlas);
+        let mut actual = CpuRenderer::new(viewport);
+        actual.draw(&replayed, &textures);
+        assert_eq!(actual.pixels(), expected.pixels());
+        assert!(expected.pixels().iter().any(|byte| *byte != 0));
+    }
+
+    #[test]
+    fn test_dump_file_roundtrip_and_versioning() {
+        let atlas = CpuAtlas::new();
+        let dump = SceneDump::capture(
+            &scene(&atlas),
+            size(DevicePixels(32), DevicePixels(32)),
+            &atlas,
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("gpui-scene-{}.json", uuid::Uuid::new_v4()));
+        dump.save(&path).unwrap();
+        assert_eq!(SceneDump::load(&path).unwrap(), dump);
+        fs::remove_file(&path).ok();
+
+        let mut newer = serde_json::to_value(&dump).unwrap();
+        newer["version"] = (SCENE_DUMP_VERSION + 1).into();
+        let error = SceneDump::from_json(newer.to_string().as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("version"), "{error}");
+
+        let mut corrupt = serde_json::to_value(&dump).unwrap();
+        corrupt["tiles"][0]["bytes"] = serde_json::json!([1, 2, 3]);
+        let corrupt = SceneDump::from_json(corrupt.to_string().as_bytes()).unwrap();
+        assert!(corrupt.replay().is_err());
+    }
+
+    #[test]
+    fn test_masked_filter_roundtrip() {
+        let atlas = CpuAtlas::new();
+        let image_id = crate::ImageData::new(image::ImageBuffer::new(1, 1)).id;
+        let key = AtlasKey::Image(crate::RenderImageParams { image_id });
+        let tile = atlas
+            .get_or_insert_with(&key, &mut || {
+                Ok((
+                    size(DevicePixels(4), DevicePixels(4)),
+                    Cow::Owned((0..64).map(|ix| (ix * 4) as u8).collect()),
+                ))
+            })
+            .unwrap();
+
+        let mut scene = Scene::default();
+        for z_index in 0..2 {
+            scene.insert(
+                &order(z_index),
+                Quad {
+                    bounds: bounds(0., 16. * z_index as f32, 32., 16.),
+                    content_mask: content_mask(),
+                    background: Background::solid(rgb(0xff0000).into()),
+                    ..Default::default()
+                },
+            );
+        }
+        let mut mask = crate::Path::new(point(px(0.), px(0.)));
+        mask.line_to(point(px(32.), px(0.)));
+        mask.line_to(point(px(0.), px(16.)));
+        let mut mask = mask.scale(1.);
+        for vertex in &mut mask.vertices {
+            vertex.content_mask = content_mask();
+        }
+        scene.push_filter_layer(FilterLayer {
+            view_id: Default::default(),
+            stacking_order: order(0),
+            orders: 0..0,
+            effect: FilterEffect {
+                content_mask: content_mask(),
+                ..Default::default()
+            },
+            mask: Some(LayerMask::Path(mask)),
+        });
+        scene.push_filter_layer(FilterLayer {
+            view_id: Default::default(),
+            stacking_order: order(1),
+            orders: 0..0,
+            effect: FilterEffect {
+                content_mask: content_mask(),
+                ..Default::default()
+            },
+            mask: Some(LayerMask::Sprite(PolychromeSprite {
+                view_id: Default::default(),
+                layer_id: 0,
+                order: 0,
+                bounds: bounds(8., 16., 16., 16.),
+                content_mask: content_mask(),
+                transform: Default::default(),
+                corner_radii: Default::default(),
+                tile,
+                grayscale: false,
+                opacity: 0.5,
+            })),
+        });
+        scene.finish();
+
+        let viewport = size(DevicePixels(32), DevicePixels(32));
+        let dump = SceneDump::capture(&scene, viewport, &atlas).unwrap();
+        let json = serde_json::to_vec(&dump).unwrap();
+        let loaded = SceneDump::from_json(&json).unwrap();
+        assert_eq!(loaded, dump);
+
+        let (replayed, textures) = loaded.replay().unwrap();
+        let masks = replayed
+            .filters
+            .iter()
+            .map(|filter| match &filter.mask {
+                Some(LayerMask::Path(_)) => "path",
+                Some(LayerMask::Quad(_)) => "quad",
+                Some(LayerMask::Sprite(_)) => "sprite",
+                None => "none",
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(masks, ["path", "sprite"]);
+        assert_eq!(
+            SceneDump::capture(&replayed, viewport, &textures).unwrap(),
+            dump
+        );
+
+        let mut expected = CpuRenderer::new(viewport);
+        expected.draw(&scene, &atlas);
+        let mut actual = CpuRenderer::new(viewport);
+        actual.draw(&replayed, &textures);
+        assert_eq!(actual.pixels(), expected.pixels());
+
+        // Dumps written before layers could be masked load as unmasked layers.
+        let mut unmasked = serde_json::to_value(&dump).unwrap();
+        for filter in unmasked["filters"].as_array_mut().unwrap() {
+            filter.as_object_mut().unwrap().remove("mask");
+        }
+        let (replayed, _) = SceneDump::from_json(unmasked.to_string().as_bytes())
+            .unwrap()
+            .replay()
+            .unwrap();
+        assert!(replayed.filters.iter().all(|filter| filter.mask.is_none()));
+    }
+
+    struct Square;
+
+    impl Render for Square {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div().size(px(8.)).bg(rgb(0xff0000))
+        }
+    }
+
+    #[crate::test]
+    fn test_dump_frame(cx: &mut TestAppContext) {
+        let (_, cx) = cx.add_window_view(|_| Square);
+        cx.simulate_resize(size(px(16.), px(16.)));
+        let dir = std::env::temp_dir().join(format!("gpui-frame-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let dump_path = dir.join("frame.json");
+        cx.update(|cx| cx.dump_frame(&dump_path)).unwrap();
+
+        let dump = SceneDump::load(&dump_path).unwrap();
+        assert_eq!(
+            dump.viewport_size(),
+            size(DevicePixels(32), DevicePixels(32))
+        );
+        let (scene, _) = dump.replay().unwrap();
+        assert_eq!(scene.quads.len(), 1);
+        assert_eq!(
+            scene.quads[0].background,
+            Background::solid(rgb(0xff0000).into())
+        );
+
+        let image_path = dir.join("frame.png");
+        render_frame_dump(&dump_path, &image_path).unwrap();
+        let image = image::open(&image_path).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (32, 32));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(20, 20).0, [0, 0, 0, 0]);
+        fs::remove_dir_all(dir).ok();
+    }
+}
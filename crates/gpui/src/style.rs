@@ -1,18 +1,20 @@
 use std::{iter, mem, ops::Range};
 
 use crate::{
-    black, phi, point, quad, rems, AbsoluteLength, Bounds, ContentMask, Corners, CornersRefinement,
-    CursorStyle, DefiniteLength, Edges, EdgesRefinement, ElementContext, Font, FontFeatures,
-    FontStyle, FontWeight, Hsla, Length, Pixels, Point, PointRefinement, Rgba, SharedString, Size,
-    SizeRefinement, Styled, TextRun,
+    black, phi, point, quad, rems, transparent_black, AbsoluteLength, Bounds, ColorMatrix,
+    ContentMask, Corners, CornersRefinement, CursorStyle, DefiniteLength, Edges, EdgesRefinement,
+    ElementContext, Font, FontFeatures, FontStyle, FontWeight, Hsla, ImageSource, Length, Pixels,
+    Point, PointRefinement, Rems, Result, Rgba, SharedString, Size, SizeRefinement, Styled,
+    TextRun, TransformationMatrix,
 };
 use collections::HashSet;
 use refineable::Refineable;
 use smallvec::SmallVec;
 pub use taffy::style::{
-    AlignContent, AlignItems, AlignSelf, Display, FlexDirection, FlexWrap, JustifyContent,
-    Overflow, Position,
+    AlignContent, AlignItems, AlignSelf, Display, FlexDirection, FlexWrap, GridAutoFlow,
+    JustifyContent, Overflow,
 };
+use util::{debug_panic, ResultExt};
 
 #[cfg(debug_assertions)]
 /// Use this struct for interfacing with the 'debug_below' styling from your own elements.
@@ -77,7 +79,7 @@ pub struct Style {
     pub align_content: Option<AlignContent>,
     /// How should contained within this item be aligned in the main/inline axis
     pub justify_content: Option<JustifyContent>,
-    /// How large should the gaps between items in a flex container be?
+    /// How large should the gaps between items in a flex or grid container be?
     #[refineable]
     pub gap: Size<DefiniteLength>,
 
@@ -93,12 +95,38 @@ pub struct Style {
     /// The relative rate at which this item shrinks when it is contracting to fit into space, 1.0 is the default value, and this value must be positive.
     pub flex_shrink: f32,
 
+    // Grid properties
+    /// The sizes of the grid's columns
+    pub grid_template_columns: Vec<GridTemplate>,
+    /// The sizes of the grid's rows
+    pub grid_template_rows: Vec<GridTemplate>,
+    /// How items without an explicit place are placed into the grid
+    pub grid_auto_flow: GridAutoFlow,
+    /// Which of its grid's columns this item lies between
+    pub grid_column: GridLines,
+    /// Which of its grid's rows this item lies between
+    pub grid_row: GridLines,
+
     /// The fill color of this element
     pub background: Option<Fill>,
 
+    /// The opacity of this element and everything inside it, from 0 (invisible) to 1 (opaque)
+    pub opacity: Option<f32>,
+
+    /// The transform applied to this element and everything inside it when painted
+    pub transform: Option<Transform>,
+
     /// The border color of this element
     pub border_color: Option<Hsla>,
 
+    /// The colors of individual edges of this element's border, overriding `border_color`
+    #[refineable]
+    pub border_colors: Edges<Option<Hsla>>,
+
+    /// Whether each edge of this element's border is solid, dashed or dotted
+    #[refineable]
+    pub border_styles: Edges<BorderStyle>,
+
     /// The radius of the corners of this element
     #[refineable]
     pub corner_radii: Corners<AbsoluteLength>,
@@ -106,6 +134,9 @@ pub struct Style {
     /// Box Shadow of the element
     pub box_shadow: SmallVec<[BoxShadow; 2]>,
 
+    /// The filters applied, in order, to this element and everything inside it
+    pub filter: SmallVec<[Filter; 2]>,
+
     /// The text style of this element
     pub text: TextStyleRefinement,
 
@@ -140,8 +171,21 @@ pub enum Visibility {
     Hidden,
 }
 
+/// The style of an edge of an element's border, similar to the CSS property `border-style`
+#[derive(Default, Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[repr(u32)]
+pub enum BorderStyle {
+    /// The edge is a single unbroken line.
+    #[default]
+    Solid,
+    /// The edge is a series of dashes, each three times as long as the edge is wide.
+    Dashed,
+    /// The edge is a series of round dots, as wide as the edge.
+    Dotted,
+}
+
 /// The possible values of the box-shadow property
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct BoxShadow {
     /// What color should the shadow have?
     pub color: Hsla,
@@ -151,6 +195,91 @@ pub struct BoxShadow {
     pub blur_radius: Pixels,
     /// How much should the shadow spread?
     pub spread_radius: Pixels,
+    /// Should the shadow be painted inside the element's bounds rather than behind it?
+    pub inset: bool,
+}
+
+impl BoxShadow {
+    /// Paints the shadow inside the element's bounds rather than behind it.
+    pub fn inset(mut self) -> Self {
+        self.inset = true;
+        self
+    }
+}
+
+/// A graphical effect applied to an element and everything inside it, similar to a CSS
+/// filter function. The element is painted into an intermediate layer, which is then
+/// filtered, so the element also forms its own stacking context.
+///
+/// Filters are rendered on macOS and by the software renderer behind headless windows and
+/// frame dumps. The Vulkan renderer on Windows doesn't support them yet: painting a
+/// filtered element there panics in debug builds, and logs an error and paints the element
+/// unfiltered in release builds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Filter {
+    /// Blurs the content with a gaussian of the given standard deviation.
+    Blur(Pixels),
+    /// Scales the brightness, where 0 is black and 1 leaves the content unchanged.
+    Brightness(f32),
+    /// Scales the contrast, where 0 is solid gray and 1 leaves the content unchanged.
+    Contrast(f32),
+    /// Scales the saturation, where 0 is fully desaturated and 1 leaves the content unchanged.
+    Saturate(f32),
+    /// Desaturates the content, from 0 leaving it unchanged to 1 making it fully gray.
+    Grayscale(f32),
+    /// Rotates the hue of the content by the given angle, in degrees.
+    HueRotate(f32),
+}
+
+impl Filter {
+    /// The change this filter makes to colors, which is the identity for blurs.
+    pub(crate) fn color_matrix(&self) -> ColorMatrix {
+        match *self {
+            Filter::Blur(_) => ColorMatrix::identity(),
+            Filter::Brightness(amount) => ColorMatrix::scale(amount.max(0.), 0.),
+            Filter::Contrast(amount) => {
+                let amount = amount.max(0.);
+                ColorMatrix::scale(amount, 0.5 - 0.5 * amount)
+            }
+            Filter::Saturate(amount) => ColorMatrix::saturate(amount.max(0.)),
+            Filter::Grayscale(amount) => ColorMatrix::saturate(1. - amount.clamp(0., 1.)),
+            Filter::HueRotate(degrees) => ColorMatrix::hue_rotate(degrees.to_radians()),
+        }
+    }
+}
+
+/// What an element's painting is masked by: everything painted inside the mask's bounds is
+/// faded by the mask's alpha at that point, and everything outside is hidden.
+#[derive(Clone, Debug)]
+pub enum MaskSource {
+    /// The alpha of an image, stretched to fill the mask's bounds.
+    Image(ImageSource),
+    /// The alpha of a color or gradient filling the mask's bounds.
+    Fill(Fill),
+}
+
+impl From<ImageSource> for MaskSource {
+    fn from(image: ImageSource) -> Self {
+        Self::Image(image)
+    }
+}
+
+impl From<Fill> for MaskSource {
+    fn from(fill: Fill) -> Self {
+        Self::Fill(fill)
+    }
+}
+
+impl From<LinearGradient> for MaskSource {
+    fn from(gradient: LinearGradient) -> Self {
+        Self::Fill(gradient.into())
+    }
+}
+
+impl From<RadialGradient> for MaskSource {
+    fn from(gradient: RadialGradient) -> Self {
+        Self::Fill(gradient.into())
+    }
 }
 
 /// How to handle whitespace in text
@@ -313,6 +442,69 @@ impl Style {
         }
     }
 
+    /// The part of the given bounds inside the element's visible border, along with the radii
+    /// of its corners, which are rounded less by however much the border is wider there.
+    pub(crate) fn padding_box(
+        &self,
+        bounds: Bounds<Pixels>,
+        rem_size: Pixels,
+    ) -> (Bounds<Pixels>, Corners<Pixels>) {
+        let corner_radii = self.corner_radii.to_pixels(bounds.size, rem_size);
+        if !self.is_border_visible() {
+            return (bounds, corner_radii);
+        }
+
+        let border_widths = self.border_widths.to_pixels(rem_size);
+        let padding_box = Bounds::from_corners(
+            bounds.origin + point(border_widths.left, border_widths.top),
+            bounds.lower_right() - point(border_widths.right, border_widths.bottom),
+        );
+        let inset = |radius: Pixels, a: Pixels, b: Pixels| (radius - a.max(b)).max(Pixels::ZERO);
+        let corner_radii = Corners {
+            top_left: inset(corner_radii.top_left, border_widths.top, border_widths.left),
+            top_right: inset(
+                corner_radii.top_right,
+                border_widths.top,
+                border_widths.right,
+            ),
+            bottom_right: inset(
+                corner_radii.bottom_right,
+                border_widths.bottom,
+                border_widths.right,
+            ),
+            bottom_left: inset(
+                corner_radii.bottom_left,
+                border_widths.bottom,
+                border_widths.left,
+            ),
+        };
+        (padding_box, corner_radii)
+    }
+
+    /// Get the bounds of this element's content, inside its border and padding, based on the given bounds.
+    pub(crate) fn content_bounds(
+        &self,
+        bounds: Bounds<Pixels>,
+        rem_size: Pixels,
+    ) -> Bounds<Pixels> {
+        let border_widths = self.border_widths.to_pixels(rem_size);
+        let padding = self
+            .padding
+            .to_pixels(bounds.size.map(Into::into), rem_size);
+        Bounds::from_corners(
+            bounds.origin
+                + point(
+                    border_widths.left + padding.left,
+                    border_widths.top + padding.top,
+                ),
+            bounds.lower_right()
+                - point(
+                    border_widths.right + padding.right,
+                    border_widths.bottom + padding.bottom,
+                ),
+        )
+    }
+
     /// Get the content mask for this element style, based on the given bounds.
     /// If the element does not hide it's overflow, this will return `None`.
     pub fn overflow_mask(
@@ -326,18 +518,9 @@ impl Style {
                 y: Overflow::Visible,
             } => None,
             _ => {
-                let mut min = bounds.origin;
-                let mut max = bounds.lower_right();
-
-                if self
-                    .border_color
-                    .map_or(false, |color| !color.is_transparent())
-                {
-                    min.x += self.border_widths.left.to_pixels(rem_size);
-                    max.x -= self.border_widths.right.to_pixels(rem_size);
-                    min.y += self.border_widths.top.to_pixels(rem_size);
-                    max.y -= self.border_widths.bottom.to_pixels(rem_size);
-                }
+                let (padding_box, corner_radii) = self.padding_box(bounds, rem_size);
+                let min = padding_box.origin;
+                let max = padding_box.lower_right();
 
                 let bounds = match (
                     self.overflow.x == Overflow::Visible,
@@ -355,11 +538,19 @@ impl Style {
                         point(bounds.origin.x, min.y),
                         point(bounds.lower_right().x, max.y),
                     ),
-                    // both hidden
-                    (false, false) => Bounds::from_corners(min, max),
+                    // both hidden, the only case where the corners can be rounded
+                    (false, false) => {
+                        return Some(ContentMask {
+                            bounds: Bounds::from_corners(min, max),
+                            corner_radii,
+                        });
+                    }
                 };
 
-                Some(ContentMask { bounds })
+                Some(ContentMask {
+                    bounds,
+                    ..Default::default()
+                })
             }
         }
     }
@@ -370,6 +561,28 @@ impl Style {
         bounds: Bounds<Pixels>,
         cx: &mut ElementContext,
         continuation: impl FnOnce(&mut ElementContext),
+    ) {
+        let filters: &[Filter] = if self.filter.is_empty() || cx.supports_layers() {
+            &self.filter
+        } else {
+            debug_panic!(
+                "filters can't be painted, as the renderer of this window doesn't support layers"
+            );
+            &[]
+        };
+        cx.with_filters(filters, |cx| {
+            cx.with_opacity(self.opacity, |cx| {
+                self.paint_with_opacity(bounds, cx, continuation)
+            })
+        })
+        .log_err();
+    }
+
+    fn paint_with_opacity(
+        &self,
+        bounds: Bounds<Pixels>,
+        cx: &mut ElementContext,
+        continuation: impl FnOnce(&mut ElementContext),
     ) {
         #[cfg(debug_assertions)]
         if self.debug_below {
@@ -383,29 +596,49 @@ impl Style {
 
         let rem_size = cx.rem_size();
 
+        let (inset_shadows, outer_shadows): (SmallVec<[BoxShadow; 2]>, SmallVec<[BoxShadow; 2]>) =
+            self.box_shadow
+                .iter()
+                .cloned()
+                .partition(|shadow| shadow.inset);
+
         cx.with_z_index(0, |cx| {
             cx.paint_shadows(
                 bounds,
                 self.corner_radii.to_pixels(bounds.size, rem_size),
-                &self.box_shadow,
+                &outer_shadows,
             );
         });
 
-        let background_color = self.background.as_ref().and_then(Fill::color);
-        if background_color.map_or(false, |color| !color.is_transparent()) {
+        if let Some(background) = self
+            .background
+            .as_ref()
+            .filter(|background| !background.is_transparent())
+        {
             cx.with_z_index(1, |cx| {
-                let mut border_color = background_color.unwrap_or_default();
+                let mut border_color = background.color().unwrap_or_default();
                 border_color.a = 0.;
                 cx.paint_quad(quad(
                     bounds,
                     self.corner_radii.to_pixels(bounds.size, rem_size),
-                    background_color.unwrap_or_default(),
+                    background.clone(),
                     Edges::default(),
                     border_color,
                 ));
             });
         }
 
+        if !inset_shadows.is_empty() {
+            // Inset shadows sit above the background but below the children, and like CSS
+            // they're cast inside the border.
+            cx.with_z_index(1, |cx| {
+                cx.with_z_index(0, |cx| {
+                    let (padding_box, corner_radii) = self.padding_box(bounds, rem_size);
+                    cx.paint_shadows(padding_box, corner_radii, &inset_shadows);
+                });
+            });
+        }
+
         cx.with_z_index(2, |cx| {
             continuation(cx);
         });
@@ -436,22 +669,32 @@ impl Style {
                     bottom_bounds.upper_right(),
                 );
 
-                let mut background = self.border_color.unwrap_or_default();
+                let border_colors = self.border_colors();
+                let mut background = border_colors.top;
                 background.a = 0.;
                 let quad = quad(
                     bounds,
                     corner_radii,
                     background,
                     border_widths,
-                    self.border_color.unwrap_or_default(),
-                );
+                    transparent_black(),
+                )
+                .border_colors(border_colors)
+                .border_styles(self.border_styles);
 
-                cx.with_content_mask(Some(ContentMask { bounds: top_bounds }), |cx| {
-                    cx.paint_quad(quad.clone());
-                });
+                cx.with_content_mask(
+                    Some(ContentMask {
+                        bounds: top_bounds,
+                        ..Default::default()
+                    }),
+                    |cx| {
+                        cx.paint_quad(quad.clone());
+                    },
+                );
                 cx.with_content_mask(
                     Some(ContentMask {
                         bounds: right_bounds,
+                        ..Default::default()
                     }),
                     |cx| {
                         cx.paint_quad(quad.clone());
@@ -460,6 +703,7 @@ impl Style {
                 cx.with_content_mask(
                     Some(ContentMask {
                         bounds: bottom_bounds,
+                        ..Default::default()
                     }),
                     |cx| {
                         cx.paint_quad(quad.clone());
@@ -468,6 +712,7 @@ impl Style {
                 cx.with_content_mask(
                     Some(ContentMask {
                         bounds: left_bounds,
+                        ..Default::default()
                     }),
                     |cx| {
                         cx.paint_quad(quad);
@@ -482,10 +727,23 @@ impl Style {
         }
     }
 
+    /// The color of each edge of the border, falling back to `border_color` for edges
+    /// without a color of their own.
+    fn border_colors(&self) -> Edges<Hsla> {
+        self.border_colors
+            .map(|color| color.or(self.border_color).unwrap_or_default())
+    }
+
     fn is_border_visible(&self) -> bool {
-        self.border_color
-            .map_or(false, |color| !color.is_transparent())
-            && self.border_widths.any(|length| !length.is_zero())
+        let colors = self.border_colors();
+        [
+            (colors.top, &self.border_widths.top),
+            (colors.right, &self.border_widths.right),
+            (colors.bottom, &self.border_widths.bottom),
+            (colors.left, &self.border_widths.left),
+        ]
+        .into_iter()
+        .any(|(color, width)| !color.is_transparent() && !width.is_zero())
     }
 }
 
@@ -520,10 +778,20 @@ impl Default for Style {
             flex_grow: 0.0,
             flex_shrink: 1.0,
             flex_basis: Length::Auto,
+            grid_template_columns: Vec::new(),
+            grid_template_rows: Vec::new(),
+            grid_auto_flow: GridAutoFlow::Row,
+            grid_column: GridLines::default(),
+            grid_row: GridLines::default(),
             background: None,
+            opacity: None,
+            transform: None,
             border_color: None,
+            border_colors: Edges::default(),
+            border_styles: Edges::default(),
             corner_radii: Corners::default(),
             box_shadow: Default::default(),
+            filter: Default::default(),
             text: TextStyleRefinement::default(),
             mouse_cursor: None,
             z_index: None,
@@ -551,10 +819,14 @@ pub struct UnderlineStyle {
 }
 
 /// The kinds of fill that can be applied to a shape.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Fill {
     /// A solid color fill.
     Color(Hsla),
+    /// A fill that blends between colors along a straight line.
+    LinearGradient(LinearGradient),
+    /// A fill that blends between colors outwards from a point.
+    RadialGradient(RadialGradient),
 }
 
 impl Fill {
@@ -562,6 +834,18 @@ impl Fill {
     pub fn color(&self) -> Option<Hsla> {
         match self {
             Fill::Color(color) => Some(*color),
+            Fill::LinearGradient(_) | Fill::RadialGradient(_) => None,
+        }
+    }
+
+    /// Returns true if painting this fill would have no visible effect.
+    pub fn is_transparent(&self) -> bool {
+        match self {
+            Fill::Color(color) => color.is_transparent(),
+            Fill::LinearGradient(LinearGradient { stops, .. })
+            | Fill::RadialGradient(RadialGradient { stops, .. }) => {
+                stops.iter().all(|stop| stop.color.is_transparent())
+            }
         }
     }
 }
@@ -578,6 +862,470 @@ impl From<Hsla> for Fill {
     }
 }
 
+impl From<LinearGradient> for Fill {
+    fn from(gradient: LinearGradient) -> Self {
+        Self::LinearGradient(gradient)
+    }
+}
+
+impl From<RadialGradient> for Fill {
+    fn from(gradient: RadialGradient) -> Self {
+        Self::RadialGradient(gradient)
+    }
+}
+
+/// The color space in which a gradient blends between its stops.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum ColorSpace {
+    /// Blend the gamma-encoded sRGB components, as browsers do by default.
+    #[default]
+    Srgb,
+    /// Blend linear-light sRGB components, which keeps blends between saturated colors bright.
+    LinearSrgb,
+    /// Blend in the perceptually uniform Oklab space, which avoids muddy midpoints.
+    Oklab,
+}
+
+/// A color at a given position along a gradient.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ColorStop {
+    /// The color at this stop.
+    pub color: Hsla,
+    /// How far along the gradient the stop lies, from 0 at the start to 1 at the end.
+    pub position: f32,
+}
+
+/// Creates a color stop at the given position, from 0 at the start of a gradient to 1 at its end.
+pub fn color_stop(color: impl Into<Hsla>, position: f32) -> ColorStop {
+    ColorStop {
+        color: color.into(),
+        position,
+    }
+}
+
+/// The most color stops a gradient can have, which is as many as renderers can paint.
+pub const MAX_GRADIENT_STOPS: usize = 4;
+
+/// A gradient that blends between colors along a line through the center of the shape,
+/// like a CSS `linear-gradient`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearGradient {
+    /// The direction the gradient runs in, in degrees clockwise from the top: 0 runs from
+    /// bottom to top and 90 from left to right.
+    pub angle: f32,
+    stops: SmallVec<[ColorStop; MAX_GRADIENT_STOPS]>,
+    /// The color space the stops are blended in.
+    pub color_space: ColorSpace,
+}
+
+/// Creates a linear gradient running at the given angle, in degrees clockwise from the top.
+/// Returns an error if given more than [`MAX_GRADIENT_STOPS`] stops.
+pub fn linear_gradient(
+    angle: f32,
+    stops: impl IntoIterator<Item = ColorStop>,
+) -> Result<LinearGradient> {
+    Ok(LinearGradient {
+        angle,
+        stops: gradient_stops(stops)?,
+        color_space: ColorSpace::default(),
+    })
+}
+
+impl LinearGradient {
+    /// The colors the gradient blends between.
+    pub fn stops(&self) -> &[ColorStop] {
+        &self.stops
+    }
+
+    /// Sets the color space the gradient's stops are blended in.
+    pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+}
+
+/// A gradient that blends between colors in circles around a center point, reaching its
+/// last stop at the corner of the shape farthest from the center, like a CSS
+/// `radial-gradient`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadialGradient {
+    /// The center of the gradient, relative to the shape's bounds: `(0, 0)` is the top left
+    /// corner and `(1, 1)` the bottom right.
+    pub center: Point<f32>,
+    stops: SmallVec<[ColorStop; MAX_GRADIENT_STOPS]>,
+    /// The color space the stops are blended in.
+    pub color_space: ColorSpace,
+}
+
+/// Creates a radial gradient around the given center, relative to the shape's bounds.
+/// Returns an error if given more than [`MAX_GRADIENT_STOPS`] stops.
+pub fn radial_gradient(
+    center: Point<f32>,
+    stops: impl IntoIterator<Item = ColorStop>,
+) -> Result<RadialGradient> {
+    Ok(RadialGradient {
+        center,
+        stops: gradient_stops(stops)?,
+        color_space: ColorSpace::default(),
+    })
+}
+
+impl RadialGradient {
+    /// The colors the gradient blends between.
+    pub fn stops(&self) -> &[ColorStop] {
+        &self.stops
+    }
+
+    /// Sets the color space the gradient's stops are blended in.
+    pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+}
+
+fn gradient_stops(
+    stops: impl IntoIterator<Item = ColorStop>,
+) -> Result<SmallVec<[ColorStop; MAX_GRADIENT_STOPS]>> {
+    let stops = stops
+        .into_iter()
+        .collect::<SmallVec<[ColorStop; MAX_GRADIENT_STOPS]>>();
+    anyhow::ensure!(
+        stops.len() <= MAX_GRADIENT_STOPS,
+        "gradients can have at most {MAX_GRADIENT_STOPS} color stops, but {} were given",
+        stops.len()
+    );
+    Ok(stops)
+}
+
+/// One bound of the range a grid track is sized within.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrackBreadth {
+    /// A fixed length, or a fraction of the grid container's size.
+    Definite(DefiniteLength),
+    /// A share of the space left over once the other tracks are sized, like the CSS `fr`
+    /// unit. As a minimum, it behaves like `Auto`.
+    Fraction(f32),
+    /// Sized to fit the track's items, then stretched to fill any remaining space.
+    Auto,
+    /// The smallest size that fits the track's items without overflowing.
+    MinContent,
+    /// The size the track's items would take up given unlimited space.
+    MaxContent,
+}
+
+impl From<DefiniteLength> for TrackBreadth {
+    fn from(length: DefiniteLength) -> Self {
+        Self::Definite(length)
+    }
+}
+
+impl From<AbsoluteLength> for TrackBreadth {
+    fn from(length: AbsoluteLength) -> Self {
+        Self::Definite(length.into())
+    }
+}
+
+impl From<Pixels> for TrackBreadth {
+    fn from(pixels: Pixels) -> Self {
+        Self::Definite(pixels.into())
+    }
+}
+
+impl From<Rems> for TrackBreadth {
+    fn from(rems: Rems) -> Self {
+        Self::Definite(rems.into())
+    }
+}
+
+/// The size of a grid column or row, as the range it's sized within.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GridTrack {
+    /// The smallest the track may be.
+    pub min: TrackBreadth,
+    /// The largest the track may grow.
+    pub max: TrackBreadth,
+}
+
+impl GridTrack {
+    /// A track sized to fit its items, like the CSS `auto` track size.
+    pub fn auto() -> Self {
+        Self {
+            min: TrackBreadth::Auto,
+            max: TrackBreadth::Auto,
+        }
+    }
+}
+
+impl From<TrackBreadth> for GridTrack {
+    fn from(breadth: TrackBreadth) -> Self {
+        match breadth {
+            TrackBreadth::Fraction(fraction) => fr(fraction),
+            breadth => Self {
+                min: breadth,
+                max: breadth,
+            },
+        }
+    }
+}
+
+impl From<DefiniteLength> for GridTrack {
+    fn from(length: DefiniteLength) -> Self {
+        TrackBreadth::from(length).into()
+    }
+}
+
+impl From<AbsoluteLength> for GridTrack {
+    fn from(length: AbsoluteLength) -> Self {
+        TrackBreadth::from(length).into()
+    }
+}
+
+impl From<Pixels> for GridTrack {
+    fn from(pixels: Pixels) -> Self {
+        TrackBreadth::from(pixels).into()
+    }
+}
+
+impl From<Rems> for GridTrack {
+    fn from(rems: Rems) -> Self {
+        TrackBreadth::from(rems).into()
+    }
+}
+
+/// A track that takes the given share of the grid's leftover space, like the CSS `fr` unit.
+pub fn fr(fraction: f32) -> GridTrack {
+    GridTrack {
+        min: TrackBreadth::Auto,
+        max: TrackBreadth::Fraction(fraction),
+    }
+}
+
+/// A track sized between the given bounds, like the CSS `minmax()` function.
+pub fn minmax(min: impl Into<TrackBreadth>, max: impl Into<TrackBreadth>) -> GridTrack {
+    GridTrack {
+        min: min.into(),
+        max: max.into(),
+    }
+}
+
+/// How many times a run of grid tracks is repeated, like the first argument of the CSS
+/// `repeat()` function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridRepetition {
+    /// Repeat the tracks this many times.
+    Count(u16),
+    /// Repeat the tracks as many times as fit in the grid container.
+    AutoFill,
+    /// Like `AutoFill`, but repetitions left without items collapse to nothing.
+    AutoFit,
+}
+
+impl From<u16> for GridRepetition {
+    fn from(count: u16) -> Self {
+        Self::Count(count)
+    }
+}
+
+/// An entry in a grid's list of columns or rows: either a single track or a run of
+/// repeated tracks.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GridTemplate {
+    /// A single track.
+    Track(GridTrack),
+    /// A run of tracks, repeated as given.
+    Repeat(GridRepetition, SmallVec<[GridTrack; 2]>),
+}
+
+impl From<GridTrack> for GridTemplate {
+    fn from(track: GridTrack) -> Self {
+        Self::Track(track)
+    }
+}
+
+impl From<TrackBreadth> for GridTemplate {
+    fn from(breadth: TrackBreadth) -> Self {
+        Self::Track(breadth.into())
+    }
+}
+
+impl From<DefiniteLength> for GridTemplate {
+    fn from(length: DefiniteLength) -> Self {
+        Self::Track(length.into())
+    }
+}
+
+impl From<AbsoluteLength> for GridTemplate {
+    fn from(length: AbsoluteLength) -> Self {
+        Self::Track(length.into())
+    }
+}
+
+impl From<Pixels> for GridTemplate {
+    fn from(pixels: Pixels) -> Self {
+        Self::Track(pixels.into())
+    }
+}
+
+impl From<Rems> for GridTemplate {
+    fn from(rems: Rems) -> Self {
+        Self::Track(rems.into())
+    }
+}
+
+/// Repeats a run of grid tracks, like the CSS `repeat()` function.
+pub fn repeat(
+    repetition: impl Into<GridRepetition>,
+    tracks: impl IntoIterator<Item = GridTrack>,
+) -> GridTemplate {
+    GridTemplate::Repeat(repetition.into(), tracks.into_iter().collect())
+}
+
+/// Where a grid item's area starts or ends along one axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridPlacement {
+    /// Placed automatically, by the grid's auto-flow.
+    #[default]
+    Auto,
+    /// At the given grid line, counting from 1 at the start of the grid, or from -1 at its end.
+    Line(i16),
+    /// The given number of tracks away from the other end of the area.
+    Span(u16),
+}
+
+/// The lines a grid item's area lies between along one axis, like the CSS `grid-column` and
+/// `grid-row` properties.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GridLines {
+    /// Where the area starts.
+    pub start: GridPlacement,
+    /// Where the area ends.
+    pub end: GridPlacement,
+}
+
+impl GridLines {
+    /// An area between the given grid lines.
+    pub fn between(start: i16, end: i16) -> Self {
+        Self {
+            start: GridPlacement::Line(start),
+            end: GridPlacement::Line(end),
+        }
+    }
+
+    /// An automatically placed area spanning the given number of tracks.
+    pub fn span(tracks: u16) -> Self {
+        Self {
+            start: GridPlacement::Auto,
+            end: GridPlacement::Span(tracks),
+        }
+    }
+}
+
+/// How an element is positioned, like the CSS `position` property.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Position {
+    /// Laid out in the normal flow, then moved by its `inset`.
+    #[default]
+    Relative,
+    /// Taken out of the normal flow, and placed by its `inset` within its parent.
+    Absolute,
+    /// Laid out in the normal flow, then kept at least its `inset` away from the edges of its
+    /// nearest scroll container for as long as its parent is in view, like a section header that
+    /// stays visible while its section scrolls past.
+    Sticky,
+}
+
+/// A 2D transform of an element and everything inside it, like the CSS `transform` property.
+/// It only affects painting and hit-testing: the element keeps its place in the layout.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    /// How far to move the element.
+    pub translate: Point<Pixels>,
+    /// The clockwise rotation of the element, in degrees.
+    pub rotate: f32,
+    /// The angles to skew the element by along the x and y axes, in degrees.
+    pub skew: Point<f32>,
+    /// The factors to scale the element by along each axis.
+    pub scale: Size<f32>,
+    /// The point the element is rotated, skewed and scaled around, relative to its bounds:
+    /// `(0, 0)` is the top left corner and `(1, 1)` the bottom right.
+    pub origin: Point<f32>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translate: Point::default(),
+            rotate: 0.,
+            skew: Point::default(),
+            scale: Size {
+                width: 1.,
+                height: 1.,
+            },
+            origin: point(0.5, 0.5),
+        }
+    }
+}
+
+impl Transform {
+    /// Sets how far to move the element.
+    pub fn translate(mut self, offset: Point<Pixels>) -> Self {
+        self.translate = offset;
+        self
+    }
+
+    /// Sets the clockwise rotation of the element, in degrees.
+    pub fn rotate(mut self, degrees: f32) -> Self {
+        self.rotate = degrees;
+        self
+    }
+
+    /// Sets the angles to skew the element by along the x and y axes, in degrees.
+    pub fn skew(mut self, x_degrees: f32, y_degrees: f32) -> Self {
+        self.skew = point(x_degrees, y_degrees);
+        self
+    }
+
+    /// Sets the factor to scale the element by along both axes.
+    pub fn scale(mut self, factor: f32) -> Self {
+        self.scale = Size {
+            width: factor,
+            height: factor,
+        };
+        self
+    }
+
+    /// Sets the factors to scale the element by along each axis.
+    pub fn scale_xy(mut self, x: f32, y: f32) -> Self {
+        self.scale = Size {
+            width: x,
+            height: y,
+        };
+        self
+    }
+
+    /// Sets the point the element is transformed around, relative to its bounds.
+    pub fn origin(mut self, origin: Point<f32>) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// The matrix for this transform of an element with the given bounds. The element is
+    /// scaled, then skewed, then rotated around its origin, and finally translated.
+    pub fn to_matrix(&self, bounds: Bounds<Pixels>) -> TransformationMatrix {
+        let origin = point(
+            bounds.origin.x + bounds.size.width * self.origin.x,
+            bounds.origin.y + bounds.size.height * self.origin.y,
+        );
+        TransformationMatrix::unit()
+            .translate(origin + self.translate)
+            .rotate(self.rotate.to_radians())
+            .skew(point(self.skew.x.to_radians(), self.skew.y.to_radians()))
+            .scale(self.scale)
+            .translate(point(-origin.x, -origin.y))
+    }
+}
+
 impl From<Rgba> for Fill {
     fn from(color: Rgba) -> Self {
         Self::Color(color.into())
@@ -723,9 +1471,590 @@ pub fn combine_highlights(
 
 #[cfg(test)]
 mod tests {
-    use crate::{blue, green, red, yellow};
+    use crate::{
+        blue, canvas, div, fill, green, px, red, size, yellow, InteractiveElement as _,
+        IntoElement, LayerMask, Modifiers, MouseButton, MouseDownEvent, MouseMoveEvent,
+        ParentElement as _, Path, PrimitiveBatch, Render, ScaledPixels, ScrollDelta,
+        ScrollWheelEvent, StatefulInteractiveElement as _, TestAppContext, ViewContext,
+    };
 
     use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    struct FadedPanel;
+
+    impl Render for FadedPanel {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div().size_full().opacity(0.5).bg(red()).child(
+                div()
+                    .size(px(8.))
+                    .opacity(0.5)
+                    .bg(blue())
+                    .border_1()
+                    .border_color(green())
+                    .child(canvas(|bounds, cx| {
+                        cx.with_opacity(Some(0.5), |cx| {
+                            cx.paint_underline(
+                                bounds.origin,
+                                px(4.),
+                                &UnderlineStyle {
+                                    thickness: px(1.),
+                                    color: Some(yellow()),
+                                    wavy: false,
+                                },
+                            );
+                        });
+                        assert_eq!(cx.opacity(), 0.25);
+                    })),
+            )
+        }
+    }
+
+    #[crate::test]
+    fn test_opacity_applies_to_subtree(cx: &mut TestAppContext) {
+        let (_, cx) = cx.add_window_view(|_| FadedPanel);
+        cx.simulate_resize(size(px(16.), px(16.)));
+
+        let (quad_alphas, underline_alpha) = cx.update(|cx| {
+            let scene = &cx.window.rendered_frame.scene;
+            let mut quad_alphas = scene
+                .quads
+                .iter()
+                .map(|quad| (quad.background.solid.a, quad.border_colors.top.a))
+                .collect::<Vec<_>>();
+            quad_alphas.dedup();
+            (quad_alphas, scene.underlines[0].color.a)
+        });
+        assert_eq!(quad_alphas, [(0.5, 0.), (0.25, 0.), (0., 0.25)]);
+        assert_eq!(underline_alpha, 0.125);
+    }
+
+    #[derive(Default)]
+    struct RotatedButton {
+        clicks: Vec<Point<Pixels>>,
+    }
+
+    impl Render for RotatedButton {
+        fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+            div().size_full().child(
+                div()
+                    .w(px(20.))
+                    .h(px(10.))
+                    .bg(red())
+                    .transform(Transform::default().rotate(90.))
+                    .on_mouse_down(
+                        MouseButton::Left,
+                        cx.listener(|this, event: &MouseDownEvent, _| {
+                            this.clicks.push(event.position)
+                        }),
+                    ),
+            )
+        }
+    }
+
+    #[crate::test]
+    fn test_transform_applies_to_painting_and_hit_testing(cx: &mut TestAppContext) {
+        let (view, cx) = cx.add_window_view(|_| RotatedButton::default());
+        cx.simulate_resize(size(px(32.), px(32.)));
+
+        // Rotating the 20x10 button around its center makes it 10 wide and 20 tall.
+        let painted_bounds = cx.update(|cx| {
+            let quad = &cx.window.rendered_frame.scene.quads[0];
+            let bounds = quad.transform.apply_to_scaled_bounds(&quad.bounds);
+            [
+                bounds.origin.x,
+                bounds.origin.y,
+                bounds.size.width,
+                bounds.size.height,
+            ]
+            .map(|value| value.0.round())
+        });
+        assert_eq!(painted_bounds, [10., -10., 20., 40.]);
+
+        for position in [point(px(3.), px(5.)), point(px(10.), px(12.))] {
+            cx.simulate_event(MouseDownEvent {
+                button: MouseButton::Left,
+                position,
+                modifiers: Modifiers::default(),
+                click_count: 1,
+            });
+        }
+        let clicks = view.update(cx, |view, _| view.clicks.clone());
+        assert_eq!(clicks, [point(px(10.), px(12.))]);
+    }
+
+    struct RotatedCard;
+
+    impl Render for RotatedCard {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div().size_full().child(
+                div()
+                    .group("card")
+                    .w(px(20.))
+                    .h(px(10.))
+                    .transform(Transform::default().rotate(90.))
+                    .child(
+                        div()
+                            .size_full()
+                            .bg(red())
+                            .group_hover("card", |style| style.bg(blue())),
+                    ),
+            )
+        }
+    }
+
+    #[crate::test]
+    fn test_group_hover_hit_tests_through_transform(cx: &mut TestAppContext) {
+        let (_, cx) = cx.add_window_view(|_| RotatedCard);
+        cx.simulate_resize(size(px(32.), px(32.)));
+
+        // The first point is only inside the untransformed card, the second only inside the rotated one.
+        let mut colors = Vec::new();
+        for position in [point(px(3.), px(5.)), point(px(10.), px(12.))] {
+            cx.simulate_event(MouseMoveEvent {
+                position,
+                pressed_button: None,
+                modifiers: Modifiers::default(),
+            });
+            colors.push(cx.update(|cx| cx.window.rendered_frame.scene.quads[0].background.solid));
+        }
+        assert_eq!(colors, [red(), blue()]);
+    }
+
+    #[derive(Default)]
+    struct RoundedButton {
+        clicks: Vec<Point<Pixels>>,
+    }
+
+    impl Render for RoundedButton {
+        fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+            div()
+                .size(px(20.))
+                .rounded(px(10.))
+                .overflow_hidden()
+                .child(div().size_full().bg(red()).on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(|this, event: &MouseDownEvent, _| this.clicks.push(event.position)),
+                ))
+        }
+    }
+
+    #[crate::test]
+    fn test_rounded_overflow_clips_painting_and_hit_testing(cx: &mut TestAppContext) {
+        let (view, cx) = cx.add_window_view(|_| RoundedButton::default());
+        cx.simulate_resize(size(px(32.), px(32.)));
+
+        let (mask_radii, scale_factor) = cx.update(|cx| {
+            let quad = &cx.window.rendered_frame.scene.quads[0];
+            (quad.content_mask.corner_radii, cx.scale_factor())
+        });
+        assert_eq!(mask_radii, Corners::all(ScaledPixels(10. * scale_factor)));
+
+        for position in [point(px(1.), px(1.)), point(px(10.), px(10.))] {
+            cx.simulate_event(MouseDownEvent {
+                button: MouseButton::Left,
+                position,
+                modifiers: Modifiers::default(),
+                click_count: 1,
+            });
+        }
+        let clicks = view.update(cx, |view, _| view.clicks.clone());
+        assert_eq!(clicks, [point(px(10.), px(10.))]);
+    }
+
+    struct SunkenField;
+
+    impl Render for SunkenField {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div()
+                .size(px(20.))
+                .bg(red())
+                .border_2()
+                .border_color(blue())
+                .shadow_inner()
+        }
+    }
+
+    #[crate::test]
+    fn test_inset_shadow_is_painted_inside_border_over_background(cx: &mut TestAppContext) {
+        let (_, cx) = cx.add_window_view(|_| SunkenField);
+        cx.simulate_resize(size(px(32.), px(32.)));
+
+        let (batches, shadow_bounds, scale_factor) = cx.update(|cx| {
+            let scene = &cx.window.rendered_frame.scene;
+            let batches = scene
+                .batches()
+                .map(|batch| match batch {
+                    PrimitiveBatch::Shadows(_) => "shadows",
+                    PrimitiveBatch::Quads(_) => "quads",
+                    _ => "other",
+                })
+                .collect::<Vec<_>>();
+            let shadow = &scene.shadows[0];
+            assert!(shadow.inset);
+            (batches, shadow.bounds, cx.scale_factor())
+        });
+        assert_eq!(batches, ["quads", "shadows", "quads"]);
+        assert_eq!(
+            shadow_bounds,
+            Bounds {
+                origin: point(px(2.), px(2.)),
+                size: size(px(16.), px(16.)),
+            }
+            .scale(scale_factor)
+        );
+    }
+
+    struct DropZone;
+
+    impl Render for DropZone {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div()
+                .size(px(20.))
+                .border_2()
+                .border_dashed()
+                .border_b_style(BorderStyle::Dotted)
+                .border_l_color(green())
+        }
+    }
+
+    #[crate::test]
+    fn test_per_edge_border_colors_and_styles(cx: &mut TestAppContext) {
+        let (_, cx) = cx.add_window_view(|_| DropZone);
+        cx.simulate_resize(size(px(32.), px(32.)));
+
+        let quads = cx.update(|cx| {
+            cx.window
+                .rendered_frame
+                .scene
+                .quads
+                .iter()
+                .map(|quad| (quad.border_colors, quad.border_styles))
+                .collect::<Vec<_>>()
+        });
+        // Only the left edge has a color, but that's enough for the border to be painted.
+        assert!(!quads.is_empty());
+        for (colors, styles) in quads {
+            assert_eq!(colors.left, green());
+            assert_eq!(colors.top, Hsla::default());
+            assert_eq!(
+                styles,
+                Edges {
+                    bottom: BorderStyle::Dotted,
+                    ..Edges::all(BorderStyle::Dashed)
+                }
+            );
+        }
+    }
+
+    struct BlurredBadge;
+
+    impl Render for BlurredBadge {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div()
+                .size_full()
+                .bg(blue())
+                .child(div().size(px(8.)).bg(red()).blur(px(2.)).grayscale(1.))
+        }
+    }
+
+    #[crate::test]
+    fn test_filters_paint_subtree_into_a_layer(cx: &mut TestAppContext) {
+        let (_, cx) = cx.add_window_view(|_| BlurredBadge);
+        cx.simulate_resize(size(px(16.), px(16.)));
+
+        let (filters, batches) = cx.update(|cx| {
+            let scene = &cx.window.rendered_frame.scene;
+            let batches = scene
+                .batches()
+                .map(|batch| match batch {
+                    PrimitiveBatch::Quads(quads) => format!("quads {}", quads.len()),
+                    PrimitiveBatch::BeginFilter(_) => "begin".into(),
+                    PrimitiveBatch::EndFilter(_) => "end".into(),
+                    _ => "other".into(),
+                })
+                .collect::<Vec<_>>();
+            (scene.filters.clone(), batches)
+        });
+        assert_eq!(filters.len(), 1);
+        let effect = &filters[0].effect;
+        assert_eq!(effect.color_matrix, ColorMatrix::saturate(0.));
+        assert!(effect.blur_radius.0 >= 2.);
+        // Only the blurred element is painted into the layer, not its parent's background.
+        assert_eq!(batches, ["quads 1", "begin", "quads 1", "end"]);
+    }
+
+    struct TintedBadge;
+
+    impl Render for TintedBadge {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div().size_full().child(
+                div()
+                    .size(px(8.))
+                    .bg(red())
+                    .grayscale(1.)
+                    .blur(px(2.))
+                    .hue_rotate(180.),
+            )
+        }
+    }
+
+    #[crate::test]
+    fn test_blur_after_color_change_gets_its_own_layer(cx: &mut TestAppContext) {
+        let (_, cx) = cx.add_window_view(|_| TintedBadge);
+        cx.simulate_resize(size(px(16.), px(16.)));
+
+        let effects = cx.update(|cx| {
+            cx.window
+                .rendered_frame
+                .scene
+                .filters
+                .iter()
+                .map(|filter| (filter.effect.blur_radius.0 > 0., filter.effect.color_matrix))
+                .collect::<Vec<_>>()
+        });
+        // The blur and the hue rotation after it wrap the layer that's made gray.
+        assert_eq!(
+            effects,
+            [
+                (true, ColorMatrix::hue_rotate(180_f32.to_radians())),
+                (false, ColorMatrix::saturate(0.)),
+            ]
+        );
+    }
+
+    struct MaskedAvatars;
+
+    impl Render for MaskedAvatars {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            div().size_full().child(
+                canvas(|bounds, cx| {
+                    let bounds = *bounds;
+                    let mut triangle = Path::new(bounds.origin);
+                    triangle.line_to(bounds.upper_right());
+                    triangle.line_to(bounds.lower_left());
+                    cx.with_clip_path(triangle, |cx| cx.paint_quad(fill(bounds, red())))
+                        .unwrap();
+                    cx.with_mask(
+                        bounds,
+                        linear_gradient(
+                            90.,
+                            [color_stop(black(), 0.), color_stop(transparent_black(), 1.)],
+                        )
+                        .unwrap(),
+                        |cx| cx.paint_quad(fill(bounds, blue())),
+                    )
+                    .unwrap();
+                })
+                .size_full(),
+            )
+        }
+    }
+
+    #[crate::test]
+    fn test_clip_paths_and_masks_paint_into_layers(cx: &mut TestAppContext) {
+        let (_, cx) = cx.add_window_view(|_| MaskedAvatars);
+        cx.simulate_resize(size(px(16.), px(16.)));
+
+        let (masks, batches) = cx.update(|cx| {
+            let scene = &cx.window.rendered_frame.scene;
+            let masks = scene
+                .filters
+                .iter()
+                .map(|filter| match &filter.mask {
+                    Some(LayerMask::Path(path)) => format!("path {}", path.vertices.len()),
+                    Some(LayerMask::Quad(quad)) => format!("quad {:?}", quad.background.tag),
+                    Some(LayerMask::Sprite(_)) => "sprite".into(),
+                    None => "none".into(),
+                })
+                .collect::<Vec<_>>();
+            let batches = scene
+                .batches()
+                .map(|batch| match batch {
+                    PrimitiveBatch::Quads(quads) => format!("quads {}", quads.len()),
+                    PrimitiveBatch::BeginFilter(_) => "begin".into(),
+                    PrimitiveBatch::EndFilter(_) => "end".into(),
+                    _ => "other".into(),
+                })
+                .collect::<Vec<_>>();
+            (masks, batches)
+        });
+        assert_eq!(masks, ["path 3", "quad LinearGradient"]);
+        assert_eq!(
+            batches,
+            ["begin", "quads 1", "end", "begin", "quads 1", "end"]
+        );
+    }
+
+    struct Dashboard {
+        cells: Rc<RefCell<Vec<Bounds<Pixels>>>>,
+    }
+
+    impl Render for Dashboard {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            let cell = |cells: &Rc<RefCell<Vec<Bounds<Pixels>>>>| {
+                let cells = cells.clone();
+                canvas(move |bounds, _| cells.borrow_mut().push(*bounds)).h(px(4.))
+            };
+            div()
+                .grid()
+                .w(px(110.))
+                .grid_template_columns([px(10.).into(), fr(1.).into(), fr(2.).into()])
+                .gap_x(px(5.))
+                .gap_y(px(2.))
+                .child(cell(&self.cells))
+                .child(cell(&self.cells))
+                .child(cell(&self.cells))
+                .child(cell(&self.cells).col_span(2))
+                .child(cell(&self.cells).col_start(-2))
+        }
+    }
+
+    #[crate::test]
+    fn test_grid_layout(cx: &mut TestAppContext) {
+        let cells = Rc::new(RefCell::new(Vec::new()));
+        let (_, cx) = cx.add_window_view({
+            let cells = cells.clone();
+            |_| Dashboard { cells }
+        });
+        cx.simulate_resize(size(px(200.), px(200.)));
+
+        let cells = cells.borrow();
+        let cells = cells[cells.len() - 5..]
+            .iter()
+            .map(|bounds| {
+                (
+                    bounds.origin.x.0,
+                    bounds.origin.y.0,
+                    bounds.size.width.0,
+                    bounds.size.height.0,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            [
+                (0., 0., 10., 4.),
+                (15., 0., 30., 4.),
+                (50., 0., 60., 4.),
+                (0., 6., 45., 4.),
+                (50., 6., 60., 4.),
+            ]
+        );
+    }
+
+    struct StickyHeaders {
+        headers: Rc<RefCell<Vec<(usize, Bounds<Pixels>)>>>,
+    }
+
+    impl Render for StickyHeaders {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            let section = |ix: usize| {
+                let headers = self.headers.clone();
+                div()
+                    .h(px(40.))
+                    .child(
+                        div().sticky().top_0().h(px(10.)).child(
+                            canvas(move |bounds, _| headers.borrow_mut().push((ix, *bounds)))
+                                .size_full(),
+                        ),
+                    )
+                    .child(div().h(px(30.)))
+            };
+            div()
+                .id("sections")
+                .size_full()
+                .overflow_y_scroll()
+                .child(section(0))
+                .child(section(1))
+        }
+    }
+
+    #[crate::test]
+    fn test_sticky_elements_stay_within_scroll_viewport(cx: &mut TestAppContext) {
+        let headers = Rc::new(RefCell::new(Vec::new()));
+        let (_, cx) = cx.add_window_view({
+            let headers = headers.clone();
+            |_| StickyHeaders { headers }
+        });
+        cx.simulate_resize(size(px(100.), px(30.)));
+
+        let mut scroll_by = |delta: f32| {
+            headers.borrow_mut().clear();
+            cx.simulate_event(ScrollWheelEvent {
+                position: point(px(50.), px(15.)),
+                delta: ScrollDelta::Pixels(point(px(0.), px(-delta))),
+                ..Default::default()
+            });
+            cx.run_until_parked();
+            headers
+                .borrow()
+                .iter()
+                .map(|(ix, bounds)| (*ix, bounds.top().0))
+                .collect::<Vec<_>>()
+        };
+
+        // The first header sticks to the top while its section scrolls past.
+        assert_eq!(scroll_by(15.), [(0, 0.), (1, 25.)]);
+        // Then it's pushed out by the end of its section, ahead of the next header.
+        assert_eq!(scroll_by(20.), [(0, -5.), (1, 5.)]);
+        // Which sticks in turn.
+        assert_eq!(scroll_by(15.), [(0, -20.), (1, 0.)]);
+    }
+
+    struct StickyList {
+        painted: Rc<RefCell<Vec<(&'static str, Bounds<Pixels>)>>>,
+    }
+
+    impl Render for StickyList {
+        fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
+            let item = |name: &'static str| {
+                let painted = self.painted.clone();
+                canvas(move |bounds, _| painted.borrow_mut().push((name, *bounds))).size_full()
+            };
+            div().id("list").size_full().overflow_y_scroll().child(
+                div()
+                    .h(px(60.))
+                    .child(div().h(px(10.)).child(item("before")))
+                    .child(
+                        div()
+                            .child(div().sticky().top(px(2.)).h(px(10.)).child(item("header")))
+                            .child(div().h(px(40.)).child(item("after"))),
+                    ),
+            )
+        }
+    }
+
+    #[crate::test]
+    fn test_sticky_descendants_paint_in_document_order(cx: &mut TestAppContext) {
+        let painted = Rc::new(RefCell::new(Vec::new()));
+        let (_, cx) = cx.add_window_view({
+            let painted = painted.clone();
+            |_| StickyList { painted }
+        });
+        cx.simulate_resize(size(px(100.), px(30.)));
+        painted.borrow_mut().clear();
+        cx.simulate_event(ScrollWheelEvent {
+            position: point(px(50.), px(15.)),
+            delta: ScrollDelta::Pixels(point(px(0.), px(-20.))),
+            ..Default::default()
+        });
+        cx.run_until_parked();
+
+        // The header sticks within its parent, which isn't the scroll container, and is painted
+        // between the elements around it rather than over them.
+        assert_eq!(
+            painted
+                .borrow()
+                .iter()
+                .map(|(name, bounds)| (*name, bounds.top().0))
+                .collect::<Vec<_>>(),
+            [("before", -20.), ("header", 2.), ("after", 0.)]
+        );
+    }
 
     #[test]
     fn test_combine_highlights() {
@@ -815,4 +2144,19 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_gradients_reject_more_stops_than_can_be_painted() {
+        let stops =
+            |count: usize| (0..count).map(move |ix| color_stop(red(), ix as f32 / count as f32));
+        assert_eq!(
+            linear_gradient(90., stops(MAX_GRADIENT_STOPS))
+                .unwrap()
+                .stops()
+                .len(),
+            MAX_GRADIENT_STOPS
+        );
+        assert!(linear_gradient(90., stops(MAX_GRADIENT_STOPS + 1)).is_err());
+        assert!(radial_gradient(point(0.5, 0.5), stops(MAX_GRADIENT_STOPS + 1)).is_err());
+    }
 }
@@ -1,8 +1,10 @@
 use crate::{
-    self as gpui, hsla, point, px, relative, rems, AbsoluteLength, AlignItems, CursorStyle,
-    DefiniteLength, Fill, FlexDirection, FontWeight, Hsla, JustifyContent, Length, Position,
-    SharedString, StyleRefinement, Visibility, WhiteSpace,
+    self as gpui, hsla, point, px, relative, rems, AbsoluteLength, AlignItems, BorderStyle,
+    CursorStyle, DefiniteLength, Fill, Filter, FlexDirection, FontWeight, GridAutoFlow, GridLines,
+    GridPlacement, GridTemplate, Hsla, JustifyContent, Length, Pixels, Position, SharedString,
+    StyleRefinement, TrackBreadth, Transform, Visibility, WhiteSpace,
 };
+use crate::{minmax, repeat};
 use crate::{BoxShadow, TextStyleRefinement};
 use smallvec::{smallvec, SmallVec};
 use taffy::style::{Display, Overflow};
@@ -42,6 +44,14 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Sets the position of the element to `sticky`, so that it stays pinned by its `inset`
+    /// to the edges of its nearest scroll container while its parent is in view.
+    /// [Docs](https://tailwindcss.com/docs/position#sticky-positioning-elements)
+    fn sticky(mut self) -> Self {
+        self.style().position = Some(Position::Sticky);
+        self
+    }
+
     /// Sets the display type of the element to `block`.
     /// [Docs](https://tailwindcss.com/docs/display)
     fn block(mut self) -> Self {
@@ -56,6 +66,13 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Sets the display type of the element to `grid`.
+    /// [Docs](https://tailwindcss.com/docs/display)
+    fn grid(mut self) -> Self {
+        self.style().display = Some(Display::Grid);
+        self
+    }
+
     /// Sets the visibility of the element to `visible`.
     /// [Docs](https://tailwindcss.com/docs/visibility)
     fn visible(mut self) -> Self {
@@ -398,6 +415,128 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Sets the column tracks of a grid container, such as `[px(100.).into(), fr(1.).into()]`.
+    /// [Docs](https://tailwindcss.com/docs/grid-template-columns)
+    fn grid_template_columns(mut self, columns: impl IntoIterator<Item = GridTemplate>) -> Self {
+        self.style().grid_template_columns = Some(columns.into_iter().collect());
+        self
+    }
+
+    /// Sets the row tracks of a grid container, such as `[px(24.).into(), fr(1.).into()]`.
+    /// [Docs](https://tailwindcss.com/docs/grid-template-rows)
+    fn grid_template_rows(mut self, rows: impl IntoIterator<Item = GridTemplate>) -> Self {
+        self.style().grid_template_rows = Some(rows.into_iter().collect());
+        self
+    }
+
+    /// Sets a grid container to have the given number of equally sized columns.
+    /// [Docs](https://tailwindcss.com/docs/grid-template-columns)
+    fn grid_cols(self, count: u16) -> Self {
+        self.grid_template_columns([repeat(count, [minmax(px(0.), TrackBreadth::Fraction(1.))])])
+    }
+
+    /// Sets a grid container to have the given number of equally sized rows.
+    /// [Docs](https://tailwindcss.com/docs/grid-template-rows)
+    fn grid_rows(self, count: u16) -> Self {
+        self.grid_template_rows([repeat(count, [minmax(px(0.), TrackBreadth::Fraction(1.))])])
+    }
+
+    /// Sets the direction in which a grid container places items without an explicit
+    /// position to `row`.
+    /// [Docs](https://tailwindcss.com/docs/grid-auto-flow)
+    fn grid_flow_row(mut self) -> Self {
+        self.style().grid_auto_flow = Some(GridAutoFlow::Row);
+        self
+    }
+
+    /// Sets the direction in which a grid container places items without an explicit
+    /// position to `column`.
+    /// [Docs](https://tailwindcss.com/docs/grid-auto-flow)
+    fn grid_flow_col(mut self) -> Self {
+        self.style().grid_auto_flow = Some(GridAutoFlow::Column);
+        self
+    }
+
+    /// Sets a grid container to place items by row, filling earlier holes in the grid.
+    /// [Docs](https://tailwindcss.com/docs/grid-auto-flow)
+    fn grid_flow_row_dense(mut self) -> Self {
+        self.style().grid_auto_flow = Some(GridAutoFlow::RowDense);
+        self
+    }
+
+    /// Sets a grid container to place items by column, filling earlier holes in the grid.
+    /// [Docs](https://tailwindcss.com/docs/grid-auto-flow)
+    fn grid_flow_col_dense(mut self) -> Self {
+        self.style().grid_auto_flow = Some(GridAutoFlow::ColumnDense);
+        self
+    }
+
+    /// Sets the column lines a grid item starts and ends at.
+    /// [Docs](https://tailwindcss.com/docs/grid-column)
+    fn grid_column(mut self, lines: GridLines) -> Self {
+        self.style().grid_column = Some(lines);
+        self
+    }
+
+    /// Sets the row lines a grid item starts and ends at.
+    /// [Docs](https://tailwindcss.com/docs/grid-row)
+    fn grid_row(mut self, lines: GridLines) -> Self {
+        self.style().grid_row = Some(lines);
+        self
+    }
+
+    /// Sets a grid item to span the given number of columns.
+    /// [Docs](https://tailwindcss.com/docs/grid-column)
+    fn col_span(self, tracks: u16) -> Self {
+        self.grid_column(GridLines::span(tracks))
+    }
+
+    /// Sets a grid item to span the given number of rows.
+    /// [Docs](https://tailwindcss.com/docs/grid-row)
+    fn row_span(self, tracks: u16) -> Self {
+        self.grid_row(GridLines::span(tracks))
+    }
+
+    /// Sets the column line a grid item starts at. Negative lines count from the end.
+    /// [Docs](https://tailwindcss.com/docs/grid-column#starting-and-ending-lines)
+    fn col_start(mut self, line: i16) -> Self {
+        self.style()
+            .grid_column
+            .get_or_insert_with(Default::default)
+            .start = GridPlacement::Line(line);
+        self
+    }
+
+    /// Sets the column line a grid item ends at. Negative lines count from the end.
+    /// [Docs](https://tailwindcss.com/docs/grid-column#starting-and-ending-lines)
+    fn col_end(mut self, line: i16) -> Self {
+        self.style()
+            .grid_column
+            .get_or_insert_with(Default::default)
+            .end = GridPlacement::Line(line);
+        self
+    }
+
+    /// Sets the row line a grid item starts at. Negative lines count from the end.
+    /// [Docs](https://tailwindcss.com/docs/grid-row#starting-and-ending-lines)
+    fn row_start(mut self, line: i16) -> Self {
+        self.style()
+            .grid_row
+            .get_or_insert_with(Default::default)
+            .start = GridPlacement::Line(line);
+        self
+    }
+
+    /// Sets the row line a grid item ends at. Negative lines count from the end.
+    /// [Docs](https://tailwindcss.com/docs/grid-row#starting-and-ending-lines)
+    fn row_end(mut self, line: i16) -> Self {
+        self.style()
+            .grid_row
+            .get_or_insert_with(Default::default)
+            .end = GridPlacement::Line(line);
+        self
+    }
+
     /// Sets the background color of the element.
     fn bg<F>(mut self, fill: F) -> Self
     where
@@ -408,6 +547,107 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Sets the opacity of the element and everything inside it, from 0 (invisible) to 1 (opaque).
+    /// [Docs](https://tailwindcss.com/docs/opacity)
+    fn opacity(mut self, opacity: f32) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().opacity = Some(opacity);
+        self
+    }
+
+    /// Sets the transform of the element and everything inside it, such as a rotation or a
+    /// scale. The element keeps its place in the layout, but is painted and hit-tested as
+    /// transformed.
+    /// [Docs](https://tailwindcss.com/docs/transform)
+    fn transform(mut self, transform: Transform) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().transform = Some(transform);
+        self
+    }
+
+    /// Sets the filters applied, in order, to the element and everything inside it.
+    fn filter(mut self, filters: SmallVec<[Filter; 2]>) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().filter = Some(filters);
+        self
+    }
+
+    /// Adds a filter to those applied to the element and everything inside it.
+    fn add_filter(mut self, filter: Filter) -> Self
+    where
+        Self: Sized,
+    {
+        self.style()
+            .filter
+            .get_or_insert_with(SmallVec::new)
+            .push(filter);
+        self
+    }
+
+    /// Blurs the element and everything inside it, with a gaussian of the given standard deviation.
+    /// [Docs](https://tailwindcss.com/docs/blur)
+    fn blur(self, radius: impl Into<Pixels>) -> Self
+    where
+        Self: Sized,
+    {
+        self.add_filter(Filter::Blur(radius.into()))
+    }
+
+    /// Scales the brightness of the element and everything inside it, where 0 is black and 1
+    /// leaves it unchanged.
+    /// [Docs](https://tailwindcss.com/docs/brightness)
+    fn brightness(self, amount: f32) -> Self
+    where
+        Self: Sized,
+    {
+        self.add_filter(Filter::Brightness(amount))
+    }
+
+    /// Scales the contrast of the element and everything inside it, where 0 is solid gray and 1
+    /// leaves it unchanged.
+    /// [Docs](https://tailwindcss.com/docs/contrast)
+    fn contrast(self, amount: f32) -> Self
+    where
+        Self: Sized,
+    {
+        self.add_filter(Filter::Contrast(amount))
+    }
+
+    /// Scales the saturation of the element and everything inside it, where 0 is fully
+    /// desaturated and 1 leaves it unchanged.
+    /// [Docs](https://tailwindcss.com/docs/saturate)
+    fn saturate(self, amount: f32) -> Self
+    where
+        Self: Sized,
+    {
+        self.add_filter(Filter::Saturate(amount))
+    }
+
+    /// Desaturates the element and everything inside it, from 0 leaving it unchanged to 1
+    /// making it fully gray.
+    /// [Docs](https://tailwindcss.com/docs/grayscale)
+    fn grayscale(self, amount: f32) -> Self
+    where
+        Self: Sized,
+    {
+        self.add_filter(Filter::Grayscale(amount))
+    }
+
+    /// Rotates the hue of the element and everything inside it by the given angle, in degrees.
+    /// [Docs](https://tailwindcss.com/docs/hue-rotate)
+    fn hue_rotate(self, degrees: f32) -> Self
+    where
+        Self: Sized,
+    {
+        self.add_filter(Filter::HueRotate(degrees))
+    }
+
     /// Sets the border color of the element.
     fn border_color<C>(mut self, border_color: C) -> Self
     where
@@ -418,6 +658,119 @@ pub trait Styled: Sized {
         self
     }
 
+    /// Sets the color of the top edge of the element's border, overriding `border_color`.
+    fn border_t_color(mut self, color: impl Into<Hsla>) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().border_colors.top = Some(Some(color.into()));
+        self
+    }
+
+    /// Sets the color of the right edge of the element's border, overriding `border_color`.
+    fn border_r_color(mut self, color: impl Into<Hsla>) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().border_colors.right = Some(Some(color.into()));
+        self
+    }
+
+    /// Sets the color of the bottom edge of the element's border, overriding `border_color`.
+    fn border_b_color(mut self, color: impl Into<Hsla>) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().border_colors.bottom = Some(Some(color.into()));
+        self
+    }
+
+    /// Sets the color of the left edge of the element's border, overriding `border_color`.
+    fn border_l_color(mut self, color: impl Into<Hsla>) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().border_colors.left = Some(Some(color.into()));
+        self
+    }
+
+    /// Sets the style of every edge of the element's border.
+    /// [Docs](https://tailwindcss.com/docs/border-style)
+    fn border_style(mut self, style: BorderStyle) -> Self
+    where
+        Self: Sized,
+    {
+        let border_styles = &mut self.style().border_styles;
+        border_styles.top = Some(style);
+        border_styles.right = Some(style);
+        border_styles.bottom = Some(style);
+        border_styles.left = Some(style);
+        self
+    }
+
+    /// Draws every edge of the element's border as a solid line.
+    /// [Docs](https://tailwindcss.com/docs/border-style)
+    fn border_solid(self) -> Self
+    where
+        Self: Sized,
+    {
+        self.border_style(BorderStyle::Solid)
+    }
+
+    /// Draws every edge of the element's border as a dashed line.
+    /// [Docs](https://tailwindcss.com/docs/border-style)
+    fn border_dashed(self) -> Self
+    where
+        Self: Sized,
+    {
+        self.border_style(BorderStyle::Dashed)
+    }
+
+    /// Draws every edge of the element's border as a dotted line.
+    /// [Docs](https://tailwindcss.com/docs/border-style)
+    fn border_dotted(self) -> Self
+    where
+        Self: Sized,
+    {
+        self.border_style(BorderStyle::Dotted)
+    }
+
+    /// Sets the style of the top edge of the element's border.
+    fn border_t_style(mut self, style: BorderStyle) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().border_styles.top = Some(style);
+        self
+    }
+
+    /// Sets the style of the right edge of the element's border.
+    fn border_r_style(mut self, style: BorderStyle) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().border_styles.right = Some(style);
+        self
+    }
+
+    /// Sets the style of the bottom edge of the element's border.
+    fn border_b_style(mut self, style: BorderStyle) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().border_styles.bottom = Some(style);
+        self
+    }
+
+    /// Sets the style of the left edge of the element's border.
+    fn border_l_style(mut self, style: BorderStyle) -> Self
+    where
+        Self: Sized,
+    {
+        self.style().border_styles.left = Some(style);
+        self
+    }
+
     /// Sets the box shadow of the element.
     /// [Docs](https://tailwindcss.com/docs/box-shadow)
     fn shadow(mut self, shadows: SmallVec<[BoxShadow; 2]>) -> Self {
@@ -440,6 +793,7 @@ pub trait Styled: Sized {
             offset: point(px(0.), px(1.)),
             blur_radius: px(2.),
             spread_radius: px(0.),
+            ..Default::default()
         }]);
         self
     }
@@ -453,12 +807,14 @@ pub trait Styled: Sized {
                 offset: point(px(0.), px(4.)),
                 blur_radius: px(6.),
                 spread_radius: px(-1.),
+                ..Default::default()
             },
             BoxShadow {
                 color: hsla(0., 0., 0., 0.1),
                 offset: point(px(0.), px(2.)),
                 blur_radius: px(4.),
                 spread_radius: px(-2.),
+                ..Default::default()
             }
         ]);
         self
@@ -473,12 +829,14 @@ pub trait Styled: Sized {
                 offset: point(px(0.), px(10.)),
                 blur_radius: px(15.),
                 spread_radius: px(-3.),
+                ..Default::default()
             },
             BoxShadow {
                 color: hsla(0., 0., 0., 0.1),
                 offset: point(px(0.), px(4.)),
                 blur_radius: px(6.),
                 spread_radius: px(-4.),
+                ..Default::default()
             }
         ]);
         self
@@ -493,12 +851,14 @@ pub trait Styled: Sized {
                 offset: point(px(0.), px(20.)),
                 blur_radius: px(25.),
                 spread_radius: px(-5.),
+                ..Default::default()
             },
             BoxShadow {
                 color: hsla(0., 0., 0., 0.1),
                 offset: point(px(0.), px(8.)),
                 blur_radius: px(10.),
                 spread_radius: px(-6.),
+                ..Default::default()
             }
         ]);
         self
@@ -512,10 +872,25 @@ pub trait Styled: Sized {
             offset: point(px(0.), px(25.)),
             blur_radius: px(50.),
             spread_radius: px(-12.),
+            ..Default::default()
         }]);
         self
     }
 
+    /// Sets an inner box shadow on the element, as if it were pressed into the page.
+    /// [Docs](https://tailwindcss.com/docs/box-shadow)
+    fn shadow_inner(mut self) -> Self {
+        self.style().box_shadow = Some(smallvec![BoxShadow {
+            color: hsla(0., 0., 0., 0.05),
+            offset: point(px(0.), px(2.)),
+            blur_radius: px(4.),
+            spread_radius: px(0.),
+            ..Default::default()
+        }
+        .inset()]);
+        self
+    }
+
     /// Get the text style that has been configured on this element.
     fn text_style(&mut self) -> &mut Option<TextStyleRefinement> {
         let style: &mut StyleRefinement = self.style();
@@ -1,4 +1,4 @@
-use crate::{AssetSource, DevicePixels, IsZero, Result, SharedString, Size};
+use crate::{AssetSource, DevicePixels, IsZero, Result, SharedString, Size, SvgColorMode};
 use anyhow::anyhow;
 use std::{hash::Hash, sync::Arc};
 
@@ -6,6 +6,7 @@ use std::{hash::Hash, sync::Arc};
 pub(crate) struct RenderSvgParams {
     pub(crate) path: SharedString,
     pub(crate) size: Size<DevicePixels>,
+    pub(crate) color_mode: SvgColorMode,
 }
 
 pub(crate) struct SvgRenderer {
@@ -17,6 +18,8 @@ impl SvgRenderer {
         Self { asset_source }
     }
 
+    /// Renders the SVG centered in the requested size, preserving its aspect ratio. Monochrome
+    /// SVGs produce an alpha mask, and full color ones produce BGRA pixels with straight alpha.
     pub fn render(&self, params: &RenderSvgParams) -> Result<Vec<u8>> {
         if params.size.is_zero() {
             return Err(anyhow!("can't render at a zero size"));
@@ -26,21 +29,98 @@ impl SvgRenderer {
         let bytes = self.asset_source.load(&params.path)?;
         let tree = usvg::Tree::from_data(&bytes, &usvg::Options::default())?;
 
-        // Render the SVG to a pixmap with the specified width and height.
-        let mut pixmap =
-            tiny_skia::Pixmap::new(params.size.width.into(), params.size.height.into()).unwrap();
+        // Render the SVG at the largest size that fits, then center it in the requested size.
+        let width = u32::from(params.size.width);
+        let height = u32::from(params.size.height);
+        let svg_size = tree.svg_node().size;
+        let scale = (width as f64 / svg_size.width()).min(height as f64 / svg_size.height());
+        let fitted_width = ((svg_size.width() * scale).round() as u32).clamp(1, width);
+        let fitted_height = ((svg_size.height() * scale).round() as u32).clamp(1, height);
+        let mut fitted = tiny_skia::Pixmap::new(fitted_width, fitted_height).unwrap();
         resvg::render(
             &tree,
-            usvg::FitTo::Width(params.size.width.into()),
-            pixmap.as_mut(),
+            usvg::FitTo::Size(fitted_width, fitted_height),
+            fitted.as_mut(),
         );
 
-        // Convert the pixmap's pixels into an alpha mask.
-        let alpha_mask = pixmap
-            .pixels()
-            .iter()
-            .map(|p| p.alpha())
-            .collect::<Vec<_>>();
-        Ok(alpha_mask)
+        let mut pixmap = tiny_skia::Pixmap::new(width, height).unwrap();
+        pixmap.draw_pixmap(
+            ((width - fitted_width) / 2) as i32,
+            ((height - fitted_height) / 2) as i32,
+            fitted.as_ref(),
+            &tiny_skia::PixmapPaint::default(),
+            tiny_skia::Transform::identity(),
+            None,
+        );
+
+        let pixels = pixmap.pixels().iter();
+        Ok(match params.color_mode {
+            // Convert the pixmap's pixels into an alpha mask.
+            SvgColorMode::Monochrome => pixels.map(|p| p.alpha()).collect(),
+            // Convert from RGBA with premultiplied alpha to BGRA with straight alpha.
+            SvgColorMode::FullColor => pixels
+                .flat_map(|p| {
+                    let p = p.demultiply();
+                    [p.blue(), p.green(), p.red(), p.alpha()]
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::size;
+    use std::borrow::Cow;
+
+    /// A two by one SVG whose left half is red and right half is blue.
+    struct FlagAsset;
+
+    impl AssetSource for FlagAsset {
+        fn load(&self, _path: &str) -> Result<Cow<[u8]>> {
+            Ok(Cow::Borrowed(
+                br##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+                    <rect width="10" height="10" fill="#ff0000"/>
+                    <rect x="10" width="10" height="10" fill="#0000ff"/>
+                </svg>"##,
+            ))
+        }
+
+        fn list(&self, _path: &str) -> Result<Vec<SharedString>> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn render(color_mode: SvgColorMode) -> Vec<u8> {
+        SvgRenderer::new(Arc::new(FlagAsset))
+            .render(&RenderSvgParams {
+                path: "flag.svg".into(),
+                size: size(DevicePixels(8), DevicePixels(8)),
+                color_mode,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_full_color_svg_keeps_colors_and_aspect_ratio() {
+        let pixels = render(SvgColorMode::FullColor);
+        assert_eq!(pixels.len(), 8 * 8 * 4);
+        let pixel = |x: usize, y: usize| &pixels[(y * 8 + x) * 4..][..4];
+
+        // The flag is letterboxed into rows 2 through 5.
+        assert_eq!(pixel(1, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(1, 7), [0, 0, 0, 0]);
+        assert_eq!(pixel(1, 3), [0, 0, 255, 255]);
+        assert_eq!(pixel(6, 4), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_monochrome_svg_is_an_alpha_mask() {
+        let alphas = render(SvgColorMode::Monochrome);
+        assert_eq!(alphas.len(), 8 * 8);
+        assert_eq!(alphas[8 + 1], 0);
+        assert_eq!(alphas[3 * 8 + 1], 255);
+        assert_eq!(alphas[4 * 8 + 6], 255);
     }
 }
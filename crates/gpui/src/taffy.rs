@@ -1,27 +1,56 @@
 use crate::{
-    AbsoluteLength, Bounds, DefiniteLength, Edges, Length, Pixels, Point, Size, Style,
-    WindowContext,
+    px, AbsoluteLength, Bounds, DefiniteLength, Edges, GlobalElementId, GridLines, GridPlacement,
+    GridRepetition, GridTemplate, GridTrack, Length, Pixels, Point, Position, Size, Style,
+    TrackBreadth, WindowContext,
 };
 use collections::{FxHashMap, FxHashSet};
 use smallvec::SmallVec;
 use std::fmt::Debug;
 use taffy::{
     geometry::{Point as TaffyPoint, Rect as TaffyRect, Size as TaffySize},
+    prelude::{TaffyGridLine as _, TaffyGridSpan as _},
     style::AvailableSpace as TaffyAvailableSpace,
     tree::NodeId,
     Taffy,
 };
+use util::debug_panic;
 
 type NodeMeasureFn =
     Box<dyn FnMut(Size<Option<Pixels>>, Size<AvailableSpace>, &mut WindowContext) -> Size<Pixels>>;
 
 pub struct TaffyLayoutEngine {
     taffy: Taffy,
+    nodes: FxHashMap<LayoutId, LayoutNode>,
+    node_ids: FxHashMap<LayoutNodeKey, LayoutId>,
+    previous_node_ids: FxHashMap<LayoutNodeKey, LayoutId>,
+    next_ordinals: FxHashMap<GlobalElementId, usize>,
+    detached_children: Vec<LayoutId>,
     styles: FxHashMap<LayoutId, Style>,
     children_to_parents: FxHashMap<LayoutId, LayoutId>,
     absolute_layout_bounds: FxHashMap<LayoutId, Bounds<Pixels>>,
     computed_layouts: FxHashSet<LayoutId>,
     nodes_to_measure: FxHashMap<LayoutId, NodeMeasureFn>,
+    length_expression_nodes: FxHashMap<LayoutId, Pixels>,
+}
+
+/// Identifies a layout node across frames: the id of the element that requested it, plus how
+/// many nodes were requested before it under that same id during the frame, as most elements
+/// share the id of their nearest ancestor that has one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct LayoutNodeKey {
+    element_id: GlobalElementId,
+    ordinal: usize,
+}
+
+/// What a layout node was last requested with, to tell whether taffy's cached layout for it
+/// is still valid.
+struct LayoutNode {
+    children: SmallVec<[LayoutId; 4]>,
+    measured: bool,
+    measure_inputs: Option<u64>,
+    /// The size of the parent's box, where known, that the node's length expressions were last
+    /// resolved against.
+    parent_size: Option<Size<Option<Pixels>>>,
 }
 
 static EXPECT_MESSAGE: &str = "we should avoid taffy layout errors by construction if possible";
@@ -30,20 +59,60 @@ impl TaffyLayoutEngine {
     pub fn new() -> Self {
         TaffyLayoutEngine {
             taffy: Taffy::new(),
+            nodes: FxHashMap::default(),
+            node_ids: FxHashMap::default(),
+            previous_node_ids: FxHashMap::default(),
+            next_ordinals: FxHashMap::default(),
+            detached_children: Vec::new(),
             styles: FxHashMap::default(),
             children_to_parents: FxHashMap::default(),
             absolute_layout_bounds: FxHashMap::default(),
             computed_layouts: FxHashSet::default(),
             nodes_to_measure: FxHashMap::default(),
+            length_expression_nodes: FxHashMap::default(),
         }
     }
 
-    pub fn clear(&mut self) {
-        self.taffy.clear();
+    /// Removes the nodes that weren't requested again this frame, and keeps the rest, along
+    /// with taffy's cached layouts for them, for the next frame to reuse.
+    pub fn finish_frame(&mut self) {
+        for (_, layout_id) in self.previous_node_ids.drain() {
+            if let Some(node) = self.nodes.remove(&layout_id) {
+                self.detached_children.extend(node.children);
+            }
+            self.taffy.remove(layout_id.into()).expect(EXPECT_MESSAGE);
+        }
+
+        // Setting a node's children or removing it detaches its former children in taffy, even
+        // those that have since moved to another parent. Reattach them, so that marking them
+        // dirty in later frames reaches their new ancestors.
+        let mut reattached_parents = SmallVec::<[LayoutId; 8]>::new();
+        for child_id in self.detached_children.drain(..) {
+            if let Some(parent_id) = self.children_to_parents.get(&child_id).copied() {
+                if self.taffy.parent(child_id.into()) != Some(parent_id.into())
+                    && !reattached_parents.contains(&parent_id)
+                {
+                    reattached_parents.push(parent_id);
+                }
+            }
+        }
+        for parent_id in reattached_parents {
+            let children = &self.nodes[&parent_id].children;
+            self.taffy
+                // This is safe because LayoutId is repr(transparent) to taffy::tree::NodeId.
+                .set_children(parent_id.into(), unsafe {
+                    std::mem::transmute::<&[LayoutId], &[NodeId]>(&children[..])
+                })
+                .expect(EXPECT_MESSAGE);
+        }
+
+        std::mem::swap(&mut self.node_ids, &mut self.previous_node_ids);
+        self.next_ordinals.clear();
         self.children_to_parents.clear();
         self.absolute_layout_bounds.clear();
         self.computed_layouts.clear();
         self.nodes_to_measure.clear();
+        self.length_expression_nodes.clear();
         self.styles.clear();
     }
 
@@ -51,52 +120,278 @@ impl TaffyLayoutEngine {
         self.styles.get(&layout_id)
     }
 
+    pub fn parent(&self, layout_id: LayoutId) -> Option<LayoutId> {
+        self.children_to_parents.get(&layout_id).copied()
+    }
+
     pub fn request_layout(
         &mut self,
+        element_id: &GlobalElementId,
         style: &Style,
         rem_size: Pixels,
         children: &[LayoutId],
     ) -> LayoutId {
-        let taffy_style = style.to_taffy(rem_size);
-        let layout_id = if children.is_empty() {
-            self.taffy
-                .new_leaf(taffy_style)
-                .expect(EXPECT_MESSAGE)
-                .into()
-        } else {
-            let parent_id = self
-                .taffy
-                // This is safe because LayoutId is repr(transparent) to taffy::tree::NodeId.
-                .new_with_children(taffy_style, unsafe { std::mem::transmute(children) })
-                .expect(EXPECT_MESSAGE)
-                .into();
-            for child_id in children {
-                self.children_to_parents.insert(*child_id, parent_id);
+        let key = self.next_key(element_id);
+        let reused_id = self.reusable_node(&key, false);
+        let taffy_style = self.style_to_taffy(reused_id, style, rem_size);
+        let layout_id = if let Some(layout_id) = reused_id {
+            self.update_style(layout_id, taffy_style);
+            let node = self.nodes.get_mut(&layout_id).unwrap();
+            if node.children.as_slice() != children {
+                self.detached_children.extend(node.children.drain(..));
+                node.children.extend_from_slice(children);
+                self.taffy
+                    // This is safe because LayoutId is repr(transparent) to taffy::tree::NodeId.
+                    .set_children(layout_id.into(), unsafe {
+                        std::mem::transmute::<&[LayoutId], &[NodeId]>(children)
+                    })
+                    .expect(EXPECT_MESSAGE);
             }
-            parent_id
+            layout_id
+        } else {
+            let layout_id = if children.is_empty() {
+                self.taffy
+                    .new_leaf(taffy_style)
+                    .expect(EXPECT_MESSAGE)
+                    .into()
+            } else {
+                self.taffy
+                    // This is safe because LayoutId is repr(transparent) to taffy::tree::NodeId.
+                    .new_with_children(taffy_style, unsafe {
+                        std::mem::transmute::<&[LayoutId], &[NodeId]>(children)
+                    })
+                    .expect(EXPECT_MESSAGE)
+                    .into()
+            };
+            self.nodes.insert(
+                layout_id,
+                LayoutNode {
+                    children: children.into(),
+                    measured: false,
+                    measure_inputs: None,
+                    parent_size: None,
+                },
+            );
+            layout_id
         };
+        for child_id in children {
+            self.children_to_parents.insert(*child_id, layout_id);
+        }
+        if style.depends_on_parent_size() {
+            self.length_expression_nodes.insert(layout_id, rem_size);
+        }
+        self.node_ids.insert(key, layout_id);
         self.styles.insert(layout_id, style.clone());
         layout_id
     }
 
+    /// Requests a leaf node sized by the given closure. If `measure_inputs` is given, and
+    /// matches what the node was requested with last frame, the node's measurements from
+    /// earlier frames are reused. The closure is still called once per frame with the node's
+    /// final size, for elements that keep the results of measuring for painting.
     pub fn request_measured_layout(
         &mut self,
+        element_id: &GlobalElementId,
         style: Style,
         rem_size: Pixels,
+        measure_inputs: Option<u64>,
         measure: impl FnMut(Size<Option<Pixels>>, Size<AvailableSpace>, &mut WindowContext) -> Size<Pixels>
             + 'static,
     ) -> LayoutId {
-        let style = style.clone();
-        let taffy_style = style.to_taffy(rem_size);
+        let key = self.next_key(element_id);
+        let reused_id = self.reusable_node(&key, true);
+        let taffy_style = self.style_to_taffy(reused_id, &style, rem_size);
+        let layout_id = if let Some(layout_id) = reused_id {
+            self.update_style(layout_id, taffy_style);
+            let node = self.nodes.get_mut(&layout_id).unwrap();
+            if measure_inputs.is_none() || node.measure_inputs != measure_inputs {
+                node.measure_inputs = measure_inputs;
+                self.taffy
+                    .mark_dirty(layout_id.into())
+                    .expect(EXPECT_MESSAGE);
+            }
+            layout_id
+        } else {
+            let layout_id = self
+                .taffy
+                .new_leaf_with_context(taffy_style, ())
+                .expect(EXPECT_MESSAGE)
+                .into();
+            self.nodes.insert(
+                layout_id,
+                LayoutNode {
+                    children: SmallVec::new(),
+                    measured: true,
+                    measure_inputs,
+                    parent_size: None,
+                },
+            );
+            layout_id
+        };
+        self.nodes_to_measure.insert(layout_id, Box::new(measure));
+        if style.depends_on_parent_size() {
+            self.length_expression_nodes.insert(layout_id, rem_size);
+        }
+        self.node_ids.insert(key, layout_id);
+        self.styles.insert(layout_id, style);
+        layout_id
+    }
+
+    fn next_key(&mut self, element_id: &GlobalElementId) -> LayoutNodeKey {
+        let ordinal = if let Some(next_ordinal) = self.next_ordinals.get_mut(element_id) {
+            *next_ordinal += 1;
+            *next_ordinal - 1
+        } else {
+            self.next_ordinals.insert(element_id.clone(), 1);
+            0
+        };
+        LayoutNodeKey {
+            element_id: element_id.clone(),
+            ordinal,
+        }
+    }
+
+    /// Takes the node requested with the given key last frame, if it was of the same kind.
+    fn reusable_node(&mut self, key: &LayoutNodeKey, measured: bool) -> Option<LayoutId> {
+        let layout_id = *self.previous_node_ids.get(key)?;
+        if self.nodes[&layout_id].measured != measured {
+            return None;
+        }
+        self.previous_node_ids.remove(key);
+        Some(layout_id)
+    }
+
+    /// Sets the style of a reused node, which marks it and its ancestors dirty, but only if it
+    /// changed in a way that affects layout. Returns whether it did.
+    fn update_style(&mut self, layout_id: LayoutId, taffy_style: taffy::style::Style) -> bool {
+        if *self.taffy.style(layout_id.into()).expect(EXPECT_MESSAGE) != taffy_style {
+            self.taffy
+                .set_style(layout_id.into(), taffy_style)
+                .expect(EXPECT_MESSAGE);
+            true
+        } else {
+            false
+        }
+    }
 
-        let layout_id = self
+    /// Converts a style for taffy. Length expressions in it are resolved against the size of
+    /// the parent they were resolved against last frame, if the node is reused, so that its
+    /// cached layout stays valid. They are corrected once the parent has been laid out.
+    fn style_to_taffy(
+        &self,
+        reused_id: Option<LayoutId>,
+        style: &Style,
+        rem_size: Pixels,
+    ) -> taffy::style::Style {
+        if style.depends_on_parent_size() {
+            let parent_size = reused_id
+                .and_then(|layout_id| self.nodes[&layout_id].parent_size)
+                .unwrap_or_default();
+            style
+                .resolve_lengths(parent_size, rem_size)
+                .to_taffy(rem_size)
+        } else {
+            style.to_taffy(rem_size)
+        }
+    }
+
+    /// Returns the nodes under the given root whose styles have length expressions, parents
+    /// first, along with how many of their ancestors below the root have them too.
+    fn length_expression_nodes_under(&self, root_id: LayoutId) -> SmallVec<[(LayoutId, usize); 8]> {
+        let mut expression_nodes = SmallVec::new();
+        if self.length_expression_nodes.is_empty() {
+            return expression_nodes;
+        }
+
+        let mut stack = SmallVec::<[(LayoutId, usize); 64]>::new();
+        stack.push((root_id, 0));
+        while let Some((layout_id, mut depth)) = stack.pop() {
+            if self.length_expression_nodes.contains_key(&layout_id) {
+                expression_nodes.push((layout_id, depth));
+                if layout_id != root_id {
+                    depth += 1;
+                }
+            }
+            stack.extend(
+                self.nodes[&layout_id]
+                    .children
+                    .iter()
+                    .map(|child_id| (*child_id, depth)),
+            );
+        }
+        expression_nodes
+    }
+
+    /// Resolves the length expressions of the given nodes against the size of their parent's
+    /// box, as last computed, or for the root, against the space available to it. Returns
+    /// whether that changed any of their styles, in which case the layout needs computing again.
+    fn resolve_length_expressions(
+        &mut self,
+        expression_nodes: &[(LayoutId, usize)],
+        root_id: LayoutId,
+        available_space: Size<AvailableSpace>,
+    ) -> bool {
+        let mut changed = false;
+        for (layout_id, _) in expression_nodes {
+            let rem_size = self.length_expression_nodes[layout_id];
+            let style = &self.styles[layout_id];
+            let parent_size = if *layout_id == root_id {
+                available_space.map(|space| match space {
+                    AvailableSpace::Definite(pixels) => Some(pixels),
+                    AvailableSpace::MinContent | AvailableSpace::MaxContent => None,
+                })
+            } else {
+                let parent_id = self.children_to_parents[layout_id];
+                self.child_box_size(parent_id, style.position, rem_size)
+                    .map(Some)
+            };
+
+            let node = self.nodes.get_mut(layout_id).unwrap();
+            if node.parent_size == Some(parent_size) {
+                continue;
+            }
+            node.parent_size = Some(parent_size);
+            let taffy_style = style
+                .resolve_lengths(parent_size, rem_size)
+                .to_taffy(rem_size);
+            changed |= self.update_style(*layout_id, taffy_style);
+        }
+        changed
+    }
+
+    /// The size of the box a child with the given position is laid out in, as last computed.
+    /// As in taffy, that's the parent's content box, or its padding box for absolutely
+    /// positioned children.
+    fn child_box_size(
+        &self,
+        parent_id: LayoutId,
+        position: Position,
+        rem_size: Pixels,
+    ) -> Size<Pixels> {
+        let parent_size: Size<Pixels> = self
             .taffy
-            .new_leaf_with_context(taffy_style, ())
+            .layout(parent_id.into())
             .expect(EXPECT_MESSAGE)
+            .size
             .into();
-        self.nodes_to_measure.insert(layout_id, Box::new(measure));
-        self.styles.insert(layout_id, style.clone());
-        layout_id
+        let parent_style = &self.styles[&parent_id];
+        if position == Position::Absolute {
+            let border_widths = parent_style.border_widths.to_pixels(rem_size);
+            Size {
+                width: parent_size.width - border_widths.left - border_widths.right,
+                height: parent_size.height - border_widths.top - border_widths.bottom,
+            }
+        } else {
+            parent_style
+                .content_bounds(
+                    Bounds {
+                        origin: Point::default(),
+                        size: parent_size,
+                    },
+                    rem_size,
+                )
+                .size
+        }
     }
 
     // Used to understand performance
@@ -180,6 +475,65 @@ impl TaffyLayoutEngine {
         }
 
         // let started_at = std::time::Instant::now();
+        let expression_nodes = self.length_expression_nodes_under(id);
+        if expression_nodes
+            .first()
+            .is_some_and(|(layout_id, _)| *layout_id == id)
+        {
+            self.resolve_length_expressions(&expression_nodes[..1], id, available_space);
+        }
+        let mut measured_nodes = FxHashSet::default();
+        self.compute_taffy_layout(id, available_space, &mut measured_nodes, cx);
+
+        // Expressions mixing absolute lengths with fractions of the parent's size can only be
+        // resolved once the parent has been laid out, so lay out again if they changed. Nodes
+        // are reused along with what they were resolved against, so this only happens when
+        // sizes change. Each pass settles one more level of nested expressions, and no more
+        // passes are made than there are levels, so that a parent sized by its children can't
+        // keep the layout changing.
+        let passes = expression_nodes
+            .iter()
+            .filter(|(layout_id, _)| *layout_id != id)
+            .map(|(_, depth)| depth + 1)
+            .max()
+            .unwrap_or(0);
+        for _ in 0..passes {
+            if !self.resolve_length_expressions(&expression_nodes, id, available_space) {
+                break;
+            }
+            self.compute_taffy_layout(id, available_space, &mut measured_nodes, cx);
+        }
+
+        // Taffy skips measuring nodes whose cached size is still valid, so measure them at
+        // their final size, for elements that paint what they computed while measuring.
+        let mut stack = SmallVec::<[LayoutId; 64]>::new();
+        stack.push(id);
+        while let Some(id) = stack.pop() {
+            if let Some(measure) = self.nodes_to_measure.get_mut(&id) {
+                if !measured_nodes.contains(&id) {
+                    let size: Size<Pixels> = self
+                        .taffy
+                        .layout(id.into())
+                        .expect(EXPECT_MESSAGE)
+                        .size
+                        .into();
+                    measure(size.map(Some), size.map(AvailableSpace::Definite), cx);
+                }
+            } else {
+                stack.extend(self.nodes[&id].children.iter().copied());
+            }
+        }
+
+        // println!("compute_layout took {:?}", started_at.elapsed());
+    }
+
+    fn compute_taffy_layout(
+        &mut self,
+        id: LayoutId,
+        available_space: Size<AvailableSpace>,
+        measured_nodes: &mut FxHashSet<LayoutId>,
+        cx: &mut WindowContext,
+    ) {
         self.taffy
             .compute_layout_with_measure(
                 id.into(),
@@ -188,6 +542,7 @@ impl TaffyLayoutEngine {
                     let Some(measure) = self.nodes_to_measure.get_mut(&node_id.into()) else {
                         return taffy::geometry::Size::default();
                     };
+                    measured_nodes.insert(LayoutId::from(node_id));
 
                     let known_dimensions = Size {
                         width: known_dimensions.width.map(Pixels),
@@ -198,8 +553,6 @@ impl TaffyLayoutEngine {
                 },
             )
             .expect(EXPECT_MESSAGE);
-
-        // println!("compute_layout took {:?}", started_at.elapsed());
     }
 
     pub fn layout_bounds(&mut self, id: LayoutId) -> Bounds<Pixels> {
@@ -256,8 +609,14 @@ impl ToTaffy<taffy::style::Style> for Style {
             display: self.display,
             overflow: self.overflow.into(),
             scrollbar_width: self.scrollbar_width,
-            position: self.position,
-            inset: self.inset.to_taffy(rem_size),
+            position: self.position.into(),
+            // Sticky elements are laid out in the normal flow, and only moved by their inset
+            // when painted inside a scroll container.
+            inset: if self.position == Position::Sticky {
+                Edges::auto().to_taffy(rem_size)
+            } else {
+                self.inset.to_taffy(rem_size)
+            },
             size: self.size.to_taffy(rem_size),
             min_size: self.min_size.to_taffy(rem_size),
             max_size: self.max_size.to_taffy(rem_size),
@@ -275,7 +634,176 @@ impl ToTaffy<taffy::style::Style> for Style {
             flex_basis: self.flex_basis.to_taffy(rem_size),
             flex_grow: self.flex_grow,
             flex_shrink: self.flex_shrink,
-            ..Default::default() // Ignore grid properties for now
+            grid_template_columns: self.grid_template_columns.to_taffy(rem_size),
+            grid_template_rows: self.grid_template_rows.to_taffy(rem_size),
+            grid_auto_flow: self.grid_auto_flow,
+            grid_column: self.grid_column.into(),
+            grid_row: self.grid_row.into(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Style {
+    /// Whether converting this style for taffy needs the size of the parent's content box,
+    /// because some of its lengths mix absolute lengths with fractions of the parent's size.
+    fn depends_on_parent_size(&self) -> bool {
+        let length = |length: &Length| length.depends_on_base_size();
+        let definite = |length: &DefiniteLength| length.depends_on_base_size();
+        self.inset.any(length)
+            || self.margin.any(length)
+            || self.padding.any(definite)
+            || [&self.size, &self.min_size, &self.max_size]
+                .iter()
+                .any(|size| length(&size.width) || length(&size.height))
+            || definite(&self.gap.width)
+            || definite(&self.gap.height)
+            || length(&self.flex_basis)
+    }
+
+    /// Returns a copy of this style with its length expressions resolved against the given size
+    /// of the parent's box, where known. As in taffy, fractions in margins and padding are
+    /// fractions of the parent's width, and sizes that are fractions of an unknown size are
+    /// auto, while other lengths count such fractions as zero.
+    fn resolve_lengths(&self, parent_size: Size<Option<Pixels>>, rem_size: Pixels) -> Style {
+        let width = |length: &Length| length.resolve(parent_size.width, rem_size);
+        let height = |length: &Length| length.resolve(parent_size.height, rem_size);
+        let dimension = |length: &Length, base_size: Option<Pixels>| match base_size {
+            None if length.depends_on_base_size() => Length::Auto,
+            _ => length.resolve(base_size, rem_size),
+        };
+        let size = |size: &Size<Length>| Size {
+            width: dimension(&size.width, parent_size.width),
+            height: dimension(&size.height, parent_size.height),
+        };
+        let flex_basis = match self.flex_direction {
+            taffy::style::FlexDirection::Row | taffy::style::FlexDirection::RowReverse => {
+                dimension(&self.flex_basis, parent_size.width)
+            }
+            taffy::style::FlexDirection::Column | taffy::style::FlexDirection::ColumnReverse => {
+                dimension(&self.flex_basis, parent_size.height)
+            }
+        };
+        Style {
+            inset: Edges {
+                top: height(&self.inset.top),
+                right: width(&self.inset.right),
+                bottom: height(&self.inset.bottom),
+                left: width(&self.inset.left),
+            },
+            size: size(&self.size),
+            min_size: size(&self.min_size),
+            max_size: size(&self.max_size),
+            margin: self.margin.map(width),
+            padding: self
+                .padding
+                .map(|length| length.resolve(parent_size.width, rem_size)),
+            gap: Size {
+                width: self.gap.width.resolve(parent_size.width, rem_size),
+                height: self.gap.height.resolve(parent_size.height, rem_size),
+            },
+            flex_basis,
+            ..self.clone()
+        }
+    }
+}
+
+impl ToTaffy<Vec<taffy::style::TrackSizingFunction>> for Vec<GridTemplate> {
+    fn to_taffy(&self, rem_size: Pixels) -> Vec<taffy::style::TrackSizingFunction> {
+        self.iter()
+            .map(|template| match template {
+                GridTemplate::Track(track) => {
+                    taffy::style::TrackSizingFunction::Single(track.to_taffy(rem_size))
+                }
+                GridTemplate::Repeat(repetition, tracks) => {
+                    taffy::style::TrackSizingFunction::Repeat(
+                        match repetition {
+                            GridRepetition::Count(count) => {
+                                taffy::style::GridTrackRepetition::Count(*count)
+                            }
+                            GridRepetition::AutoFill => taffy::style::GridTrackRepetition::AutoFill,
+                            GridRepetition::AutoFit => taffy::style::GridTrackRepetition::AutoFit,
+                        },
+                        tracks
+                            .iter()
+                            .map(|track| track.to_taffy(rem_size))
+                            .collect(),
+                    )
+                }
+            })
+            .collect()
+    }
+}
+
+impl ToTaffy<taffy::style::NonRepeatedTrackSizingFunction> for GridTrack {
+    fn to_taffy(&self, rem_size: Pixels) -> taffy::style::NonRepeatedTrackSizingFunction {
+        taffy::style::NonRepeatedTrackSizingFunction {
+            min: self.min.to_taffy(rem_size),
+            max: self.max.to_taffy(rem_size),
+        }
+    }
+}
+
+impl ToTaffy<taffy::style::MinTrackSizingFunction> for TrackBreadth {
+    fn to_taffy(&self, rem_size: Pixels) -> taffy::style::MinTrackSizingFunction {
+        match self {
+            TrackBreadth::Definite(length) => {
+                taffy::style::MinTrackSizingFunction::Fixed(length.to_taffy(rem_size))
+            }
+            // Fractions can only be the maximum size of a track.
+            TrackBreadth::Fraction(_) | TrackBreadth::Auto => {
+                taffy::style::MinTrackSizingFunction::Auto
+            }
+            TrackBreadth::MinContent => taffy::style::MinTrackSizingFunction::MinContent,
+            TrackBreadth::MaxContent => taffy::style::MinTrackSizingFunction::MaxContent,
+        }
+    }
+}
+
+impl ToTaffy<taffy::style::MaxTrackSizingFunction> for TrackBreadth {
+    fn to_taffy(&self, rem_size: Pixels) -> taffy::style::MaxTrackSizingFunction {
+        match self {
+            TrackBreadth::Definite(length) => {
+                taffy::style::MaxTrackSizingFunction::Fixed(length.to_taffy(rem_size))
+            }
+            TrackBreadth::Fraction(fraction) => {
+                taffy::style::MaxTrackSizingFunction::Fraction(*fraction)
+            }
+            TrackBreadth::Auto => taffy::style::MaxTrackSizingFunction::Auto,
+            TrackBreadth::MinContent => taffy::style::MaxTrackSizingFunction::MinContent,
+            TrackBreadth::MaxContent => taffy::style::MaxTrackSizingFunction::MaxContent,
+        }
+    }
+}
+
+impl From<Position> for taffy::style::Position {
+    fn from(position: Position) -> Self {
+        match position {
+            Position::Relative | Position::Sticky => taffy::style::Position::Relative,
+            Position::Absolute => taffy::style::Position::Absolute,
+        }
+    }
+}
+
+impl From<taffy::style::Position> for Position {
+    fn from(position: taffy::style::Position) -> Self {
+        match position {
+            taffy::style::Position::Relative => Position::Relative,
+            taffy::style::Position::Absolute => Position::Absolute,
+        }
+    }
+}
+
+impl From<GridLines> for taffy::geometry::Line<taffy::style::GridPlacement> {
+    fn from(lines: GridLines) -> Self {
+        let placement = |placement: GridPlacement| match placement {
+            GridPlacement::Auto => taffy::style::GridPlacement::Auto,
+            GridPlacement::Line(index) => taffy::style::GridPlacement::from_line_index(index),
+            GridPlacement::Span(tracks) => taffy::style::GridPlacement::from_span(tracks),
+        };
+        taffy::geometry::Line {
+            start: placement(lines.start),
+            end: placement(lines.end),
         }
     }
 }
@@ -300,35 +828,32 @@ impl ToTaffy<taffy::style::Dimension> for Length {
 
 impl ToTaffy<taffy::style::LengthPercentage> for DefiniteLength {
     fn to_taffy(&self, rem_size: Pixels) -> taffy::style::LengthPercentage {
-        match self {
-            DefiniteLength::Absolute(length) => match length {
-                AbsoluteLength::Pixels(pixels) => {
-                    taffy::style::LengthPercentage::Length(pixels.into())
-                }
-                AbsoluteLength::Rems(rems) => {
-                    taffy::style::LengthPercentage::Length((*rems * rem_size).into())
-                }
-            },
-            DefiniteLength::Fraction(fraction) => {
-                taffy::style::LengthPercentage::Percent(*fraction)
-            }
+        // Lengths in styles that mix fractions of the parent's size with absolute lengths are
+        // resolved against it before being converted. Grid tracks are sized against the grid
+        // container itself, whose size isn't known here, so they can't mix the two.
+        if self.depends_on_base_size() {
+            debug_panic!(
+                "{self:?} mixes fractions of the grid container's size with absolute lengths, \
+                which grid tracks don't support. Its fractions count as zero."
+            );
+        }
+        match self.resolve(None, rem_size) {
+            DefiniteLength::Fraction(fraction) => taffy::style::LengthPercentage::Percent(fraction),
+            length => taffy::style::LengthPercentage::Length(
+                length.to_pixels(px(0.).into(), rem_size).into(),
+            ),
         }
     }
 }
 
 impl ToTaffy<taffy::style::LengthPercentageAuto> for DefiniteLength {
     fn to_taffy(&self, rem_size: Pixels) -> taffy::style::LengthPercentageAuto {
-        match self {
-            DefiniteLength::Absolute(length) => match length {
-                AbsoluteLength::Pixels(pixels) => {
-                    taffy::style::LengthPercentageAuto::Length(pixels.into())
-                }
-                AbsoluteLength::Rems(rems) => {
-                    taffy::style::LengthPercentageAuto::Length((*rems * rem_size).into())
-                }
-            },
-            DefiniteLength::Fraction(fraction) => {
-                taffy::style::LengthPercentageAuto::Percent(*fraction)
+        match self.to_taffy(rem_size) {
+            taffy::style::LengthPercentage::Length(length) => {
+                taffy::style::LengthPercentageAuto::Length(length)
+            }
+            taffy::style::LengthPercentage::Percent(fraction) => {
+                taffy::style::LengthPercentageAuto::Percent(fraction)
             }
         }
     }
@@ -336,14 +861,13 @@ impl ToTaffy<taffy::style::LengthPercentageAuto> for DefiniteLength {
 
 impl ToTaffy<taffy::style::Dimension> for DefiniteLength {
     fn to_taffy(&self, rem_size: Pixels) -> taffy::style::Dimension {
-        match self {
-            DefiniteLength::Absolute(length) => match length {
-                AbsoluteLength::Pixels(pixels) => taffy::style::Dimension::Length(pixels.into()),
-                AbsoluteLength::Rems(rems) => {
-                    taffy::style::Dimension::Length((*rems * rem_size).into())
-                }
-            },
-            DefiniteLength::Fraction(fraction) => taffy::style::Dimension::Percent(*fraction),
+        match self.to_taffy(rem_size) {
+            taffy::style::LengthPercentage::Length(length) => {
+                taffy::style::Dimension::Length(length)
+            }
+            taffy::style::LengthPercentage::Percent(fraction) => {
+                taffy::style::Dimension::Percent(fraction)
+            }
         }
     }
 }
@@ -481,3 +1005,214 @@ impl From<Size<Pixels>> for Size<AvailableSpace> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{px, relative, rems, size, TestAppContext};
+    use std::{cell::Cell, rc::Rc};
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    #[test]
+    #[should_panic(expected = "which grid tracks don't support")]
+    fn test_grid_tracks_reject_mixed_lengths() {
+        let track = GridTrack::from(relative(1.) - px(10.));
+        let _: Vec<taffy::style::TrackSizingFunction> =
+            vec![GridTemplate::from(track)].to_taffy(px(16.));
+    }
+
+    #[crate::test]
+    fn test_layout_nodes_are_reused_across_frames(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let mut engine = TaffyLayoutEngine::new();
+        let element_id = GlobalElementId::default();
+        let measurements = Rc::new(Cell::new(0));
+        let request_frame = |engine: &mut TaffyLayoutEngine, sibling_width: f32| {
+            let label =
+                engine.request_measured_layout(&element_id, Style::default(), px(16.), Some(0), {
+                    let measurements = measurements.clone();
+                    move |known_dimensions, _, _| {
+                        if known_dimensions.width.is_none() {
+                            measurements.set(measurements.get() + 1);
+                        }
+                        size(px(20.), px(10.))
+                    }
+                });
+            let sibling_style = Style {
+                size: Size {
+                    width: px(sibling_width).into(),
+                    height: Length::Auto,
+                },
+                ..Default::default()
+            };
+            let sibling = engine.request_layout(&element_id, &sibling_style, px(16.), &[]);
+            let root_style = Style {
+                display: taffy::style::Display::Flex,
+                ..Default::default()
+            };
+            let root = engine.request_layout(&element_id, &root_style, px(16.), &[label, sibling]);
+            (root, label, sibling)
+        };
+
+        let (root, label, sibling) = cx.update(|cx| {
+            let ids = request_frame(&mut engine, 30.);
+            engine.compute_layout(ids.0, size(px(100.), px(100.)).into(), cx);
+            ids
+        });
+        assert!(measurements.get() > 0);
+        assert_eq!(engine.layout_bounds(sibling).origin.x, px(20.));
+        engine.finish_frame();
+
+        // Requesting the same nodes again reuses them and their cached layout.
+        measurements.set(0);
+        cx.update(|cx| {
+            assert_eq!(request_frame(&mut engine, 30.), (root, label, sibling));
+            engine.compute_layout(root, size(px(100.), px(100.)).into(), cx);
+        });
+        assert_eq!(measurements.get(), 0);
+        assert_eq!(engine.layout_bounds(sibling).size.width, px(30.));
+        engine.finish_frame();
+
+        // Changing a node's style lays it out again, without measuring its unchanged sibling.
+        cx.update(|cx| {
+            assert_eq!(request_frame(&mut engine, 50.), (root, label, sibling));
+            engine.compute_layout(root, size(px(100.), px(100.)).into(), cx);
+        });
+        assert_eq!(measurements.get(), 0);
+        assert_eq!(engine.layout_bounds(sibling).size.width, px(50.));
+        engine.finish_frame();
+
+        // Nodes that aren't requested again are removed.
+        let leaf =
+            cx.update(|_| engine.request_layout(&element_id, &Style::default(), px(16.), &[]));
+        assert_ne!(leaf, label);
+        engine.finish_frame();
+        assert_eq!(engine.taffy.total_node_count(), 1);
+    }
+
+    #[crate::test]
+    fn test_length_expressions_resolve_against_parent(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let mut engine = TaffyLayoutEngine::new();
+        let element_id = GlobalElementId::default();
+        let request_frame = |engine: &mut TaffyLayoutEngine, root_width: f32| {
+            let content_style = Style {
+                size: Size {
+                    width: (relative(1.) - px(10.)).into(),
+                    height: px(10.).into(),
+                },
+                ..Default::default()
+            };
+            let content = engine.request_layout(&element_id, &content_style, px(16.), &[]);
+            let panel_style = Style {
+                size: Size {
+                    width: DefiniteLength::clamp(px(200.), relative(0.3), px(600.)).into(),
+                    height: (relative(1.) - rems(2.)).into(),
+                },
+                ..Default::default()
+            };
+            let panel = engine.request_layout(&element_id, &panel_style, px(16.), &[content]);
+            let sibling_style = Style {
+                size: Size {
+                    width: DefiniteLength::min(relative(0.5), px(400.)).into(),
+                    height: Length::Auto,
+                },
+                margin: Edges {
+                    left: (relative(0.05) + px(2.)).into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let sibling = engine.request_layout(&element_id, &sibling_style, px(16.), &[]);
+            let root_style = Style {
+                display: taffy::style::Display::Flex,
+                size: size(px(root_width).into(), px(500.).into()),
+                padding: Edges::all(px(20.).into()),
+                ..Default::default()
+            };
+            let root = engine.request_layout(&element_id, &root_style, px(16.), &[panel, sibling]);
+            (root, panel, content, sibling)
+        };
+        let mut layout_frame = |root_width: f32| {
+            let (_, panel, content, sibling) = cx.update(|cx| {
+                let ids = request_frame(&mut engine, root_width);
+                engine.compute_layout(ids.0, size(px(root_width), px(500.)).into(), cx);
+                ids
+            });
+            let bounds = (
+                engine.layout_bounds(panel),
+                engine.layout_bounds(content).size.width,
+                engine.layout_bounds(sibling),
+            );
+            engine.finish_frame();
+            bounds
+        };
+
+        // The root's content box is 960px wide and 460px tall.
+        for _ in 0..2 {
+            let (panel, content_width, sibling) = layout_frame(1000.);
+            assert_eq!(panel.size, size(px(288.), px(428.)));
+            assert_eq!(content_width, px(278.));
+            assert_eq!(sibling.size.width, px(400.));
+            assert_eq!(sibling.origin.x, px(20. + 288. + 50.));
+        }
+
+        // The panel is clamped on narrow and wide windows.
+        let (panel, content_width, sibling) = layout_frame(500.);
+        assert_eq!(panel.size.width, px(200.));
+        assert_eq!(content_width, px(190.));
+        assert_eq!(sibling.size.width, px(230.));
+        let (panel, _, sibling) = layout_frame(2540.);
+        assert_eq!(panel.size.width, px(600.));
+        assert_eq!(sibling.size.width, px(400.));
+    }
+
+    #[crate::test]
+    fn test_length_expressions_without_definite_parent_size(cx: &mut TestAppContext) {
+        let cx = cx.add_empty_window();
+        let mut engine = TaffyLayoutEngine::new();
+        let element_id = GlobalElementId::default();
+        let child_style = Style {
+            size: size((relative(1.) - px(10.)).into(), px(40.).into()),
+            ..Default::default()
+        };
+        let child = engine.request_layout(&element_id, &child_style, px(16.), &[]);
+        let overlay_style = Style {
+            position: Position::Absolute,
+            size: size((relative(1.) - px(10.)).into(), px(10.).into()),
+            ..Default::default()
+        };
+        let overlay = engine.request_layout(&element_id, &overlay_style, px(16.), &[]);
+        let root_style = Style {
+            display: taffy::style::Display::Flex,
+            size: size(
+                (relative(1.) - px(20.)).into(),
+                DefiniteLength::max(relative(0.5), px(30.)).into(),
+            ),
+            padding: Edges::all(px(10.).into()),
+            border_widths: Edges::all(px(5.).into()),
+            ..Default::default()
+        };
+        let root = engine.request_layout(&element_id, &root_style, px(16.), &[child, overlay]);
+
+        // Measuring the root's height, it resolves its width against the available width, and
+        // its height, which depends on the unknown available height, is auto.
+        cx.update(|cx| {
+            engine.compute_layout(
+                root,
+                size(
+                    AvailableSpace::Definite(px(300.)),
+                    AvailableSpace::MinContent,
+                ),
+                cx,
+            )
+        });
+        assert_eq!(engine.layout_bounds(root).size, size(px(280.), px(70.)));
+        // Children resolve against the root's content box, or its padding box when absolutely
+        // positioned.
+        assert_eq!(engine.layout_bounds(child).size.width, px(240.));
+        assert_eq!(engine.layout_bounds(overlay).size.width, px(260.));
+    }
+}
@@ -1,13 +1,13 @@
 use crate::{
     px, size, transparent_black, Action, AnyDrag, AnyView, AppContext, Arena, AsyncWindowContext,
-    AvailableSpace, Bounds, Context, Corners, CursorStyle, DispatchActionListener, DispatchNodeId,
-    DispatchTree, DisplayId, Edges, Effect, Entity, EntityId, EventEmitter, FileDropEvent, Flatten,
-    GlobalElementId, Hsla, KeyBinding, KeyContext, KeyDownEvent, KeyMatch, KeymatchMode,
-    KeymatchResult, Keystroke, KeystrokeEvent, Model, ModelContext, Modifiers, MouseButton,
-    MouseMoveEvent, MouseUpEvent, Pixels, PlatformAtlas, PlatformDisplay, PlatformInput,
-    PlatformWindow, Point, PromptLevel, Render, ScaledPixels, SharedString, Size, SubscriberSet,
-    Subscription, TaffyLayoutEngine, Task, View, VisualContext, WeakView, WindowBounds,
-    WindowOptions,
+    AvailableSpace, BorderStyle, Bounds, Context, Corners, CursorStyle, DispatchActionListener,
+    DispatchNodeId, DispatchTree, DisplayId, Edges, Effect, Entity, EntityId, EventEmitter,
+    FileDropEvent, Fill, Flatten, GlobalElementId, Hsla, KeyBinding, KeyContext, KeyDownEvent,
+    KeyMatch, KeymatchMode, KeymatchResult, Keystroke, KeystrokeEvent, Model, ModelContext,
+    Modifiers, MouseButton, MouseMoveEvent, MouseUpEvent, Pixels, PlatformAtlas, PlatformDisplay,
+    PlatformInput, PlatformWindow, Point, PromptLevel, Render, ScaledPixels, SharedString, Size,
+    SubscriberSet, Subscription, TaffyLayoutEngine, Task, View, VisualContext, WeakView,
+    WindowBounds, WindowOptions,
 };
 use anyhow::{anyhow, Context as _, Result};
 use collections::FxHashSet;
@@ -39,7 +39,9 @@ use std::{
 use util::{measure, ResultExt};
 
 mod element_cx;
+mod input_recording;
 pub use element_cx::*;
+pub use input_recording::*;
 
 const ACTIVE_DRAG_Z_INDEX: u16 = 1;
 
@@ -254,6 +256,8 @@ pub struct Window {
     pub(crate) rem_size: Pixels,
     pub(crate) viewport_size: Size<Pixels>,
     layout_engine: Option<TaffyLayoutEngine>,
+    scratch_layout_engine: Option<TaffyLayoutEngine>,
+    scratch_element_states: Option<ScratchElementStates>,
     pub(crate) root_view: Option<AnyView>,
     pub(crate) element_id_stack: GlobalElementId,
     pub(crate) rendered_frame: Frame,
@@ -276,6 +280,7 @@ pub struct Window {
     pub(crate) focus: Option<FocusId>,
     focus_enabled: bool,
     pending_input: Option<PendingInput>,
+    input_recorder: Option<InputRecorder>,
 
     #[cfg(any(test, feature = "test-support"))]
     pub(crate) focus_invalidated: bool,
@@ -348,9 +353,15 @@ impl Window {
         }));
         platform_window.on_resize(Box::new({
             let mut cx = cx.to_async();
-            move |_, _| {
+            move |size, scale_factor| {
                 handle
-                    .update(&mut cx, |_, cx| cx.window_bounds_changed())
+                    .update(&mut cx, |_, cx| {
+                        cx.record_platform_event(|| RecordedEventKind::Resize {
+                            size,
+                            scale_factor,
+                        });
+                        cx.window_bounds_changed()
+                    })
                     .log_err();
             }
         }));
@@ -367,6 +378,9 @@ impl Window {
             move |active| {
                 handle
                     .update(&mut cx, |_, cx| {
+                        cx.record_platform_event(|| RecordedEventKind::ActiveStatusChange {
+                            active,
+                        });
                         cx.window.active = active;
                         cx.window
                             .activation_observers
@@ -381,7 +395,10 @@ impl Window {
             let mut cx = cx.to_async();
             Box::new(move |event| {
                 handle
-                    .update(&mut cx, |_, cx| cx.dispatch_event(event))
+                    .update(&mut cx, |_, cx| {
+                        cx.record_platform_event(|| RecordedEventKind::Input(event.clone()));
+                        cx.dispatch_event(event)
+                    })
                     .log_err()
                     .unwrap_or(false)
             })
@@ -396,6 +413,8 @@ impl Window {
             rem_size: px(16.),
             viewport_size: content_size,
             layout_engine: Some(TaffyLayoutEngine::new()),
+            scratch_layout_engine: Some(TaffyLayoutEngine::new()),
+            scratch_element_states: None,
             root_view: None,
             element_id_stack: GlobalElementId::default(),
             rendered_frame: Frame::new(DispatchTree::new(cx.keymap.clone(), cx.actions.clone())),
@@ -418,6 +437,7 @@ impl Window {
             focus: None,
             focus_enabled: true,
             pending_input: None,
+            input_recorder: None,
 
             #[cfg(any(test, feature = "test-support"))]
             focus_invalidated: false,
@@ -426,13 +446,15 @@ impl Window {
 }
 
 /// Indicates which region of the window is visible. Content falling outside of this mask will not be
-/// rendered. Currently, only rectangular content masks are supported, but we give the mask its own type
+/// rendered. Masks are rectangles whose corners can be rounded, and we give the mask its own type
 /// to leave room to support more complex shapes in the future.
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 #[repr(C)]
 pub struct ContentMask<P: Clone + Default + Debug> {
     /// The bounds
     pub bounds: Bounds<P>,
+    /// The radii of the corners of the bounds
+    pub corner_radii: Corners<P>,
 }
 
 impl ContentMask<Pixels> {
@@ -440,13 +462,56 @@ impl ContentMask<Pixels> {
     pub fn scale(&self, factor: f32) -> ContentMask<ScaledPixels> {
         ContentMask {
             bounds: self.bounds.scale(factor),
+            corner_radii: self.corner_radii.scale(factor),
         }
     }
 
     /// Intersect the content mask with the given content mask.
     pub fn intersect(&self, other: &Self) -> Self {
         let bounds = self.bounds.intersect(&other.bounds);
-        ContentMask { bounds }
+        // Two rounded rectangles don't generally intersect in another one, so each corner is
+        // rounded like the corners of the masks it lies on. That's exact for nested masks that
+        // share corners or keep their rounded corners clear of each other.
+        let corner_radius = |corner: fn(&Bounds<Pixels>) -> Point<Pixels>,
+                             radius: fn(&Corners<Pixels>) -> Pixels| {
+            [self, other]
+                .into_iter()
+                .filter(|mask| corner(&mask.bounds) == corner(&bounds))
+                .map(|mask| radius(&mask.corner_radii))
+                .max()
+                .unwrap_or_default()
+        };
+        let corner_radii = Corners {
+            top_left: corner_radius(|bounds| bounds.origin, |radii| radii.top_left),
+            top_right: corner_radius(Bounds::upper_right, |radii| radii.top_right),
+            bottom_right: corner_radius(Bounds::lower_right, |radii| radii.bottom_right),
+            bottom_left: corner_radius(Bounds::lower_left, |radii| radii.bottom_left),
+        };
+        ContentMask {
+            bounds,
+            corner_radii,
+        }
+    }
+
+    /// Checks whether the given point is inside the mask, outside of its rounded corners.
+    pub fn contains(&self, point: &Point<Pixels>) -> bool {
+        if !self.bounds.contains(point) {
+            return false;
+        }
+
+        let center = self.bounds.center();
+        let (radius, corner) = match (point.x < center.x, point.y < center.y) {
+            (true, true) => (self.corner_radii.top_left, self.bounds.origin),
+            (false, true) => (self.corner_radii.top_right, self.bounds.upper_right()),
+            (false, false) => (self.corner_radii.bottom_right, self.bounds.lower_right()),
+            (true, false) => (self.corner_radii.bottom_left, self.bounds.lower_left()),
+        };
+        let dx = radius - (point.x - corner.x).abs();
+        let dy = radius - (point.y - corner.y).abs();
+        if dx <= Pixels::ZERO || dy <= Pixels::ZERO {
+            return true;
+        }
+        dx.0 * dx.0 + dy.0 * dy.0 <= radius.0 * radius.0
     }
 }
 
@@ -844,14 +909,20 @@ impl<'a> WindowContext<'a> {
     pub fn was_top_layer(&self, point: &Point<Pixels>, layer: &StackingOrder) -> bool {
         // Precondition: the depth map is ordered from topmost to bottomost.
 
-        for (opaque_layer, _, bounds) in self.window.rendered_frame.depth_map.iter() {
+        for (opaque_layer, _, bounds, transform, content_mask) in
+            self.window.rendered_frame.depth_map.iter()
+        {
             if layer >= opaque_layer {
                 // The queried layer is either above or is the same as the this opaque layer.
                 // Anything after this point is guaranteed to be below the queried layer.
                 return true;
             }
 
-            if !bounds.contains(point) {
+            if !content_mask.contains(point)
+                || !transform
+                    .untransform(*point)
+                    .is_some_and(|point| bounds.contains(&point))
+            {
                 // This opaque layer is above the queried layer but it doesn't contain
                 // the given position, so we can ignore it even if it's above.
                 continue;
@@ -882,14 +953,20 @@ impl<'a> WindowContext<'a> {
     ) -> bool {
         // Precondition: the depth map is ordered from topmost to bottomost.
 
-        for (opaque_layer, _, bounds) in self.window.rendered_frame.depth_map.iter() {
+        for (opaque_layer, _, bounds, transform, content_mask) in
+            self.window.rendered_frame.depth_map.iter()
+        {
             if layer >= opaque_layer {
                 // The queried layer is either above or is the same as the this opaque layer.
                 // Anything after this point is guaranteed to be below the queried layer.
                 return true;
             }
 
-            if !bounds.contains(point) {
+            if !content_mask.contains(point)
+                || !transform
+                    .untransform(*point)
+                    .is_some_and(|point| bounds.contains(&point))
+            {
                 // This opaque layer is above the queried layer but it doesn't contain
                 // the given position, so we can ignore it even if it's above.
                 continue;
@@ -1024,7 +1101,12 @@ impl<'a> WindowContext<'a> {
             }
         }
 
-        self.window.layout_engine.as_mut().unwrap().clear();
+        self.window.layout_engine.as_mut().unwrap().finish_frame();
+        self.window
+            .scratch_layout_engine
+            .as_mut()
+            .unwrap()
+            .finish_frame();
         self.text_system()
             .finish_frame(&self.window.next_frame.reused_views);
         self.window
@@ -1080,6 +1162,34 @@ impl<'a> WindowContext<'a> {
         self.window.drawing = false;
     }
 
+    /// Returns the headless window backing this window, when running on the headless
+    /// platform, so tests can deliver input to it and read back what it painted.
+    #[cfg(all(target_os = "linux", any(test, feature = "test-support")))]
+    pub fn headless_window(&self) -> Option<crate::HeadlessWindow> {
+        self.window.platform_window.as_headless()
+    }
+
+    /// Write the last frame drawn by this window to a file, including the pixels of
+    /// every glyph, icon and image it draws, so rendering bugs can be reproduced
+    /// without the application state that produced the frame.
+    pub fn dump_frame(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        self.frame_dump()?.save(path.as_ref())
+    }
+
+    /// Capture the last frame drawn by this window, like [`WindowContext::dump_frame`],
+    /// without writing it to a file.
+    pub fn frame_dump(&self) -> Result<crate::SceneDump> {
+        let atlas = self.window.platform_window.sprite_atlas();
+        let textures = atlas
+            .texture_source()
+            .ok_or_else(|| anyhow!("this platform's sprite atlas can't be read back"))?;
+        let viewport_size = self
+            .window
+            .viewport_size
+            .map(|pixels| crate::DevicePixels((pixels.0 * self.window.scale_factor).ceil() as i32));
+        crate::SceneDump::capture(&self.window.rendered_frame.scene, viewport_size, textures)
+    }
+
     /// Dispatch a mouse or keyboard event on the window.
     pub fn dispatch_event(&mut self, event: PlatformInput) -> bool {
         // Handlers may set this to false by calling `stop_propagation`.
@@ -2683,9 +2793,10 @@ impl From<(&'static str, u64)> for ElementId {
 pub struct PaintQuad {
     bounds: Bounds<Pixels>,
     corner_radii: Corners<Pixels>,
-    background: Hsla,
+    background: Fill,
     border_widths: Edges<Pixels>,
-    border_color: Hsla,
+    border_colors: Edges<Hsla>,
+    border_styles: Edges<BorderStyle>,
 }
 
 impl PaintQuad {
@@ -2708,13 +2819,29 @@ impl PaintQuad {
     /// Sets the border color of the quad.
     pub fn border_color(self, border_color: impl Into<Hsla>) -> Self {
         PaintQuad {
-            border_color: border_color.into(),
+            border_colors: Edges::all(border_color.into()),
+            ..self
+        }
+    }
+
+    /// Sets the color of each edge of the quad's border.
+    pub fn border_colors(self, border_colors: Edges<Hsla>) -> Self {
+        PaintQuad {
+            border_colors,
+            ..self
+        }
+    }
+
+    /// Sets whether each edge of the quad's border is solid, dashed or dotted.
+    pub fn border_styles(self, border_styles: Edges<BorderStyle>) -> Self {
+        PaintQuad {
+            border_styles,
             ..self
         }
     }
 
-    /// Sets the background color of the quad.
-    pub fn background(self, background: impl Into<Hsla>) -> Self {
+    /// Sets the background of the quad, which can be a color or a gradient.
+    pub fn background(self, background: impl Into<Fill>) -> Self {
         PaintQuad {
             background: background.into(),
             ..self
@@ -2726,7 +2853,7 @@ impl PaintQuad {
 pub fn quad(
     bounds: Bounds<Pixels>,
     corner_radii: impl Into<Corners<Pixels>>,
-    background: impl Into<Hsla>,
+    background: impl Into<Fill>,
     border_widths: impl Into<Edges<Pixels>>,
     border_color: impl Into<Hsla>,
 ) -> PaintQuad {
@@ -2735,18 +2862,20 @@ pub fn quad(
         corner_radii: corner_radii.into(),
         background: background.into(),
         border_widths: border_widths.into(),
-        border_color: border_color.into(),
+        border_colors: Edges::all(border_color.into()),
+        border_styles: Edges::default(),
     }
 }
 
-/// Creates a filled quad with the given bounds and background color.
-pub fn fill(bounds: impl Into<Bounds<Pixels>>, background: impl Into<Hsla>) -> PaintQuad {
+/// Creates a filled quad with the given bounds and background.
+pub fn fill(bounds: impl Into<Bounds<Pixels>>, background: impl Into<Fill>) -> PaintQuad {
     PaintQuad {
         bounds: bounds.into(),
         corner_radii: (0.).into(),
         background: background.into(),
         border_widths: (0.).into(),
-        border_color: transparent_black(),
+        border_colors: Edges::all(transparent_black()),
+        border_styles: Edges::default(),
     }
 }
 
@@ -2755,8 +2884,9 @@ pub fn outline(bounds: impl Into<Bounds<Pixels>>, border_color: impl Into<Hsla>)
     PaintQuad {
         bounds: bounds.into(),
         corner_radii: (0.).into(),
-        background: transparent_black(),
+        background: transparent_black().into(),
         border_widths: (1.).into(),
-        border_color: border_color.into(),
+        border_colors: Edges::all(border_color.into()),
+        border_styles: Edges::default(),
     }
 }
@@ -15,6 +15,7 @@
 use std::{
     any::{Any, TypeId},
     borrow::{Borrow, BorrowMut, Cow},
+    hash::{Hash, Hasher},
     mem,
     rc::Rc,
     sync::Arc,
@@ -24,18 +25,22 @@ use anyhow::Result;
 use collections::{FxHashMap, FxHashSet};
 use derive_more::{Deref, DerefMut};
 // WIN_DIRTY use media::core_video::CVImageBuffer;
+use futures::FutureExt;
+use seahash::SeaHasher;
 use smallvec::SmallVec;
-use util::post_inc;
+use util::{post_inc, ResultExt};
 
 use crate::{
-    prelude::*, size, AnyTooltip, AppContext, AvailableSpace, Bounds, BoxShadow, ContentMask,
-    Corners, CursorStyle, DevicePixels, DispatchPhase, DispatchTree, ElementId, ElementStateBox,
-    EntityId, FocusHandle, FocusId, FontId, GlobalElementId, GlyphId, Hsla, ImageData,
-    InputHandler, IsZero, KeyContext, KeyEvent, KeymatchMode, LayoutId, MonochromeSprite,
-    MouseEvent, PaintQuad, Path, Pixels, PlatformInputHandler, Point, PolychromeSprite, Quad,
-    RenderGlyphParams, RenderImageParams, RenderSvgParams, Scene, Shadow, SharedString, Size,
-    StackingContext, StackingOrder, Style, Surface, TextStyleRefinement, Underline, UnderlineStyle,
-    Window, WindowContext, SUBPIXEL_VARIANTS,
+    black, prelude::*, size, AnyTooltip, AppContext, AtlasTile, AvailableSpace, Background, Bounds,
+    BoxShadow, ColorMatrix, ContentMask, Corners, CursorStyle, DevicePixels, DispatchPhase,
+    DispatchTree, ElementId, ElementStateBox, EntityId, Filter, FilterEffect, FilterLayer,
+    FocusHandle, FocusId, FontId, GlobalElementId, GlyphId, Hsla, ImageData, ImageSource,
+    InputHandler, IsZero, KeyContext, KeyEvent, KeymatchMode, LayerMask, LayoutId, Length,
+    MaskSource, MonochromeSprite, MouseEvent, Overflow, PaintQuad, Path, Pixels,
+    PlatformInputHandler, Point, PolychromeSprite, Position, Quad, RenderGlyphParams,
+    RenderImageParams, RenderSvgParams, ScaledPixels, Scene, Shadow, SharedString, Size,
+    StackingContext, StackingOrder, Style, Surface, SvgColorMode, TextStyleRefinement,
+    TransformationMatrix, Underline, UnderlineStyle, Window, WindowContext, SUBPIXEL_VARIANTS,
 };
 
 type AnyMouseListener = Box<dyn FnMut(&dyn Any, DispatchPhase, &mut ElementContext) + 'static>;
@@ -50,6 +55,16 @@ pub(crate) struct TooltipRequest {
     pub(crate) tooltip: AnyTooltip,
 }
 
+/// The element states used while measuring elements on the scratch layout tree, which are kept
+/// apart from the frame's so that measuring leaves them where they were.
+#[derive(Default)]
+pub(crate) struct ScratchElementStates {
+    states: FxHashMap<GlobalElementId, ElementStateBox>,
+    /// Whether each state that existed before measuring was taken from the rendered frame, rather
+    /// than the frame being drawn.
+    taken_from_rendered_frame: FxHashMap<GlobalElementId, bool>,
+}
+
 pub(crate) struct Frame {
     pub(crate) focus: Option<FocusId>,
     pub(crate) window_active: bool,
@@ -57,12 +72,21 @@ pub(crate) struct Frame {
     pub(crate) mouse_listeners: FxHashMap<TypeId, Vec<(StackingOrder, EntityId, AnyMouseListener)>>,
     pub(crate) dispatch_tree: DispatchTree,
     pub(crate) scene: Scene,
-    pub(crate) depth_map: Vec<(StackingOrder, EntityId, Bounds<Pixels>)>,
+    pub(crate) depth_map: Vec<(
+        StackingOrder,
+        EntityId,
+        Bounds<Pixels>,
+        TransformationMatrix,
+        ContentMask<Pixels>,
+    )>,
     pub(crate) z_index_stack: StackingOrder,
     pub(crate) next_stacking_order_id: u16,
     pub(crate) next_root_z_index: u16,
     pub(crate) content_mask_stack: Vec<ContentMask<Pixels>>,
     pub(crate) element_offset_stack: Vec<Point<Pixels>>,
+    pub(crate) scroll_viewport_stack: Vec<Bounds<Pixels>>,
+    pub(crate) opacity_stack: Vec<f32>,
+    pub(crate) transform_stack: Vec<TransformationMatrix>,
     pub(crate) requested_input_handler: Option<RequestedInputHandler>,
     pub(crate) tooltip_request: Option<TooltipRequest>,
     pub(crate) cursor_styles: FxHashMap<EntityId, CursorStyle>,
@@ -89,6 +113,9 @@ impl Frame {
             next_root_z_index: 0,
             content_mask_stack: Vec::new(),
             element_offset_stack: Vec::new(),
+            scroll_viewport_stack: Vec::new(),
+            opacity_stack: Vec::new(),
+            transform_stack: Vec::new(),
             requested_input_handler: None,
             tooltip_request: None,
             cursor_styles: FxHashMap::default(),
@@ -135,13 +162,15 @@ impl Frame {
         }
 
         // Reuse entries in the depth map that didn't change since the last frame.
-        for (order, view_id, bounds) in prev_frame.depth_map.drain(..) {
+        for (order, view_id, bounds, transform, content_mask) in prev_frame.depth_map.drain(..) {
             if self.reused_views.contains(&view_id) {
                 match self
                     .depth_map
-                    .binary_search_by(|(level, _, _)| order.cmp(level))
+                    .binary_search_by(|(level, _, _, _, _)| order.cmp(level))
                 {
-                    Ok(i) | Err(i) => self.depth_map.insert(i, (order, view_id, bounds)),
+                    Ok(i) | Err(i) => self
+                        .depth_map
+                        .insert(i, (order, view_id, bounds, transform, content_mask)),
                 }
             }
         }
@@ -412,7 +441,12 @@ impl<'a> ElementContext<'a> {
         mask: Option<ContentMask<Pixels>>,
         f: impl FnOnce(&mut Self) -> R,
     ) -> R {
-        if let Some(mask) = mask {
+        if let Some(mut mask) = mask {
+            // Content masks are applied in window space, so a mask inside a transformed
+            // element clips to the bounds its transformed region covers.
+            let transform = self.transform();
+            mask.bounds = transform.apply_to_bounds(&mask.bounds);
+            mask.corner_radii *= transform.average_scale();
             let mask = mask.intersect(&self.content_mask());
             self.window_mut().next_frame.content_mask_stack.push(mask);
             let result = f(self);
@@ -423,6 +457,243 @@ impl<'a> ElementContext<'a> {
         }
     }
 
+    /// Invoke the given function with the given opacity multiplied into the current one.
+    /// Everything painted inside it has its alpha scaled by the combined opacity.
+    pub fn with_opacity<R>(&mut self, opacity: Option<f32>, f: impl FnOnce(&mut Self) -> R) -> R {
+        if let Some(opacity) = opacity {
+            let opacity = self.opacity() * opacity.clamp(0., 1.);
+            self.window_mut().next_frame.opacity_stack.push(opacity);
+            let result = f(self);
+            self.window_mut().next_frame.opacity_stack.pop();
+            result
+        } else {
+            f(self)
+        }
+    }
+
+    /// Invoke the given function with the given transformation applied after any transformation
+    /// that is already in effect. Everything painted inside it is transformed, and mouse
+    /// hit-testing of interactive elements maps positions back through it.
+    pub fn with_transform<R>(
+        &mut self,
+        transform: Option<TransformationMatrix>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        if let Some(transform) = transform.filter(|transform| !transform.is_unit()) {
+            let transform = self.transform().compose(transform);
+            self.window_mut().next_frame.transform_stack.push(transform);
+            let result = f(self);
+            self.window_mut().next_frame.transform_stack.pop();
+            result
+        } else {
+            f(self)
+        }
+    }
+
+    /// Invoke the given function in a new stacking context that is painted into an intermediate
+    /// layer, which is then filtered by each of the given filters in order. Anything blurred
+    /// beyond the painted content is still clipped by the current content mask. See [`Filter`]
+    /// for the platforms that render filters.
+    ///
+    /// Returns an error without invoking the function if any filters are given, but the window
+    /// can't paint them. See [`ElementContext::supports_layers`].
+    pub fn with_filters<R>(
+        &mut self,
+        filters: &[Filter],
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> Result<R> {
+        if filters.is_empty() {
+            return Ok(f(self));
+        }
+        self.ensure_layers_supported("filters")?;
+
+        // Each layer blurs and then changes colors. Consecutive blurs are combined into one,
+        // as are consecutive color changes, which skips clamping between them. A blur doesn't
+        // commute with an earlier color change, as color matrices clamp and work on straight
+        // alpha, so it gets a layer of its own around the earlier filters.
+        let mut layers = SmallVec::<[(f32, ColorMatrix); 1]>::new();
+        for filter in filters {
+            match (filter, layers.last_mut()) {
+                (Filter::Blur(radius), Some((blur_variance, color_matrix)))
+                    if *color_matrix == ColorMatrix::identity() =>
+                {
+                    *blur_variance += radius.0 * radius.0;
+                }
+                (Filter::Blur(radius), _) => {
+                    layers.push((radius.0 * radius.0, ColorMatrix::identity()));
+                }
+                (filter, Some((_, color_matrix))) => {
+                    *color_matrix = filter.color_matrix().compose(*color_matrix);
+                }
+                (filter, None) => layers.push((0., filter.color_matrix())),
+            }
+        }
+        let scale = self.transform().average_scale() * self.scale_factor();
+        let mut f = Some(f);
+        Ok(self.with_filter_layers(&layers, scale, &mut |cx| (f.take().unwrap())(cx)))
+    }
+
+    /// Paints into nested layers, where the last layer is the outermost one.
+    fn with_filter_layers<R>(
+        &mut self,
+        layers: &[(f32, ColorMatrix)],
+        scale: f32,
+        f: &mut dyn FnMut(&mut Self) -> R,
+    ) -> R {
+        match layers.split_last() {
+            Some(((blur_variance, color_matrix), inner_layers)) => {
+                let blur_radius = ScaledPixels(blur_variance.sqrt() * scale);
+                self.with_layer(blur_radius, *color_matrix, None, |cx| {
+                    cx.with_filter_layers(inner_layers, scale, f)
+                })
+            }
+            None => f(self),
+        }
+    }
+
+    /// Invoke the given function in a new stacking context whose painting is clipped to the
+    /// given path, with antialiased edges. The path is positioned like one passed to
+    /// [`ElementContext::paint_path`].
+    ///
+    /// Returns an error without invoking the function if the window can't paint clip paths.
+    /// See [`ElementContext::supports_layers`].
+    pub fn with_clip_path<R>(
+        &mut self,
+        mut path: Path<Pixels>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> Result<R> {
+        self.ensure_layers_supported("clip paths")?;
+        path.transform(&self.transform());
+        path.content_mask = self.content_mask();
+        path.color = black();
+        path.view_id = self.parent_view_id().into();
+        let mask = LayerMask::Path(path.scale(self.scale_factor()));
+        Ok(self.with_layer(ScaledPixels(0.), ColorMatrix::identity(), Some(mask), f))
+    }
+
+    /// Invoke the given function in a new stacking context whose painting is faded by the
+    /// alpha of the given mask, stretched over the given bounds. Anything painted outside
+    /// the bounds is hidden, as is everything while an image mask is still loading.
+    ///
+    /// Returns an error without invoking the function if the window can't paint masks. See
+    /// [`ElementContext::supports_layers`].
+    pub fn with_mask<R>(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        mask: impl Into<MaskSource>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> Result<R> {
+        self.ensure_layers_supported("masks")?;
+        let scale_factor = self.scale_factor();
+        let quad = Quad {
+            view_id: self.parent_view_id().into(),
+            bounds: bounds.scale(scale_factor),
+            content_mask: self.content_mask().scale(scale_factor),
+            transform: self.transform().scale_translation(scale_factor),
+            ..Default::default()
+        };
+        let mask = match mask.into() {
+            MaskSource::Fill(fill) => LayerMask::Quad(Quad {
+                background: Background::from(&fill),
+                ..quad
+            }),
+            MaskSource::Image(source) => match self.mask_image_tile(source) {
+                Some(tile) => LayerMask::Sprite(PolychromeSprite {
+                    view_id: quad.view_id,
+                    layer_id: 0,
+                    order: 0,
+                    bounds: quad.bounds,
+                    content_mask: quad.content_mask,
+                    transform: quad.transform,
+                    corner_radii: Corners::default(),
+                    tile,
+                    grayscale: false,
+                    opacity: 1.,
+                }),
+                // A transparent quad hides everything.
+                None => LayerMask::Quad(quad),
+            },
+        };
+        Ok(self.with_layer(ScaledPixels(0.), ColorMatrix::identity(), Some(mask), f))
+    }
+
+    /// Whether this window can paint filters, clip paths and masks. The Vulkan renderer on
+    /// Windows can't paint them yet.
+    pub fn supports_layers(&self) -> bool {
+        self.window.platform_window.supports_layers()
+    }
+
+    fn ensure_layers_supported(&self, effect: &str) -> Result<()> {
+        anyhow::ensure!(
+            self.supports_layers(),
+            "{effect} can't be painted, as the renderer of this window doesn't support layers"
+        );
+        Ok(())
+    }
+
+    /// Returns the atlas tile of an image used as a mask, if it has loaded. Otherwise the
+    /// window is refreshed once it has.
+    fn mask_image_tile(&mut self, source: ImageSource) -> Option<AtlasTile> {
+        let data = match source {
+            ImageSource::Data(data) => data,
+            ImageSource::Uri(uri) => {
+                let image_future = self.image_cache.get(uri, self);
+                if let Some(data) = image_future
+                    .clone()
+                    .now_or_never()
+                    .and_then(|result| result.ok())
+                {
+                    data
+                } else {
+                    self.spawn(|mut cx| async move {
+                        if image_future.await.ok().is_some() {
+                            cx.on_next_frame(|cx| cx.refresh());
+                        }
+                    })
+                    .detach();
+                    return None;
+                }
+            }
+        };
+
+        let params = RenderImageParams { image_id: data.id };
+        self.window
+            .sprite_atlas
+            .get_or_insert_with(&params.into(), &mut || {
+                Ok((data.size(), Cow::Borrowed(data.as_bytes())))
+            })
+            .log_err()
+    }
+
+    /// Invoke the given function in a new stacking context that is painted into an
+    /// intermediate layer, which is then blurred, color-filtered and masked as given.
+    fn with_layer<R>(
+        &mut self,
+        blur_radius: ScaledPixels,
+        color_matrix: ColorMatrix,
+        mask: Option<LayerMask>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let content_mask = self.content_mask().scale(self.scale_factor());
+        let view_id = self.parent_view_id();
+
+        self.with_z_index(0, |cx| {
+            let frame = &mut cx.window.next_frame;
+            frame.scene.push_filter_layer(FilterLayer {
+                view_id: view_id.into(),
+                stacking_order: frame.z_index_stack.clone(),
+                orders: 0..0,
+                effect: FilterEffect {
+                    content_mask,
+                    blur_radius,
+                    color_matrix,
+                },
+                mask,
+            });
+            f(cx)
+        })
+    }
+
     /// Invoke the given function with the content mask reset to that
     /// of the window.
     pub fn break_content_mask<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
@@ -431,6 +702,7 @@ impl<'a> ElementContext<'a> {
                 origin: Point::default(),
                 size: self.window().viewport_size,
             },
+            ..Default::default()
         };
 
         let new_root_z_index = post_inc(&mut self.window_mut().next_frame.next_root_z_index);
@@ -500,6 +772,85 @@ impl<'a> ElementContext<'a> {
         result
     }
 
+    /// Invoke the given function with the given bounds as the visible area of the nearest scroll
+    /// container, which sticky elements painted within the function stay pinned to.
+    pub(crate) fn with_scroll_viewport<R>(
+        &mut self,
+        viewport: Bounds<Pixels>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        self.window_mut()
+            .next_frame
+            .scroll_viewport_stack
+            .push(viewport);
+        let result = f(self);
+        self.window_mut().next_frame.scroll_viewport_stack.pop();
+        result
+    }
+
+    /// Obtain the visible area of the nearest scroll container, if any.
+    pub(crate) fn scroll_viewport(&self) -> Option<Bounds<Pixels>> {
+        self.window()
+            .next_frame
+            .scroll_viewport_stack
+            .last()
+            .copied()
+    }
+
+    /// The offset that keeps the element with the given layout, if it is sticky, within the
+    /// visible area of the nearest scroll container, without leaving its parent's content box.
+    /// The children of a scroll container can stick anywhere within its content.
+    pub(crate) fn sticky_offset(&mut self, layout_id: LayoutId) -> Point<Pixels> {
+        let Some(viewport) = self.scroll_viewport() else {
+            return Point::default();
+        };
+        let inset = match self.layout_style(layout_id) {
+            Some(style) if style.position == Position::Sticky => style.inset,
+            _ => return Point::default(),
+        };
+
+        let rem_size = self.rem_size();
+        let bounds = self.layout_bounds(layout_id);
+        let containing_block = self.layout_parent(layout_id).and_then(|parent_id| {
+            let parent_bounds = self.layout_bounds(parent_id);
+            let parent_style = self.layout_style(parent_id)?;
+            (parent_style.overflow.x != Overflow::Scroll
+                && parent_style.overflow.y != Overflow::Scroll)
+                .then(|| parent_style.content_bounds(parent_bounds, rem_size))
+        });
+        let threshold = |length: Length, viewport_length: Pixels| match length {
+            Length::Definite(length) => Some(length.to_pixels(viewport_length.into(), rem_size)),
+            Length::Auto => None,
+        };
+
+        let mut offset = Point::default();
+        if let Some(bottom) = threshold(inset.bottom, viewport.size.height) {
+            offset.y = (viewport.bottom() - bottom - bounds.bottom()).min(Pixels::ZERO);
+        }
+        // When both thresholds can't be met, the top one wins, as it does in CSS.
+        if let Some(top) = threshold(inset.top, viewport.size.height) {
+            offset.y = offset.y.max(viewport.top() + top - bounds.top());
+        }
+        if let Some(right) = threshold(inset.right, viewport.size.width) {
+            offset.x = (viewport.right() - right - bounds.right()).min(Pixels::ZERO);
+        }
+        if let Some(left) = threshold(inset.left, viewport.size.width) {
+            offset.x = offset.x.max(viewport.left() + left - bounds.left());
+        }
+
+        if let Some(containing_block) = containing_block {
+            offset.y = offset.y.clamp(
+                (containing_block.top() - bounds.top()).min(Pixels::ZERO),
+                (containing_block.bottom() - bounds.bottom()).max(Pixels::ZERO),
+            );
+            offset.x = offset.x.clamp(
+                (containing_block.left() - bounds.left()).min(Pixels::ZERO),
+                (containing_block.right() - bounds.right()).max(Pixels::ZERO),
+            );
+        }
+        offset
+    }
+
     /// Obtain the current element offset.
     pub fn element_offset(&self) -> Point<Pixels> {
         self.window()
@@ -522,9 +873,30 @@ impl<'a> ElementContext<'a> {
                     origin: Point::default(),
                     size: self.window().viewport_size,
                 },
+                ..Default::default()
             })
     }
 
+    /// Obtain the current opacity, which is applied to everything painted.
+    pub fn opacity(&self) -> f32 {
+        self.window()
+            .next_frame
+            .opacity_stack
+            .last()
+            .copied()
+            .unwrap_or(1.)
+    }
+
+    /// Obtain the current transformation, which maps everything painted into window space.
+    pub fn transform(&self) -> TransformationMatrix {
+        self.window()
+            .next_frame
+            .transform_stack
+            .last()
+            .copied()
+            .unwrap_or_default()
+    }
+
     /// The size of an em for the base font of the application. Adjusting this value allows the
     /// UI to scale, just like zooming a web page.
     pub fn rem_size(&self) -> Pixels {
@@ -546,18 +918,7 @@ impl<'a> ElementContext<'a> {
         self.with_element_id(Some(id), |cx| {
                 let global_id = cx.window().element_id_stack.clone();
 
-                if let Some(any) = cx
-                    .window_mut()
-                    .next_frame
-                    .element_states
-                    .remove(&global_id)
-                    .or_else(|| {
-                        cx.window_mut()
-                            .rendered_frame
-                            .element_states
-                            .remove(&global_id)
-                    })
-                {
+                if let Some(any) = cx.take_element_state(&global_id) {
                     let ElementStateBox {
                         inner,
                         parent_view_id,
@@ -594,36 +955,67 @@ impl<'a> ElementContext<'a> {
                         .expect("element state is already on the stack");
                     let (result, state) = f(Some(state), cx);
                     state_box.replace(state);
-                    cx.window_mut()
-                        .next_frame
-                        .element_states
-                        .insert(global_id, ElementStateBox {
-                            inner: state_box,
-                            parent_view_id,
-                            #[cfg(debug_assertions)]
-                            type_name
-                        });
+                    cx.insert_element_state(global_id, ElementStateBox {
+                        inner: state_box,
+                        parent_view_id,
+                        #[cfg(debug_assertions)]
+                        type_name
+                    });
                     result
                 } else {
                     let (result, state) = f(None, cx);
                     let parent_view_id = cx.parent_view_id();
-                    cx.window_mut()
-                        .next_frame
-                        .element_states
-                        .insert(global_id,
-                            ElementStateBox {
-                                inner: Box::new(Some(state)),
-                                parent_view_id,
-                                #[cfg(debug_assertions)]
-                                type_name: std::any::type_name::<S>()
-                            }
-
-                        );
+                    cx.insert_element_state(global_id,
+                        ElementStateBox {
+                            inner: Box::new(Some(state)),
+                            parent_view_id,
+                            #[cfg(debug_assertions)]
+                            type_name: std::any::type_name::<S>()
+                        }
+                    );
                     result
                 }
             })
     }
+
+    /// Takes an element's state out of the frame being drawn, or else the frame rendered before
+    /// it. While measuring on the scratch layout tree, the states measuring put aside come first.
+    fn take_element_state(&mut self, global_id: &GlobalElementId) -> Option<ElementStateBox> {
+        let window = &mut *self.window;
+        let Some(scratch) = window.scratch_element_states.as_mut() else {
+            return window
+                .next_frame
+                .element_states
+                .remove(global_id)
+                .or_else(|| window.rendered_frame.element_states.remove(global_id));
+        };
+        if let Some(state) = scratch.states.remove(global_id) {
+            return Some(state);
+        }
+        let (state, taken_from_rendered_frame) =
+            match window.next_frame.element_states.remove(global_id) {
+                Some(state) => (state, false),
+                None => (
+                    window.rendered_frame.element_states.remove(global_id)?,
+                    true,
+                ),
+            };
+        scratch
+            .taken_from_rendered_frame
+            .insert(global_id.clone(), taken_from_rendered_frame);
+        Some(state)
+    }
+
+    /// Stores an element's state in the frame being drawn, or puts it aside while measuring.
+    fn insert_element_state(&mut self, global_id: GlobalElementId, state: ElementStateBox) {
+        let window = &mut *self.window;
+        match window.scratch_element_states.as_mut() {
+            Some(scratch) => scratch.states.insert(global_id, state),
+            None => window.next_frame.element_states.insert(global_id, state),
+        };
+    }
     /// Paint one or more drop shadows into the scene for the next frame at the current z-index.
+    /// Inset shadows are painted inside the given bounds and corner radii instead of behind them.
     pub fn paint_shadows(
         &mut self,
         bounds: Bounds<Pixels>,
@@ -632,12 +1024,19 @@ impl<'a> ElementContext<'a> {
     ) {
         let scale_factor = self.scale_factor();
         let content_mask = self.content_mask();
+        let transform = self.transform().scale_translation(scale_factor);
+        let opacity = self.opacity();
         let view_id = self.parent_view_id();
         let window = &mut *self.window;
         for shadow in shadows {
-            let mut shadow_bounds = bounds;
-            shadow_bounds.origin += shadow.offset;
-            shadow_bounds.dilate(shadow.spread_radius);
+            let (shadow_bounds, offset, spread_radius) = if shadow.inset {
+                (bounds, shadow.offset, shadow.spread_radius)
+            } else {
+                let mut shadow_bounds = bounds;
+                shadow_bounds.origin += shadow.offset;
+                shadow_bounds.dilate(shadow.spread_radius);
+                (shadow_bounds, Point::default(), Pixels::ZERO)
+            };
             window.next_frame.scene.insert(
                 &window.next_frame.z_index_stack,
                 Shadow {
@@ -646,9 +1045,13 @@ impl<'a> ElementContext<'a> {
                     order: 0,
                     bounds: shadow_bounds.scale(scale_factor),
                     content_mask: content_mask.scale(scale_factor),
+                    transform,
                     corner_radii: corner_radii.scale(scale_factor),
-                    color: shadow.color,
+                    color: shadow.color.opacity(opacity),
                     blur_radius: shadow.blur_radius.scale(scale_factor),
+                    offset: offset.scale(scale_factor),
+                    spread_radius: spread_radius.scale(scale_factor),
+                    inset: shadow.inset,
                 },
             );
         }
@@ -660,6 +1063,8 @@ impl<'a> ElementContext<'a> {
     pub fn paint_quad(&mut self, quad: PaintQuad) {
         let scale_factor = self.scale_factor();
         let content_mask = self.content_mask();
+        let transform = self.transform().scale_translation(scale_factor);
+        let opacity = self.opacity();
         let view_id = self.parent_view_id();
 
         let window = &mut *self.window;
@@ -671,10 +1076,12 @@ impl<'a> ElementContext<'a> {
                 order: 0,
                 bounds: quad.bounds.scale(scale_factor),
                 content_mask: content_mask.scale(scale_factor),
-                background: quad.background,
-                border_color: quad.border_color,
+                transform,
+                background: Background::from(&quad.background).opacity(opacity),
+                border_colors: quad.border_colors.map(|color| color.opacity(opacity)),
                 corner_radii: quad.corner_radii.scale(scale_factor),
                 border_widths: quad.border_widths.scale(scale_factor),
+                border_styles: quad.border_styles,
             },
         );
     }
@@ -685,8 +1092,9 @@ impl<'a> ElementContext<'a> {
         let content_mask = self.content_mask();
         let view_id = self.parent_view_id();
 
+        path.transform(&self.transform());
         path.content_mask = content_mask;
-        path.color = color.into();
+        path.color = color.into().opacity(self.opacity());
         path.view_id = view_id.into();
         let window = &mut *self.window;
         window
@@ -713,6 +1121,8 @@ impl<'a> ElementContext<'a> {
             size: size(width, height),
         };
         let content_mask = self.content_mask();
+        let transform = self.transform().scale_translation(scale_factor);
+        let opacity = self.opacity();
         let view_id = self.parent_view_id();
 
         let window = &mut *self.window;
@@ -724,8 +1134,9 @@ impl<'a> ElementContext<'a> {
                 order: 0,
                 bounds: bounds.scale(scale_factor),
                 content_mask: content_mask.scale(scale_factor),
+                transform,
                 thickness: style.thickness.scale(scale_factor),
-                color: style.color.unwrap_or_default(),
+                color: style.color.unwrap_or_default().opacity(opacity),
                 wavy: style.wavy,
             },
         );
@@ -774,6 +1185,8 @@ impl<'a> ElementContext<'a> {
                 size: tile.bounds.size.map(Into::into),
             };
             let content_mask = self.content_mask().scale(scale_factor);
+            let transform = self.transform().scale_translation(scale_factor);
+            let opacity = self.opacity();
             let view_id = self.parent_view_id();
             let window = &mut *self.window;
             window.next_frame.scene.insert(
@@ -784,7 +1197,8 @@ impl<'a> ElementContext<'a> {
                     order: 0,
                     bounds,
                     content_mask,
-                    color,
+                    transform,
+                    color: color.opacity(opacity),
                     tile,
                 },
             );
@@ -831,6 +1245,8 @@ impl<'a> ElementContext<'a> {
                 size: tile.bounds.size.map(Into::into),
             };
             let content_mask = self.content_mask().scale(scale_factor);
+            let transform = self.transform().scale_translation(scale_factor);
+            let opacity = self.opacity();
             let view_id = self.parent_view_id();
             let window = &mut *self.window;
 
@@ -843,8 +1259,10 @@ impl<'a> ElementContext<'a> {
                     bounds,
                     corner_radii: Default::default(),
                     content_mask,
+                    transform,
                     tile,
                     grayscale: false,
+                    opacity,
                 },
             );
         }
@@ -860,22 +1278,10 @@ impl<'a> ElementContext<'a> {
     ) -> Result<()> {
         let scale_factor = self.scale_factor();
         let bounds = bounds.scale(scale_factor);
-        // Render the SVG at twice the size to get a higher quality result.
-        let params = RenderSvgParams {
-            path,
-            size: bounds
-                .size
-                .map(|pixels| DevicePixels::from((pixels.0 * 2.).ceil() as i32)),
-        };
-
-        let tile =
-            self.window
-                .sprite_atlas
-                .get_or_insert_with(&params.clone().into(), &mut || {
-                    let bytes = self.svg_renderer.render(&params)?;
-                    Ok((params.size, Cow::Owned(bytes)))
-                })?;
+        let tile = self.rasterize_svg(&bounds, path, SvgColorMode::Monochrome)?;
         let content_mask = self.content_mask().scale(scale_factor);
+        let transform = self.transform().scale_translation(scale_factor);
+        let opacity = self.opacity();
         let view_id = self.parent_view_id();
 
         let window = &mut *self.window;
@@ -887,14 +1293,74 @@ impl<'a> ElementContext<'a> {
                 order: 0,
                 bounds,
                 content_mask,
-                color,
+                transform,
+                color: color.opacity(opacity),
+                tile,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Paint an SVG with its own colors into the scene for the next frame at the current z-index.
+    pub fn paint_full_color_svg(
+        &mut self,
+        bounds: Bounds<Pixels>,
+        path: SharedString,
+    ) -> Result<()> {
+        let scale_factor = self.scale_factor();
+        let bounds = bounds.scale(scale_factor);
+        let tile = self.rasterize_svg(&bounds, path, SvgColorMode::FullColor)?;
+        let content_mask = self.content_mask().scale(scale_factor);
+        let transform = self.transform().scale_translation(scale_factor);
+        let opacity = self.opacity();
+        let view_id = self.parent_view_id();
+
+        let window = &mut *self.window;
+        window.next_frame.scene.insert(
+            &window.next_frame.z_index_stack,
+            PolychromeSprite {
+                view_id: view_id.into(),
+                layer_id: 0,
+                order: 0,
+                bounds,
+                content_mask,
+                transform,
+                corner_radii: Default::default(),
                 tile,
+                grayscale: false,
+                opacity,
             },
         );
 
         Ok(())
     }
 
+    /// Rasterizes the SVG into the sprite atlas at the given device size, unless it's
+    /// already there, and returns its tile.
+    fn rasterize_svg(
+        &mut self,
+        bounds: &Bounds<ScaledPixels>,
+        path: SharedString,
+        color_mode: SvgColorMode,
+    ) -> Result<AtlasTile> {
+        // Render the SVG at twice the size to get a higher quality result.
+        let params = RenderSvgParams {
+            path,
+            size: bounds
+                .size
+                .map(|pixels| DevicePixels::from((pixels.0 * 2.).ceil() as i32)),
+            color_mode,
+        };
+
+        self.window
+            .sprite_atlas
+            .get_or_insert_with(&params.clone().into(), &mut || {
+                let bytes = self.svg_renderer.render(&params)?;
+                Ok((params.size, Cow::Owned(bytes)))
+            })
+    }
+
     /// Paint an image into the scene for the next frame at the current z-index.
     pub fn paint_image(
         &mut self,
@@ -914,7 +1380,9 @@ impl<'a> ElementContext<'a> {
                 Ok((data.size(), Cow::Borrowed(data.as_bytes())))
             })?;
         let content_mask = self.content_mask().scale(scale_factor);
+        let transform = self.transform().scale_translation(scale_factor);
         let corner_radii = corner_radii.scale(scale_factor);
+        let opacity = self.opacity();
         let view_id = self.parent_view_id();
 
         let window = &mut *self.window;
@@ -926,9 +1394,11 @@ impl<'a> ElementContext<'a> {
                 order: 0,
                 bounds,
                 content_mask,
+                transform,
                 corner_radii,
                 tile,
                 grayscale,
+                opacity,
             },
         );
         Ok(())
@@ -970,12 +1440,13 @@ impl<'a> ElementContext<'a> {
         self.app.layout_id_buffer.extend(children);
         let rem_size = self.rem_size();
 
-        self.cx
-            .window
-            .layout_engine
-            .as_mut()
-            .unwrap()
-            .request_layout(style, rem_size, &self.cx.app.layout_id_buffer)
+        let window = &mut *self.cx.window;
+        window.layout_engine.as_mut().unwrap().request_layout(
+            &window.element_id_stack,
+            style,
+            rem_size,
+            &self.cx.app.layout_id_buffer,
+        )
     }
 
     /// Add a node to the layout tree for the current frame. Instead of taking a `Style` and children,
@@ -983,7 +1454,8 @@ impl<'a> ElementContext<'a> {
     /// determine the element's size. One place this is used internally is when measuring text.
     ///
     /// The given closure is invoked at layout time with the known dimensions and available space and
-    /// returns a `Size`.
+    /// returns a `Size`. As the closure can't be compared across frames, the element is measured again
+    /// on every frame; use `request_cached_measured_layout` to avoid that.
     pub fn request_measured_layout<
         F: FnMut(Size<Option<Pixels>>, Size<AvailableSpace>, &mut WindowContext) -> Size<Pixels>
             + 'static,
@@ -991,13 +1463,83 @@ impl<'a> ElementContext<'a> {
         &mut self,
         style: Style,
         measure: F,
+    ) -> LayoutId {
+        self.request_measured_layout_internal(style, None, measure)
+    }
+
+    /// Like `request_measured_layout`, but the element's measurements are reused across frames for
+    /// as long as its style and the given `inputs`, which should cover everything the closure's result
+    /// depends on, stay the same.
+    ///
+    /// Even when the measurements are reused, the closure is called once per frame with the element's
+    /// final size, so elements can keep what they computed while measuring for painting.
+    pub fn request_cached_measured_layout<
+        F: FnMut(Size<Option<Pixels>>, Size<AvailableSpace>, &mut WindowContext) -> Size<Pixels>
+            + 'static,
+    >(
+        &mut self,
+        style: Style,
+        inputs: impl Hash,
+        measure: F,
+    ) -> LayoutId {
+        let mut hasher = SeaHasher::new();
+        inputs.hash(&mut hasher);
+        self.request_measured_layout_internal(style, Some(hasher.finish()), measure)
+    }
+
+    fn request_measured_layout_internal(
+        &mut self,
+        style: Style,
+        measure_inputs: Option<u64>,
+        measure: impl FnMut(Size<Option<Pixels>>, Size<AvailableSpace>, &mut WindowContext) -> Size<Pixels>
+            + 'static,
     ) -> LayoutId {
         let rem_size = self.rem_size();
-        self.window
+        let window = &mut *self.cx.window;
+        window
             .layout_engine
             .as_mut()
             .unwrap()
-            .request_measured_layout(style, rem_size, measure)
+            .request_measured_layout(
+                &window.element_id_stack,
+                style,
+                rem_size,
+                measure_inputs,
+                measure,
+            )
+    }
+
+    /// Invoke the given function with the frame's layout tree swapped out for a separate one, so that
+    /// elements can be laid out to measure them without affecting the layout of the frame.
+    pub(crate) fn with_scratch_layout<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        // Measuring within a measurement can share the same scratch tree.
+        let Some(scratch_layout_engine) = self.window.scratch_layout_engine.take() else {
+            return f(self);
+        };
+        let layout_engine = self.window.layout_engine.replace(scratch_layout_engine);
+        self.window.scratch_element_states = Some(ScratchElementStates::default());
+        let result = f(self);
+        self.window.scratch_layout_engine =
+            mem::replace(&mut self.window.layout_engine, layout_engine);
+
+        // Put the states that existed before measuring back where they were found, and drop the
+        // ones that were only created for measuring.
+        let scratch = self.window.scratch_element_states.take().unwrap();
+        for (global_id, state) in scratch.states {
+            let frame = match scratch.taken_from_rendered_frame.get(&global_id) {
+                Some(true) => &mut self.window.rendered_frame,
+                Some(false) => &mut self.window.next_frame,
+                None => continue,
+            };
+            frame.element_states.insert(global_id, state);
+        }
+        result
+    }
+
+    /// Whether elements are being laid out on the scratch layout tree, to measure them. Their
+    /// layout is requested again afterwards, so anything it consumes has to be kept for then.
+    pub(crate) fn is_measuring_in_scratch(&self) -> bool {
+        self.window.scratch_element_states.is_some()
     }
 
     /// Compute the layout for the given id within the given available space.
@@ -1031,13 +1573,26 @@ impl<'a> ElementContext<'a> {
             .requested_style(layout_id)
     }
 
+    pub(crate) fn layout_parent(&self, layout_id: LayoutId) -> Option<LayoutId> {
+        self.window
+            .layout_engine
+            .as_ref()
+            .unwrap()
+            .parent(layout_id)
+    }
+
     /// Called during painting to track which z-index is on top at each pixel position
     pub fn add_opaque_layer(&mut self, bounds: Bounds<Pixels>) {
         let stacking_order = self.window.next_frame.z_index_stack.clone();
         let view_id = self.parent_view_id();
+        let transform = self.transform();
+        let content_mask = self.content_mask();
         let depth_map = &mut self.window.next_frame.depth_map;
-        match depth_map.binary_search_by(|(level, _, _)| stacking_order.cmp(level)) {
-            Ok(i) | Err(i) => depth_map.insert(i, (stacking_order, view_id, bounds)),
+        match depth_map.binary_search_by(|(level, _, _, _, _)| stacking_order.cmp(level)) {
+            Ok(i) | Err(i) => depth_map.insert(
+                i,
+                (stacking_order, view_id, bounds, transform, content_mask),
+            ),
         }
     }
 
@@ -0,0 +1,288 @@
+use crate::{Pixels, PlatformInput, Size, WindowContext};
+use anyhow::{bail, Context as _, Result};
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// The version of the input recording format. Bump it whenever the format changes in
+/// a way older readers can't load.
+pub(crate) const INPUT_RECORDING_VERSION: u32 = 1;
+
+/// Everything a window received from the platform while it was being recorded: input
+/// events, resizes and activation changes, each stamped with the time it arrived.
+///
+/// Recordings are started with [`WindowContext::start_recording_input`], and can be
+/// saved to a file and replayed against a test window to reproduce a session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InputRecording {
+    version: u32,
+    pub(crate) initial_state: RecordedWindowState,
+    pub(crate) events: Vec<RecordedEvent>,
+}
+
+/// The state of the window when recording started.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RecordedWindowState {
+    pub(crate) size: Size<Pixels>,
+    pub(crate) scale_factor: f32,
+    pub(crate) active: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct RecordedEvent {
+    /// The time since recording started.
+    pub(crate) offset: Duration,
+    pub(crate) kind: RecordedEventKind,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) enum RecordedEventKind {
+    Input(PlatformInput),
+    Resize {
+        size: Size<Pixels>,
+        scale_factor: f32,
+    },
+    ActiveStatusChange {
+        active: bool,
+    },
+}
+
+pub(crate) struct InputRecorder {
+    start: Instant,
+    recording: InputRecording,
+}
+
+impl InputRecording {
+    /// Write the recording to a file as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_vec(self).context("failed to serialize input recording")?;
+        fs::write(path, json).with_context(|| format!("failed to write {path:?}"))
+    }
+
+    /// Read a recording written by [`InputRecording::save`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+        Self::from_json(&json).with_context(|| format!("failed to load {path:?}"))
+    }
+
+    fn from_json(json: &[u8]) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        let Version { version } =
+            serde_json::from_slice(json).context("failed to read input recording version")?;
+        if version > INPUT_RECORDING_VERSION {
+            bail!(
+                "input recording has version {version}, but only versions up to {INPUT_RECORDING_VERSION} are supported"
+            );
+        }
+        serde_json::from_slice(json).context("failed to parse input recording")
+    }
+}
+
+impl InputRecorder {
+    fn new(start: Instant, initial_state: RecordedWindowState) -> Self {
+        Self {
+            start,
+            recording: InputRecording {
+                version: INPUT_RECORDING_VERSION,
+                initial_state,
+                events: Vec::new(),
+            },
+        }
+    }
+
+    fn record(&mut self, now: Instant, kind: RecordedEventKind) {
+        self.recording.events.push(RecordedEvent {
+            offset: now.saturating_duration_since(self.start),
+            kind,
+        });
+    }
+}
+
+impl<'a> WindowContext<'a> {
+    /// Start recording the input this window receives from the platform, along with its
+    /// resizes and activation changes. Any recording already in progress is discarded.
+    pub fn start_recording_input(&mut self) {
+        let initial_state = RecordedWindowState {
+            size: self.window.viewport_size,
+            scale_factor: self.window.scale_factor,
+            active: self.window.active,
+        };
+        self.window.input_recorder = Some(InputRecorder::new(
+            self.background_executor().now(),
+            initial_state,
+        ));
+    }
+
+    /// Stop recording input, returning everything recorded since
+    /// [`WindowContext::start_recording_input`] was called.
+    pub fn stop_recording_input(&mut self) -> Option<InputRecording> {
+        self.window
+            .input_recorder
+            .take()
+            .map(|recorder| recorder.recording)
+    }
+
+    /// Records an event delivered by the platform, if input is being recorded.
+    pub(crate) fn record_platform_event(&mut self, event: impl FnOnce() -> RecordedEventKind) {
+        let now = self.background_executor().now();
+        if let Some(recorder) = self.window.input_recorder.as_mut() {
+            recorder.record(now, event());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        div, point, px, size, FocusHandle, InteractiveElement, IntoElement, KeyDownEvent,
+        Keystroke, Modifiers, MouseButton, MouseDownEvent, MouseUpEvent, ParentElement as _,
+        Render, ScrollDelta, ScrollWheelEvent, Styled, TestAppContext, TouchPhase, ViewContext,
+        VisualTestContext,
+    };
+
+    // For compatibility with the test macro
+    use crate as gpui;
+
+    /// Logs the input it receives, stamped with the time since it was created.
+    struct InputLog {
+        created_at: Instant,
+        focus_handle: FocusHandle,
+        entries: Vec<String>,
+    }
+
+    impl InputLog {
+        fn new(cx: &mut ViewContext<Self>) -> Self {
+            let focus_handle = cx.focus_handle();
+            cx.focus(&focus_handle);
+            Self {
+                created_at: cx.background_executor().now(),
+                focus_handle,
+                entries: Vec::new(),
+            }
+        }
+
+        fn log(&mut self, entry: String, cx: &mut ViewContext<Self>) {
+            let elapsed = cx.background_executor().now() - self.created_at;
+            self.entries
+                .push(format!("{}ms {entry}", elapsed.as_millis()));
+        }
+    }
+
+    impl Render for InputLog {
+        fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+            div()
+                .size_full()
+                .track_focus(&self.focus_handle)
+                .on_key_down(cx.listener(|this, event: &KeyDownEvent, cx| {
+                    this.log(format!("key {}", event.keystroke.key), cx)
+                }))
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(|this, event: &MouseDownEvent, cx| {
+                        this.log(format!("down {:?}", event.position), cx)
+                    }),
+                )
+                .on_scroll_wheel(cx.listener(|this, event: &ScrollWheelEvent, cx| {
+                    this.log(format!("scroll {:?}", event.delta), cx)
+                }))
+                .child(format!("{} events", self.entries.len()))
+        }
+    }
+
+    fn window_state(cx: &mut VisualTestContext) -> (Size<Pixels>, bool) {
+        cx.update(|cx| (cx.viewport_size(), cx.is_window_active()))
+    }
+
+    #[crate::test]
+    fn test_record_and_replay(cx: &mut TestAppContext) {
+        let (recorded_view, recorded_cx) = cx.add_window_view(InputLog::new);
+        recorded_cx.update(|cx| cx.start_recording_input());
+
+        recorded_cx.simulate_event(MouseDownEvent {
+            button: MouseButton::Left,
+            position: point(px(10.), px(12.)),
+            modifiers: Modifiers::default(),
+            click_count: 1,
+        });
+        recorded_cx.simulate_event(MouseUpEvent {
+            button: MouseButton::Left,
+            position: point(px(10.), px(12.)),
+            modifiers: Modifiers::default(),
+            click_count: 1,
+        });
+        recorded_cx
+            .executor()
+            .advance_clock(Duration::from_millis(250));
+        recorded_cx.simulate_event(KeyDownEvent {
+            keystroke: Keystroke::parse("ctrl-k").unwrap(),
+            is_held: false,
+        });
+        recorded_cx
+            .executor()
+            .advance_clock(Duration::from_millis(40));
+        recorded_cx.simulate_resize(size(px(300.), px(200.)));
+        recorded_cx.simulate_event(ScrollWheelEvent {
+            position: point(px(5.), px(5.)),
+            delta: ScrollDelta::Lines(point(0., -3.)),
+            modifiers: Modifiers::default(),
+            touch_phase: TouchPhase::Moved,
+        });
+        recorded_cx.run_until_parked();
+
+        let recording = recorded_cx.update(|cx| cx.stop_recording_input()).unwrap();
+        assert_eq!(recording.events.len(), 5);
+        let path = std::env::temp_dir().join(format!("gpui-input-{}.json", uuid::Uuid::new_v4()));
+        recording.save(&path).unwrap();
+        let expected_entries = recorded_view.update(recorded_cx, |view, _| view.entries.clone());
+        let expected_state = window_state(recorded_cx);
+        assert_eq!(
+            expected_entries,
+            [
+                "0ms down Point { x: 10 px, y: 12 px }",
+                "250ms key k",
+                "290ms scroll Lines(Point { x: 0.0, y: -3.0 })",
+            ]
+        );
+
+        let (replayed_view, replayed_cx) = cx.add_window_view(InputLog::new);
+        replayed_cx.replay(&path);
+        fs::remove_file(&path).ok();
+        let replayed_entries = replayed_view.update(replayed_cx, |view, _| view.entries.clone());
+        assert_eq!(replayed_entries, expected_entries);
+        assert_eq!(window_state(replayed_cx), expected_state);
+    }
+
+    #[test]
+    fn test_rejects_newer_versions() {
+        let recording = InputRecording {
+            version: INPUT_RECORDING_VERSION,
+            initial_state: RecordedWindowState {
+                size: size(px(100.), px(100.)),
+                scale_factor: 2.,
+                active: true,
+            },
+            events: vec![RecordedEvent {
+                offset: Duration::from_millis(5),
+                kind: RecordedEventKind::ActiveStatusChange { active: false },
+            }],
+        };
+        let json = serde_json::to_vec(&recording).unwrap();
+        let loaded = InputRecording::from_json(&json).unwrap();
+        assert_eq!(loaded.events.len(), 1);
+
+        let mut newer = serde_json::to_value(&recording).unwrap();
+        newer["version"] = (INPUT_RECORDING_VERSION + 1).into();
+        let error = InputRecording::from_json(newer.to_string().as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("version"), "{error}");
+    }
+}
@@ -27,6 +27,7 @@ impl Render for HelloWorld {
                         blur_radius: Pixels(30.0),
                         offset: Point::default(),
                         spread_radius: Pixels::default(),
+                        ..Default::default()
                     }]),
             )
             .child(
@@ -40,6 +41,7 @@ impl Render for HelloWorld {
                         blur_radius: Pixels(30.0),
                         offset: Point::default(),
                         spread_radius: Pixels::default(),
+                        ..Default::default()
                     }]),
             )
             .child(
@@ -53,6 +55,7 @@ impl Render for HelloWorld {
                         blur_radius: Pixels(30.0),
                         offset: Point::default(),
                         spread_radius: Pixels::default(),
+                        ..Default::default()
                     }]),
             )
     }